@@ -3,6 +3,7 @@
  *
  * Displays Claude Code weekly usage as a progress bar in the GNOME Shell panel.
  * Connects to the CC Usage Widget Tauri app via D-Bus.
+ * Left click opens the widget window, right click forces a refresh.
  */
 
 import Clutter from 'gi://Clutter';
@@ -26,7 +27,8 @@ const BLOCK_COUNT = 8;
 const CCUsageIndicator = GObject.registerClass(
     class CCUsageIndicator extends PanelMenu.Button {
         _init() {
-            super._init(0.0, 'CC Usage Widget');
+            // No popup menu: clicks are handled by _onButtonPress
+            super._init(0.0, 'CC Usage Widget', true);
 
             this._usagePercent = 0;
             this._daysLeft = 0;
@@ -57,6 +59,7 @@ const CCUsageIndicator = GObject.registerClass(
             // Connect hover events
             this.connect('enter-event', this._onEnter.bind(this));
             this.connect('leave-event', this._onLeave.bind(this));
+            this.connect('button-press-event', this._onButtonPress.bind(this));
 
             // D-Bus proxy
             this._proxy = null;
@@ -73,6 +76,8 @@ const CCUsageIndicator = GObject.registerClass(
                             <arg type="d" direction="out" name="week_usage_percent"/>
                             <arg type="u" direction="out" name="days_left"/>
                         </method>
+                        <method name="Refresh"/>
+                        <method name="ShowWindow"/>
                     </interface>
                 </node>
             `);
@@ -132,6 +137,35 @@ const CCUsageIndicator = GObject.registerClass(
             );
         }
 
+        _callMethod(method, onDone) {
+            if (!this._proxy) return;
+
+            this._proxy.call(
+                method,
+                null,
+                Gio.DBusCallFlags.NONE,
+                -1,
+                null,
+                (proxy, result) => {
+                    try {
+                        proxy.call_finish(result);
+                        if (onDone) onDone();
+                    } catch (e) {
+                        console.debug(`CC Usage Widget: ${method} failed: ${e.message}`);
+                    }
+                }
+            );
+        }
+
+        _onButtonPress(actor, event) {
+            if (event.get_button() === Clutter.BUTTON_SECONDARY) {
+                this._callMethod('Refresh', () => this._fetchUsage());
+            } else {
+                this._callMethod('ShowWindow');
+            }
+            return Clutter.EVENT_STOP;
+        }
+
         _onRepaint(area) {
             const cr = area.get_context();
             const [width, height] = area.get_surface_size();
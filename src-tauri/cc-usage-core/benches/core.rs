@@ -0,0 +1,127 @@
+//! Performance baselines for the hot paths of a refresh: journal parsing, aggregation and
+//! the 12-week activity scan, over a synthetic history generated once into the temp dir.
+//!
+//! Run with `cargo bench -p cc-usage-core`. The default dataset (200 sessions x 500 lines)
+//! keeps a run to a few minutes; `CC_BENCH_LARGE=1` uses 10K sessions x 500 lines (5M lines,
+//! several GB of disk) to check behavior on very large histories.
+
+use cc_usage_core::aggregate::{aggregate_usage, collect_daily_activity, compute_weekly_usage};
+use cc_usage_core::quota::build_quota_info;
+use cc_usage_core::{parse_usage_from_file, ParsedEntry};
+use chrono::{Duration, Utc};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+const LINES_PER_SESSION: usize = 500;
+
+const MODELS: [&str; 3] = [
+    "claude-opus-4-5-20251101",
+    "claude-sonnet-4-5-20250929",
+    "claude-haiku-4-5-20251001",
+];
+
+fn session_count() -> usize {
+    if std::env::var_os("CC_BENCH_LARGE").is_some() {
+        10_000
+    } else {
+        200
+    }
+}
+
+/// One journal per session, alternating user prompts and assistant responses spread over
+/// the last 12 weeks. Reused across runs when already generated.
+fn dataset() -> Vec<PathBuf> {
+    let sessions = session_count();
+    let dir = std::env::temp_dir()
+        .join("cc-usage-bench")
+        .join(format!("{sessions}x{LINES_PER_SESSION}"));
+    let files: Vec<PathBuf> = (0..sessions)
+        .map(|i| dir.join(format!("session-{i:05}.jsonl")))
+        .collect();
+    if files.iter().all(|f| f.exists()) {
+        return files;
+    }
+
+    fs::create_dir_all(&dir).unwrap();
+    let now = Utc::now();
+    for (i, path) in files.iter().enumerate() {
+        let mut out = BufWriter::new(File::create(path).unwrap());
+        let session_id = format!("{i:08x}-0000-4000-8000-000000000000");
+        let cwd = format!("/home/dev/project-{}", i % 25);
+        let start = now - Duration::minutes((i as i64 * 1013) % (84 * 24 * 60));
+        let mut parent = String::from("null");
+        for line in 0..LINES_PER_SESSION {
+            let ts = (start + Duration::seconds(line as i64 * 20)).to_rfc3339();
+            let uuid = format!("{i:08x}-{line:04x}-4000-8000-000000000000");
+            let entry = if line % 2 == 0 {
+                format!(
+                    r#"{{"type":"user","uuid":"{uuid}","parentUuid":{parent},"sessionId":"{session_id}","cwd":"{cwd}","timestamp":"{ts}","message":{{"role":"user","content":"Refactor the parser module and add tests"}}}}"#
+                )
+            } else {
+                format!(
+                    r#"{{"type":"assistant","uuid":"{uuid}","parentUuid":{parent},"sessionId":"{session_id}","cwd":"{cwd}","timestamp":"{ts}","message":{{"model":"{}","role":"assistant","content":[{{"type":"text","text":"Done."}}],"usage":{{"input_tokens":{},"output_tokens":{},"cache_creation_input_tokens":{},"cache_read_input_tokens":{}}}}}}}"#,
+                    MODELS[(i + line) % MODELS.len()],
+                    10 + line % 50,
+                    200 + line % 800,
+                    1000 + line % 3000,
+                    20_000 + line * 100
+                )
+            };
+            writeln!(out, "{entry}").unwrap();
+            parent = format!("\"{uuid}\"");
+        }
+    }
+    files
+}
+
+fn parse_all(files: &[PathBuf]) -> Vec<ParsedEntry> {
+    files
+        .iter()
+        .flat_map(|f| parse_usage_from_file(f).unwrap())
+        .collect()
+}
+
+fn benches(c: &mut Criterion) {
+    let files = dataset();
+    let lines = (files.len() * LINES_PER_SESSION) as u64;
+    let mut group = c.benchmark_group("core");
+    group.sample_size(10);
+
+    group.throughput(Throughput::Elements(lines));
+    group.bench_function("parse_usage_from_file", |b| b.iter(|| parse_all(&files)));
+    group.bench_function("collect_daily_activity", |b| {
+        b.iter(|| collect_daily_activity(&files))
+    });
+
+    let entries = parse_all(&files);
+    let daily_activity = collect_daily_activity(&files);
+    let weekly_usage = compute_weekly_usage(&daily_activity);
+    group.throughput(Throughput::Elements(entries.len() as u64));
+    group.bench_function("aggregate_usage", |b| {
+        b.iter_batched(
+            || {
+                (
+                    entries.clone(),
+                    daily_activity.clone(),
+                    weekly_usage.clone(),
+                )
+            },
+            |(entries, daily_activity, weekly_usage)| {
+                aggregate_usage(
+                    entries,
+                    None,
+                    build_quota_info(None, 0.0, 0.0, Vec::new()),
+                    daily_activity,
+                    weekly_usage,
+                )
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(core, benches);
+criterion_main!(core);
@@ -0,0 +1,7 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|line: &str| {
+    cc_usage_core::parser::parse_user_prompt_timestamp(line);
+});
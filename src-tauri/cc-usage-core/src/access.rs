@@ -0,0 +1,118 @@
+//! Read-only access to Claude's data: user-configured roots must be inside an allow-list,
+//! journals reached through symlinks must stay inside their data dir, and nothing may be
+//! written inside a Claude root. Violations are skipped and kept for diagnostics.
+
+use chrono::Utc;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+use crate::types::AccessViolation;
+
+/// Violations kept for `access_violations`, oldest dropped first
+const MAX_VIOLATIONS: usize = 100;
+
+static ALLOWED_ROOTS: RwLock<Vec<PathBuf>> = RwLock::new(Vec::new());
+
+static VIOLATIONS: Mutex<Vec<AccessViolation>> = Mutex::new(Vec::new());
+
+/// Directories, besides the home directory, that `CLAUDE_CONFIG_DIR` roots may point into
+pub(crate) fn set_allowed_roots(roots: Vec<PathBuf>) {
+    if let Ok(mut allowed) = ALLOWED_ROOTS.write() {
+        *allowed = roots;
+    }
+}
+
+/// Violations seen since startup, each (kind, path) once, oldest first
+pub fn access_violations() -> Vec<AccessViolation> {
+    VIOLATIONS
+        .lock()
+        .map(|violations| violations.clone())
+        .unwrap_or_default()
+}
+
+pub(crate) fn record_violation(kind: &str, path: &Path, detail: String) {
+    let mut violations = match VIOLATIONS.lock() {
+        Ok(violations) => violations,
+        Err(_) => return,
+    };
+    let path = path.display().to_string();
+    if violations.iter().any(|v| v.kind == kind && v.path == path) {
+        return;
+    }
+    if violations.len() >= MAX_VIOLATIONS {
+        violations.remove(0);
+    }
+    violations.push(AccessViolation {
+        kind: kind.to_string(),
+        path,
+        detail,
+        detected_at: Utc::now().to_rfc3339(),
+    });
+}
+
+/// `path` with symlinks resolved; for a path that doesn't exist yet, its nearest existing
+/// ancestor is resolved and the rest appended
+fn resolve(path: &Path) -> PathBuf {
+    if let Ok(resolved) = std::fs::canonicalize(path) {
+        return resolved;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => resolve(parent).join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Whether `path` resolves to a location inside `root` (both resolved)
+pub fn stays_within(path: &Path, root: &Path) -> bool {
+    resolve(path).starts_with(resolve(root))
+}
+
+/// Whether a user-configured root may be read: absolute and inside the home directory or
+/// an allowed root. Rejected roots are recorded as "root_not_allowed".
+pub(crate) fn root_allowed(root: &Path) -> bool {
+    if !root.is_absolute() {
+        record_violation(
+            "root_not_allowed",
+            root,
+            "Data dirs must be absolute paths".to_string(),
+        );
+        return false;
+    }
+    let mut allowed: Vec<PathBuf> = dirs::home_dir().into_iter().collect();
+    if let Ok(extra) = ALLOWED_ROOTS.read() {
+        allowed.extend(extra.iter().cloned());
+    }
+    if allowed.iter().any(|prefix| stays_within(root, prefix)) {
+        return true;
+    }
+    record_violation(
+        "root_not_allowed",
+        root,
+        format!(
+            "Resolves to {}, outside the home directory and allowed_data_roots",
+            resolve(root).display()
+        ),
+    );
+    false
+}
+
+/// Refuse writes inside any Claude root, so the widget never modifies Claude Code's data.
+/// Call before creating or replacing a file.
+pub fn ensure_outside_data_dirs(path: &Path) -> Result<(), String> {
+    let target = resolve(path);
+    for root in crate::sources::claude_roots() {
+        if target.starts_with(resolve(&root)) {
+            record_violation(
+                "write_in_data_dir",
+                path,
+                format!("Inside Claude data root {}", root.display()),
+            );
+            return Err(format!(
+                "Refusing to write {}: it is inside Claude's data dir {}",
+                path.display(),
+                root.display()
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,1658 @@
+//! Aggregation of parsed entries into the stats shown by the widget.
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, SecondsFormat, Utc};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::cancel::CancellationToken;
+use crate::history::{fill_missing_directories, load_session_histories};
+use crate::hours::{
+    billing_anchor_day, billing_cycle_start, counted_hours_in, is_day_off, local_day_start,
+    month_period_start, next_billing_cycle_start, week_start, working_hours,
+};
+use crate::otlp::{otlp_entries, usage_source, UsageSource};
+use crate::parser::{
+    begin_parse_health, finish_parse_health, journal_lines, parse_usage_from_file,
+    parse_user_prompt_timestamp, quarantine_future_entries, ParsedEntry,
+};
+use crate::perf::PerfSpan;
+use crate::pricing::{
+    calculate_context_remaining, calculate_cost_for, get_model_context_limit,
+    get_model_display_name, thinking_cost_for,
+};
+use crate::projects::project_name;
+use crate::quota::{
+    build_quota_info, collect_weighted_prompts, count_user_prompts_in_window,
+    count_week_weighted_usage, current_quota_block, custom_window_statuses, quota_block_files,
+    quota_windows, reconstruct_quota_blocks, QuotaBlock, ESTIMATED_WINDOW_LIMIT,
+    QUOTA_WINDOW_HOURS,
+};
+use crate::sources::{
+    check_data_dirs, collect_jsonl_files, collect_jsonl_files_checked, get_claude_data_dirs,
+    get_configured_model, get_pending_todo_count, get_plan_hint, is_excluded_journal,
+    is_session_excluded, own_data_dirs, source_issue,
+};
+use crate::types::{
+    ActiveSession, BillingCycle, ContextBreakdown, DailyActivity, DailyDelta, DailyDigest,
+    DayTotals, HourlyBucket, ModelUsage, PeerBaseline, PeerComparison, PeerMetric, PeriodTotals,
+    ProductivityStats, ProjectSeries, ProjectWeekMatrix, ProjectWeekRow, QuotaInfo,
+    TaskProductivity, TokenUsage, UsageChange, UsageComparison, UsageStats, WeekDay,
+    WeeklyProjectSeries, WeeklyUsage,
+};
+use crate::users::{quota_by_user, session_owners, usage_by_user};
+
+/// A stretch of continuous activity within one session (no gap longer than the idle threshold)
+#[derive(Debug, Clone)]
+pub struct ActivityBlock {
+    pub session_id: String,
+    pub project: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub message_count: u32,
+    pub cost_usd: f64,
+}
+
+/// Split session activity since `since` into blocks separated by more than `idle_gap` of silence.
+/// Blocks are sorted by start time.
+pub fn collect_activity_blocks(
+    since: DateTime<Utc>,
+    idle_gap: chrono::Duration,
+) -> Vec<ActivityBlock> {
+    let hours_back = (Utc::now() - since).num_hours().max(0) + 1;
+    let files = collect_jsonl_files(&get_claude_data_dirs(), Some(hours_back));
+
+    // session_id -> [(timestamp, cwd, cost)]
+    let mut by_session: HashMap<String, Vec<(DateTime<Utc>, String, f64)>> = HashMap::new();
+    for file in &files {
+        let entries = match parse_usage_from_file(file) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        for entry in entries {
+            if entry.session_id.is_empty() {
+                continue;
+            }
+            let ts = match DateTime::parse_from_rfc3339(&entry.timestamp) {
+                Ok(ts) => ts.with_timezone(&Utc),
+                Err(_) => continue,
+            };
+            if ts < since {
+                continue;
+            }
+            let cost = entry.cost();
+            by_session
+                .entry(entry.session_id)
+                .or_default()
+                .push((ts, entry.cwd, cost));
+        }
+    }
+
+    let mut blocks = Vec::new();
+    for (session_id, mut events) in by_session {
+        events.sort_by_key(|a| a.0);
+        let mut current: Option<ActivityBlock> = None;
+
+        for (ts, cwd, cost) in events {
+            if let Some(ref mut block) = current {
+                if ts - block.end <= idle_gap {
+                    block.end = ts;
+                    block.message_count += 1;
+                    block.cost_usd += cost;
+                    continue;
+                }
+            }
+            if let Some(done) = current.take() {
+                blocks.push(done);
+            }
+            current = Some(ActivityBlock {
+                session_id: session_id.clone(),
+                project: project_name(&cwd),
+                start: ts,
+                end: ts,
+                message_count: 1,
+                cost_usd: cost,
+            });
+        }
+        if let Some(done) = current {
+            blocks.push(done);
+        }
+    }
+
+    blocks.sort_by_key(|a| a.start);
+    blocks
+}
+
+/// Correlate todo completions with token spend per session and per project.
+/// A task counts once per session, however many `TodoWrite` snapshots list it as completed.
+pub fn compute_productivity(
+    entries: &[ParsedEntry],
+    since: Option<DateTime<Utc>>,
+) -> ProductivityStats {
+    // session_id -> (project, completed todo contents, tokens, cost)
+    let mut sessions: HashMap<&str, (String, std::collections::HashSet<&str>, u64, f64)> =
+        HashMap::new();
+
+    for entry in entries {
+        if entry.session_id.is_empty() {
+            continue;
+        }
+        if let Some(since_dt) = since {
+            if let Ok(ts) = DateTime::parse_from_rfc3339(&entry.timestamp) {
+                if ts < since_dt {
+                    continue;
+                }
+            }
+        }
+        let session = sessions.entry(entry.session_id.as_str()).or_insert_with(|| {
+            let project = project_name(&entry.cwd);
+            (project, std::collections::HashSet::new(), 0, 0.0)
+        });
+        session.1.extend(entry.completed_todos.iter().map(|t| t.as_str()));
+        session.2 = session.2.saturating_add(entry.tokens.total());
+        session.3 += entry.cost();
+    }
+
+    let make = |key: String, project: String, completed: u32, tokens: u64, cost: f64| {
+        TaskProductivity {
+            key,
+            project,
+            completed_tasks: completed,
+            total_tokens: tokens,
+            cost_usd: cost,
+            cost_per_task: (completed > 0).then_some(cost / completed as f64),
+            tokens_per_task: (completed > 0).then_some(tokens as f64 / completed as f64),
+        }
+    };
+
+    // project -> (completed, tokens, cost)
+    let mut projects: HashMap<String, (u32, u64, f64)> = HashMap::new();
+    let mut session_stats: Vec<TaskProductivity> = Vec::new();
+    for (session_id, (project, completed, tokens, cost)) in sessions {
+        let completed = completed.len() as u32;
+        let totals = projects.entry(project.clone()).or_insert((0, 0, 0.0));
+        totals.0 += completed;
+        totals.1 = totals.1.saturating_add(tokens);
+        totals.2 += cost;
+        if completed > 0 {
+            let short_id: String = session_id.chars().take(8).collect();
+            session_stats.push(make(short_id, project, completed, tokens, cost));
+        }
+    }
+
+    let mut project_stats: Vec<TaskProductivity> = projects
+        .into_iter()
+        .filter(|(_, (completed, _, _))| *completed > 0)
+        .map(|(project, (completed, tokens, cost))| {
+            make(project.clone(), project, completed, tokens, cost)
+        })
+        .collect();
+
+    session_stats.sort_by_key(|s| std::cmp::Reverse(s.completed_tasks));
+    project_stats.sort_by_key(|s| std::cmp::Reverse(s.completed_tasks));
+
+    ProductivityStats {
+        sessions: session_stats,
+        projects: project_stats,
+    }
+}
+
+/// Days of prompt history in `UsageStats::daily_activity`
+pub const ACTIVITY_DAYS: i64 = 84;
+
+/// Days of prompt history kept in low-memory mode
+pub const LOW_MEMORY_ACTIVITY_DAYS: i64 = 7;
+
+static LOW_MEMORY: AtomicBool = AtomicBool::new(false);
+
+/// Trade detail for memory: daily activity shrinks to `LOW_MEMORY_ACTIVITY_DAYS`, active
+/// sessions get no sparklines and the "all" period only reads the last 30 days
+pub(crate) fn set_low_memory(enabled: bool) {
+    LOW_MEMORY.store(enabled, Ordering::Relaxed);
+}
+
+pub fn low_memory() -> bool {
+    LOW_MEMORY.load(Ordering::Relaxed)
+}
+
+/// Days of prompt history collected for `UsageStats::daily_activity`
+pub fn activity_days() -> i64 {
+    if low_memory() {
+        LOW_MEMORY_ACTIVITY_DAYS
+    } else {
+        ACTIVITY_DAYS
+    }
+}
+
+/// Width of one bucket in `ActiveSession::recent_activity`
+pub const SPARKLINE_BUCKET_MINUTES: i64 = 5;
+
+/// Time span covered by `ActiveSession::recent_activity`
+pub const SPARKLINE_WINDOW_MINUTES: i64 = 120;
+
+/// Longer gaps between consecutive assistant entries are idle time or tool runs, not generation
+pub const MAX_GENERATION_GAP_SECS: f64 = 120.0;
+
+/// Estimated output generation speed (tokens/sec), keyed by full session id and by model
+#[derive(Debug, Clone, Default)]
+pub struct OutputSpeeds {
+    pub by_session: HashMap<String, f64>,
+    pub by_model: HashMap<String, f64>,
+}
+
+/// Estimate output tokens/sec from the time between consecutive assistant entries of a
+/// session: each entry's output tokens are attributed to the gap since the previous one.
+/// Only entries at or after `since` count.
+pub fn estimate_output_speeds(
+    entries: &[ParsedEntry],
+    since: Option<DateTime<Utc>>,
+) -> OutputSpeeds {
+    let mut by_session: HashMap<&str, Vec<(DateTime<Utc>, &ParsedEntry)>> = HashMap::new();
+    for entry in entries {
+        if entry.session_id.is_empty() {
+            continue;
+        }
+        if let Ok(ts) = DateTime::parse_from_rfc3339(&entry.timestamp) {
+            by_session
+                .entry(entry.session_id.as_str())
+                .or_default()
+                .push((ts.with_timezone(&Utc), entry));
+        }
+    }
+
+    // key -> (output tokens, seconds)
+    let mut session_totals: HashMap<String, (u64, f64)> = HashMap::new();
+    let mut model_totals: HashMap<String, (u64, f64)> = HashMap::new();
+    for (session_id, mut timeline) in by_session {
+        timeline.sort_by_key(|(ts, _)| *ts);
+        for pair in timeline.windows(2) {
+            let ((prev_ts, _), (ts, entry)) = (pair[0], pair[1]);
+            if since.is_some_and(|since_dt| ts < since_dt) || entry.tokens.output_tokens == 0 {
+                continue;
+            }
+            let secs = (ts - prev_ts).num_milliseconds() as f64 / 1000.0;
+            if secs <= 0.0 || secs > MAX_GENERATION_GAP_SECS {
+                continue;
+            }
+            for (totals, key) in [
+                (&mut session_totals, session_id),
+                (&mut model_totals, entry.model.as_str()),
+            ] {
+                let total = totals.entry(key.to_string()).or_default();
+                total.0 = total.0.saturating_add(entry.tokens.output_tokens);
+                total.1 += secs;
+            }
+        }
+    }
+
+    let to_tps = |totals: HashMap<String, (u64, f64)>| -> HashMap<String, f64> {
+        totals
+            .into_iter()
+            .map(|(key, (tokens, secs))| (key, tokens as f64 / secs))
+            .collect()
+    };
+    OutputSpeeds {
+        by_session: to_tps(session_totals),
+        by_model: to_tps(model_totals),
+    }
+}
+
+/// Abandoned branches in one session's conversation tree and the tokens spent on them, as
+/// `(branch_count, abandoned_tokens)`. The kept path runs from the latest entry up to its
+/// root; every other subtree hanging off it was rewound or replaced by an edited prompt.
+pub fn conversation_branches(entries: &[&ParsedEntry]) -> (u32, u64) {
+    let nodes: Vec<&ParsedEntry> = entries
+        .iter()
+        .copied()
+        .filter(|e| !e.uuid.is_empty() && !e.is_sidechain)
+        .collect();
+    let latest = match nodes.iter().max_by(|a, b| a.timestamp.cmp(&b.timestamp)) {
+        Some(latest) => latest,
+        None => return (0, 0),
+    };
+
+    let by_uuid: HashMap<&str, &ParsedEntry> =
+        nodes.iter().map(|e| (e.uuid.as_str(), *e)).collect();
+    let mut kept: HashSet<&str> = HashSet::new();
+    let mut current = Some(*latest);
+    while let Some(node) = current {
+        if !kept.insert(node.uuid.as_str()) {
+            break;
+        }
+        current = node
+            .parent_uuid
+            .as_deref()
+            .and_then(|p| by_uuid.get(p).copied());
+    }
+
+    let mut branch_count = 0;
+    let mut abandoned_tokens: u64 = 0;
+    for node in &nodes {
+        if kept.contains(node.uuid.as_str()) {
+            continue;
+        }
+        abandoned_tokens = abandoned_tokens.saturating_add(node.tokens.total());
+        // A branch starts where an abandoned node hangs off the kept path (or is a root)
+        let parent_kept = node
+            .parent_uuid
+            .as_deref()
+            .filter(|p| by_uuid.contains_key(p))
+            .is_none_or(|p| kept.contains(p));
+        if parent_kept {
+            branch_count += 1;
+        }
+    }
+    (branch_count, abandoned_tokens)
+}
+
+/// Split the context of a session's latest main-conversation request into the fixed prefix,
+/// cached history and fresh input. The prefix is estimated as the context of the earliest
+/// request seen, which carries little conversation yet.
+pub fn context_breakdown(entries: &[&ParsedEntry]) -> Option<ContextBreakdown> {
+    let main: Vec<&ParsedEntry> = entries
+        .iter()
+        .copied()
+        .filter(|e| !e.is_sidechain)
+        .collect();
+    let first = main.iter().min_by(|a, b| a.timestamp.cmp(&b.timestamp))?;
+    let latest = main.iter().max_by(|a, b| a.timestamp.cmp(&b.timestamp))?;
+
+    let tokens = &latest.tokens;
+    let total = tokens.context_tokens();
+    let base = first.tokens.context_tokens();
+    let system_tokens = base.min(total);
+    let fresh_input_tokens = tokens
+        .input_tokens
+        .saturating_add(tokens.cache_creation_input_tokens)
+        .min(total - system_tokens);
+    let history_tokens = total - system_tokens - fresh_input_tokens;
+
+    let context_limit = get_model_context_limit(&latest.model);
+    let percent = |part: u64| part as f64 / context_limit as f64 * 100.0;
+    Some(ContextBreakdown {
+        system_tokens,
+        history_tokens,
+        fresh_input_tokens,
+        context_limit,
+        system_percent: percent(system_tokens),
+        history_percent: percent(history_tokens),
+        fresh_input_percent: percent(fresh_input_tokens),
+    })
+}
+
+/// Per-session running totals while building active sessions: (cwd, first_activity,
+/// last_activity, count, total_tokens, cost, last_model, current_context_tokens).
+type SessionAccumulator = (String, String, String, u32, u64, f64, String, u64);
+
+/// Build active sessions from parsed entries (last 24 hours)
+pub fn build_active_sessions(entries: Vec<ParsedEntry>) -> Vec<ActiveSession> {
+    let now = Utc::now();
+    let day_ago = now - chrono::Duration::hours(24);
+    let output_speeds = estimate_output_speeds(&entries, Some(day_ago));
+    let sparkline_start = now - chrono::Duration::minutes(SPARKLINE_WINDOW_MINUTES);
+    let bucket_count = (SPARKLINE_WINDOW_MINUTES / SPARKLINE_BUCKET_MINUTES) as usize;
+    let with_sparklines = !low_memory();
+    // session_id -> tokens per bucket
+    let mut sparklines: HashMap<String, Vec<u32>> = HashMap::new();
+    // session_id -> entries in the window, for branch detection
+    let mut session_entries: HashMap<&str, Vec<&ParsedEntry>> = HashMap::new();
+    let mut session_data: HashMap<String, SessionAccumulator> = HashMap::new();
+
+    for entry in &entries {
+        if let Ok(ts) = DateTime::parse_from_rfc3339(&entry.timestamp) {
+            if ts >= day_ago && !entry.session_id.is_empty() {
+                let entry_tokens = entry.tokens.total();
+                let entry_cost = entry.cost();
+                let context_tokens = entry.tokens.context_tokens();
+
+                let session = session_data
+                    .entry(entry.session_id.clone())
+                    .or_insert((
+                        entry.cwd.clone(),
+                        entry.timestamp.clone(),
+                        entry.timestamp.clone(),
+                        0,
+                        0,
+                        0.0,
+                        entry.model.clone(),
+                        context_tokens,
+                    ));
+                if entry.timestamp < session.1 {
+                    session.1 = entry.timestamp.clone();
+                }
+                if entry.timestamp > session.2 {
+                    session.2 = entry.timestamp.clone();
+                    session.6 = entry.model.clone();
+                    session.7 = context_tokens;
+                }
+                session.3 += 1;
+                session.4 = session.4.saturating_add(entry_tokens);
+                session.5 += entry_cost;
+                session_entries
+                    .entry(entry.session_id.as_str())
+                    .or_default()
+                    .push(entry);
+
+                if with_sparklines && ts >= sparkline_start {
+                    let minutes = (ts.with_timezone(&Utc) - sparkline_start).num_minutes();
+                    let bucket =
+                        ((minutes / SPARKLINE_BUCKET_MINUTES) as usize).min(bucket_count - 1);
+                    let buckets = sparklines
+                        .entry(entry.session_id.clone())
+                        .or_insert_with(|| vec![0; bucket_count]);
+                    buckets[bucket] = buckets[bucket]
+                        .saturating_add(u32::try_from(entry_tokens).unwrap_or(u32::MAX));
+                }
+            }
+        }
+    }
+
+    let mut active_sessions: Vec<ActiveSession> = session_data
+        .into_iter()
+        .map(
+            |(
+                session_id,
+                (
+                    cwd,
+                    first_activity,
+                    last_activity,
+                    msg_count,
+                    total_tokens,
+                    cost,
+                    model,
+                    current_context_tokens,
+                ),
+            )| {
+                let directory = cwd.clone();
+                let short_project = project_name(&directory);
+
+                let duration_minutes = if let (Ok(first), Ok(last)) = (
+                    DateTime::parse_from_rfc3339(&first_activity),
+                    DateTime::parse_from_rfc3339(&last_activity),
+                ) {
+                    ((last - first).num_minutes().max(0)) as u32
+                } else {
+                    0
+                };
+
+                let model_display_name = get_model_display_name(&model);
+                let context_remaining_percent =
+                    calculate_context_remaining(current_context_tokens, &model);
+                let todo_count = get_pending_todo_count(&session_id);
+                let recent_activity = if with_sparklines {
+                    sparklines
+                        .remove(&session_id)
+                        .unwrap_or_else(|| vec![0; bucket_count])
+                } else {
+                    Vec::new()
+                };
+                let avg_output_tps = output_speeds.by_session.get(&session_id).copied();
+                let (branch_count, abandoned_branch_tokens) = session_entries
+                    .get(session_id.as_str())
+                    .map(|entries| conversation_branches(entries))
+                    .unwrap_or((0, 0));
+                let context_breakdown = session_entries
+                    .get(session_id.as_str())
+                    .and_then(|entries| context_breakdown(entries));
+
+                ActiveSession {
+                    session_id: session_id.chars().take(8).collect(),
+                    project: short_project,
+                    directory,
+                    first_activity,
+                    last_activity,
+                    duration_minutes,
+                    message_count: msg_count,
+                    total_tokens,
+                    cost_usd: cost,
+                    model,
+                    model_display_name,
+                    context_remaining_percent,
+                    todo_count,
+                    recent_activity,
+                    avg_output_tps,
+                    branch_count,
+                    abandoned_branch_tokens,
+                    context_breakdown,
+                    commands_executed: 0,
+                    user: None,
+                    process_running: false,
+                    pid: None,
+                    display_last_activity: String::new(),
+                    display_tokens: String::new(),
+                    display_cost: String::new(),
+                }
+            },
+        )
+        .collect();
+
+    active_sessions.sort_by(|a, b| b.last_activity.cmp(&a.last_activity));
+    active_sessions
+}
+
+/// Tokens and cost per clock hour for the last `hours` hours, oldest first; the last bucket
+/// is the current, partial hour. Hours without usage are included as zeros.
+pub fn compute_hourly_series(entries: &[ParsedEntry], hours: u32) -> Vec<HourlyBucket> {
+    let hours = hours.max(1) as i64;
+    let now = Utc::now();
+    let current_hour = now - chrono::Duration::seconds(now.timestamp().rem_euclid(3600));
+    let start = current_hour - chrono::Duration::hours(hours - 1);
+
+    let mut buckets: Vec<HourlyBucket> = (0..hours)
+        .map(|i| HourlyBucket {
+            timestamp: (start + chrono::Duration::hours(i))
+                .format("%Y-%m-%dT%H:00:00Z")
+                .to_string(),
+            tokens: 0,
+            cost_usd: 0.0,
+        })
+        .collect();
+    for entry in entries {
+        let ts = match DateTime::parse_from_rfc3339(&entry.timestamp) {
+            Ok(ts) => ts.with_timezone(&Utc),
+            Err(_) => continue,
+        };
+        if ts < start || ts > now {
+            continue;
+        }
+        let bucket = &mut buckets[((ts - start).num_hours() as usize).min(hours as usize - 1)];
+        bucket.tokens = bucket.tokens.saturating_add(entry.tokens.total());
+        bucket.cost_usd += entry.cost();
+    }
+    buckets
+}
+
+/// Hourly tokens and cost from local journals (see `compute_hourly_series`)
+pub fn get_hourly_series(hours: u32) -> Vec<HourlyBucket> {
+    let files = collect_jsonl_files(&get_claude_data_dirs(), Some(hours.max(1) as i64 + 1));
+    let mut entries = Vec::new();
+    for file in &files {
+        if let Ok(parsed) = parse_usage_from_file(file) {
+            entries.extend(parsed);
+        }
+    }
+    compute_hourly_series(&entries, hours)
+}
+
+/// Share of tokens (0-100) since `since` used outside the configured working hours
+pub fn compute_off_hours_percent(entries: &[ParsedEntry], since: Option<DateTime<Utc>>) -> f64 {
+    let hours = working_hours();
+    let mut total: u64 = 0;
+    let mut off_hours: u64 = 0;
+
+    for entry in entries {
+        let ts = match DateTime::parse_from_rfc3339(&entry.timestamp) {
+            Ok(ts) => ts,
+            Err(_) => continue,
+        };
+        if since.is_some_and(|since_dt| ts < since_dt) {
+            continue;
+        }
+        let tokens = entry.tokens.total();
+        total = total.saturating_add(tokens);
+        if !hours.contains(&ts) {
+            off_hours = off_hours.saturating_add(tokens);
+        }
+    }
+
+    if total == 0 {
+        0.0
+    } else {
+        off_hours as f64 / total as f64 * 100.0
+    }
+}
+
+/// `timestamp`, or `now` if it is later (entries a little ahead of the clock are kept)
+fn clamp_to_now(timestamp: String, now: DateTime<Utc>) -> String {
+    match DateTime::parse_from_rfc3339(&timestamp) {
+        Ok(ts) if ts > now => now.to_rfc3339_opts(SecondsFormat::Millis, true),
+        _ => timestamp,
+    }
+}
+
+/// Usage stats of `entries` since `since`, with the quota and activity computed beforehand
+/// (they span more than the period, and are shared between periods)
+pub fn aggregate_usage(
+    mut entries: Vec<ParsedEntry>,
+    since: Option<DateTime<Utc>>,
+    quota: QuotaInfo,
+    daily_activity: Vec<DailyActivity>,
+    weekly_usage: WeeklyUsage,
+) -> UsageStats {
+    // Subagent journals of excluded sessions carry the parent's id
+    entries.retain(|entry| !is_session_excluded(&entry.session_id));
+    let now = Utc::now();
+    quarantine_future_entries(&mut entries, now);
+    // (model, provider) -> tokens
+    let mut by_model: HashMap<(String, Option<String>), TokenUsage> = HashMap::new();
+    let mut total = TokenUsage::default();
+    let mut latest_timestamp = String::new();
+    let mut message_count: u32 = 0;
+
+    for entry in &entries {
+        // Filter by date if specified for totals
+        if let Some(since_dt) = since {
+            if let Ok(ts) = DateTime::parse_from_rfc3339(&entry.timestamp) {
+                if ts < since_dt {
+                    continue;
+                }
+            }
+        }
+
+        message_count += 1;
+
+        if entry.timestamp > latest_timestamp {
+            latest_timestamp = entry.timestamp.clone();
+        }
+
+        // Aggregate by model
+        let model_entry = by_model
+            .entry((entry.model.clone(), entry.provider.clone()))
+            .or_default();
+        model_entry.add(&entry.tokens);
+
+        // Total
+        total.add(&entry.tokens);
+    }
+
+    let output_speeds = estimate_output_speeds(&entries, since);
+    let mut model_usages: Vec<ModelUsage> = by_model
+        .into_iter()
+        .map(|((model, provider), tokens)| {
+            let cost = calculate_cost_for(&model, provider.as_deref(), &tokens);
+            let thinking_cost_usd = thinking_cost_for(&model, provider.as_deref(), &tokens);
+            let display_name = get_model_display_name(&model);
+            let avg_output_tps = output_speeds.by_model.get(&model).copied();
+            ModelUsage {
+                model,
+                display_name,
+                tokens,
+                cost_usd: cost,
+                provider,
+                avg_output_tps,
+                thinking_cost_usd,
+                display_tokens: String::new(),
+                display_cost: String::new(),
+            }
+        })
+        .collect();
+
+    // Sort by total tokens (highest first)
+    model_usages.sort_by(|a, b| {
+        let a_total = a.tokens.input_tokens.saturating_add(a.tokens.output_tokens);
+        let b_total = b.tokens.input_tokens.saturating_add(b.tokens.output_tokens);
+        b_total.cmp(&a_total)
+    });
+
+    let total_cost: f64 = model_usages.iter().map(|m| m.cost_usd).sum();
+
+    let productivity = compute_productivity(&entries, since);
+    let off_hours_percent = compute_off_hours_percent(&entries, since);
+    let active_sessions = build_active_sessions(entries);
+
+    UsageStats {
+        total_tokens: total,
+        total_cost_usd: total_cost,
+        by_model: model_usages,
+        session_count: message_count,
+        last_updated: clamp_to_now(latest_timestamp, now),
+        quota,
+        active_sessions,
+        daily_activity,
+        weekly_usage,
+        peer_comparison: None,
+        productivity,
+        off_hours_percent,
+        configured_model: get_configured_model(),
+        plan_hint: get_plan_hint(),
+        goals: Vec::new(),
+        billing_cycle: None,
+        org_analytics: None,
+        org_utilization: None,
+        org_summary: None,
+        truncated: false,
+        degraded: Vec::new(),
+        by_user: Vec::new(),
+        display_last_updated: String::new(),
+        display_total_tokens: String::new(),
+        display_total_cost: String::new(),
+    }
+}
+
+/// Summarize a local calendar day: tokens, cost, busiest project and peak 5hr quota usage
+pub fn compute_daily_digest(date: NaiveDate) -> DailyDigest {
+    let day_start = local_day_start(date);
+    let day_end = local_day_start(date + chrono::Duration::days(1));
+    let window = chrono::Duration::hours(QUOTA_WINDOW_HOURS);
+
+    // Files modified since the day began, plus a lead-in for windows that started the day before
+    let hours_back = (Utc::now() - day_start).num_hours().max(0) + 6;
+    let files = collect_jsonl_files(&get_claude_data_dirs(), Some(hours_back));
+
+    let mut total_tokens: u64 = 0;
+    let mut cost_usd: f64 = 0.0;
+    let mut project_costs: HashMap<String, f64> = HashMap::new();
+
+    for file in &files {
+        let entries = match parse_usage_from_file(file) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        for entry in entries {
+            let ts = match DateTime::parse_from_rfc3339(&entry.timestamp) {
+                Ok(ts) => ts.with_timezone(&Utc),
+                Err(_) => continue,
+            };
+            if ts < day_start || ts >= day_end {
+                continue;
+            }
+
+            let entry_cost = entry.cost();
+            total_tokens = total_tokens.saturating_add(entry.tokens.total());
+            cost_usd += entry_cost;
+
+            let project = project_name(&entry.cwd);
+            if !project.is_empty() {
+                *project_costs.entry(project).or_insert(0.0) += entry_cost;
+            }
+        }
+    }
+
+    let (top_project, top_project_cost_usd) = project_costs
+        .into_iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(project, cost)| (Some(project), cost))
+        .unwrap_or((None, 0.0));
+
+    // Highest weighted total of any quota block that overlapped the day
+    let prompts = collect_weighted_prompts(&files, day_start - window);
+    let prompt_count = prompts
+        .iter()
+        .filter(|(ts, _)| *ts >= day_start && *ts < day_end)
+        .count() as u32;
+    let peak_weighted = reconstruct_quota_blocks(&prompts)
+        .iter()
+        .filter(|block| block.end > day_start && block.start < day_end)
+        .map(|block| block.weighted)
+        .fold(0.0, f64::max);
+    let peak_quota_percent =
+        (peak_weighted / ESTIMATED_WINDOW_LIMIT as f64 * 100.0).min(100.0);
+
+    DailyDigest {
+        date: date.format("%Y-%m-%d").to_string(),
+        total_tokens,
+        cost_usd,
+        prompt_count,
+        top_project,
+        top_project_cost_usd,
+        peak_quota_percent,
+    }
+}
+
+/// Compare `today` (so far) with the day before: tokens, cost, prompts, sessions started
+/// and projects touched today that weren't touched yesterday
+pub fn compute_daily_delta(today: NaiveDate) -> DailyDelta {
+    let yesterday = today - chrono::Duration::days(1);
+    let days = [yesterday, today];
+    let bounds = days.map(|date| {
+        (
+            local_day_start(date),
+            local_day_start(date + chrono::Duration::days(1)),
+        )
+    });
+    let day_of = |ts: DateTime<Utc>| {
+        bounds
+            .iter()
+            .position(|(start, end)| ts >= *start && ts < *end)
+    };
+
+    let hours_back = (Utc::now() - bounds[0].0).num_hours().max(0) + 1;
+    let files = collect_jsonl_files(&get_claude_data_dirs(), Some(hours_back));
+
+    let mut totals = days.map(|date| DayTotals {
+        date: date.format("%Y-%m-%d").to_string(),
+        ..Default::default()
+    });
+    let mut projects: [HashSet<String>; 2] = Default::default();
+    // session_id -> first entry seen, across every parsed journal
+    let mut session_starts: HashMap<String, DateTime<Utc>> = HashMap::new();
+
+    for file in &files {
+        let entries = match parse_usage_from_file(file) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        for entry in entries {
+            let ts = match DateTime::parse_from_rfc3339(&entry.timestamp) {
+                Ok(ts) => ts.with_timezone(&Utc),
+                Err(_) => continue,
+            };
+            if !entry.session_id.is_empty() {
+                let start = session_starts.entry(entry.session_id.clone()).or_insert(ts);
+                *start = (*start).min(ts);
+            }
+            let day = match day_of(ts) {
+                Some(day) => day,
+                None => continue,
+            };
+            totals[day].total_tokens = totals[day]
+                .total_tokens
+                .saturating_add(entry.tokens.total());
+            totals[day].cost_usd += entry.cost();
+            let project = project_name(&entry.cwd);
+            if !project.is_empty() {
+                projects[day].insert(project);
+            }
+        }
+    }
+
+    for start in session_starts.values() {
+        if let Some(day) = day_of(*start) {
+            totals[day].sessions_started += 1;
+        }
+    }
+    for (ts, _) in collect_weighted_prompts(&files, bounds[0].0) {
+        if let Some(day) = day_of(ts) {
+            totals[day].prompt_count += 1;
+        }
+    }
+
+    let mut new_projects: Vec<String> = projects[1].difference(&projects[0]).cloned().collect();
+    new_projects.sort();
+    for (day, set) in projects.into_iter().enumerate() {
+        totals[day].projects = set.into_iter().collect();
+        totals[day].projects.sort();
+    }
+    let [yesterday, today] = totals;
+
+    let cost_delta_usd = today.cost_usd - yesterday.cost_usd;
+    let prompts_delta = today.prompt_count as i64 - yesterday.prompt_count as i64;
+    let mut parts = vec![
+        format!(
+            "{}${:.2}",
+            if cost_delta_usd < 0.0 { "-" } else { "+" },
+            cost_delta_usd.abs()
+        ),
+        format!("{prompts_delta:+} prompts"),
+    ];
+    if !new_projects.is_empty() {
+        let noun = if new_projects.len() == 1 {
+            "project"
+        } else {
+            "projects"
+        };
+        parts.push(format!("{} new {noun}", new_projects.len()));
+    }
+
+    DailyDelta {
+        tokens_delta: today.total_tokens as i64 - yesterday.total_tokens as i64,
+        cost_delta_usd,
+        prompts_delta,
+        sessions_started_delta: today.sessions_started as i64 - yesterday.sessions_started as i64,
+        new_projects,
+        summary: parts.join(", "),
+        today,
+        yesterday,
+    }
+}
+
+/// Periods accepted by `compare_usage`, besides `YYYY-MM-DD..YYYY-MM-DD` ranges
+pub const COMPARE_PERIODS: &[&str] = &[
+    "today",
+    "yesterday",
+    "this_week",
+    "last_week",
+    "this_month",
+    "last_month",
+    "week",
+    "month",
+];
+
+/// Time range `[start, end)` of a comparison period, in local calendar terms: `this_week`
+/// starts on the configured week start, `this_month` on the billing anchor day (the 1st
+/// without one), `week` is the last 7 days, `month` follows the "month" period and
+/// `YYYY-MM-DD..YYYY-MM-DD` covers both dates
+pub fn comparison_range(
+    period: &str,
+    now: DateTime<Utc>,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let today = now.with_timezone(&Local).date_naive();
+    let day = chrono::Duration::days(1);
+    let month_start = billing_cycle_start(today);
+    let range = match period {
+        "today" => (local_day_start(today), now),
+        "yesterday" => (local_day_start(today - day), local_day_start(today)),
+        "this_week" => (local_day_start(week_start(today)), now),
+        "last_week" => {
+            let this_week = week_start(today);
+            (
+                local_day_start(this_week - chrono::Duration::days(7)),
+                local_day_start(this_week),
+            )
+        }
+        "this_month" => (local_day_start(month_start), now),
+        "last_month" => {
+            let last_month = billing_cycle_start(month_start - day);
+            (local_day_start(last_month), local_day_start(month_start))
+        }
+        "week" => (now - chrono::Duration::days(7), now),
+        "month" if billing_anchor_day().is_some() => (local_day_start(month_start), now),
+        "month" => (now - chrono::Duration::days(30), now),
+        _ => {
+            let parse = |date: &str| NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok();
+            let (from, to) = period
+                .split_once("..")
+                .and_then(|(from, to)| Some((parse(from)?, parse(to)?)))
+                .ok_or_else(|| {
+                    format!(
+                        "Unknown period '{period}' (expected one of {} or YYYY-MM-DD..YYYY-MM-DD)",
+                        COMPARE_PERIODS.join(", ")
+                    )
+                })?;
+            if to < from {
+                return Err(format!("Period '{period}' ends before it starts"));
+            }
+            (local_day_start(from), local_day_start(to + day))
+        }
+    };
+    Ok(range)
+}
+
+/// Change from `baseline` to `value` in percent; None without a baseline
+fn change_percent(value: f64, baseline: f64) -> Option<f64> {
+    (baseline > 0.0).then(|| (value - baseline) / baseline * 100.0)
+}
+
+/// Per-key (model or project) tokens and cost in each of the two periods
+type PeriodBreakdown = HashMap<String, [(u64, f64); 2]>;
+
+fn usage_changes(
+    breakdown: PeriodBreakdown,
+    display_name: impl Fn(&str) -> String,
+) -> Vec<UsageChange> {
+    let mut changes: Vec<UsageChange> = breakdown
+        .into_iter()
+        .map(
+            |(key, [(tokens_a, cost_a_usd), (tokens_b, cost_b_usd)])| UsageChange {
+                display_name: display_name(&key),
+                key,
+                tokens_a,
+                tokens_b,
+                cost_a_usd,
+                cost_b_usd,
+                tokens_change_percent: change_percent(tokens_a as f64, tokens_b as f64),
+                cost_change_percent: change_percent(cost_a_usd, cost_b_usd),
+            },
+        )
+        .collect();
+    changes.sort_by(|a, b| {
+        let cost = |c: &UsageChange| c.cost_a_usd.max(c.cost_b_usd);
+        cost(b).total_cmp(&cost(a)).then_with(|| a.key.cmp(&b.key))
+    });
+    changes
+}
+
+/// Compare usage in `period_a` against `period_b` (see `comparison_range`): totals, and
+/// tokens and cost per model and per project aligned across both, read in one pass so both
+/// sides are counted the same way
+pub fn compare_usage(period_a: &str, period_b: &str) -> Result<UsageComparison, String> {
+    let now = Utc::now();
+    let ranges = [
+        comparison_range(period_a, now)?,
+        comparison_range(period_b, now)?,
+    ];
+    let earliest = ranges[0].0.min(ranges[1].0);
+    let hours_back = (now - earliest).num_hours().max(0) + 1;
+    let files = collect_jsonl_files(&get_claude_data_dirs(), Some(hours_back));
+
+    let mut totals = [period_a, period_b].map(|period| PeriodTotals {
+        period: period.to_string(),
+        ..Default::default()
+    });
+    for (side, (start, end)) in totals.iter_mut().zip(&ranges) {
+        side.start = start.to_rfc3339();
+        side.end = end.to_rfc3339();
+        side.days = (*end - *start).num_seconds().max(0) as f64 / 86_400.0;
+    }
+    let mut by_model: PeriodBreakdown = HashMap::new();
+    let mut by_project: PeriodBreakdown = HashMap::new();
+    let sides_of = |ts: DateTime<Utc>| {
+        [0, 1]
+            .into_iter()
+            .filter(move |&i| ts >= ranges[i].0 && ts < ranges[i].1)
+    };
+
+    for file in &files {
+        let entries = match parse_usage_from_file(file) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        for entry in entries {
+            let ts = match DateTime::parse_from_rfc3339(&entry.timestamp) {
+                Ok(ts) => ts.with_timezone(&Utc),
+                Err(_) => continue,
+            };
+            let tokens = entry.tokens.total();
+            let cost = entry.cost();
+            for side in sides_of(ts) {
+                totals[side].total_tokens = totals[side].total_tokens.saturating_add(tokens);
+                totals[side].cost_usd += cost;
+                for (breakdown, key) in [
+                    (&mut by_model, entry.model.clone()),
+                    (&mut by_project, project_name(&entry.cwd)),
+                ] {
+                    let slot = &mut breakdown.entry(key).or_default()[side];
+                    slot.0 = slot.0.saturating_add(tokens);
+                    slot.1 += cost;
+                }
+            }
+        }
+    }
+    for (ts, _) in collect_weighted_prompts(&files, earliest) {
+        for side in sides_of(ts) {
+            totals[side].prompt_count += 1;
+        }
+    }
+    by_project.remove("");
+
+    let [period_a, period_b] = totals;
+    Ok(UsageComparison {
+        tokens_change_percent: change_percent(
+            period_a.total_tokens as f64,
+            period_b.total_tokens as f64,
+        ),
+        cost_change_percent: change_percent(period_a.cost_usd, period_b.cost_usd),
+        prompts_change_percent: change_percent(
+            period_a.prompt_count as f64,
+            period_b.prompt_count as f64,
+        ),
+        by_model: usage_changes(by_model, get_model_display_name),
+        by_project: usage_changes(by_project, |project| project.to_string()),
+        period_a,
+        period_b,
+    })
+}
+
+/// Compute weekly usage breakdown for the current week (from the first day of the week, see
+/// `week_start`)
+pub fn compute_weekly_usage(daily_activity: &[DailyActivity]) -> WeeklyUsage {
+    let today = Utc::now().date_naive();
+    let today_str = today.format("%Y-%m-%d").to_string();
+
+    let week_start = week_start(today);
+    let week_start_str = week_start.format("%Y-%m-%d").to_string();
+
+    // Build a map of date -> prompt_count from daily_activity
+    let activity_map: HashMap<String, u32> = daily_activity
+        .iter()
+        .map(|d| (d.date.clone(), d.prompt_count))
+        .collect();
+
+    // Generate 7 days of the week
+    let days: Vec<WeekDay> = (0..7)
+        .map(|i| {
+            let date = week_start + chrono::Duration::days(i);
+            let date_str = date.format("%Y-%m-%d").to_string();
+            let prompt_count = activity_map.get(&date_str).copied().unwrap_or(0);
+            let is_today = date_str == today_str;
+            let is_future = date > today;
+
+            WeekDay {
+                date: date_str,
+                day_name: date.format("%a").to_string(),
+                prompt_count,
+                is_today,
+                is_future,
+            }
+        })
+        .collect();
+
+    // Estimated weekly limit - calibrated based on observed Claude /usage
+    // Note: this is raw prompt count for chart display, not weighted
+    let estimated_weekly_limit: u32 = 1100; // ~3050 weighted / 2.75 avg weight
+
+    WeeklyUsage {
+        days,
+        week_start: week_start_str,
+        estimated_weekly_limit,
+    }
+}
+
+/// Project -> (cost, tokens) per week.
+type ProjectWeeks = HashMap<String, Vec<(f64, u64)>>;
+
+/// Weekly usage per project over the last `weeks` weeks (local time, starting on the first day
+/// of the week), the current week included: the first day of each week, and project ->
+/// (cost, tokens) per week. Excluded sessions are left out.
+fn project_week_usage(weeks: u32) -> (Vec<NaiveDate>, ProjectWeeks) {
+    let weeks = weeks.max(1);
+    let today = Local::now().date_naive();
+    let current_week = week_start(today);
+    let first_week = current_week - chrono::Duration::weeks(weeks as i64 - 1);
+    let week_starts: Vec<NaiveDate> = (0..weeks as i64)
+        .map(|i| first_week + chrono::Duration::weeks(i))
+        .collect();
+
+    let hours_back = (Utc::now() - local_day_start(first_week))
+        .num_hours()
+        .max(0)
+        + 1;
+    let files = collect_jsonl_files(&get_claude_data_dirs(), Some(hours_back));
+
+    let mut usage: HashMap<String, Vec<(f64, u64)>> = HashMap::new();
+    for file in &files {
+        let entries = match parse_usage_from_file(file) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        for entry in entries {
+            let date = match DateTime::parse_from_rfc3339(&entry.timestamp) {
+                Ok(ts) => ts.with_timezone(&Local).date_naive(),
+                Err(_) => continue,
+            };
+            if date < first_week {
+                continue;
+            }
+            let week = ((date - first_week).num_days() / 7) as usize;
+            if week >= week_starts.len() {
+                continue;
+            }
+
+            let project = project_name(&entry.cwd);
+            if project.is_empty() || is_session_excluded(&entry.session_id) {
+                continue;
+            }
+            let slot = &mut usage
+                .entry(project)
+                .or_insert_with(|| vec![(0.0, 0); week_starts.len()])[week];
+            slot.0 += entry.cost();
+            slot.1 = slot.1.saturating_add(entry.tokens.total());
+        }
+    }
+    (week_starts, usage)
+}
+
+fn week_labels(week_starts: &[NaiveDate]) -> Vec<String> {
+    week_starts
+        .iter()
+        .map(|week| week.format("%Y-%m-%d").to_string())
+        .collect()
+}
+
+/// Cost of each project in each of the last `weeks` weeks (local time, starting on the first
+/// day of the week), the current week included
+pub fn get_project_week_matrix(weeks: u32) -> ProjectWeekMatrix {
+    let (week_starts, usage) = project_week_usage(weeks);
+    let mut projects: Vec<ProjectWeekRow> = usage
+        .into_iter()
+        .map(|(project, weekly)| {
+            let costs_usd: Vec<f64> = weekly.iter().map(|(cost, _)| *cost).collect();
+            ProjectWeekRow {
+                project,
+                total_cost_usd: costs_usd.iter().sum(),
+                costs_usd,
+            }
+        })
+        .collect();
+    projects.sort_by(|a, b| b.total_cost_usd.total_cmp(&a.total_cost_usd));
+
+    ProjectWeekMatrix {
+        weeks: week_labels(&week_starts),
+        projects,
+    }
+}
+
+/// Cost and tokens per week over the last `weeks` weeks for the `top` projects with the
+/// highest total cost, the rest summed into an "other" series, for a stacked bar chart
+pub fn get_weekly_project_series(weeks: u32, top: usize) -> WeeklyProjectSeries {
+    let (week_starts, usage) = project_week_usage(weeks);
+    let mut series: Vec<ProjectSeries> = usage
+        .into_iter()
+        .map(|(project, weekly)| ProjectSeries {
+            project,
+            other: false,
+            projects: 1,
+            costs_usd: weekly.iter().map(|(cost, _)| *cost).collect(),
+            tokens: weekly.iter().map(|(_, tokens)| *tokens).collect(),
+            total_cost_usd: weekly.iter().map(|(cost, _)| cost).sum(),
+            total_tokens: weekly
+                .iter()
+                .fold(0u64, |sum, (_, tokens)| sum.saturating_add(*tokens)),
+        })
+        .collect();
+    series.sort_by(|a, b| {
+        b.total_cost_usd
+            .total_cmp(&a.total_cost_usd)
+            .then_with(|| a.project.cmp(&b.project))
+    });
+
+    let mut other = ProjectSeries {
+        project: "other".to_string(),
+        other: true,
+        projects: 0,
+        costs_usd: vec![0.0; week_starts.len()],
+        tokens: vec![0; week_starts.len()],
+        total_cost_usd: 0.0,
+        total_tokens: 0,
+    };
+    for rest in series.split_off(top.min(series.len())) {
+        for (week, (cost, tokens)) in rest.costs_usd.iter().zip(&rest.tokens).enumerate() {
+            other.costs_usd[week] += cost;
+            other.tokens[week] = other.tokens[week].saturating_add(*tokens);
+        }
+        other.total_cost_usd += rest.total_cost_usd;
+        other.total_tokens = other.total_tokens.saturating_add(rest.total_tokens);
+        other.projects += 1;
+    }
+    if other.projects > 0 {
+        series.push(other);
+    }
+
+    let total_cost_usd = (0..week_starts.len())
+        .map(|week| series.iter().map(|s| s.costs_usd[week]).sum())
+        .collect();
+    let total_tokens = (0..week_starts.len())
+        .map(|week| {
+            series
+                .iter()
+                .fold(0u64, |sum, s| sum.saturating_add(s.tokens[week]))
+        })
+        .collect();
+    WeeklyProjectSeries {
+        weeks: week_labels(&week_starts),
+        series,
+        total_cost_usd,
+        total_tokens,
+    }
+}
+
+/// Collect daily user prompt counts for the last `activity_days()` days (12 weeks unless
+/// low-memory mode is on)
+pub fn collect_daily_activity(files: &[PathBuf]) -> Vec<DailyActivity> {
+    let mut perf = PerfSpan::start("daily_activity");
+    perf.add_files(files.len());
+    let mut daily_counts: HashMap<String, u32> = HashMap::new();
+    let activity_start = Utc::now() - chrono::Duration::days(activity_days());
+
+    for path in files {
+        if is_excluded_journal(path) {
+            continue;
+        }
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => continue,
+        };
+        for line in journal_lines(BufReader::new(file)) {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // Parse once: get timestamp only if this is an actual user prompt
+            if let Some(ts_str) = parse_user_prompt_timestamp(&line) {
+                if let Ok(ts) = DateTime::parse_from_rfc3339(&ts_str) {
+                    if ts >= activity_start {
+                        let date = ts.format("%Y-%m-%d").to_string();
+                        *daily_counts.entry(date).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    // Convert to sorted vec of DailyActivity
+    let mut activities: Vec<DailyActivity> = daily_counts
+        .into_iter()
+        .map(|(date, prompt_count)| DailyActivity {
+            date,
+            prompt_count,
+            data_complete: true,
+        })
+        .collect();
+
+    activities.sort_by(|a, b| a.date.cmp(&b.date));
+    perf.add_entries(activities.iter().map(|day| day.prompt_count as usize).sum());
+    mark_activity_gaps(activities)
+}
+
+/// Gaps between active days with at least this many working days (not counting days off)
+/// are flagged as possibly missing journals
+pub const MIN_GAP_WORKING_DAYS: usize = 3;
+
+/// Add the working days of suspicious gaps to sorted daily activity, with no prompts and
+/// `data_complete: false`. A gap is a run of days without prompts bounded by active days on
+/// both sides that spans `MIN_GAP_WORKING_DAYS` working days; weekends and days off alone
+/// never make one, and weekends and days off inside a gap aren't flagged.
+pub fn mark_activity_gaps(activities: Vec<DailyActivity>) -> Vec<DailyActivity> {
+    let working_days = working_hours().days;
+    let is_working_day = |date: NaiveDate| {
+        working_days.contains(&date.weekday().number_from_monday()) && !is_day_off(date)
+    };
+    let parse = |day: &DailyActivity| NaiveDate::parse_from_str(&day.date, "%Y-%m-%d").ok();
+
+    let mut marked = Vec::with_capacity(activities.len());
+    let mut previous: Option<NaiveDate> = None;
+    for day in activities {
+        let date = parse(&day);
+        if let (Some(start), Some(end)) = (previous, date) {
+            let gap: Vec<NaiveDate> = start.iter_days().skip(1).take_while(|d| *d < end).collect();
+            if gap.iter().filter(|d| is_working_day(**d)).count() >= MIN_GAP_WORKING_DAYS {
+                marked.extend(gap.into_iter().filter(|d| is_working_day(*d)).map(|d| {
+                    DailyActivity {
+                        date: d.format("%Y-%m-%d").to_string(),
+                        prompt_count: 0,
+                        data_complete: false,
+                    }
+                }));
+            }
+        }
+        previous = date.or(previous);
+        marked.push(day);
+    }
+    marked
+}
+
+/// Compare the last 7 days against team medians (None when no median is set)
+pub fn compute_peer_comparison(baseline: &PeerBaseline) -> Option<PeerComparison> {
+    let week_start = Utc::now() - chrono::Duration::days(7);
+    let week_files = collect_jsonl_files(&get_claude_data_dirs(), Some(24 * 8));
+    let week_prompts = count_user_prompts_in_window(&week_files, 24 * 7) as f64;
+
+    let mut week_tokens: u64 = 0;
+    let mut week_cost: f64 = 0.0;
+    for file in &week_files {
+        let entries = match parse_usage_from_file(file) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        for entry in entries {
+            match DateTime::parse_from_rfc3339(&entry.timestamp) {
+                Ok(ts) if ts >= week_start => {}
+                _ => continue,
+            }
+            week_tokens = week_tokens.saturating_add(entry.tokens.total());
+            week_cost += entry.cost();
+        }
+    }
+
+    let candidates = [
+        ("weekly_prompts", week_prompts, baseline.weekly_prompts_median),
+        ("weekly_tokens", week_tokens as f64, baseline.weekly_tokens_median),
+        ("weekly_cost", week_cost, baseline.weekly_cost_median),
+    ];
+    let metrics: Vec<PeerMetric> = candidates
+        .iter()
+        .filter_map(|&(name, yours, median)| {
+            let team_median = median.filter(|m| *m > 0.0)?;
+            let ratio = yours / team_median;
+            Some(PeerMetric {
+                name: name.to_string(),
+                yours,
+                team_median,
+                ratio,
+                summary: format!("{:.1}× team median", ratio),
+            })
+        })
+        .collect();
+
+    if metrics.is_empty() {
+        None
+    } else {
+        Some(PeerComparison { metrics })
+    }
+}
+
+/// The current billing cycle with the cost and tokens of `stats` (the "month" period) projected
+/// to its end at the pace so far; None without a billing anchor day. Days off (see
+/// `CoreSettings::days_off`) count neither toward the elapsed share nor the pace.
+pub fn billing_cycle_forecast(stats: &UsageStats, now: DateTime<Utc>) -> Option<BillingCycle> {
+    billing_anchor_day()?;
+    let today = now.with_timezone(&Local).date_naive();
+    let start = billing_cycle_start(today);
+    let renews_on = next_billing_cycle_start(start);
+    let days: Vec<NaiveDate> = start
+        .iter_days()
+        .take_while(|day| *day < renews_on)
+        .collect();
+    let (cycle_hours, elapsed_hours) = counted_hours_in(&Local, &days, now);
+
+    let tokens = &stats.total_tokens;
+    let total_tokens = tokens.total();
+    // Before the first hour counts the usage so far is all there is to go on
+    let scale = if elapsed_hours >= 1.0 {
+        cycle_hours / elapsed_hours
+    } else {
+        1.0
+    };
+    Some(BillingCycle {
+        start: start.format("%Y-%m-%d").to_string(),
+        renews_on: renews_on.format("%Y-%m-%d").to_string(),
+        elapsed_percent: (elapsed_hours / cycle_hours * 100.0).clamp(0.0, 100.0),
+        days_left: (renews_on - today).num_days().max(0) as u32,
+        cost_usd: stats.total_cost_usd,
+        projected_cost_usd: stats.total_cost_usd * scale,
+        projected_tokens: (total_tokens as f64 * scale) as u64,
+    })
+}
+
+/// Usage stats for a period ("current-window", "today", "week", "month" or "all") from the
+/// local journals. `peer_comparison` is left unset; see `compute_peer_comparison`.
+pub fn get_current_usage(period: &str) -> Result<UsageStats, String> {
+    get_current_usage_with(period, &CancellationToken::new())
+}
+
+/// Like `get_current_usage`, but stops reading journals once `cancel` fires and returns
+/// the partial result with `truncated` set. Newest files are read first, so a truncated
+/// result still covers the most recent activity.
+pub fn get_current_usage_with(
+    period: &str,
+    cancel: &CancellationToken,
+) -> Result<UsageStats, String> {
+    let mut stats = get_usage_multi_with(&[period.to_string()], cancel)?;
+    stats
+        .remove(period)
+        .ok_or_else(|| format!("No stats computed for {period}"))
+}
+
+/// Journal age filter for a period's files, in hours (with a buffer); None reads all files
+pub fn period_file_hours(period: &str) -> Option<i64> {
+    match period {
+        "current-window" => Some(QUOTA_WINDOW_HOURS + 1),
+        "today" => Some(25),                // 24hr + 1hr buffer
+        "week" => Some(24 * 8),             // 7 days + 1 day buffer
+        "month" => Some(24 * 32),           // 30 days (or a 31-day cycle) + buffer
+        _ if low_memory() => Some(24 * 32), // "all" is capped to a month
+        _ => None,                          // "all" - no filter
+    }
+}
+
+/// Start of a period's totals; "current-window" starts with the active 5hr block, and
+/// counts nothing while no block is running
+pub fn period_since(period: &str, quota_block: Option<&QuotaBlock>) -> Option<DateTime<Utc>> {
+    match period {
+        "current-window" => Some(quota_block.map_or_else(Utc::now, |block| block.start)),
+        "today" => Some(Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc()),
+        "week" => Some(Utc::now() - chrono::Duration::days(7)),
+        "month" if billing_anchor_day().is_some() => Some(local_day_start(month_period_start(
+            Utc::now().with_timezone(&Local).date_naive(),
+        ))),
+        "month" => Some(Utc::now() - chrono::Duration::days(30)),
+        _ if low_memory() => Some(Utc::now() - chrono::Duration::days(30)),
+        _ => None, // "all"
+    }
+}
+
+/// Usage stats for several periods at once, keyed by period. Journals are read and parsed
+/// once, for the longest period, and the quota windows and activity are shared, so this
+/// costs little more than the longest period alone.
+pub fn get_usage_multi(periods: &[String]) -> Result<HashMap<String, UsageStats>, String> {
+    get_usage_multi_with(periods, &CancellationToken::new())
+}
+
+/// `get_usage_multi` that stops reading journals once `cancel` fires, see
+/// `get_current_usage_with`
+pub fn get_usage_multi_with(
+    periods: &[String],
+    cancel: &CancellationToken,
+) -> Result<HashMap<String, UsageStats>, String> {
+    let mut perf = PerfSpan::start("get_usage");
+    begin_parse_health();
+    let data_dirs = get_claude_data_dirs();
+    // Unreadable sources are reported with the partial totals rather than failing them
+    let mut degraded = check_data_dirs();
+    // OTLP usage needs no journals; the quota windows are just empty without them
+    let from_otlp = usage_source() == UsageSource::Otlp;
+    if data_dirs.is_empty() && !from_otlp {
+        return Err(match degraded.first() {
+            Some(issue) => format!(
+                "No readable Claude data directories: {} ({})",
+                issue.path, issue.detail
+            ),
+            None => "No Claude data directories found".to_string(),
+        });
+    }
+
+    // Quota windows only look at recent files, so they are computed before the
+    // potentially unbounded period scan. They count the running account's prompts only:
+    // other accounts read with `all_users` have their own limits, reported in `by_user`.
+    // 5hr block: reconstructed from the last day of prompt history
+    let quota_dirs = own_data_dirs();
+    let quota_block = current_quota_block(&quota_block_files(&quota_dirs));
+
+    // Week window: files modified in last 8 days
+    let week_files = collect_jsonl_files(&quota_dirs, Some(24 * 8));
+    let (week_weighted, week_to_date_weighted) = count_week_weighted_usage(&week_files);
+    // Custom windows: scanned once here rather than for every period
+    let custom_windows = custom_window_statuses(&quota_windows(), Utc::now());
+    let quota = build_quota_info(
+        quota_block.as_ref(),
+        week_weighted,
+        week_to_date_weighted,
+        custom_windows,
+    );
+
+    // Daily activity: files modified in the activity span plus a 1 day buffer
+    let mut truncated = cancel.is_cancelled();
+    let daily_activity = if truncated {
+        Vec::new()
+    } else {
+        let activity_files = collect_jsonl_files(&data_dirs, Some(24 * (activity_days() + 1)));
+        collect_daily_activity(&activity_files)
+    };
+
+    // Compute weekly usage breakdown from daily activity
+    let weekly_usage = compute_weekly_usage(&daily_activity);
+
+    // Files of the longest period cover every shorter one
+    let file_hours = periods
+        .iter()
+        .map(|period| period_file_hours(period))
+        .max_by_key(|hours| hours.unwrap_or(i64::MAX))
+        .flatten();
+
+    // Collect files filtered by modification time for token usage, newest first
+    let (mut usage_files, dir_issues) = if from_otlp {
+        (Vec::new(), Vec::new())
+    } else {
+        collect_jsonl_files_checked(&data_dirs, file_hours)
+    };
+    degraded.extend(dir_issues);
+    usage_files.sort_by_cached_key(|file| {
+        std::cmp::Reverse(file.metadata().and_then(|m| m.modified()).ok())
+    });
+    // Entries by file, in the order read
+    let mut parsed: Vec<(&PathBuf, Vec<ParsedEntry>)> = Vec::new();
+
+    for file in &usage_files {
+        if cancel.is_cancelled() {
+            truncated = true;
+            break;
+        }
+        match parse_usage_from_file(file) {
+            Ok(entries) => parsed.push((file, entries)),
+            Err(e) => degraded.push(source_issue("unreadable_file", file, e)),
+        }
+    }
+
+    perf.add_files(parsed.len());
+    perf.add_entries(parsed.iter().map(|(_, entries)| entries.len()).sum());
+    let histories = load_session_histories();
+    let owners = session_owners(
+        parsed
+            .iter()
+            .map(|(file, entries)| (file.as_path(), entries.as_slice())),
+    );
+    let user_quotas = quota_by_user(&data_dirs);
+    // Journals are quarantined as they are parsed; OTLP events once here, so each is counted
+    // once in the parse health rather than once per period
+    let otlp = if from_otlp {
+        let mut entries = otlp_entries();
+        quarantine_future_entries(&mut entries, Utc::now());
+        entries
+    } else {
+        Vec::new()
+    };
+
+    let mut results = HashMap::new();
+    for period in periods {
+        if results.contains_key(period) {
+            continue;
+        }
+        // Shorter periods only get the files their own age filter selects
+        let period_files: Option<HashSet<PathBuf>> = match period_file_hours(period) {
+            Some(hours) if file_hours != Some(hours) => Some(
+                collect_jsonl_files(&data_dirs, Some(hours))
+                    .into_iter()
+                    .collect(),
+            ),
+            _ => None,
+        };
+        let mut entries: Vec<ParsedEntry> = if from_otlp {
+            otlp.clone()
+        } else {
+            parsed
+                .iter()
+                .filter(|(file, _)| {
+                    period_files
+                        .as_ref()
+                        .is_none_or(|files| files.contains(*file))
+                })
+                .flat_map(|(_, entries)| entries.iter().cloned())
+                .collect()
+        };
+        fill_missing_directories(&mut entries, &histories);
+        let since = period_since(period, quota_block.as_ref());
+        let mut by_user = usage_by_user(&entries, &owners, since);
+        for usage in &mut by_user {
+            if let Some(&(window, week)) = user_quotas.get(&usage.user) {
+                usage.usage_percent = window;
+                usage.week_usage_percent = week;
+            }
+        }
+        let mut stats = aggregate_usage(
+            entries,
+            since,
+            quota.clone(),
+            daily_activity.clone(),
+            weekly_usage.clone(),
+        );
+        stats.truncated = truncated;
+        stats.degraded = degraded.clone();
+        stats.by_user = by_user;
+        // Active sessions carry an id prefix
+        for session in &mut stats.active_sessions {
+            session.commands_executed = histories
+                .iter()
+                .find(|(id, _)| id.starts_with(&session.session_id))
+                .map_or(0, |(_, history)| history.commands);
+            session.user = owners
+                .iter()
+                .find(|(id, _)| id.starts_with(&session.session_id))
+                .map(|(_, user)| user.clone());
+        }
+        results.insert(period.clone(), stats);
+    }
+    finish_parse_health();
+    Ok(results)
+}
@@ -0,0 +1,355 @@
+//! Persistent cache of parsed journals: the `ParsedEntry` values of each journal, stored as
+//! zstd-compressed postcard and keyed by the journal's size and mtime, so a cold start on a
+//! large history skips JSON parsing. Disabled until `CoreSettings::cache_dir` is set.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, Metadata};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::access::ensure_outside_data_dirs;
+use crate::parser::{max_line_bytes, parse_journal, ParsedEntry};
+use crate::sources::{collect_jsonl_files, get_claude_data_dirs};
+
+/// Bump when `ParsedEntry`, the parsing rules or the encoding change, invalidating every
+/// cache file
+const CACHE_VERSION: u32 = 3;
+
+/// Journals modified more recently than this are still being written; caching them would
+/// only rewrite the cache file on every refresh
+const MIN_AGE: Duration = Duration::from_secs(300);
+
+const ZSTD_LEVEL: i32 = 3;
+
+const EXTENSION: &str = "bin.zst";
+
+/// Decoded bytes read to find a cache file's journal when pruning; paths are far shorter
+const HEADER_BYTES: u64 = 8192;
+
+static CACHE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    source: String,
+    size: u64,
+    mtime_nanos: u128,
+    /// Lines over the limit are skipped, so entries depend on it
+    max_line_bytes: u64,
+    entries: Vec<ParsedEntry>,
+}
+
+/// The leading fields of a `CacheFile`, decoded without its entries
+#[derive(Deserialize)]
+struct CacheHeader {
+    version: u32,
+    source: String,
+}
+
+/// Decode a cache file; None if it's unreadable or corrupt
+fn read_cache_file(path: &Path) -> Option<CacheFile> {
+    let file = File::open(path).ok()?;
+    let mut bytes = Vec::new();
+    zstd::Decoder::new(BufReader::new(file))
+        .ok()?
+        .read_to_end(&mut bytes)
+        .ok()?;
+    postcard::from_bytes(&bytes).ok()
+}
+
+/// Where cache files are kept; `None` disables the cache
+pub(crate) fn set_cache_dir(dir: Option<PathBuf>) {
+    if let Ok(mut current) = CACHE_DIR.write() {
+        *current = dir;
+    }
+}
+
+fn cache_dir() -> Option<PathBuf> {
+    CACHE_DIR.read().ok()?.clone()
+}
+
+/// Cache file for a journal, named after a hash of its path
+fn cache_path(dir: &Path, source: &Path) -> PathBuf {
+    // FNV-1a, stable across runs unlike `DefaultHasher`
+    let hash = source
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    dir.join(format!("{hash:016x}.{EXTENSION}"))
+}
+
+fn mtime_nanos(metadata: &Metadata) -> Option<u128> {
+    let modified = metadata.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+/// Cached entries of `source` if the cache is enabled and matches its current `metadata`
+pub(crate) fn load(source: &Path, metadata: &Metadata) -> Option<Vec<ParsedEntry>> {
+    let cached = read_cache_file(&cache_path(&cache_dir()?, source))?;
+    let fresh = cached.version == CACHE_VERSION
+        && cached.source == source.to_string_lossy()
+        && cached.size == metadata.len()
+        && Some(cached.mtime_nanos) == mtime_nanos(metadata)
+        && cached.max_line_bytes == max_line_bytes() as u64;
+    fresh.then_some(cached.entries)
+}
+
+/// Cache the entries parsed from `source`, as it was when `metadata` was read. Journals
+/// still being written are skipped; failures only cost a re-parse next time.
+pub(crate) fn store(source: &Path, metadata: &Metadata, entries: &[ParsedEntry]) {
+    let dir = match cache_dir() {
+        Some(dir) => dir,
+        None => return,
+    };
+    let mtime = match metadata.modified() {
+        Ok(mtime) => mtime,
+        Err(_) => return,
+    };
+    let settled = SystemTime::now()
+        .duration_since(mtime)
+        .is_ok_and(|age| age >= MIN_AGE);
+    if !settled {
+        return;
+    }
+    let path = cache_path(&dir, source);
+    if ensure_outside_data_dirs(&path).is_err() || fs::create_dir_all(&dir).is_err() {
+        return;
+    }
+    let cached = CacheFile {
+        version: CACHE_VERSION,
+        source: source.to_string_lossy().to_string(),
+        size: metadata.len(),
+        mtime_nanos: match mtime_nanos(metadata) {
+            Some(nanos) => nanos,
+            None => return,
+        },
+        max_line_bytes: max_line_bytes() as u64,
+        entries: entries.to_vec(),
+    };
+
+    // Write then rename so a concurrent reader never sees a partial file
+    let tmp = path.with_extension("tmp");
+    let written = File::create(&tmp).ok().and_then(|file| {
+        let bytes = postcard::to_stdvec(&cached).ok()?;
+        let mut encoder = zstd::Encoder::new(BufWriter::new(file), ZSTD_LEVEL).ok()?;
+        encoder.write_all(&bytes).ok()?;
+        // Flushes the buffer, reporting errors that dropping it would hide
+        encoder.finish().ok()?.into_inner().ok()
+    });
+    if written.is_none() || fs::rename(&tmp, &path).is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+}
+
+/// A cache file whose entries differ from a fresh parse of its journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheDrift {
+    pub source: String,
+    pub reason: String, // "corrupt" (undecodable) or "mismatch"
+    pub cached_entries: usize,
+    pub parsed_entries: usize,
+    pub cached_tokens: u64,
+    pub parsed_tokens: u64,
+}
+
+/// Outcome of `verify_cache`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheVerification {
+    pub cache_files: usize,
+    /// Sampled cache files re-parsed and compared
+    pub checked: usize,
+    pub matched: usize,
+    /// Journals changed since they were cached; the next read refreshes them anyway
+    pub stale: usize,
+    pub drifted: Vec<CacheDrift>,
+    /// Cache files whose journal no longer exists
+    pub orphaned: usize,
+    /// Settled journals without a cache file
+    pub uncached_journals: usize,
+    /// Cache files rewritten or removed, with `repair`
+    pub rebuilt: usize,
+}
+
+fn entry_tokens(entries: &[ParsedEntry]) -> u64 {
+    entries
+        .iter()
+        .fold(0u64, |sum, e| sum.saturating_add(e.tokens.total()))
+}
+
+/// Cross-check the cache against its journals: `sample` evenly spread cache files (0 for
+/// all) are compared with a fresh parse of their journal, and every settled journal is
+/// checked for a cache file. With `repair`, drifted files are rewritten from the fresh
+/// parse, orphans removed and missing files written.
+pub fn verify_cache(sample: usize, repair: bool) -> Result<CacheVerification, String> {
+    let dir = cache_dir().ok_or_else(|| "The entry cache is disabled".to_string())?;
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.to_string_lossy().ends_with(EXTENSION))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    let mut report = CacheVerification {
+        cache_files: files.len(),
+        ..Default::default()
+    };
+    let step = match sample {
+        0 => 1,
+        sample => files.len().div_ceil(sample).max(1),
+    };
+
+    for path in files.iter().step_by(step) {
+        report.checked += 1;
+        let cached = match read_cache_file(path) {
+            Some(cached) if cached.version == CACHE_VERSION => cached,
+            // Written by another cache version; replaced on the next read
+            Some(_) => {
+                report.stale += 1;
+                continue;
+            }
+            None => {
+                report.drifted.push(CacheDrift {
+                    source: path.display().to_string(),
+                    reason: "corrupt".to_string(),
+                    cached_entries: 0,
+                    parsed_entries: 0,
+                    cached_tokens: 0,
+                    parsed_tokens: 0,
+                });
+                if repair && fs::remove_file(path).is_ok() {
+                    report.rebuilt += 1;
+                }
+                continue;
+            }
+        };
+
+        let source = PathBuf::from(&cached.source);
+        let file = match File::open(&source) {
+            Ok(file) => file,
+            Err(_) => {
+                report.orphaned += 1;
+                if repair && fs::remove_file(path).is_ok() {
+                    report.rebuilt += 1;
+                }
+                continue;
+            }
+        };
+        let metadata = match file.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        if load(&source, &metadata).is_none() {
+            report.stale += 1;
+            continue;
+        }
+        let parsed = parse_journal(file, Some(&metadata));
+        let same = postcard::to_stdvec(&parsed).ok() == postcard::to_stdvec(&cached.entries).ok();
+        if same {
+            report.matched += 1;
+            continue;
+        }
+        report.drifted.push(CacheDrift {
+            source: cached.source.clone(),
+            reason: "mismatch".to_string(),
+            cached_entries: cached.entries.len(),
+            parsed_entries: parsed.len(),
+            cached_tokens: entry_tokens(&cached.entries),
+            parsed_tokens: entry_tokens(&parsed),
+        });
+        if repair {
+            store(&source, &metadata, &parsed);
+            report.rebuilt += 1;
+        }
+    }
+
+    // Journals old enough to be cached but never written (missed ingestion)
+    for journal in collect_jsonl_files(&get_claude_data_dirs(), None) {
+        let settled = fs::metadata(&journal)
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|mtime| {
+                SystemTime::now()
+                    .duration_since(mtime)
+                    .is_ok_and(|age| age >= MIN_AGE)
+            });
+        if !settled || cache_path(&dir, &journal).exists() {
+            continue;
+        }
+        report.uncached_journals += 1;
+        if repair {
+            if let Ok(file) = File::open(&journal) {
+                if let Ok(metadata) = file.metadata() {
+                    let entries = parse_journal(file, Some(&metadata));
+                    store(&journal, &metadata, &entries);
+                    report.rebuilt += 1;
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Delete the cache files of journals that no longer exist and those written by another
+/// cache version, returning how many were removed. Files that can't be decoded are left to
+/// `verify_cache`.
+pub fn prune_cache() -> usize {
+    let Some(dir) = cache_dir() else {
+        return 0;
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.to_string_lossy().ends_with(EXTENSION) {
+            continue;
+        }
+        let header = File::open(&path).ok().and_then(|file| {
+            let mut bytes = Vec::new();
+            zstd::Decoder::new(BufReader::new(file))
+                .ok()?
+                .take(HEADER_BYTES)
+                .read_to_end(&mut bytes)
+                .ok()?;
+            let (header, _) = postcard::take_from_bytes::<CacheHeader>(&bytes).ok()?;
+            Some(header)
+        });
+        let Some(header) = header else {
+            continue;
+        };
+        let orphaned = header.version != CACHE_VERSION || !Path::new(&header.source).exists();
+        if orphaned && fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// Delete every cache file, returning how many were removed
+pub fn clear_cache() -> Result<usize, String> {
+    let dir = match cache_dir() {
+        Some(dir) => dir,
+        None => return Ok(0),
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(0),
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.to_string_lossy().ends_with(EXTENSION) {
+            fs::remove_file(&path)
+                .map_err(|e| format!("Failed to remove {}: {e}", path.display()))?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
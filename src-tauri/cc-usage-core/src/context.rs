@@ -0,0 +1,104 @@
+//! Context growth of a session: the context size after each assistant message, the
+//! compactions along the way, and when Claude Code's auto-compaction will likely hit.
+
+use chrono::DateTime;
+
+use crate::parser::{parse_usage_from_file, ParsedEntry};
+use crate::pricing::{calculate_context_remaining, get_model_context_limit};
+use crate::sources::resolve_session_id;
+use crate::types::{ContextPoint, SessionContextHistory};
+
+/// Share of the context limit at which Claude Code compacts automatically (approximate)
+pub const AUTO_COMPACT_PERCENT: f64 = 92.0;
+
+/// A context smaller than this share of the previous message's is taken as a compaction
+const COMPACTION_DROP_RATIO: f64 = 0.5;
+
+/// Journals of active sessions were written within this many hours
+const ACTIVE_SESSION_HOURS: i64 = 25;
+
+/// Context history of a session from `entries`, which may include other sessions; `session_id`
+/// is the full id or a prefix of it
+pub fn context_history(session_id: &str, entries: &[ParsedEntry]) -> SessionContextHistory {
+    let prefix = session_id.to_lowercase();
+    let mut messages: Vec<&ParsedEntry> = entries
+        .iter()
+        .filter(|entry| !entry.is_sidechain && entry.session_id.to_lowercase().starts_with(&prefix))
+        .collect();
+    messages.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+    let mut points: Vec<ContextPoint> = Vec::with_capacity(messages.len());
+    for entry in &messages {
+        let context_tokens = entry.tokens.context_tokens();
+        let after_compaction = points.last().is_some_and(|previous| {
+            (context_tokens as f64) < previous.context_tokens as f64 * COMPACTION_DROP_RATIO
+        });
+        points.push(ContextPoint {
+            timestamp: entry.timestamp.clone(),
+            context_tokens,
+            used_percent: 100.0 - calculate_context_remaining(context_tokens, &entry.model),
+            model: entry.model.clone(),
+            after_compaction,
+        });
+    }
+
+    let context_limit = points
+        .last()
+        .map_or(0, |point| get_model_context_limit(&point.model));
+    let mut history = SessionContextHistory {
+        session_id: messages
+            .last()
+            .map_or(session_id.to_string(), |entry| entry.session_id.clone()),
+        context_limit,
+        compactions: points.iter().filter(|point| point.after_compaction).count() as u32,
+        ..SessionContextHistory::default()
+    };
+
+    // Forecast from the messages since the last compaction
+    let segment_start = points
+        .iter()
+        .rposition(|point| point.after_compaction)
+        .unwrap_or(0);
+    let segment = &points[segment_start..];
+    if let (Some(first), Some(last)) = (segment.first(), segment.last()) {
+        if segment.len() >= 2 {
+            let steps = (segment.len() - 1) as f64;
+            let per_message = (last.context_tokens as f64 - first.context_tokens as f64) / steps;
+            history.tokens_per_message = Some(per_message);
+            if per_message > 0.0 {
+                let threshold = context_limit as f64 * AUTO_COMPACT_PERCENT / 100.0;
+                let remaining = (threshold - last.context_tokens as f64).max(0.0);
+                let messages_left = (remaining / per_message).ceil() as u32;
+                history.messages_until_compaction = Some(messages_left);
+                if let (Ok(start), Ok(end)) = (
+                    DateTime::parse_from_rfc3339(&first.timestamp),
+                    DateTime::parse_from_rfc3339(&last.timestamp),
+                ) {
+                    let secs_per_message = (end - start).num_seconds() as f64 / steps;
+                    let expected = end
+                        + chrono::Duration::seconds(
+                            (secs_per_message * messages_left as f64) as i64,
+                        );
+                    history.compaction_expected_at = Some(expected.to_rfc3339());
+                }
+            }
+        }
+    }
+    history.points = points;
+    history
+}
+
+/// Context history of an active session, read from its journal. `session_id` is the full id
+/// or a prefix of at least `MIN_SESSION_PREFIX` characters, like the ids of active sessions.
+pub fn session_context_history(session_id: &str) -> Result<SessionContextHistory, String> {
+    let (full_id, journals) = resolve_session_id(session_id, Some(ACTIVE_SESSION_HOURS))?;
+    let entries: Vec<ParsedEntry> = journals
+        .iter()
+        .filter_map(|file| parse_usage_from_file(file).ok())
+        .flatten()
+        .collect();
+    if !entries.iter().any(|entry| entry.session_id == full_id) {
+        return Err(format!("No recent messages in session '{session_id}'"));
+    }
+    Ok(context_history(&full_id, &entries))
+}
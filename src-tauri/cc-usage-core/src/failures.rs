@@ -0,0 +1,358 @@
+//! Requests lost to failures: assistant turns that ended in an API error (Claude Code writes
+//! them as synthetic messages flagged `isApiErrorMessage`) and requests interrupted by the
+//! user, marked by a "[Request interrupted by user]" prompt.
+//!
+//! The wasted tokens of a failure are those of the responses it cut off: the assistant
+//! messages since the last user line. For "[Request interrupted by user for tool use]" they
+//! are those of the request whose tool call was refused. Error messages carry no usage of
+//! their own, so an error wastes only what streamed before it; the retry that follows is
+//! counted as regular usage.
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::hours::local_day_start;
+use crate::parser::{from_line, future_cutoff, journal_lines};
+use crate::pricing::{calculate_cost_for, canonical_model_id, model_provider};
+use crate::projects::project_name;
+use crate::sources::{
+    collect_jsonl_files, get_claude_data_dirs, is_excluded_journal, is_session_excluded,
+};
+use crate::types::{
+    DayFailedRequests, FailedRequestReport, FailedRequests, SessionFailedRequests, TokenUsage,
+};
+
+/// Prompt text Claude Code writes when the user interrupts a request
+const INTERRUPTED: &str = "[Request interrupted by user";
+
+/// Prompt text written when the user refuses a tool call
+const INTERRUPTED_TOOL_USE: &str = "[Request interrupted by user for tool use]";
+
+/// A journal's failed requests, with the mtime and size it had when parsed
+type CachedFailures = (Option<SystemTime>, u64, Arc<Vec<FailedRequest>>);
+
+/// Failed requests per journal, parsed again only once the journal changes
+static CACHE: Mutex<Option<HashMap<PathBuf, CachedFailures>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// The turn ended in an API error
+    Error,
+    /// The user interrupted the request or refused its tool call
+    Aborted,
+}
+
+/// One failed request from a journal
+#[derive(Debug, Clone)]
+pub struct FailedRequest {
+    pub kind: FailureKind,
+    pub timestamp: DateTime<Utc>,
+    pub session_id: String,
+    pub cwd: String,
+    /// Uuid of the error message or interruption prompt; resumed sessions copy it into their
+    /// new journal
+    pub uuid: String,
+    pub wasted_tokens: TokenUsage,
+    pub wasted_cost_usd: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct FailureLine {
+    #[serde(rename = "type")]
+    entry_type: Option<String>,
+    timestamp: Option<String>,
+    #[serde(rename = "sessionId")]
+    session_id: Option<String>,
+    cwd: Option<String>,
+    uuid: Option<String>,
+    #[serde(rename = "isApiErrorMessage", default)]
+    is_api_error: bool,
+    /// Error kind (e.g. "rate_limit") on error messages of newer versions
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+    message: Option<FailureMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FailureMessage {
+    model: Option<String>,
+    usage: Option<FailureUsage>,
+    #[serde(default)]
+    content: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FailureUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+    cache_creation: Option<FailureCacheCreation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FailureCacheCreation {
+    #[serde(default)]
+    ephemeral_5m_input_tokens: u64,
+    #[serde(default)]
+    ephemeral_1h_input_tokens: u64,
+}
+
+impl FailureUsage {
+    fn tokens(&self) -> TokenUsage {
+        let cache_creation = self.cache_creation.as_ref();
+        TokenUsage {
+            input_tokens: self.input_tokens,
+            output_tokens: self.output_tokens,
+            cache_creation_input_tokens: self.cache_creation_input_tokens,
+            cache_read_input_tokens: self.cache_read_input_tokens,
+            cache_creation_5m_input_tokens: cache_creation
+                .map_or(0, |c| c.ephemeral_5m_input_tokens),
+            cache_creation_1h_input_tokens: cache_creation
+                .map_or(0, |c| c.ephemeral_1h_input_tokens),
+            thinking_tokens: 0,
+        }
+    }
+}
+
+/// Responses of a stretch of the conversation
+#[derive(Debug, Default)]
+struct Responses {
+    count: u32,
+    tokens: TokenUsage,
+    cost: f64,
+}
+
+impl Responses {
+    fn add(&mut self, model: &str, usage: &FailureUsage) {
+        let tokens = usage.tokens();
+        self.cost += calculate_cost_for(&canonical_model_id(model), model_provider(model), &tokens);
+        self.tokens.add(&tokens);
+        self.count += 1;
+    }
+}
+
+/// The interruption marker among a user message's text, if any
+fn interruption(content: &serde_json::Value) -> Option<&str> {
+    let text = match content {
+        serde_json::Value::String(text) => Some(text.as_str()),
+        serde_json::Value::Array(blocks) => blocks
+            .iter()
+            .filter(|block| block.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|block| block.get("text").and_then(|t| t.as_str()))
+            .find(|text| text.starts_with(INTERRUPTED)),
+        _ => None,
+    };
+    text.filter(|text| text.starts_with(INTERRUPTED))
+}
+
+/// Failed requests in journal lines, in journal order. Undated failures are skipped.
+pub fn parse_failed_requests<R: BufRead>(reader: R) -> Vec<FailedRequest> {
+    let mut requests = Vec::new();
+    let mut last_cwd = String::new();
+    // Responses since the last user line, and those before it
+    let mut pending = Responses::default();
+    let mut previous = Responses::default();
+
+    for line in journal_lines(reader) {
+        let Some(line) = from_line::<FailureLine>(&line) else {
+            continue;
+        };
+        if let Some(ref cwd) = line.cwd {
+            last_cwd = cwd.clone();
+        }
+        let content = line.message.as_ref().and_then(|m| m.content.as_ref());
+        let failure = match line.entry_type.as_deref() {
+            Some("assistant") => {
+                if let Some(message) = &line.message {
+                    if let Some(ref usage) = message.usage {
+                        pending.add(message.model.as_deref().unwrap_or_default(), usage);
+                    }
+                }
+                let errored =
+                    line.is_api_error || line.error.as_ref().is_some_and(|e| !e.is_null());
+                errored.then(|| {
+                    previous = Responses::default();
+                    (FailureKind::Error, std::mem::take(&mut pending))
+                })
+            }
+            Some("user") => match content.and_then(interruption) {
+                Some(marker) => {
+                    let refused_tool = marker.starts_with(INTERRUPTED_TOOL_USE);
+                    let wasted = if refused_tool && pending.count == 0 {
+                        std::mem::take(&mut previous)
+                    } else {
+                        std::mem::take(&mut pending)
+                    };
+                    previous = Responses::default();
+                    pending = Responses::default();
+                    Some((FailureKind::Aborted, wasted))
+                }
+                None => {
+                    previous = std::mem::take(&mut pending);
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        let Some((kind, wasted)) = failure else {
+            continue;
+        };
+        let Some(timestamp) = line
+            .timestamp
+            .as_deref()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        else {
+            continue;
+        };
+        requests.push(FailedRequest {
+            kind,
+            timestamp: timestamp.with_timezone(&Utc),
+            session_id: line.session_id.unwrap_or_default(),
+            cwd: line.cwd.unwrap_or_else(|| last_cwd.clone()),
+            uuid: line.uuid.unwrap_or_default(),
+            wasted_tokens: wasted.tokens,
+            wasted_cost_usd: wasted.cost,
+        });
+    }
+    requests
+}
+
+fn count(failed: &mut FailedRequests, request: &FailedRequest) {
+    match request.kind {
+        FailureKind::Error => failed.errors += 1,
+        FailureKind::Aborted => failed.aborted += 1,
+    }
+    failed.wasted_tokens.add(&request.wasted_tokens);
+    failed.wasted_cost_usd += request.wasted_cost_usd;
+}
+
+/// Failed requests at or after `since`, in total, per local day and per session. Copies in
+/// resumed sessions, excluded sessions and future-dated failures are left out.
+pub fn failed_request_report(
+    requests: &[FailedRequest],
+    since: DateTime<Utc>,
+) -> FailedRequestReport {
+    let cutoff = future_cutoff(Utc::now());
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut total = FailedRequests::default();
+    let mut by_day: BTreeMap<NaiveDate, FailedRequests> = BTreeMap::new();
+    let mut by_session: HashMap<&str, (SessionFailedRequests, DateTime<Utc>)> = HashMap::new();
+
+    for request in requests {
+        if request.timestamp < since
+            || request.timestamp > cutoff
+            || is_session_excluded(&request.session_id)
+            || (!request.uuid.is_empty() && !seen.insert(request.uuid.as_str()))
+        {
+            continue;
+        }
+        count(&mut total, request);
+        let date = request.timestamp.with_timezone(&Local).date_naive();
+        count(by_day.entry(date).or_default(), request);
+        if request.session_id.is_empty() {
+            continue;
+        }
+        let (session, last) = by_session
+            .entry(request.session_id.as_str())
+            .or_insert_with(|| {
+                (
+                    SessionFailedRequests {
+                        session_id: request.session_id.clone(),
+                        project: project_name(&request.cwd),
+                        directory: request.cwd.clone(),
+                        failed: FailedRequests::default(),
+                        last_failed_at: String::new(),
+                    },
+                    request.timestamp,
+                )
+            });
+        count(&mut session.failed, request);
+        *last = (*last).max(request.timestamp);
+    }
+
+    let mut sessions: Vec<SessionFailedRequests> = by_session
+        .into_values()
+        .map(|(mut session, last)| {
+            session.last_failed_at = last.to_rfc3339();
+            session
+        })
+        .collect();
+    sessions.sort_by(|a, b| {
+        b.failed
+            .wasted_cost_usd
+            .total_cmp(&a.failed.wasted_cost_usd)
+            .then_with(|| b.last_failed_at.cmp(&a.last_failed_at))
+    });
+    FailedRequestReport {
+        since: since.to_rfc3339(),
+        total,
+        by_day: by_day
+            .into_iter()
+            .map(|(date, failed)| DayFailedRequests {
+                date: date.format("%Y-%m-%d").to_string(),
+                failed,
+            })
+            .collect(),
+        by_session: sessions,
+    }
+}
+
+/// Failed requests of a journal, from the cache while its mtime and size are unchanged
+fn journal_failures(path: &Path) -> Arc<Vec<FailedRequest>> {
+    let metadata = path.metadata().ok();
+    let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+    let len = metadata.map_or(0, |m| m.len());
+    if let Ok(cache) = CACHE.lock() {
+        let cached = cache.as_ref().and_then(|cache| cache.get(path));
+        if let Some((cached_modified, cached_len, requests)) = cached {
+            if *cached_modified == modified && *cached_len == len {
+                return requests.clone();
+            }
+        }
+    }
+
+    let requests = Arc::new(match File::open(path) {
+        Ok(journal) => parse_failed_requests(BufReader::new(journal)),
+        Err(_) => Vec::new(),
+    });
+    if let Ok(mut cache) = CACHE.lock() {
+        cache
+            .get_or_insert_with(HashMap::new)
+            .insert(path.to_path_buf(), (modified, len, requests.clone()));
+    }
+    requests
+}
+
+/// Failed requests of the last `days` local days, today included. Only journals changed since
+/// the previous call are parsed again.
+pub fn get_failed_requests(days: u32) -> FailedRequestReport {
+    let today = Local::now().date_naive();
+    let since = local_day_start(today - chrono::Duration::days(days.max(1) as i64 - 1));
+    let hours_back = (Utc::now() - since).num_hours().max(0) + 1;
+    let mut requests = Vec::new();
+    for file in collect_jsonl_files(&get_claude_data_dirs(), Some(hours_back)) {
+        if is_excluded_journal(&file) {
+            continue;
+        }
+        requests.extend(journal_failures(&file).iter().cloned());
+    }
+    // Forget deleted journals
+    if let Ok(mut cache) = CACHE.lock() {
+        if let Some(cache) = cache.as_mut() {
+            cache.retain(|path, _| path.exists());
+        }
+    }
+    failed_request_report(&requests, since)
+}
@@ -0,0 +1,214 @@
+//! Optimization hints: patterns in recent usage that waste money, each with a concrete
+//! suggestion and an estimated saving at API prices.
+//!
+//! Current checks:
+//! - "opus_short_exchanges": Opus responses short enough that Sonnet would likely do
+//! - "low_cache_hit_rate": sessions re-sending context without hitting the prompt cache
+//! - "large_base_context": sessions that start with a huge context (CLAUDE.md, MCP tool
+//!   definitions), re-read on every turn
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use crate::parser::{parse_usage_from_file, ParsedEntry};
+use crate::pricing::{calculate_cost_for, get_model_pricing};
+use crate::sources::{collect_jsonl_files, get_claude_data_dirs};
+use crate::types::OptimizationHint;
+
+/// Opus responses with at most this many output tokens count as short exchanges
+pub const SHORT_EXCHANGE_OUTPUT_TOKENS: u64 = 400;
+
+/// Prompt cache hit rates below this are flagged
+pub const LOW_CACHE_HIT_RATE: f64 = 0.6;
+
+/// Cache hit rate assumed reachable when estimating the cache saving
+const TARGET_CACHE_HIT_RATE: f64 = 0.85;
+
+/// Sessions whose first request carries more context than this are flagged
+pub const LARGE_BASE_CONTEXT_TOKENS: u64 = 40_000;
+
+/// Model used to price the "switch to Sonnet" alternative
+const SONNET_MODEL: &str = "claude-sonnet-4-5";
+
+/// Hints are skipped when the saving they'd bring is below this (USD)
+const MIN_SAVINGS_USD: f64 = 0.5;
+
+const MILLION: f64 = 1_000_000.0;
+
+/// Analyze entries at or after `since`; hints are sorted by estimated saving, highest first
+pub fn compute_optimization_hints(
+    entries: &[ParsedEntry],
+    since: Option<DateTime<Utc>>,
+) -> Vec<OptimizationHint> {
+    let recent: Vec<&ParsedEntry> = entries
+        .iter()
+        .filter(|e| {
+            DateTime::parse_from_rfc3339(&e.timestamp)
+                .is_ok_and(|ts| since.is_none_or(|since_dt| ts >= since_dt))
+        })
+        .collect();
+
+    let mut hints: Vec<OptimizationHint> = [
+        opus_short_exchanges(&recent),
+        low_cache_hit_rate(&recent),
+        large_base_context(&recent),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|hint| hint.estimated_savings_usd >= MIN_SAVINGS_USD)
+    .collect();
+    hints.sort_by(|a, b| b.estimated_savings_usd.total_cmp(&a.estimated_savings_usd));
+    hints
+}
+
+fn opus_short_exchanges(entries: &[&ParsedEntry]) -> Option<OptimizationHint> {
+    let short: Vec<&ParsedEntry> = entries
+        .iter()
+        .copied()
+        .filter(|e| {
+            e.model.contains("opus") && e.tokens.output_tokens <= SHORT_EXCHANGE_OUTPUT_TOKENS
+        })
+        .collect();
+    if short.is_empty() {
+        return None;
+    }
+
+    let opus_cost: f64 = short.iter().map(|e| e.cost()).sum();
+    let sonnet_cost: f64 = short
+        .iter()
+        .map(|e| calculate_cost_for(SONNET_MODEL, e.provider.as_deref(), &e.tokens))
+        .sum();
+    Some(OptimizationHint {
+        kind: "opus_short_exchanges".to_string(),
+        title: "Use Sonnet for quick exchanges".to_string(),
+        detail: format!(
+            "{} Opus responses had at most {} output tokens and cost ${:.2}. Switching to \
+             Sonnet (/model) for short questions and small edits would cost about ${:.2}.",
+            short.len(),
+            SHORT_EXCHANGE_OUTPUT_TOKENS,
+            opus_cost,
+            sonnet_cost
+        ),
+        estimated_savings_usd: opus_cost - sonnet_cost,
+        affected_count: short.len() as u32,
+    })
+}
+
+fn low_cache_hit_rate(entries: &[&ParsedEntry]) -> Option<OptimizationHint> {
+    // session_id -> (cache reads, all context tokens, extra cost of uncached tokens per token)
+    let mut sessions: HashMap<&str, (u64, u64, f64)> = HashMap::new();
+    for entry in entries {
+        let (input_price, _, cache_write_price, cache_read_price) = get_model_pricing(&entry.model);
+        let session = sessions.entry(entry.session_id.as_str()).or_default();
+        session.0 = session
+            .0
+            .saturating_add(entry.tokens.cache_read_input_tokens);
+        session.1 = session.1.saturating_add(entry.tokens.context_tokens());
+        // Uncached context costs input or cache write price instead of the cache read price
+        let uncached_premium = (entry.tokens.input_tokens as f64
+            * (input_price - cache_read_price)
+            + entry.tokens.cache_creation_input_tokens as f64
+                * (cache_write_price - cache_read_price))
+            / MILLION;
+        session.2 += uncached_premium;
+    }
+
+    let mut affected = 0u32;
+    let mut savings = 0.0;
+    let (mut reads, mut total) = (0u64, 0u64);
+    for (cache_reads, context, premium) in sessions.values() {
+        // Tiny sessions never warm the cache; don't count them
+        if *context < 100_000 {
+            continue;
+        }
+        let hit_rate = *cache_reads as f64 / *context as f64;
+        if hit_rate >= LOW_CACHE_HIT_RATE {
+            continue;
+        }
+        affected += 1;
+        reads += cache_reads;
+        total += context;
+        // Share of the uncached premium that a target hit rate would remove
+        let uncached_share = 1.0 - hit_rate;
+        savings += premium * ((TARGET_CACHE_HIT_RATE - hit_rate) / uncached_share).min(1.0);
+    }
+    if affected == 0 {
+        return None;
+    }
+
+    Some(OptimizationHint {
+        kind: "low_cache_hit_rate".to_string(),
+        title: "Improve prompt cache hits".to_string(),
+        detail: format!(
+            "{} sessions hit the prompt cache for only {:.0}% of their context. Frequent \
+             /clear, model switches, editing CLAUDE.md mid-session or long pauses (the cache \
+             expires after 5 minutes) force the context to be re-sent at full price.",
+            affected,
+            reads as f64 / total as f64 * 100.0
+        ),
+        estimated_savings_usd: savings,
+        affected_count: affected,
+    })
+}
+
+fn large_base_context(entries: &[&ParsedEntry]) -> Option<OptimizationHint> {
+    // session_id -> entries, to find each session's first request
+    let mut sessions: HashMap<&str, Vec<&ParsedEntry>> = HashMap::new();
+    for &entry in entries {
+        if !entry.session_id.is_empty() {
+            sessions
+                .entry(entry.session_id.as_str())
+                .or_default()
+                .push(entry);
+        }
+    }
+
+    let mut affected = 0u32;
+    let mut base_total = 0u64;
+    let mut savings = 0.0;
+    for session in sessions.values_mut() {
+        session.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        let base = session[0].tokens.context_tokens();
+        if base <= LARGE_BASE_CONTEXT_TOKENS {
+            continue;
+        }
+        affected += 1;
+        base_total += base;
+        // The excess is re-read (at least at cache read price) on every turn
+        let excess = (base - LARGE_BASE_CONTEXT_TOKENS) as f64;
+        savings += session
+            .iter()
+            .map(|e| excess / MILLION * get_model_pricing(&e.model).3)
+            .sum::<f64>();
+    }
+    if affected == 0 {
+        return None;
+    }
+
+    Some(OptimizationHint {
+        kind: "large_base_context".to_string(),
+        title: "Trim the starting context".to_string(),
+        detail: format!(
+            "{} sessions started with {} tokens of context on average before any work. \
+             Shorten CLAUDE.md and disable unused MCP servers; their content is re-read on \
+             every turn.",
+            affected,
+            base_total / affected as u64
+        ),
+        estimated_savings_usd: savings,
+        affected_count: affected,
+    })
+}
+
+/// Optimization hints for the last `days` days of local usage
+pub fn get_optimization_hints(days: u32) -> Vec<OptimizationHint> {
+    let files = collect_jsonl_files(&get_claude_data_dirs(), Some(24 * (days as i64 + 1)));
+    let mut entries = Vec::new();
+    for file in &files {
+        if let Ok(parsed) = parse_usage_from_file(file) {
+            entries.extend(parsed);
+        }
+    }
+    let since = Utc::now() - chrono::Duration::days(days as i64);
+    compute_optimization_hints(&entries, Some(since))
+}
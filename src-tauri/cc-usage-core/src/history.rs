@@ -0,0 +1,217 @@
+//! Claude Code's global prompt history: `history.jsonl` in each Claude root, one line per
+//! prompt typed, with its project directory and (in recent versions) its session. Used for
+//! titles of sessions whose journal has no summary, the directory of usage entries without
+//! one (OTLP events), and the commands run in each session.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::aggregate::low_memory;
+use crate::parser::{journal_lines, read_appended_lines, ParsedEntry};
+use crate::sources::claude_history_files;
+
+/// Session titles taken from a prompt are cut to this many characters
+const MAX_TITLE_CHARS: usize = 80;
+
+#[derive(Debug, Deserialize)]
+struct HistoryLine {
+    #[serde(default)]
+    display: String,
+    /// Milliseconds since the epoch, or RFC 3339 in some versions
+    #[serde(default)]
+    timestamp: Option<serde_json::Value>,
+    #[serde(default)]
+    project: Option<String>,
+    #[serde(default, rename = "sessionId")]
+    session_id: Option<String>,
+}
+
+/// One prompt from the history
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    /// The prompt as typed
+    pub display: String,
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Working directory the prompt was typed in
+    pub project: String,
+    /// Missing in history written by older Claude Code versions
+    pub session_id: Option<String>,
+}
+
+impl HistoryEntry {
+    /// A slash command (`/compact`) or a shell command run with `!`, not a prompt
+    pub fn is_command(&self) -> bool {
+        self.display.starts_with('/') || self.display.starts_with('!')
+    }
+}
+
+/// What the history tells about a session
+#[derive(Debug, Clone, Default)]
+pub struct SessionHistory {
+    /// First line of the first prompt that isn't a command, shortened
+    pub first_prompt: Option<String>,
+    /// Directory of the latest prompt
+    pub project: Option<String>,
+    pub prompts: u32,
+    /// Slash and shell commands run
+    pub commands: u32,
+}
+
+fn parse_timestamp(value: &serde_json::Value) -> Option<DateTime<Utc>> {
+    match value {
+        serde_json::Value::Number(millis) => DateTime::from_timestamp_millis(millis.as_i64()?),
+        serde_json::Value::String(text) => DateTime::parse_from_rfc3339(text)
+            .ok()
+            .map(|ts| ts.with_timezone(&Utc)),
+        _ => None,
+    }
+}
+
+/// Parse history lines; malformed and empty prompts are skipped
+pub fn parse_history<R: BufRead>(reader: R) -> Vec<HistoryEntry> {
+    journal_lines(reader)
+        .filter_map(|line| serde_json::from_str::<HistoryLine>(&line).ok())
+        .filter(|line| !line.display.trim().is_empty())
+        .map(|line| HistoryEntry {
+            timestamp: line.timestamp.as_ref().and_then(parse_timestamp),
+            display: line.display,
+            project: line.project.unwrap_or_default(),
+            session_id: line.session_id.filter(|id| !id.is_empty()),
+        })
+        .collect()
+}
+
+fn shorten(prompt: &str) -> String {
+    let first_line = prompt.lines().next().unwrap_or_default().trim();
+    if first_line.chars().count() <= MAX_TITLE_CHARS {
+        return first_line.to_string();
+    }
+    let cut: String = first_line.chars().take(MAX_TITLE_CHARS - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// A session's history with the times of the prompts its title and directory came from, so
+/// entries can be added in any order
+#[derive(Default)]
+struct SessionFold {
+    history: SessionHistory,
+    first_prompt_at: Option<DateTime<Utc>>,
+    project_at: Option<DateTime<Utc>>,
+}
+
+impl SessionFold {
+    /// Count `entry`. The title is the earliest prompt's and the directory the latest's; of
+    /// equally dated prompts the title comes from the first added, the directory from the last.
+    fn add(&mut self, entry: &HistoryEntry) {
+        if entry.is_command() {
+            self.history.commands += 1;
+        } else {
+            self.history.prompts += 1;
+            if self.history.first_prompt.is_none() || entry.timestamp < self.first_prompt_at {
+                self.history.first_prompt = Some(shorten(&entry.display));
+                self.first_prompt_at = entry.timestamp;
+            }
+        }
+        if !entry.project.is_empty()
+            && (self.history.project.is_none() || entry.timestamp >= self.project_at)
+        {
+            self.history.project = Some(entry.project.clone());
+            self.project_at = entry.timestamp;
+        }
+    }
+}
+
+/// Add `entries` to their sessions; entries without a session id are skipped
+fn fold_entries<'a>(
+    sessions: &mut HashMap<String, SessionFold>,
+    entries: impl IntoIterator<Item = &'a HistoryEntry>,
+) {
+    for entry in entries {
+        if let Some(ref session_id) = entry.session_id {
+            sessions.entry(session_id.clone()).or_default().add(entry);
+        }
+    }
+}
+
+/// Group `entries` by session; entries without a session id are skipped
+pub fn session_histories(entries: &[HistoryEntry]) -> HashMap<String, SessionHistory> {
+    let mut sessions = HashMap::new();
+    fold_entries(&mut sessions, entries);
+    sessions
+        .into_iter()
+        .map(|(id, fold)| (id, fold.history))
+        .collect()
+}
+
+/// The history files read so far and the sessions folded from them
+#[derive(Default)]
+struct HistoryState {
+    /// Bytes of each file read
+    offsets: HashMap<PathBuf, u64>,
+    sessions: HashMap<String, SessionFold>,
+    histories: Arc<HashMap<String, SessionHistory>>,
+}
+
+/// State of `load_session_histories`, kept between calls so only appended prompts are read
+static STATE: Mutex<Option<HistoryState>> = Mutex::new(None);
+
+/// Session histories from every Claude root's `history.jsonl` (empty without one). Each call
+/// reads only the prompts appended since the previous one. Low-memory mode, which can't
+/// spare a record of every session ever run, returns none.
+pub fn load_session_histories() -> Arc<HashMap<String, SessionHistory>> {
+    let Ok(mut state) = STATE.lock() else {
+        return Arc::default();
+    };
+    if low_memory() {
+        *state = None;
+        return Arc::default();
+    }
+    let files = claude_history_files();
+    let state = state.get_or_insert_with(HistoryState::default);
+
+    // Prompts of a file that was dropped or rewritten shorter can't be taken back out
+    let rewritten = state.offsets.iter().any(|(path, offset)| {
+        !files.contains(path) || path.metadata().map_or(0, |m| m.len()) < *offset
+    });
+    if rewritten {
+        *state = HistoryState::default();
+    }
+    let mut changed = rewritten;
+    for file in &files {
+        let offset = state.offsets.entry(file.clone()).or_insert(0);
+        let appended = read_appended_lines(file, offset);
+        if !appended.is_empty() {
+            fold_entries(&mut state.sessions, &parse_history(appended.as_slice()));
+            changed = true;
+        }
+    }
+    if changed {
+        state.histories = Arc::new(
+            state
+                .sessions
+                .iter()
+                .map(|(id, fold)| (id.clone(), fold.history.clone()))
+                .collect(),
+        );
+    }
+    state.histories.clone()
+}
+
+/// Give entries without a working directory the one their session's prompts were typed in
+pub fn fill_missing_directories(
+    entries: &mut [ParsedEntry],
+    histories: &HashMap<String, SessionHistory>,
+) {
+    for entry in entries.iter_mut().filter(|entry| entry.cwd.is_empty()) {
+        if let Some(project) = histories
+            .get(&entry.session_id)
+            .and_then(|history| history.project.as_ref())
+        {
+            entry.cwd = project.clone();
+        }
+    }
+}
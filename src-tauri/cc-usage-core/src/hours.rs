@@ -0,0 +1,401 @@
+//! Working hours, used to separate regular usage from off-hours runs such as a forgotten
+//! autonomous agent working through the night, the first day of the week used by weekly
+//! resets and buckets, planned days off left out of the weekly pace, the day billing cycles
+//! renew on, and local day bounds that hold across daylight saving changes.
+
+use chrono::{DateTime, Datelike, Local, Months, NaiveDate, NaiveTime, Offset, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{OnceLock, RwLock};
+
+use crate::types::TimezoneInfo;
+
+/// First day of the week, 1 = Monday ... 7 = Sunday
+static WEEK_START: AtomicU32 = AtomicU32::new(7);
+
+/// Set the first day of the week (1 = Monday ... 7 = Sunday) for the weekly reset, weekly
+/// charts, goals and week buckets; other values restore the default, Sunday
+pub(crate) fn set_week_start(day: u32) {
+    let day = if (1..=7).contains(&day) { day } else { 7 };
+    WEEK_START.store(day, Ordering::Relaxed);
+}
+
+/// The first day of the week currently in effect, 1 = Monday ... 7 = Sunday
+pub fn week_start_day() -> u32 {
+    WEEK_START.load(Ordering::Relaxed)
+}
+
+/// First day of the week containing `date`
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    let offset = (date.weekday().number_from_monday() + 7 - week_start_day()) % 7;
+    date - chrono::Duration::days(offset as i64)
+}
+
+/// Day of the month billing cycles renew on, 1-31; 0 when unset
+static BILLING_ANCHOR_DAY: AtomicU32 = AtomicU32::new(0);
+
+/// Set the day of the month the subscription renews on, for the "month" period, monthly
+/// goals and the cycle forecast; None (or a day outside 1-31) restores the rolling 30 days
+pub(crate) fn set_billing_anchor_day(day: Option<u32>) {
+    let day = day.filter(|day| (1..=31).contains(day)).unwrap_or(0);
+    BILLING_ANCHOR_DAY.store(day, Ordering::Relaxed);
+}
+
+/// The billing anchor day currently in effect, if any
+pub fn billing_anchor_day() -> Option<u32> {
+    Some(BILLING_ANCHOR_DAY.load(Ordering::Relaxed)).filter(|day| *day > 0)
+}
+
+/// The anchor day in the month of `date`, or the month's last day when it is shorter
+/// (a cycle anchored on the 31st renews on February 28th)
+fn anchor_in_month(date: NaiveDate, anchor: u32) -> NaiveDate {
+    let first = date.with_day(1).unwrap_or(date);
+    let last_day = (first + Months::new(1) - chrono::Duration::days(1)).day();
+    first.with_day(anchor.min(last_day)).unwrap_or(first)
+}
+
+/// First day of the billing cycle containing `date`; calendar months without an anchor day
+pub fn billing_cycle_start(date: NaiveDate) -> NaiveDate {
+    let anchor = billing_anchor_day().unwrap_or(1);
+    let this_month = anchor_in_month(date, anchor);
+    if date >= this_month {
+        this_month
+    } else {
+        anchor_in_month(date - Months::new(1), anchor)
+    }
+}
+
+/// First day of the cycle after the one starting on `start`
+pub fn next_billing_cycle_start(start: NaiveDate) -> NaiveDate {
+    anchor_in_month(
+        start.with_day(1).unwrap_or(start) + Months::new(1),
+        billing_anchor_day().unwrap_or(1),
+    )
+}
+
+/// First day of the "month" period ending `today`: the current billing cycle's start with an
+/// anchor day, the last 30 days without
+pub fn month_period_start(today: NaiveDate) -> NaiveDate {
+    match billing_anchor_day() {
+        Some(_) => billing_cycle_start(today),
+        None => today - chrono::Duration::days(30),
+    }
+}
+
+/// Planned days off (holidays, PTO), local dates
+static DAYS_OFF: RwLock<BTreeSet<NaiveDate>> = RwLock::new(BTreeSet::new());
+
+/// Longest range or .ics event taken as days off, so a multi-year block can't swallow the
+/// calendar
+const MAX_EVENT_DAYS: i64 = 366;
+
+/// Replace the days left out of the weekly pace and projection
+pub(crate) fn set_days_off(days: Vec<NaiveDate>) {
+    if let Ok(mut current) = DAYS_OFF.write() {
+        *current = days.into_iter().collect();
+    }
+}
+
+/// The days off currently in effect, in order
+pub fn days_off() -> Vec<NaiveDate> {
+    DAYS_OFF
+        .read()
+        .map(|days| days.iter().copied().collect())
+        .unwrap_or_default()
+}
+
+pub fn is_day_off(date: NaiveDate) -> bool {
+    DAYS_OFF.read().is_ok_and(|days| days.contains(&date))
+}
+
+/// Dates of a day off spec: "YYYY-MM-DD" or an inclusive range "YYYY-MM-DD..YYYY-MM-DD"
+pub fn parse_days_off(spec: &str) -> Result<Vec<NaiveDate>, String> {
+    let parse = |value: &str| {
+        NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", value.trim()))
+    };
+    let (first, last) = match spec.split_once("..") {
+        Some((first, last)) => (parse(first)?, parse(last)?),
+        None => (parse(spec)?, parse(spec)?),
+    };
+    if last < first {
+        return Err(format!("Range '{spec}' ends before it starts"));
+    }
+    if (last - first).num_days() >= MAX_EVENT_DAYS {
+        return Err(format!(
+            "Range '{spec}' is longer than {MAX_EVENT_DAYS} days"
+        ));
+    }
+    Ok(first.iter_days().take_while(|day| *day <= last).collect())
+}
+
+/// Days covered by the events of an iCalendar file: each VEVENT from its DTSTART date up to
+/// DTEND (exclusive for all-day events, inclusive for timed ones), or just the start day
+/// without an end. Recurrence rules aren't expanded.
+pub fn parse_ics_days(contents: &str) -> Vec<NaiveDate> {
+    // Folded lines continue with a leading space or tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in contents.lines() {
+        match line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(line.trim_end().to_string()),
+        }
+    }
+
+    let mut days = BTreeSet::new();
+    // (DTSTART, DTEND) of the event being read
+    let mut event: Option<(Option<IcsDate>, Option<IcsDate>)> = None;
+    for line in &lines {
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name.to_ascii_uppercase(), value.trim()),
+            None => continue,
+        };
+        let property = name.split(';').next().unwrap_or_default();
+        match (property, value.to_ascii_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") => event = Some((None, None)),
+            ("END", "VEVENT") => {
+                if let Some((Some((start, _)), end)) = event.take() {
+                    let last = match end {
+                        Some((end, true)) => end - chrono::Duration::days(1),
+                        Some((end, false)) => end,
+                        None => start,
+                    };
+                    let last = last
+                        .max(start)
+                        .min(start + chrono::Duration::days(MAX_EVENT_DAYS - 1));
+                    days.extend(start.iter_days().take_while(|day| *day <= last));
+                }
+            }
+            ("DTSTART", _) => {
+                if let Some(event) = event.as_mut() {
+                    event.0 = parse_ics_date(value);
+                }
+            }
+            ("DTEND", _) => {
+                if let Some(event) = event.as_mut() {
+                    event.1 = parse_ics_date(value);
+                }
+            }
+            _ => {}
+        }
+    }
+    days.into_iter().collect()
+}
+
+/// A date and whether it was an all-day DATE value
+type IcsDate = (NaiveDate, bool);
+
+/// Date of an iCalendar DATE ("20250101") or DATE-TIME ("20250101T090000Z") value
+fn parse_ics_date(value: &str) -> Option<IcsDate> {
+    let date = NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()?;
+    Some((date, value.len() == 8))
+}
+
+/// Start of the calendar day `date` in `tz`, expressed in UTC. Where a daylight saving change
+/// skips midnight the day starts at the first local time that exists; where it repeats
+/// midnight, at the first of the two.
+pub fn day_start_in<Tz: TimeZone>(tz: &Tz, date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+    // Gaps are at most a few hours; step through them in quarter hours
+    (0..=16)
+        .find_map(|quarter| {
+            (midnight + chrono::Duration::minutes(15 * quarter))
+                .and_local_timezone(tz.clone())
+                .earliest()
+        })
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|| midnight.and_utc())
+}
+
+/// Start of a local calendar day, expressed in UTC
+pub fn local_day_start(date: NaiveDate) -> DateTime<Utc> {
+    day_start_in(&Local, date)
+}
+
+/// Real length of the calendar day `date` in `tz`: 23 or 25 hours on daylight saving changes
+pub fn day_hours_in<Tz: TimeZone>(tz: &Tz, date: NaiveDate) -> f64 {
+    let end = day_start_in(tz, date + chrono::Duration::days(1));
+    (end - day_start_in(tz, date)).num_seconds() as f64 / 3600.0
+}
+
+/// Bounds of the counted days among `days` in `tz`: days off are left out, unless every one
+/// of `days` is off
+pub fn counted_days_in<Tz: TimeZone>(
+    tz: &Tz,
+    days: &[NaiveDate],
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let all_off = days.iter().all(|day| is_day_off(*day));
+    days.iter()
+        .filter(|day| all_off || !is_day_off(**day))
+        .map(|day| {
+            let end = day_start_in(tz, *day + chrono::Duration::days(1));
+            (day_start_in(tz, *day), end)
+        })
+        .collect()
+}
+
+/// Hours of the counted days among `days` (see `counted_days_in`), and how many of them
+/// passed by `at`
+pub fn counted_hours_in<Tz: TimeZone>(
+    tz: &Tz,
+    days: &[NaiveDate],
+    at: DateTime<Utc>,
+) -> (f64, f64) {
+    let hours = |duration: chrono::Duration| duration.num_seconds() as f64 / 3600.0;
+    counted_days_in(tz, days)
+        .into_iter()
+        .fold((0.0, 0.0), |(total, elapsed), (start, end)| {
+            (
+                total + hours(end - start),
+                elapsed + hours(at.clamp(start, end) - start),
+            )
+        })
+}
+
+/// Real hours from `at` until `counted` more hours of the counted days among `days` passed,
+/// skipping days off; the hours to the end of `days` if they run out first
+pub fn hours_until_counted<Tz: TimeZone>(
+    tz: &Tz,
+    days: &[NaiveDate],
+    at: DateTime<Utc>,
+    counted: f64,
+) -> f64 {
+    let hours = |duration: chrono::Duration| duration.num_seconds() as f64 / 3600.0;
+    if counted <= 0.0 {
+        return 0.0;
+    }
+    let mut left = counted;
+    let mut last_end = at;
+    for (start, end) in counted_days_in(tz, days) {
+        let from = at.max(start);
+        if from >= end {
+            continue;
+        }
+        let available = hours(end - from);
+        if available >= left {
+            return hours(from - at) + left;
+        }
+        left -= available;
+        last_end = end;
+    }
+    hours(last_end - at)
+}
+
+fn format_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.abs() / 60;
+    format!("{sign}{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// IANA name of the local time zone: `TZ`, or the target of the `/etc/localtime` link
+fn local_timezone_name() -> Option<String> {
+    if let Some(tz) = std::env::var("TZ").ok().filter(|tz| !tz.is_empty()) {
+        return Some(tz.trim_start_matches(':').to_string());
+    }
+    let target = std::fs::read_link("/etc/localtime").ok()?;
+    let target = target.to_string_lossy();
+    target
+        .split_once("zoneinfo/")
+        .map(|(_, name)| name.to_string())
+}
+
+/// The local time zone and its offset at `at`, with the next offset change
+pub fn timezone_info(at: DateTime<Utc>) -> TimezoneInfo {
+    let offset_at = |at: DateTime<Utc>| at.with_timezone(&Local).offset().fix().local_minus_utc();
+    let offset = offset_at(at);
+    let year = at.with_timezone(&Local).year();
+    let standard = [1, 7]
+        .iter()
+        .filter_map(|month| NaiveDate::from_ymd_opt(year, *month, 1))
+        .map(|date| offset_at(local_day_start(date)))
+        .min()
+        .unwrap_or(offset);
+    // Offsets change on the hour or half hour; find the hour, then the minute
+    let next = (1..=24 * 366)
+        .map(|hour| at + chrono::Duration::hours(hour))
+        .find(|later| offset_at(*later) != offset)
+        .map(|later| {
+            let mut change = later - chrono::Duration::hours(1);
+            while offset_at(change) == offset {
+                change += chrono::Duration::minutes(1);
+            }
+            change
+        });
+    TimezoneInfo {
+        name: local_timezone_name(),
+        utc_offset: format_offset(offset),
+        utc_offset_seconds: offset,
+        dst_active: offset > standard,
+        next_transition: next.map(|change| change.to_rfc3339()),
+        next_utc_offset: next.map(|change| format_offset(offset_at(change))),
+    }
+}
+
+/// Local working schedule. `end` before `start` means the working day spans midnight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkingHours {
+    pub start: String, // "HH:MM", local time
+    pub end: String,   // "HH:MM", local time
+    /// Working days, 1 = Monday ... 7 = Sunday
+    pub days: Vec<u32>,
+}
+
+impl Default for WorkingHours {
+    fn default() -> Self {
+        Self {
+            start: "09:00".to_string(),
+            end: "18:00".to_string(),
+            days: vec![1, 2, 3, 4, 5],
+        }
+    }
+}
+
+impl WorkingHours {
+    /// Whether `at` falls inside working hours (in local time). Unparsable times fall back
+    /// to the defaults.
+    pub fn contains<Tz: TimeZone>(&self, at: &DateTime<Tz>) -> bool {
+        let local = at.with_timezone(&Local);
+        let parse = |value: &str, fallback: u32| {
+            NaiveTime::parse_from_str(value, "%H:%M")
+                .unwrap_or_else(|_| NaiveTime::from_hms_opt(fallback, 0, 0).unwrap())
+        };
+        let start = parse(&self.start, 9);
+        let end = parse(&self.end, 18);
+        let time = local.time();
+
+        // A shift spanning midnight belongs to the day it started on
+        let (in_shift, shift_day) = if start <= end {
+            (start <= time && time < end, local.date_naive())
+        } else if time >= start {
+            (true, local.date_naive())
+        } else {
+            (time < end, local.date_naive() - chrono::Duration::days(1))
+        };
+        in_shift
+            && self
+                .days
+                .contains(&shift_day.weekday().number_from_monday())
+    }
+}
+
+static WORKING_HOURS: OnceLock<RwLock<WorkingHours>> = OnceLock::new();
+
+fn schedule() -> &'static RwLock<WorkingHours> {
+    WORKING_HOURS.get_or_init(|| RwLock::new(WorkingHours::default()))
+}
+
+/// Replace the working hours used for `off_hours_percent` (`None` restores the defaults)
+pub(crate) fn set_working_hours(hours: Option<WorkingHours>) {
+    if let Ok(mut current) = schedule().write() {
+        *current = hours.unwrap_or_default();
+    }
+}
+
+/// The working hours currently in effect
+pub fn working_hours() -> WorkingHours {
+    schedule()
+        .read()
+        .map(|hours| hours.clone())
+        .unwrap_or_default()
+}
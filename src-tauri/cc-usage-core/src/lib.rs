@@ -0,0 +1,86 @@
+//! Claude Code usage parsing and aggregation, independent of the desktop widget.
+//!
+//! The API is organized in layers:
+//! - [`sources`]: locate Claude config roots and the JSONL journals under them
+//! - [`access`]: read-only guard for Claude's data (allow-lists, symlinks, no writes)
+//! - [`parser`]: parse journal files into [`ParsedEntry`] values
+//! - [`otlp`]: usage events from Claude Code's OpenTelemetry export, instead of journals
+//! - [`history`]: Claude Code's prompt history, for session titles, directories and commands
+//! - `cache` (feature `entry-cache`): persistent zstd cache of parsed journals
+//! - [`pricing`]: model display names, API prices, context limits and quota weights
+//! - [`projects`]: project names for working directories (symlinks, worktrees, rules)
+//! - [`quota`]: weighted prompt counts, activity-anchored 5hr quota blocks and planning
+//!   upcoming work against them
+//! - [`hours`]: working hours, for telling off-hours usage apart, and the week start
+//! - [`aggregate`]: turn entries into [`UsageStats`], daily digests, productivity, ...
+//! - [`context`]: context growth of a session and when auto-compaction will hit
+//! - [`hints`]: optimization suggestions with estimated savings
+//! - [`failures`]: requests lost to API errors and interruptions, and their wasted tokens
+//! - [`search`]: text search and filters over all sessions
+//! - [`users`]: usage per OS user on shared machines, from journal ownership
+//! - [`cancel`]: cooperative cancellation and timeouts for long aggregations
+//! - [`perf`]: timings of aggregation, parsing and API calls, also as `tracing` spans
+//! - [`schema`]: JSON Schemas of the serialized types
+//! - [`settings`]: all of the above's configuration, applied at once with [`configure`]
+//!
+//! Most tools only need [`get_current_usage`]:
+//!
+//! ```no_run
+//! let stats = cc_usage_core::get_current_usage("today").unwrap();
+//! println!("${:.2} across {} messages", stats.total_cost_usd, stats.session_count);
+//! ```
+//!
+//! The crate root exports the configuration, the serialized types and the entry points of
+//! each layer; lower-level building blocks stay in their modules.
+
+pub mod access;
+pub mod aggregate;
+#[cfg(feature = "entry-cache")]
+pub mod cache;
+pub mod cancel;
+pub mod context;
+pub mod failures;
+pub mod hints;
+pub mod history;
+pub mod hours;
+pub mod otlp;
+pub mod parser;
+pub mod perf;
+pub mod pricing;
+pub mod projects;
+pub mod quota;
+pub mod schema;
+pub mod search;
+pub mod settings;
+pub mod sources;
+pub mod types;
+pub mod users;
+
+pub use settings::{configure, CoreSettings};
+pub use hours::WorkingHours;
+pub use otlp::UsageSource;
+pub use pricing::{ModelPrice, PricingProfile, QuotaWeightRule, QuotaWeights};
+pub use projects::{check_project_rules, ProjectRule};
+pub use quota::QuotaWindowDef;
+pub use sources::ChangeDetection;
+
+pub use cancel::CancellationToken;
+pub use parser::ParsedEntry;
+pub use schema::{write_schemas, SCHEMA_TYPES};
+pub use types::*;
+
+pub use access::access_violations;
+pub use aggregate::{
+    billing_cycle_forecast, compare_usage, compute_daily_delta, compute_daily_digest,
+    compute_peer_comparison, compute_productivity, get_current_usage, get_current_usage_with,
+    get_hourly_series, get_project_week_matrix, get_usage_multi, get_usage_multi_with,
+    get_weekly_project_series,
+};
+pub use context::session_context_history;
+pub use failures::get_failed_requests;
+pub use hints::get_optimization_hints;
+pub use parser::{parse_health, parse_usage_from_file, parse_usage_from_reader};
+pub use perf::perf_stats;
+pub use quota::{plan_usage, quota_info_at, throttle_advice};
+pub use search::{search_sessions, session_directory};
+pub use sources::{get_claude_data_dirs, get_data_dir_report};
@@ -0,0 +1,194 @@
+//! Usage events received from Claude Code's OpenTelemetry export, as an alternative to
+//! reading the JSONL journals.
+//!
+//! With `UsageSource::Otlp`, token usage comes from the `claude_code.api_request` log
+//! events handed to `ingest_otlp_logs` (OTLP/HTTP with JSON encoding, the body of a
+//! `POST /v1/logs`) instead of from parsed journals. Events are kept in memory for
+//! `OTLP_RETENTION_DAYS`; callers that want them across restarts persist the entries
+//! returned by `ingest_otlp_logs` and hand them back to `restore_otlp_entries`.
+//!
+//! The events carry no working directory, so OTLP usage has no project names, and they
+//! carry no prompts: the quota windows still come from the prompt history in the journals
+//! when it is present.
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Mutex, RwLock};
+
+use crate::parser::ParsedEntry;
+use crate::pricing::{canonical_model_id, model_provider};
+use crate::types::TokenUsage;
+
+/// Where `get_usage_multi` reads token usage from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageSource {
+    /// Parse the JSONL journals under the Claude data dirs
+    #[default]
+    Journals,
+    /// Events ingested with `ingest_otlp_logs`
+    Otlp,
+}
+
+/// Ingested events older than this are dropped; covers the "month" period
+pub const OTLP_RETENTION_DAYS: i64 = 32;
+
+/// Log event carrying the usage of one API request
+const API_REQUEST_EVENT: &str = "api_request";
+
+static USAGE_SOURCE: RwLock<UsageSource> = RwLock::new(UsageSource::Journals);
+
+static OTLP_ENTRIES: Mutex<Vec<ParsedEntry>> = Mutex::new(Vec::new());
+
+pub(crate) fn set_usage_source(source: UsageSource) {
+    if let Ok(mut current) = USAGE_SOURCE.write() {
+        *current = source;
+    }
+}
+
+pub fn usage_source() -> UsageSource {
+    USAGE_SOURCE
+        .read()
+        .map(|source| *source)
+        .unwrap_or_default()
+}
+
+/// Parse an OTLP/JSON `ExportLogsServiceRequest` and keep its API request events; returns
+/// the new entries. Other events (prompts, tool results, ...) are ignored.
+pub fn ingest_otlp_logs(body: &[u8]) -> Result<Vec<ParsedEntry>, String> {
+    let request: Value =
+        serde_json::from_slice(body).map_err(|e| format!("Invalid OTLP JSON: {e}"))?;
+    let records = request
+        .get("resourceLogs")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .flat_map(|resource_logs| {
+            let resource = attributes(resource_logs.pointer("/resource/attributes"));
+            resource_logs
+                .get("scopeLogs")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|scope| scope.get("logRecords").and_then(Value::as_array))
+                .flatten()
+                .map(move |record| (resource.clone(), record))
+        });
+
+    let entries: Vec<ParsedEntry> = records
+        .filter_map(|(resource, record)| api_request_entry(&resource, record))
+        .collect();
+    add_entries(entries.clone());
+    Ok(entries)
+}
+
+/// Put back entries saved from earlier `ingest_otlp_logs` calls, e.g. at startup
+pub fn restore_otlp_entries(entries: Vec<ParsedEntry>) {
+    add_entries(entries);
+}
+
+/// Ingested entries still within `OTLP_RETENTION_DAYS`, oldest first
+pub fn otlp_entries() -> Vec<ParsedEntry> {
+    OTLP_ENTRIES
+        .lock()
+        .map(|entries| entries.clone())
+        .unwrap_or_default()
+}
+
+fn add_entries(new: Vec<ParsedEntry>) {
+    let cutoff = Utc::now() - chrono::Duration::days(OTLP_RETENTION_DAYS);
+    if let Ok(mut entries) = OTLP_ENTRIES.lock() {
+        entries.extend(new);
+        entries.retain(|entry| {
+            DateTime::parse_from_rfc3339(&entry.timestamp).is_ok_and(|ts| ts >= cutoff)
+        });
+        // RFC 3339 timestamps in UTC sort chronologically as strings
+        entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    }
+}
+
+/// OTLP `KeyValue` list as (key, value) pairs, with every value rendered as a string
+fn attributes(list: Option<&Value>) -> Vec<(String, String)> {
+    list.and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|kv| {
+            let key = kv.get("key")?.as_str()?;
+            let value = any_value(kv.get("value")?)?;
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+/// OTLP `AnyValue` as a string; int64 values arrive as JSON strings or numbers
+fn any_value(value: &Value) -> Option<String> {
+    ["stringValue", "intValue", "doubleValue", "boolValue"]
+        .iter()
+        .find_map(|kind| value.get(*kind))
+        .map(|v| match v {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+}
+
+fn api_request_entry(resource: &[(String, String)], record: &Value) -> Option<ParsedEntry> {
+    let attrs = attributes(record.get("attributes"));
+    let get = |key: &str| {
+        attrs
+            .iter()
+            .chain(resource)
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    };
+    let body = record.pointer("/body/stringValue").and_then(Value::as_str);
+    let is_api_request = get("event.name") == Some(API_REQUEST_EVENT)
+        || body.is_some_and(|b| b.ends_with(API_REQUEST_EVENT));
+    if !is_api_request {
+        return None;
+    }
+
+    let count = |key: &str| {
+        get(key)
+            .and_then(|v| v.parse::<f64>().ok())
+            .map_or(0, |n| n.max(0.0) as u64)
+    };
+    let timestamp = get("event.timestamp")
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|ts| ts.with_timezone(&Utc))
+        .or_else(|| unix_nanos(record.get("timeUnixNano")))
+        .or_else(|| unix_nanos(record.get("observedTimeUnixNano")))?;
+    let raw_model = get("model").unwrap_or("unknown");
+
+    Some(ParsedEntry {
+        model: canonical_model_id(raw_model),
+        provider: model_provider(raw_model).map(str::to_string),
+        tokens: TokenUsage {
+            input_tokens: count("input_tokens"),
+            output_tokens: count("output_tokens"),
+            cache_creation_input_tokens: count("cache_creation_tokens"),
+            cache_read_input_tokens: count("cache_read_tokens"),
+            ..TokenUsage::default()
+        },
+        timestamp: timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        session_id: get("session.id").unwrap_or_default().to_string(),
+        cwd: String::new(),
+        completed_todos: Vec::new(),
+        uuid: String::new(),
+        parent_uuid: None,
+        is_sidechain: false,
+    })
+}
+
+fn unix_nanos(value: Option<&Value>) -> Option<DateTime<Utc>> {
+    let nanos: i64 = match value? {
+        Value::String(s) => s.parse().ok()?,
+        Value::Number(n) => n.as_i64()?,
+        _ => return None,
+    };
+    if nanos <= 0 {
+        return None;
+    }
+    Utc.timestamp_opt(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+        .single()
+}
@@ -0,0 +1,663 @@
+//! Claude Code journal (JSONL) parsing.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, Metadata};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::perf::PerfSpan;
+use crate::pricing::{calculate_cost_for, canonical_model_id, model_provider};
+use crate::types::{ParseHealth, TokenUsage};
+
+/// Journal lines longer than this (bytes) are skipped, unless changed with
+/// `CoreSettings::max_line_bytes`
+pub const DEFAULT_MAX_LINE_BYTES: usize = 16 * 1024 * 1024;
+
+static MAX_LINE_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_LINE_BYTES);
+
+thread_local! {
+    // Parse health of the load running on this thread, see `begin_parse_health`
+    static LOAD_HEALTH: RefCell<ParseHealth> = RefCell::new(ParseHealth::default());
+}
+
+/// Parse health of the latest finished load, see `parse_health`
+static LATEST_HEALTH: Mutex<Option<ParseHealth>> = Mutex::new(None);
+
+/// Count something in the parse health of this thread's load
+fn record(update: impl FnOnce(&mut ParseHealth)) {
+    LOAD_HEALTH.with(|health| update(&mut health.borrow_mut()));
+}
+
+/// Journals are appended in order, so an entry dated more than this many seconds before an
+/// earlier entry of the same file points at a clock change
+pub const CLOCK_SKEW_TOLERANCE_SECS: i64 = 60;
+
+/// Entries dated more than this many seconds after now were written under a clock set ahead
+pub const FUTURE_TOLERANCE_SECS: i64 = 300;
+
+/// Latest plausible timestamp at `now`; anything after it is quarantined
+pub fn future_cutoff(now: DateTime<Utc>) -> DateTime<Utc> {
+    now + chrono::Duration::seconds(FUTURE_TOLERANCE_SECS)
+}
+
+/// Remove entries dated after `future_cutoff(now)`. They would sit in every window until the
+/// clock catches up and push `last_updated` ahead. How many were removed is counted in the
+/// load's parse health.
+pub fn quarantine_future_entries(entries: &mut Vec<ParsedEntry>, now: DateTime<Utc>) -> usize {
+    let cutoff = future_cutoff(now);
+    let mut max_ahead: i64 = 0;
+    let before = entries.len();
+    entries.retain(|entry| {
+        let ahead = DateTime::parse_from_rfc3339(&entry.timestamp)
+            .ok()
+            .filter(|ts| *ts > cutoff)
+            .map(|ts| (ts.with_timezone(&Utc) - now).num_seconds());
+        max_ahead = max_ahead.max(ahead.unwrap_or(0));
+        ahead.is_none()
+    });
+    let quarantined = before - entries.len();
+    record(|health| {
+        health.future_entries = health.future_entries.saturating_add(quarantined as u64);
+        health.max_future_seconds = health.max_future_seconds.max(max_ahead as u64);
+    });
+    quarantined
+}
+
+/// Set the longest journal line read, in bytes (`None` restores the default). Longer lines,
+/// such as huge files pasted into a prompt, are skipped without being held in memory.
+pub(crate) fn set_max_line_bytes(limit: Option<usize>) {
+    MAX_LINE_BYTES.store(
+        limit.filter(|l| *l > 0).unwrap_or(DEFAULT_MAX_LINE_BYTES),
+        Ordering::Relaxed,
+    );
+}
+
+pub fn max_line_bytes() -> usize {
+    MAX_LINE_BYTES.load(Ordering::Relaxed)
+}
+
+/// Start counting the parse health of a load on this thread. Journals read by other threads
+/// meanwhile (alerts, search) aren't counted in it.
+pub fn begin_parse_health() {
+    LOAD_HEALTH.with(|health| *health.borrow_mut() = ParseHealth::default());
+}
+
+/// End the load begun with `begin_parse_health`: its counts become `parse_health` and are
+/// returned
+pub fn finish_parse_health() -> ParseHealth {
+    let mut health = LOAD_HEALTH.with(|health| health.take());
+    health.max_line_bytes = max_line_bytes() as u64;
+    if let Ok(mut latest) = LATEST_HEALTH.lock() {
+        *latest = Some(health.clone());
+    }
+    health
+}
+
+/// Counts of journal lines read and skipped by the latest usage load. Journals read from the
+/// entry cache aren't parsed, so they aren't counted.
+pub fn parse_health() -> ParseHealth {
+    let latest = LATEST_HEALTH.lock().ok().and_then(|latest| latest.clone());
+    let mut health = latest.unwrap_or_default();
+    health.max_line_bytes = max_line_bytes() as u64;
+    health
+}
+
+/// Per-file timestamp checks: entries dated before the latest one seen so far
+struct SkewTracker {
+    latest: Option<DateTime<Utc>>,
+    skewed: bool,
+    /// Future-dated entries don't count as the latest, or every later entry would look skewed
+    cutoff: DateTime<Utc>,
+}
+
+impl SkewTracker {
+    fn new() -> Self {
+        Self {
+            latest: None,
+            skewed: false,
+            cutoff: future_cutoff(Utc::now()),
+        }
+    }
+
+    fn observe(&mut self, timestamp: &str) {
+        let at = match DateTime::parse_from_rfc3339(timestamp) {
+            Ok(at) if at <= self.cutoff => at.with_timezone(&Utc),
+            _ => return,
+        };
+        match self.latest {
+            Some(latest) if at < latest => {
+                let skew = (latest - at).num_seconds();
+                if skew > CLOCK_SKEW_TOLERANCE_SECS {
+                    let first_in_file = !self.skewed;
+                    self.skewed = true;
+                    record(|health| {
+                        health.non_monotonic_entries =
+                            health.non_monotonic_entries.saturating_add(1);
+                        health.max_skew_seconds = health.max_skew_seconds.max(skew as u64);
+                        if first_in_file {
+                            health.files_with_skew = health.files_with_skew.saturating_add(1);
+                        }
+                    });
+                }
+            }
+            _ => self.latest = Some(at),
+        }
+    }
+}
+
+/// Lines of a journal, like `BufRead::lines` but with bounded memory: lines longer than
+/// `max_line_bytes` are discarded as they are read, and lines that aren't valid UTF-8 are
+/// skipped. Both are counted in `parse_health`. Iteration ends at EOF or on an I/O error.
+pub struct JournalLines<R> {
+    reader: R,
+    limit: usize,
+}
+
+pub fn journal_lines<R: BufRead>(reader: R) -> JournalLines<R> {
+    JournalLines {
+        reader,
+        limit: max_line_bytes(),
+    }
+}
+
+impl<R: BufRead> Iterator for JournalLines<R> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            let mut line = Vec::new();
+            let mut oversized = false;
+            let mut read_any = false;
+            loop {
+                let available = match self.reader.fill_buf() {
+                    Ok(buf) => buf,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(_) => return None,
+                };
+                if available.is_empty() {
+                    break;
+                }
+                read_any = true;
+                let (chunk, used, done) = match available.iter().position(|b| *b == b'\n') {
+                    Some(i) => (&available[..i], i + 1, true),
+                    None => (available, available.len(), false),
+                };
+                if !oversized {
+                    if line.len() + chunk.len() > self.limit {
+                        // Keep consuming up to the newline, but stop storing
+                        oversized = true;
+                        line = Vec::new();
+                    } else {
+                        line.extend_from_slice(chunk);
+                    }
+                }
+                self.reader.consume(used);
+                if done {
+                    break;
+                }
+            }
+            if !read_any {
+                return None;
+            }
+
+            record(|health| {
+                health.lines_read = health.lines_read.saturating_add(1);
+                if oversized {
+                    health.oversized_lines = health.oversized_lines.saturating_add(1);
+                }
+            });
+            if oversized {
+                continue;
+            }
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            match String::from_utf8(line) {
+                Ok(line) => return Some(line),
+                Err(_) => record(|health| {
+                    health.invalid_utf8_lines = health.invalid_utf8_lines.saturating_add(1);
+                }),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageUsage {
+    input_tokens: Option<u64>,
+    output_tokens: Option<u64>,
+    cache_creation_input_tokens: Option<u64>,
+    cache_read_input_tokens: Option<u64>,
+    /// Cache writes split by TTL, in newer journals
+    cache_creation: Option<CacheCreation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CacheCreation {
+    ephemeral_5m_input_tokens: Option<u64>,
+    ephemeral_1h_input_tokens: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    model: Option<String>,
+    usage: Option<MessageUsage>,
+    #[serde(default)]
+    content: Option<MessageContent>,
+}
+
+/// Message content: a list of blocks for assistant turns; anything else is ignored
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum MessageContent {
+    Blocks(Vec<ContentBlock>),
+    Other(serde::de::IgnoredAny),
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    block_type: Option<String>,
+    name: Option<String>,
+    input: Option<ToolInput>,
+    /// Reasoning text of a `thinking` block
+    thinking: Option<String>,
+}
+
+/// Characters per token used to estimate thinking tokens from their text
+const CHARS_PER_THINKING_TOKEN: usize = 4;
+
+/// Tool input; only the `TodoWrite` todo list is of interest
+#[derive(Debug, Deserialize)]
+struct ToolInput {
+    todos: Option<Vec<TodoItem>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JournalEntry {
+    #[serde(rename = "type")]
+    entry_type: Option<String>,
+    message: Option<Message>,
+    timestamp: Option<String>,
+    #[serde(rename = "sessionId")]
+    session_id: Option<String>,
+    cwd: Option<String>,
+    uuid: Option<String>,
+    #[serde(rename = "parentUuid")]
+    parent_uuid: Option<String>,
+    /// Set on the first entry after a compaction, which starts a new tree
+    #[serde(rename = "logicalParentUuid")]
+    logical_parent_uuid: Option<String>,
+    #[serde(rename = "isSidechain", default)]
+    is_sidechain: bool,
+}
+
+/// Parse a JSON line and return timestamp if it's an actual user prompt (not just tool results)
+/// Returns None if not a user prompt or parsing fails
+pub fn parse_user_prompt_timestamp(line: &str) -> Option<String> {
+    parse_entry_info(line)
+        .filter(|info| info.is_user_prompt)
+        .map(|info| info.timestamp)
+}
+
+/// serde_json's recursion limit; deeper lines skip the simd-json path
+#[cfg(feature = "simd-json")]
+const MAX_SIMD_DEPTH: usize = 128;
+
+/// Whether brackets nest deeper than `MAX_SIMD_DEPTH`. Brackets inside strings count too,
+/// so this can only over-estimate, which just means falling back to serde_json.
+#[cfg(feature = "simd-json")]
+fn too_deep(line: &str) -> bool {
+    let mut depth = 0usize;
+    for byte in line.bytes() {
+        match byte {
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > MAX_SIMD_DEPTH {
+                    return true;
+                }
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Deserialize one journal line. With the `simd-json` feature, lines are parsed with
+/// simd-json first, falling back to serde_json for lines it rejects.
+pub(crate) fn from_line<T: DeserializeOwned>(line: &str) -> Option<T> {
+    #[cfg(feature = "simd-json")]
+    if !too_deep(line) {
+        let mut bytes = line.as_bytes().to_vec();
+        if let Ok(value) = simd_json::serde::from_slice(&mut bytes) {
+            return Some(value);
+        }
+    }
+    serde_json::from_str(line).ok()
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct TodoItem {
+    #[serde(default)]
+    content: Option<String>,
+    pub(crate) status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedEntry {
+    /// Canonical model id, see `canonical_model_id`
+    pub model: String,
+    /// Cloud provider from the original model id, see `model_provider`
+    pub provider: Option<String>,
+    pub tokens: TokenUsage,
+    pub timestamp: String,
+    pub session_id: String,
+    pub cwd: String,
+    /// Todo items marked completed by a `TodoWrite` call in this message
+    pub completed_todos: Vec<String>,
+    /// Journal entry uuid; empty for old journals without one
+    pub uuid: String,
+    /// Uuid of the nearest assistant entry above this one in the conversation tree (user
+    /// turns in between are skipped), so branches can be found from usage entries alone
+    pub parent_uuid: Option<String>,
+    /// Subagent (Task tool) message, outside the main conversation tree
+    pub is_sidechain: bool,
+}
+
+impl ParsedEntry {
+    /// API-equivalent cost, using the pricing profile of the entry's provider
+    pub fn cost(&self) -> f64 {
+        calculate_cost_for(&self.model, self.provider.as_deref(), &self.tokens)
+    }
+}
+
+/// Estimated tokens of the message's thinking blocks, at most `output_tokens`. Redacted
+/// thinking carries no text and isn't counted.
+fn thinking_tokens_in(content: Option<&MessageContent>, output_tokens: u64) -> u64 {
+    let blocks = match content {
+        Some(MessageContent::Blocks(blocks)) => blocks,
+        _ => return 0,
+    };
+    let chars: usize = blocks
+        .iter()
+        .filter(|b| b.block_type.as_deref() == Some("thinking"))
+        .filter_map(|b| b.thinking.as_deref())
+        .map(|text| text.chars().count())
+        .sum();
+    (chars.div_ceil(CHARS_PER_THINKING_TOKEN) as u64).min(output_tokens)
+}
+
+/// Contents of todos marked completed by `TodoWrite` tool calls in a message
+fn completed_todos_in(content: Option<MessageContent>) -> Vec<String> {
+    let blocks = match content {
+        Some(MessageContent::Blocks(blocks)) => blocks,
+        _ => return Vec::new(),
+    };
+    blocks
+        .into_iter()
+        .filter(|b| {
+            b.block_type.as_deref() == Some("tool_use") && b.name.as_deref() == Some("TodoWrite")
+        })
+        .filter_map(|b| b.input.and_then(|input| input.todos))
+        .flatten()
+        .filter(|todo| todo.status.as_deref() == Some("completed"))
+        .filter_map(|todo| todo.content)
+        .collect()
+}
+
+/// The complete lines appended to `path` since `offset`, which is moved past them. A line
+/// still being written is left for the next call; a file that shrank is read from the start.
+pub fn read_appended_lines(path: &Path, offset: &mut u64) -> Vec<u8> {
+    let Ok(mut file) = File::open(path) else {
+        return Vec::new();
+    };
+    let len = file.metadata().map_or(0, |metadata| metadata.len());
+    if len < *offset {
+        *offset = 0;
+    }
+    let mut appended = Vec::new();
+    let read = file
+        .seek(SeekFrom::Start(*offset))
+        .and_then(|_| file.take(len - *offset).read_to_end(&mut appended));
+    if read.is_err() {
+        return Vec::new();
+    }
+    let Some(end) = appended.iter().rposition(|byte| *byte == b'\n') else {
+        return Vec::new();
+    };
+    appended.truncate(end + 1);
+    *offset += appended.len() as u64;
+    appended
+}
+
+/// Parse a journal file; entries without a usable timestamp are dated with the file's
+/// modification time. With the `entry-cache` feature, unchanged files are read from the
+/// persistent cache (see `cache`). Future-dated entries are quarantined; the cache keeps
+/// them, so they count once the clock catches up.
+pub fn parse_usage_from_file(path: &PathBuf) -> Result<Vec<ParsedEntry>, String> {
+    // Cache hits count as parsed files too
+    let mut perf = PerfSpan::start("parse_file");
+    perf.add_files(1);
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let metadata = file.metadata().ok();
+    #[cfg(feature = "entry-cache")]
+    if let Some(mut entries) = metadata.as_ref().and_then(|m| crate::cache::load(path, m)) {
+        perf.add_entries(entries.len());
+        quarantine_future_entries(&mut entries, Utc::now());
+        return Ok(entries);
+    }
+    let mut entries = parse_journal(file, metadata.as_ref());
+    perf.add_entries(entries.len());
+    #[cfg(feature = "entry-cache")]
+    if let Some(ref metadata) = metadata {
+        crate::cache::store(path, metadata, &entries);
+    }
+    quarantine_future_entries(&mut entries, Utc::now());
+    Ok(entries)
+}
+
+/// Parse an open journal, bypassing the cache; `metadata` supplies the fallback timestamp
+pub(crate) fn parse_journal(file: File, metadata: Option<&Metadata>) -> Vec<ParsedEntry> {
+    let mtime = metadata
+        .and_then(|m| m.modified().ok())
+        .map(|t| DateTime::<Utc>::from(t).to_rfc3339_opts(SecondsFormat::Millis, true));
+    parse_usage_with_fallback(BufReader::new(file), mtime)
+}
+
+/// Parse assistant usage entries from journal lines. Malformed lines (invalid JSON or
+/// UTF-8, unexpected shapes, out-of-range numbers) and oversized lines are skipped.
+/// Entries without a usable timestamp keep an empty one; future-dated ones are quarantined.
+pub fn parse_usage_from_reader<R: BufRead>(reader: R) -> Vec<ParsedEntry> {
+    let mut entries = parse_usage_with_fallback(reader, None);
+    quarantine_future_entries(&mut entries, Utc::now());
+    entries
+}
+
+/// `parse_usage_from_reader`, dating entries with a missing or unparsable timestamp with
+/// `fallback_timestamp`. Undated entries and timestamps that jump backwards are counted in
+/// `parse_health`.
+pub fn parse_usage_with_fallback<R: BufRead>(
+    reader: R,
+    fallback_timestamp: Option<String>,
+) -> Vec<ParsedEntry> {
+    let mut usages = Vec::new();
+    let mut skew = SkewTracker::new();
+
+    // Track the cwd from the most recent entry (for entries that don't have cwd)
+    let mut last_cwd = String::new();
+    // uuid -> parent uuid for every entry, and the uuids of usage entries
+    let mut parents: HashMap<String, Option<String>> = HashMap::new();
+    let mut assistant_uuids: HashSet<String> = HashSet::new();
+
+    for line in journal_lines(reader) {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entry: JournalEntry = match from_line(&line) {
+            Some(e) => e,
+            None => {
+                record(|health| {
+                    health.malformed_lines = health.malformed_lines.saturating_add(1);
+                });
+                continue;
+            }
+        };
+
+        if let Some(ref timestamp) = entry.timestamp {
+            skew.observe(timestamp);
+        }
+
+        // Update last_cwd if this entry has a cwd
+        if let Some(ref cwd) = entry.cwd {
+            last_cwd = cwd.clone();
+        }
+        let parent = entry
+            .parent_uuid
+            .clone()
+            .or_else(|| entry.logical_parent_uuid.clone());
+        if let Some(ref uuid) = entry.uuid {
+            parents.insert(uuid.clone(), parent.clone());
+        }
+
+        // Only process assistant messages with usage data
+        if entry.entry_type.as_deref() != Some("assistant") {
+            continue;
+        }
+
+        if let Some(message) = entry.message {
+            if let (Some(model), Some(usage)) = (message.model, message.usage) {
+                let timestamp = match entry.timestamp {
+                    Some(ts) if DateTime::parse_from_rfc3339(&ts).is_ok() => ts,
+                    _ => {
+                        record(|health| {
+                            health.undated_entries = health.undated_entries.saturating_add(1);
+                        });
+                        fallback_timestamp.clone().unwrap_or_default()
+                    }
+                };
+                let session_id = entry.session_id.unwrap_or_default();
+                let cwd = entry.cwd.unwrap_or_else(|| last_cwd.clone());
+                let output_tokens = usage.output_tokens.unwrap_or(0);
+                let tokens = TokenUsage {
+                    input_tokens: usage.input_tokens.unwrap_or(0),
+                    output_tokens,
+                    cache_creation_input_tokens: usage.cache_creation_input_tokens.unwrap_or(0),
+                    cache_read_input_tokens: usage.cache_read_input_tokens.unwrap_or(0),
+                    cache_creation_5m_input_tokens: usage
+                        .cache_creation
+                        .as_ref()
+                        .and_then(|c| c.ephemeral_5m_input_tokens)
+                        .unwrap_or(0),
+                    cache_creation_1h_input_tokens: usage
+                        .cache_creation
+                        .as_ref()
+                        .and_then(|c| c.ephemeral_1h_input_tokens)
+                        .unwrap_or(0),
+                    thinking_tokens: thinking_tokens_in(message.content.as_ref(), output_tokens),
+                };
+                let uuid = entry.uuid.unwrap_or_default();
+                let parent_uuid = nearest_assistant_ancestor(parent, &parents, &assistant_uuids);
+                if !uuid.is_empty() {
+                    assistant_uuids.insert(uuid.clone());
+                }
+                usages.push(ParsedEntry {
+                    provider: model_provider(&model).map(str::to_string),
+                    model: canonical_model_id(&model),
+                    tokens,
+                    timestamp,
+                    session_id,
+                    cwd,
+                    completed_todos: completed_todos_in(message.content),
+                    uuid,
+                    parent_uuid,
+                    is_sidechain: entry.is_sidechain,
+                });
+            }
+        }
+    }
+
+    usages
+}
+
+/// Walk up from `parent` to the first entry in `assistant_uuids`. Parents always precede
+/// their children in a journal, so `parents` already holds the whole chain.
+fn nearest_assistant_ancestor(
+    mut parent: Option<String>,
+    parents: &HashMap<String, Option<String>>,
+    assistant_uuids: &HashSet<String>,
+) -> Option<String> {
+    // Bounded walk in case of a malformed cycle
+    for _ in 0..=parents.len() {
+        let uuid = parent?;
+        if assistant_uuids.contains(&uuid) {
+            return Some(uuid);
+        }
+        parent = parents.get(&uuid).cloned().flatten();
+    }
+    None
+}
+
+/// What the quota and activity scans need from a journal line
+#[derive(Debug)]
+pub(crate) struct EntryInfo {
+    pub(crate) entry_type: String,
+    pub(crate) timestamp: String,
+    /// Only present for assistant messages
+    pub(crate) model: Option<String>,
+    /// An actual user prompt, not a tool_result-only message
+    pub(crate) is_user_prompt: bool,
+}
+
+/// A prompt has text content: a plain string, or a text block besides any tool results
+fn has_prompt_text(content: &serde_json::Value) -> bool {
+    if content.is_string() {
+        return true;
+    }
+    content.as_array().is_some_and(|blocks| {
+        blocks
+            .iter()
+            .any(|block| block.get("type").and_then(|t| t.as_str()) == Some("text"))
+    })
+}
+
+/// Parse a line once into its type, timestamp, model and prompt flag; None if the line is
+/// not JSON, lacks a type or timestamp, or is dated after `future_cutoff`
+pub(crate) fn parse_entry_info(line: &str) -> Option<EntryInfo> {
+    let entry: serde_json::Value = from_line(line)?;
+
+    let entry_type = entry.get("type").and_then(|t| t.as_str())?.to_string();
+    let timestamp = entry.get("timestamp").and_then(|t| t.as_str())?.to_string();
+    if DateTime::parse_from_rfc3339(&timestamp).is_ok_and(|ts| ts > future_cutoff(Utc::now())) {
+        return None;
+    }
+    let message = entry.get("message");
+
+    let model = if entry_type == "assistant" {
+        message
+            .and_then(|m| m.get("model"))
+            .and_then(|m| m.as_str())
+            .map(canonical_model_id)
+    } else {
+        None
+    };
+
+    let is_user_prompt = entry_type == "user"
+        && message
+            .and_then(|m| m.get("content"))
+            .is_some_and(has_prompt_text);
+
+    Some(EntryInfo {
+        entry_type,
+        timestamp,
+        model,
+        is_user_prompt,
+    })
+}
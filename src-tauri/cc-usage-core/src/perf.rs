@@ -0,0 +1,97 @@
+//! Timing of the expensive operations (usage aggregation, journal parsing, API calls), for
+//! "slow widget" reports and spotting regressions.
+//!
+//! A `PerfSpan` measures one run of an operation. It is also a `tracing` span named
+//! "perf" with the operation as its `op` field, so a tracing subscriber sees the same
+//! durations and counts; without a subscriber, the timings are still kept for `perf_stats`.
+
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::field::Empty;
+
+use crate::types::PerfTiming;
+
+static TIMINGS: Mutex<BTreeMap<&'static str, PerfTiming>> = Mutex::new(BTreeMap::new());
+
+/// One timed run of an operation; recorded when dropped
+pub struct PerfSpan {
+    op: &'static str,
+    started: Instant,
+    files: u64,
+    entries: u64,
+    span: tracing::Span,
+}
+
+impl PerfSpan {
+    pub fn start(op: &'static str) -> Self {
+        Self {
+            op,
+            started: Instant::now(),
+            files: 0,
+            entries: 0,
+            span: tracing::info_span!(
+                "perf",
+                op,
+                duration_ms = Empty,
+                files = Empty,
+                entries = Empty
+            ),
+        }
+    }
+
+    /// Count files scanned by this run
+    pub fn add_files(&mut self, count: usize) {
+        self.files += count as u64;
+    }
+
+    /// Count entries (journal entries, API buckets, ...) produced by this run
+    pub fn add_entries(&mut self, count: usize) {
+        self.entries += count as u64;
+    }
+
+    /// The tracing span, e.g. to make it the parent of nested spans
+    pub fn span(&self) -> &tracing::Span {
+        &self.span
+    }
+}
+
+impl Drop for PerfSpan {
+    fn drop(&mut self) {
+        let duration_ms = self.started.elapsed().as_secs_f64() * 1000.0;
+        self.span.record("duration_ms", duration_ms);
+        self.span.record("files", self.files);
+        self.span.record("entries", self.entries);
+
+        let Ok(mut timings) = TIMINGS.lock() else {
+            return;
+        };
+        let timing = timings.entry(self.op).or_insert_with(|| PerfTiming {
+            op: self.op.to_string(),
+            ..PerfTiming::default()
+        });
+        timing.calls += 1;
+        timing.last_ms = duration_ms;
+        timing.total_ms += duration_ms;
+        timing.max_ms = timing.max_ms.max(duration_ms);
+        timing.last_files = self.files;
+        timing.last_entries = self.entries;
+        timing.last_at = Utc::now().to_rfc3339();
+    }
+}
+
+/// Timings of every operation run since startup, by operation name
+pub fn perf_stats() -> Vec<PerfTiming> {
+    TIMINGS
+        .lock()
+        .map(|timings| timings.values().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Forget the recorded timings, e.g. before reproducing a slow refresh
+pub fn reset_perf_stats() {
+    if let Ok(mut timings) = TIMINGS.lock() {
+        timings.clear();
+    }
+}
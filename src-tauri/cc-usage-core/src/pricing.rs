@@ -0,0 +1,296 @@
+//! Model display names, API pricing, context limits and quota weights.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use crate::types::TokenUsage;
+
+/// Cloud provider a model id routes through, from its format: Bedrock ids look like
+/// "us.anthropic.claude-sonnet-4-20250514-v1:0" (or an inference profile ARN), Vertex ids like
+/// "claude-sonnet-4@20250514". None for Anthropic API ids.
+pub fn model_provider(model: &str) -> Option<&'static str> {
+    if model.starts_with("arn:aws:bedrock") || model.contains("anthropic.claude") {
+        Some("bedrock")
+    } else if model.contains('@') || model.contains("publishers/anthropic/models/") {
+        Some("vertex")
+    } else {
+        None
+    }
+}
+
+/// Anthropic API form of a provider-prefixed model id, e.g.
+/// "us.anthropic.claude-sonnet-4-20250514-v1:0" -> "claude-sonnet-4-20250514", so usage
+/// through Bedrock or Vertex is priced and named like the same model on the API
+pub fn canonical_model_id(model: &str) -> String {
+    if model_provider(model).is_none() {
+        return model.to_string();
+    }
+    // ARNs and Vertex resource paths end in the model id
+    let id = model.rsplit('/').next().unwrap_or(model);
+    // Bedrock region and vendor prefix: "us.anthropic."
+    let id = match id.find("anthropic.") {
+        Some(i) => &id[i + "anthropic.".len()..],
+        None => id,
+    };
+    // Bedrock version suffix: "-v1:0"
+    let id = match id.rfind("-v") {
+        Some(i)
+            if id.len() > i + 2 && id[i + 2..].chars().all(|c| c.is_ascii_digit() || c == ':') =>
+        {
+            &id[..i]
+        }
+        _ => id,
+    };
+    // Vertex version separator: "claude-sonnet-4@20250514"
+    id.replace('@', "-")
+}
+
+pub fn get_model_display_name(model: &str) -> String {
+    let model = &canonical_model_id(model);
+    // Extract meaningful parts from model ID like "claude-opus-4-5-20251101"
+    if model.contains("opus-4-5") || model.contains("opus-4.5") {
+        "Opus 4.5".to_string()
+    } else if model.contains("opus-4") {
+        "Opus 4".to_string()
+    } else if model.contains("opus") {
+        "Opus".to_string()
+    } else if model.contains("sonnet-4") {
+        "Sonnet 4".to_string()
+    } else if model.contains("sonnet-3-5") || model.contains("sonnet-3.5") {
+        "Sonnet 3.5".to_string()
+    } else if model.contains("sonnet") {
+        "Sonnet".to_string()
+    } else if model.contains("haiku-3-5") || model.contains("haiku-3.5") {
+        "Haiku 3.5".to_string()
+    } else if model.contains("haiku") {
+        "Haiku".to_string()
+    } else {
+        model.to_string()
+    }
+}
+
+// Pricing per million tokens (as of 2025)
+pub fn get_model_pricing(model: &str) -> (f64, f64, f64, f64) {
+    // (input, output, cache_write, cache_read) per million tokens
+    match model {
+        m if m.contains("opus") => (15.0, 75.0, 18.75, 1.50),
+        m if m.contains("sonnet") => (3.0, 15.0, 3.75, 0.30),
+        m if m.contains("haiku") => (0.25, 1.25, 0.30, 0.03),
+        _ => (3.0, 15.0, 3.75, 0.30), // default to sonnet pricing
+    }
+}
+
+/// Price per million tokens of cache writes with a 1 hour TTL: twice the input price, where
+/// the 5 minute writes in `get_model_pricing` cost 1.25 times
+pub fn get_cache_write_1h_price(model: &str) -> f64 {
+    get_model_pricing(model).0 * 2.0
+}
+
+pub fn calculate_cost(model: &str, tokens: &TokenUsage) -> f64 {
+    cost_at_prices(get_model_pricing(model), tokens)
+}
+
+/// Cost of `tokens` at (input, output, cache_write, cache_read) prices per million tokens
+fn cost_at_prices(prices: (f64, f64, f64, f64), tokens: &TokenUsage) -> f64 {
+    let (input_price, output_price, cache_write_price, cache_read_price) = prices;
+    let million = 1_000_000.0;
+    // Cache writes not reported as 1h are priced as 5m
+    let cache_1h = tokens
+        .cache_creation_1h_input_tokens
+        .min(tokens.cache_creation_input_tokens);
+    let cache_5m = tokens.cache_creation_input_tokens - cache_1h;
+
+    (tokens.input_tokens as f64 / million * input_price)
+        + (tokens.output_tokens as f64 / million * output_price)
+        + (cache_5m as f64 / million * cache_write_price)
+        + (cache_1h as f64 / million * input_price * 2.0)
+        + (tokens.cache_read_input_tokens as f64 / million * cache_read_price)
+}
+
+/// Prices of one model in a pricing profile, per million tokens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPrice {
+    /// Matched against the canonical model id by substring
+    pub pattern: String,
+    pub input: f64,
+    pub output: f64,
+    pub cache_write: f64,
+    pub cache_read: f64,
+}
+
+/// Rates for usage billed through a cloud provider (Bedrock, Vertex) rather than the
+/// Anthropic API. Models matching no `models` entry cost the API price times `multiplier`,
+/// e.g. 1.1 for regional endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricingProfile {
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f64,
+    /// Tried in order, first match wins
+    #[serde(default)]
+    pub models: Vec<ModelPrice>,
+}
+
+fn default_multiplier() -> f64 {
+    1.0
+}
+
+impl Default for PricingProfile {
+    fn default() -> Self {
+        Self {
+            multiplier: default_multiplier(),
+            models: Vec::new(),
+        }
+    }
+}
+
+impl PricingProfile {
+    pub fn cost(&self, model: &str, tokens: &TokenUsage) -> f64 {
+        match self
+            .models
+            .iter()
+            .find(|m| model.contains(m.pattern.as_str()))
+        {
+            Some(p) => cost_at_prices((p.input, p.output, p.cache_write, p.cache_read), tokens),
+            None => calculate_cost(model, tokens) * self.multiplier,
+        }
+    }
+}
+
+/// Built-in profiles: Bedrock and Vertex list Claude models at the Anthropic API's prices on
+/// their global endpoints. Regional endpoints cost more; configure a profile to price them.
+pub fn builtin_pricing_profiles() -> HashMap<String, PricingProfile> {
+    ["bedrock", "vertex"]
+        .into_iter()
+        .map(|provider| (provider.to_string(), PricingProfile::default()))
+        .collect()
+}
+
+static PROVIDER_PRICING: OnceLock<RwLock<HashMap<String, PricingProfile>>> = OnceLock::new();
+
+fn provider_table() -> &'static RwLock<HashMap<String, PricingProfile>> {
+    PROVIDER_PRICING.get_or_init(|| RwLock::new(builtin_pricing_profiles()))
+}
+
+/// Set the pricing profiles by provider ("bedrock", "vertex"); providers without one use
+/// the built-in profile, if any, and are priced like the Anthropic API otherwise
+pub(crate) fn set_provider_pricing(profiles: HashMap<String, PricingProfile>) {
+    if let Ok(mut table) = provider_table().write() {
+        let mut merged = builtin_pricing_profiles();
+        merged.extend(profiles);
+        *table = merged;
+    }
+}
+
+/// The pricing profiles currently in effect, by provider, built-in ones included
+pub fn provider_pricing() -> HashMap<String, PricingProfile> {
+    provider_table()
+        .read()
+        .map(|table| table.clone())
+        .unwrap_or_default()
+}
+
+/// `calculate_cost` with the pricing profile of the provider the usage went through
+pub fn calculate_cost_for(model: &str, provider: Option<&str>, tokens: &TokenUsage) -> f64 {
+    if let (Some(provider), Ok(table)) = (provider, provider_table().read()) {
+        if let Some(profile) = table.get(provider) {
+            return profile.cost(model, tokens);
+        }
+    }
+    calculate_cost(model, tokens)
+}
+
+/// Cost of the thinking tokens in `tokens`, which are billed as output tokens
+pub fn thinking_cost_for(model: &str, provider: Option<&str>, tokens: &TokenUsage) -> f64 {
+    let thinking = TokenUsage {
+        output_tokens: tokens.thinking_tokens,
+        ..Default::default()
+    };
+    calculate_cost_for(model, provider, &thinking)
+}
+
+/// Get context window size for a model (in tokens)
+pub fn get_model_context_limit(_model: &str) -> u64 {
+    // All Claude 3.5/4 models have 200K context windows
+    200_000
+}
+
+/// Calculate context remaining percentage
+pub fn calculate_context_remaining(total_tokens: u64, model: &str) -> f64 {
+    let limit = get_model_context_limit(model);
+    let used_percent = (total_tokens as f64 / limit as f64) * 100.0;
+    (100.0 - used_percent).max(0.0)
+}
+
+/// One row of the quota weight table: models whose id contains `pattern` count `weight`
+/// Sonnet-equivalent prompts each
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaWeightRule {
+    pub pattern: String,
+    pub weight: f64,
+}
+
+/// How much each model's prompts count toward quota limits, relative to Sonnet.
+/// Rules are matched in order; models matching none use `default_weight`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaWeights {
+    pub rules: Vec<QuotaWeightRule>,
+    pub default_weight: f64,
+}
+
+impl Default for QuotaWeights {
+    /// Weights calibrated based on observed Claude /usage percentages
+    fn default() -> Self {
+        let rule = |pattern: &str, weight: f64| QuotaWeightRule {
+            pattern: pattern.to_string(),
+            weight,
+        };
+        Self {
+            rules: vec![
+                rule("opus", 2.75), // Opus uses ~2.75x quota vs Sonnet based on observed rate limits
+                rule("haiku", 0.25), // Haiku is much cheaper
+            ],
+            default_weight: 1.0, // Sonnet is baseline
+        }
+    }
+}
+
+impl QuotaWeights {
+    pub fn weight_for(&self, model: &str) -> f64 {
+        self.rules
+            .iter()
+            .find(|rule| model.contains(rule.pattern.as_str()))
+            .map(|rule| rule.weight)
+            .unwrap_or(self.default_weight)
+    }
+}
+
+static QUOTA_WEIGHTS: OnceLock<RwLock<QuotaWeights>> = OnceLock::new();
+
+fn weight_table() -> &'static RwLock<QuotaWeights> {
+    QUOTA_WEIGHTS.get_or_init(|| RwLock::new(QuotaWeights::default()))
+}
+
+/// Replace the weight table used by all quota calculations (`None` restores the defaults)
+pub(crate) fn set_quota_weights(weights: Option<QuotaWeights>) {
+    if let Ok(mut table) = weight_table().write() {
+        *table = weights.unwrap_or_default();
+    }
+}
+
+/// The weight table currently in effect
+pub fn quota_weights() -> QuotaWeights {
+    weight_table()
+        .read()
+        .map(|table| table.clone())
+        .unwrap_or_default()
+}
+
+/// Get model weight for quota calculation
+/// Claude's rate limiting weights usage by model - Opus uses more quota than Sonnet/Haiku
+pub fn get_model_quota_weight(model: &str) -> f64 {
+    weight_table()
+        .read()
+        .map(|table| table.weight_for(model))
+        .unwrap_or(1.0)
+}
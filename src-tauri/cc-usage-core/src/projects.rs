@@ -0,0 +1,219 @@
+//! Project names for session working directories.
+//!
+//! The same project can show up under several paths: through a symlink, from a git worktree
+//! or with a trailing slash. Paths are normalized (symlinks resolved, worktrees mapped to
+//! their main checkout) before the last component is taken as the project name, and
+//! user-defined rules can map paths to names explicitly.
+//!
+//! With `CoreSettings::group_by_remote`, checkouts are named after their git remote instead, so the
+//! same repository cloned in two places counts as one project. Remotes are read from the
+//! repository's `.git/config` (never written) once per checkout.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+
+/// Maps working directories matching `pattern` (a regex, matched against the normalized
+/// path) to `project`, which may refer to capture groups (`$1`, `${name}`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectRule {
+    pub pattern: String,
+    pub project: String,
+}
+
+static RULES: RwLock<Vec<(Regex, String)>> = RwLock::new(Vec::new());
+
+/// cwd -> normalized path; resolving touches the file system, and cwds repeat on every line
+static NORMALIZED: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+static GROUP_BY_REMOTE: AtomicBool = AtomicBool::new(false);
+
+/// Repository root -> remote identity; None for checkouts without a remote
+static REMOTES: Mutex<Option<HashMap<PathBuf, Option<String>>>> = Mutex::new(None);
+
+/// Name projects after their git remote (`owner/repo`) rather than their directory, for
+/// checkouts that have one; project rules still take precedence
+pub(crate) fn set_group_by_remote(enabled: bool) {
+    GROUP_BY_REMOTE.store(enabled, Ordering::Relaxed);
+}
+
+fn compile_rules(rules: &[ProjectRule]) -> Result<Vec<(Regex, String)>, String> {
+    rules
+        .iter()
+        .map(|rule| {
+            Regex::new(&rule.pattern)
+                .map(|re| (re, rule.project.clone()))
+                .map_err(|e| format!("Invalid project rule pattern '{}': {e}", rule.pattern))
+        })
+        .collect()
+}
+
+/// Check that every rule's pattern compiles, e.g. before saving rules the user entered
+pub fn check_project_rules(rules: &[ProjectRule]) -> Result<(), String> {
+    compile_rules(rules).map(|_| ())
+}
+
+/// Replace the path mapping rules, tried in order. Fails on the first invalid pattern,
+/// leaving the previous rules in place.
+pub(crate) fn set_project_rules(rules: &[ProjectRule]) -> Result<(), String> {
+    let compiled = compile_rules(rules)?;
+    if let Ok(mut current) = RULES.write() {
+        *current = compiled;
+    }
+    Ok(())
+}
+
+/// Main checkout of a git worktree: its `.git` is a file pointing into
+/// `<main>/.git/worktrees/<name>`
+fn worktree_main_checkout(dir: &Path) -> Option<PathBuf> {
+    let git_file = std::fs::read_to_string(dir.join(".git")).ok()?;
+    let gitdir = PathBuf::from(git_file.trim().strip_prefix("gitdir:")?.trim());
+    let gitdir = if gitdir.is_absolute() {
+        gitdir
+    } else {
+        dir.join(gitdir)
+    };
+    let worktrees = gitdir.parent()?;
+    if worktrees.file_name()? != "worktrees" {
+        return None;
+    }
+    let main = worktrees.parent()?.parent()?;
+    Some(std::fs::canonicalize(main).unwrap_or_else(|_| main.to_path_buf()))
+}
+
+fn resolve(cwd: &str) -> String {
+    let trimmed = cwd.trim_end_matches('/');
+    let path = Path::new(if trimmed.is_empty() { cwd } else { trimmed });
+    // Directories that no longer exist keep their recorded path
+    let canonical = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    // Only the enclosing repository's own `.git` decides whether this is a worktree
+    let worktree = canonical
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .and_then(|root| worktree_main_checkout(root).map(|main| (root, main)));
+    let resolved = match worktree {
+        Some((root, main)) => match canonical.strip_prefix(root) {
+            Ok(rest) if !rest.as_os_str().is_empty() => main.join(rest),
+            _ => main,
+        },
+        None => canonical.clone(),
+    };
+    resolved.to_string_lossy().replace('\\', "/")
+}
+
+/// `cwd` with symlinks resolved, worktrees mapped to their main checkout and no trailing
+/// slash
+pub fn normalize_project_path(cwd: &str) -> String {
+    if cwd.is_empty() {
+        return String::new();
+    }
+    if let Ok(mut cache) = NORMALIZED.lock() {
+        let cache = cache.get_or_insert_with(HashMap::new);
+        if let Some(path) = cache.get(cwd) {
+            return path.clone();
+        }
+        let path = resolve(cwd);
+        cache.insert(cwd.to_string(), path.clone());
+        return path;
+    }
+    resolve(cwd)
+}
+
+/// `host/owner/repo` for a remote URL, so https, ssh and scp-style URLs of the same
+/// repository compare equal
+fn remote_identity(url: &str) -> Option<String> {
+    let url = url.trim();
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest.to_string(),
+        // scp-style `git@host:owner/repo`
+        None => url.replacen(':', "/", 1),
+    };
+    let (authority, path) = rest.split_once('/')?;
+    // Drop the user and the port of `ssh://git@host:22/...`
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host).to_lowercase();
+    let path = path.trim_matches('/').trim_end_matches(".git");
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some(format!("{host}/{path}"))
+}
+
+/// URL of the `origin` remote in a git config file, else of the first remote
+fn config_remote_url(config: &str) -> Option<String> {
+    let mut current: Option<String> = None;
+    let mut first: Option<String> = None;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            current = line
+                .strip_prefix("[remote \"")
+                .and_then(|rest| rest.strip_suffix("\"]"))
+                .map(str::to_string);
+            continue;
+        }
+        let remote = match current.as_deref() {
+            Some(remote) => remote,
+            None => continue,
+        };
+        let url = line
+            .split_once('=')
+            .filter(|(key, _)| key.trim() == "url")
+            .map(|(_, value)| value.trim().to_string());
+        match url {
+            Some(url) if remote == "origin" => return Some(url),
+            Some(url) => {
+                first.get_or_insert(url);
+            }
+            None => {}
+        }
+    }
+    first
+}
+
+fn read_remote(repo_root: &Path) -> Option<String> {
+    let config = std::fs::read_to_string(repo_root.join(".git").join("config")).ok()?;
+    remote_identity(&config_remote_url(&config)?)
+}
+
+/// Remote identity (`host/owner/repo`) of the git checkout containing `cwd`, if it has a
+/// remote. Worktrees resolve to their main checkout's remote.
+pub fn repository_remote(cwd: &str) -> Option<String> {
+    let path = PathBuf::from(normalize_project_path(cwd));
+    let root = path.ancestors().find(|dir| dir.join(".git").is_dir())?;
+    if let Ok(mut cache) = REMOTES.lock() {
+        let cache = cache.get_or_insert_with(HashMap::new);
+        return cache
+            .entry(root.to_path_buf())
+            .or_insert_with(|| read_remote(root))
+            .clone();
+    }
+    read_remote(root)
+}
+
+/// Project a working directory belongs to: the first matching rule's name, else (with
+/// `CoreSettings::group_by_remote`) `owner/repo` of its git remote, else the last component of the
+/// normalized path
+pub fn project_name(cwd: &str) -> String {
+    let path = normalize_project_path(cwd);
+    if let Ok(rules) = RULES.read() {
+        for (re, project) in rules.iter() {
+            if let Some(caps) = re.captures(&path) {
+                let mut name = String::new();
+                caps.expand(project, &mut name);
+                return name;
+            }
+        }
+    }
+    if GROUP_BY_REMOTE.load(Ordering::Relaxed) {
+        if let Some(remote) = repository_remote(cwd) {
+            // Without the host: `owner/repo`
+            return remote
+                .split_once('/')
+                .map_or(remote.clone(), |(_, path)| path.to_string());
+        }
+    }
+    path.rsplit('/').next().unwrap_or(&path).to_string()
+}
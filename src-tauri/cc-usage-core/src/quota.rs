@@ -0,0 +1,721 @@
+//! Quota estimation: model-weighted prompt counts and activity-anchored 5hr blocks, plus
+//! custom rolling windows for deployments with their own rate limits.
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use crate::hours::{counted_hours_in, day_start_in, hours_until_counted, week_start};
+use crate::parser::{
+    future_cutoff, journal_lines, parse_entry_info, parse_usage_from_file,
+    parse_user_prompt_timestamp,
+};
+use crate::pricing::{get_model_quota_weight, quota_weights, QuotaWeights};
+use crate::sources::{collect_jsonl_files, is_excluded_journal, own_data_dirs};
+use crate::types::{
+    PaceStatus, QuotaInfo, QuotaMetric, QuotaWindowStatus, ThrottleAction, ThrottleAdvice,
+    UsagePlan,
+};
+
+/// Estimated Sonnet-equivalent prompts per 5hr window (calibrated to match Claude /usage)
+pub const ESTIMATED_WINDOW_LIMIT: u32 = 500;
+
+/// Estimated Sonnet-equivalent prompts per week (calibrated to match Claude /usage)
+pub const ESTIMATED_WEEKLY_LIMIT: u32 = 2590;
+
+/// Weekly usage hours advertised for the plan
+pub const WEEK_LIMIT_HOURS: u32 = 210;
+
+const PLAN_NAME: &str = "Max 5x";
+
+/// Weekly usage within this many percentage points of the even pace counts as on track
+pub const PACE_TOLERANCE_PERCENT: f64 = 5.0;
+
+/// `throttle_advice` says stop once the 5hr window or the week is this full
+pub const THROTTLE_STOP_PERCENT: f64 = 95.0;
+
+/// The 5hr burn rate is measured over at least this long, so the first prompts of a block
+/// don't extrapolate to an absurd rate
+const MIN_BURN_HOURS: f64 = 0.25;
+
+/// A custom rolling quota window, e.g. an enterprise rate limit of 40,000 tokens per minute
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaWindowDef {
+    pub name: String,
+    pub window_minutes: u32,
+    #[serde(default)]
+    pub metric: QuotaMetric,
+    pub limit: f64,
+}
+
+static QUOTA_WINDOWS: RwLock<Vec<QuotaWindowDef>> = RwLock::new(Vec::new());
+
+/// Replace the custom windows reported in `QuotaInfo.windows` after the built-in ones
+pub(crate) fn set_quota_windows(windows: Vec<QuotaWindowDef>) {
+    if let Ok(mut current) = QUOTA_WINDOWS.write() {
+        *current = windows;
+    }
+}
+
+/// The custom windows currently in effect
+pub fn quota_windows() -> Vec<QuotaWindowDef> {
+    QUOTA_WINDOWS
+        .read()
+        .map(|windows| windows.clone())
+        .unwrap_or_default()
+}
+
+/// The exact quota estimation model, for users who want to verify or tune the estimates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaModel {
+    pub formula: String,
+    pub weights: QuotaWeights,
+    pub plan: String,
+    pub window_hours: u32,
+    pub window_limit: u32,
+    pub weekly_limit: u32,
+    pub week_limit_hours: u32,
+}
+
+/// Describe the current quota estimation: weight table, limits and how they combine
+pub fn quota_model() -> QuotaModel {
+    QuotaModel {
+        formula: format!(
+            "weighted = sum over user prompts of weight(model of the preceding assistant reply, \
+             default_weight if none yet); \
+             window_percent = min(100, weighted in the active {h}h block / {w} * 100), \
+             where a block starts at the first prompt after the previous block ended; \
+             week_percent = min(100, weighted over the last 7 days / {k} * 100)",
+            h = QUOTA_WINDOW_HOURS,
+            w = ESTIMATED_WINDOW_LIMIT,
+            k = ESTIMATED_WEEKLY_LIMIT,
+        ),
+        weights: quota_weights(),
+        plan: PLAN_NAME.to_string(),
+        window_hours: QUOTA_WINDOW_HOURS as u32,
+        window_limit: ESTIMATED_WINDOW_LIMIT,
+        weekly_limit: ESTIMATED_WEEKLY_LIMIT,
+        week_limit_hours: WEEK_LIMIT_HOURS,
+    }
+}
+
+/// Build quota info from the active 5hr block, the weighted prompt count of the last 7 days,
+/// that of the week so far (see `count_week_weighted_usage`) and the custom windows' usage
+/// (see `custom_window_statuses`)
+pub fn build_quota_info(
+    quota_block: Option<&QuotaBlock>,
+    week_weighted: f64,
+    week_to_date_weighted: f64,
+    custom_windows: Vec<QuotaWindowStatus>,
+) -> QuotaInfo {
+    build_quota_info_at(
+        quota_block,
+        week_weighted,
+        week_to_date_weighted,
+        custom_windows,
+        Utc::now(),
+    )
+}
+
+/// `build_quota_info` as of `at`: the weekly pace is judged at that time
+fn build_quota_info_at(
+    quota_block: Option<&QuotaBlock>,
+    week_weighted: f64,
+    week_to_date_weighted: f64,
+    custom_windows: Vec<QuotaWindowStatus>,
+    at: DateTime<Utc>,
+) -> QuotaInfo {
+    // Estimate quota - Max 5x plan defaults (based on Anthropic docs: 50-200 prompts/5hr)
+    // Using 500 as the base limit for Sonnet-equivalent prompts (calibrated to match Claude /usage)
+    // Weighted count accounts for model costs (see `QuotaWeights`)
+    let estimated_limit: u32 = ESTIMATED_WINDOW_LIMIT;
+    let window_weighted = quota_block.map(|b| b.weighted).unwrap_or(0.0);
+    let usage_percent = (window_weighted / estimated_limit as f64 * 100.0).min(100.0);
+
+    // Weekly limit estimation - calibrated to match Claude /usage output
+    let week_usage_percent = (week_weighted / ESTIMATED_WEEKLY_LIMIT as f64 * 100.0).min(100.0);
+    let week_to_date_percent =
+        (week_to_date_weighted / ESTIMATED_WEEKLY_LIMIT as f64 * 100.0).min(100.0);
+
+    let mut info = QuotaInfo {
+        messages_in_window: quota_block.map(|b| b.prompts).unwrap_or(0),
+        window_hours: QUOTA_WINDOW_HOURS as u32,
+        estimated_limit,
+        usage_percent,
+        plan: PLAN_NAME.to_string(),
+        week_usage_percent,
+        week_limit_hours: WEEK_LIMIT_HOURS,
+        window_started_at: quota_block.map(|b| b.start.to_rfc3339()),
+        window_ends_at: quota_block.map(|b| b.end.to_rfc3339()),
+        week_to_date_percent,
+        pace_status: PaceStatus::OnTrack,
+        pace_delta_percent: 0.0,
+        safe_pace_percent_per_hour: 0.0,
+        expected_week_percent: 0.0,
+        projected_week_usage_percent: week_to_date_percent,
+        windows: vec![
+            QuotaWindowStatus {
+                name: format!("{QUOTA_WINDOW_HOURS}hr"),
+                metric: QuotaMetric::Weighted,
+                window_minutes: QUOTA_WINDOW_HOURS as u32 * 60,
+                limit: estimated_limit as f64,
+                used: window_weighted,
+                usage_percent,
+                builtin: true,
+            },
+            QuotaWindowStatus {
+                name: "week".to_string(),
+                metric: QuotaMetric::Weighted,
+                window_minutes: 7 * 24 * 60,
+                limit: ESTIMATED_WEEKLY_LIMIT as f64,
+                used: week_weighted,
+                usage_percent: week_usage_percent,
+                builtin: true,
+            },
+        ],
+    };
+    apply_weekly_pace(&mut info, at);
+    info.windows.extend(custom_windows);
+    info
+}
+
+/// Usage of each custom window over the `window_minutes` before `at`
+pub fn custom_window_statuses(
+    defs: &[QuotaWindowDef],
+    at: DateTime<Utc>,
+) -> Vec<QuotaWindowStatus> {
+    let longest = match defs.iter().map(|def| def.window_minutes).max() {
+        Some(minutes) => chrono::Duration::minutes(minutes as i64),
+        None => return Vec::new(),
+    };
+    let since = at - longest;
+    let hours_back = (Utc::now() - since).num_hours().max(0) + 1;
+    let files = collect_jsonl_files(&own_data_dirs(), Some(hours_back));
+
+    let prompts = if defs.iter().any(|def| def.metric != QuotaMetric::Tokens) {
+        collect_weighted_prompts(&files, since)
+    } else {
+        Vec::new()
+    };
+    // (timestamp, rate-limited tokens) of every response in range
+    let mut token_events: Vec<(DateTime<Utc>, u64)> = Vec::new();
+    if defs.iter().any(|def| def.metric == QuotaMetric::Tokens) {
+        for file in &files {
+            let entries = match parse_usage_from_file(file) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries {
+                if let Ok(ts) = DateTime::parse_from_rfc3339(&entry.timestamp) {
+                    let ts = ts.with_timezone(&Utc);
+                    if ts >= since && ts <= at {
+                        let tokens = entry
+                            .tokens
+                            .input_tokens
+                            .saturating_add(entry.tokens.output_tokens)
+                            .saturating_add(entry.tokens.cache_creation_input_tokens);
+                        token_events.push((ts, tokens));
+                    }
+                }
+            }
+        }
+    }
+
+    defs.iter()
+        .map(|def| {
+            let start = at - chrono::Duration::minutes(def.window_minutes as i64);
+            let in_window = |ts: &DateTime<Utc>| *ts >= start && *ts <= at;
+            let used = match def.metric {
+                QuotaMetric::Prompts => {
+                    prompts.iter().filter(|(ts, _)| in_window(ts)).count() as f64
+                }
+                QuotaMetric::Weighted => prompts
+                    .iter()
+                    .filter(|(ts, _)| in_window(ts))
+                    .map(|(_, weight)| weight)
+                    .sum(),
+                QuotaMetric::Tokens => token_events
+                    .iter()
+                    .filter(|(ts, _)| in_window(ts))
+                    .map(|(_, tokens)| *tokens as f64)
+                    .sum(),
+            };
+            let usage_percent = if def.limit > 0.0 {
+                (used / def.limit * 100.0).min(100.0)
+            } else {
+                0.0
+            };
+            QuotaWindowStatus {
+                name: def.name.clone(),
+                metric: def.metric,
+                window_minutes: def.window_minutes,
+                limit: def.limit,
+                used,
+                usage_percent,
+                builtin: false,
+            }
+        })
+        .collect()
+}
+
+/// Fill in the pace fields as of `at`: compare the usage since the week started
+/// (`week_to_date_percent`) with the share of the week (local 00:00 of the first day of the
+/// week, see `week_start`, to the next) elapsed, and spread what's left over the hours
+/// remaining. Days off (see `CoreSettings::days_off`) don't count
+/// toward the week unless every day of it is off.
+pub fn apply_weekly_pace(info: &mut QuotaInfo, at: DateTime<Utc>) {
+    apply_weekly_pace_in(info, at, &Local);
+}
+
+/// `apply_weekly_pace` with weeks in the time zone `tz`. Days are measured in real hours, so
+/// a week with a daylight saving change has 167 or 169 of them.
+pub fn apply_weekly_pace_in<Tz: TimeZone>(info: &mut QuotaInfo, at: DateTime<Utc>, tz: &Tz) {
+    let week_start = week_start(at.with_timezone(tz).date_naive());
+    let days: Vec<NaiveDate> = week_start.iter_days().take(7).collect();
+    let (week_hours, elapsed_hours) = counted_hours_in(tz, &days, at);
+    let expected = (elapsed_hours / week_hours * 100.0).clamp(0.0, 100.0);
+
+    info.expected_week_percent = expected;
+    info.pace_delta_percent = info.week_to_date_percent - expected;
+    info.pace_status = if info.pace_delta_percent > PACE_TOLERANCE_PERCENT {
+        PaceStatus::Ahead
+    } else if info.pace_delta_percent < -PACE_TOLERANCE_PERCENT {
+        PaceStatus::Behind
+    } else {
+        PaceStatus::OnTrack
+    };
+    // Before the first hour counts the usage so far is all there is to go on
+    info.projected_week_usage_percent = if elapsed_hours >= 1.0 {
+        info.week_to_date_percent * week_hours / elapsed_hours
+    } else {
+        info.week_to_date_percent
+    };
+    let remaining_hours = (week_hours - elapsed_hours).max(1.0);
+    info.safe_pace_percent_per_hour =
+        (100.0 - info.week_to_date_percent).max(0.0) / remaining_hours;
+}
+
+/// Whether an automated workload should proceed, slow down or stop as of `at`, and for how
+/// long, from `info` (see `apply_weekly_pace`):
+/// - stop while the 5hr window or the week is at `THROTTLE_STOP_PERCENT`, until the block
+///   ends or the week resets
+/// - slow when the burn rate since the block started would use up the window before it
+///   ends; the wait is the pause after which the same rate fits in what is left
+/// - slow while the week is more than `PACE_TOLERANCE_PERCENT` ahead of an even pace and
+///   projected past its allowance, until the even pace catches up
+///
+/// The most severe advice wins, and the longer wait among equals.
+pub fn throttle_advice(info: &QuotaInfo, at: DateTime<Utc>) -> ThrottleAdvice {
+    let hours = |duration: chrono::Duration| duration.num_seconds().max(0) as f64 / 3600.0;
+    let parse = |ts: &Option<String>| {
+        ts.as_deref()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .map(|ts| ts.with_timezone(&Utc))
+    };
+    let window_start = parse(&info.window_started_at);
+    let window_end = parse(&info.window_ends_at);
+    let window_burn = window_start.map_or(0.0, |start| {
+        info.usage_percent / hours(at - start).max(MIN_BURN_HOURS)
+    });
+    let today = at.with_timezone(&Local).date_naive();
+    let week_days: Vec<NaiveDate> = week_start(today).iter_days().take(7).collect();
+    let week_reset = day_start_in(&Local, week_start(today) + chrono::Duration::days(7));
+
+    // (action, hours until ok, reason)
+    let mut candidates: Vec<(ThrottleAction, f64, String)> = Vec::new();
+    if info.usage_percent >= THROTTLE_STOP_PERCENT {
+        if let Some(end) = window_end {
+            candidates.push((
+                ThrottleAction::Stop,
+                hours(end - at),
+                format!("5hr window at {:.0}%", info.usage_percent),
+            ));
+        }
+    } else if let Some(end) = window_end {
+        let remaining = hours(end - at);
+        if window_burn > 0.0 && info.usage_percent + window_burn * remaining > 100.0 {
+            let affordable = (100.0 - info.usage_percent) / window_burn;
+            candidates.push((
+                ThrottleAction::Slow,
+                remaining - affordable,
+                format!(
+                    "5hr window at {:.0}%, burning {:.0}% per hour",
+                    info.usage_percent, window_burn
+                ),
+            ));
+        }
+    }
+    if info.week_usage_percent >= THROTTLE_STOP_PERCENT {
+        candidates.push((
+            ThrottleAction::Stop,
+            hours(week_reset - at),
+            format!("week at {:.0}%", info.week_usage_percent),
+        ));
+    } else if info.pace_delta_percent > PACE_TOLERANCE_PERCENT
+        && info.projected_week_usage_percent > 100.0
+    {
+        // The even pace gains 100% over the week's counted hours; days off don't close the gap
+        let (week_hours, _) = counted_hours_in(&Local, &week_days, at);
+        let catch_up = info.pace_delta_percent / 100.0 * week_hours;
+        candidates.push((
+            ThrottleAction::Slow,
+            hours_until_counted(&Local, &week_days, at, catch_up),
+            format!(
+                "week at {:.0}%, {:.0} points ahead of pace",
+                info.week_usage_percent, info.pace_delta_percent
+            ),
+        ));
+    }
+
+    let severity = |action: &ThrottleAction| match action {
+        ThrottleAction::Proceed => 0,
+        ThrottleAction::Slow => 1,
+        ThrottleAction::Stop => 2,
+    };
+    let (action, wait_hours, reason) = candidates
+        .into_iter()
+        .max_by(|a, b| {
+            severity(&a.0)
+                .cmp(&severity(&b.0))
+                .then(a.1.total_cmp(&b.1))
+        })
+        .unwrap_or((ThrottleAction::Proceed, 0.0, String::new()));
+    ThrottleAdvice {
+        action,
+        seconds_until_ok: (wait_hours * 3600.0).ceil().max(0.0) as u64,
+        reason,
+        window_percent: info.usage_percent,
+        week_percent: info.week_usage_percent,
+        window_burn_percent_per_hour: window_burn,
+    }
+}
+
+/// Whether `prompts_needed` prompts of `model` fit the quota left at `at`, given the
+/// time-sorted weighted prompts before it (see `collect_weighted_prompts`; the 7 days before
+/// `at` are enough). The work is assumed to start at once and nothing else to run meanwhile.
+/// If it doesn't fit, the earliest start is when the active block ends (5hr window) or when
+/// enough prompts have left the rolling week, whichever is later.
+pub fn plan_prompts(
+    prompts: &[(DateTime<Utc>, f64)],
+    prompts_needed: u32,
+    model: &str,
+    at: DateTime<Utc>,
+) -> UsagePlan {
+    let week = chrono::Duration::days(7);
+    let weight = get_model_quota_weight(model);
+    let needed = prompts_needed as f64 * weight;
+    let prompts: Vec<(DateTime<Utc>, f64)> = prompts
+        .iter()
+        .filter(|(ts, _)| *ts > at - week && *ts <= at)
+        .copied()
+        .collect();
+
+    let window_limit = ESTIMATED_WINDOW_LIMIT as f64;
+    let block = reconstruct_quota_blocks(&prompts)
+        .pop()
+        .filter(|block| at < block.end);
+    let window_remaining = (window_limit - block.as_ref().map_or(0.0, |b| b.weighted)).max(0.0);
+    let window_start = if needed <= window_remaining {
+        Some(at)
+    } else if needed <= window_limit {
+        block.as_ref().map(|b| b.end)
+    } else {
+        None
+    };
+
+    let week_limit = ESTIMATED_WEEKLY_LIMIT as f64;
+    let week_used: f64 = prompts.iter().map(|(_, weight)| weight).sum();
+    let week_remaining = (week_limit - week_used).max(0.0);
+    let week_start = if needed > week_limit {
+        None
+    } else {
+        // Drop the oldest prompts out of the rolling week until the work fits
+        let mut used = week_used;
+        let mut start = at;
+        let mut expiring = prompts.iter();
+        while used + needed > week_limit {
+            let Some((ts, weight)) = expiring.next() else {
+                break;
+            };
+            used -= weight;
+            start = start.max(*ts + week);
+        }
+        Some(start)
+    };
+
+    let earliest_start = match (window_start, week_start) {
+        (Some(window), Some(week)) => Some(window.max(week).to_rfc3339()),
+        _ => None,
+    };
+    UsagePlan {
+        prompts_needed,
+        model: model.to_string(),
+        weight,
+        weighted_needed: needed,
+        window_remaining,
+        week_remaining,
+        fits_window: needed <= window_remaining,
+        fits_week: needed <= week_remaining,
+        earliest_start,
+        windows_needed: (needed / window_limit).ceil() as u32,
+    }
+}
+
+/// `plan_prompts` against the local journals, starting now
+pub fn plan_usage(prompts_needed: u32, model: &str) -> UsagePlan {
+    let now = Utc::now();
+    let files = collect_jsonl_files(&own_data_dirs(), Some(24 * 8));
+    let prompts = collect_weighted_prompts(&files, now - chrono::Duration::days(7));
+    plan_prompts(&prompts, prompts_needed, model, now)
+}
+
+/// Count model-weighted usage in a time window
+/// Counts user prompts weighted by the model of the subsequent assistant response.
+/// Prompts dated in the future (see `future_cutoff`) are left out.
+pub fn count_weighted_usage_in_window(files: &[PathBuf], hours: i64) -> f64 {
+    let now = Utc::now();
+    weighted_usage_since(files, &[now - chrono::Duration::hours(hours)], now)[0]
+}
+
+/// Weighted usage of the last 7 days and of the week so far (since local 00:00 of its first
+/// day, see `week_start`), read in one pass
+pub fn count_week_weighted_usage(files: &[PathBuf]) -> (f64, f64) {
+    let now = Utc::now();
+    let week_started = day_start_in(&Local, week_start(now.with_timezone(&Local).date_naive()));
+    let counts = weighted_usage_since(files, &[now - chrono::Duration::days(7), week_started], now);
+    (counts[0], counts[1])
+}
+
+/// Weighted usage from each of `starts` until `now`, as `count_weighted_usage_in_window`
+fn weighted_usage_since(
+    files: &[PathBuf],
+    starts: &[DateTime<Utc>],
+    now: DateTime<Utc>,
+) -> Vec<f64> {
+    let window_end = future_cutoff(now);
+    let mut counts = vec![0.0; starts.len()];
+
+    for path in files {
+        if is_excluded_journal(path) {
+            continue;
+        }
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => continue,
+        };
+        let lines: Vec<String> = journal_lines(BufReader::new(file)).collect();
+
+        // Track model from assistant responses - we'll use the most recent model
+        // seen before each user prompt as the "current model" for that session
+        let mut current_model: Option<String> = None;
+
+        for line in &lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // Parse entry to get type, timestamp, and model (if assistant)
+            if let Some(info) = parse_entry_info(line) {
+                // Update current model when we see assistant messages
+                if info.entry_type == "assistant" {
+                    if let Some(m) = info.model {
+                        current_model = Some(m);
+                    }
+                }
+
+                // Count user prompts within the time window
+                if info.is_user_prompt {
+                    if let Ok(ts) = DateTime::parse_from_rfc3339(&info.timestamp) {
+                        if ts <= window_end {
+                            // Use the current model's weight (default to Sonnet if unknown)
+                            let weight = current_model
+                                .as_ref()
+                                .map(|m| get_model_quota_weight(m))
+                                .unwrap_or(1.0);
+                            for (count, start) in counts.iter_mut().zip(starts) {
+                                if ts >= *start {
+                                    *count += weight;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    counts
+}
+
+/// Count actual user prompts (excluding tool_result-only messages) in a time window, leaving
+/// out prompts dated in the future
+pub fn count_user_prompts_in_window(files: &[PathBuf], hours: i64) -> u32 {
+    let now = Utc::now();
+    let window_start = now - chrono::Duration::hours(hours);
+    let window_end = future_cutoff(now);
+    let mut count: u32 = 0;
+
+    for path in files {
+        if is_excluded_journal(path) {
+            continue;
+        }
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => continue,
+        };
+        for line in journal_lines(BufReader::new(file)) {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // Parse once: get timestamp only if this is an actual user prompt
+            if let Some(ts_str) = parse_user_prompt_timestamp(&line) {
+                if let Ok(ts) = DateTime::parse_from_rfc3339(&ts_str) {
+                    if ts >= window_start && ts <= window_end {
+                        count += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    count
+}
+
+/// Collect (timestamp, weight) for every user prompt at or after `since`, sorted by time.
+/// Weights use the model of the preceding assistant response, as in `count_weighted_usage_in_window`.
+/// Prompts dated in the future are left out: one would open a quota block that hasn't started.
+pub fn collect_weighted_prompts(files: &[PathBuf], since: DateTime<Utc>) -> Vec<(DateTime<Utc>, f64)> {
+    let until = future_cutoff(Utc::now());
+    let mut prompts = Vec::new();
+
+    for path in files {
+        if is_excluded_journal(path) {
+            continue;
+        }
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => continue,
+        };
+        let mut current_model: Option<String> = None;
+
+        for line in journal_lines(BufReader::new(file)) {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if let Some(info) = parse_entry_info(&line) {
+                if info.entry_type == "assistant" {
+                    if let Some(m) = info.model {
+                        current_model = Some(m);
+                    }
+                }
+
+                if info.is_user_prompt {
+                    if let Ok(ts) = DateTime::parse_from_rfc3339(&info.timestamp) {
+                        let ts = ts.with_timezone(&Utc);
+                        if ts >= since && ts <= until {
+                            let weight = current_model
+                                .as_deref()
+                                .map(get_model_quota_weight)
+                                .unwrap_or(1.0);
+                            prompts.push((ts, weight));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    prompts.sort_by_key(|a| a.0);
+    prompts
+}
+
+/// Length of an Anthropic quota block
+pub const QUOTA_WINDOW_HOURS: i64 = 5;
+
+/// How far back prompt history is replayed to anchor the current quota block
+const QUOTA_BLOCK_LOOKBACK_HOURS: i64 = 24;
+
+/// A fixed 5hr quota block and the prompts that fell into it
+#[derive(Debug, Clone)]
+pub struct QuotaBlock {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub prompts: u32,
+    pub weighted: f64,
+}
+
+/// Reconstruct quota blocks from time-sorted prompts.
+/// A block starts at the first prompt after the previous block ended and lasts exactly
+/// 5 hours - Anthropic's window is anchored to activity, not a rolling now-5h.
+pub fn reconstruct_quota_blocks(prompts: &[(DateTime<Utc>, f64)]) -> Vec<QuotaBlock> {
+    let window = chrono::Duration::hours(QUOTA_WINDOW_HOURS);
+    let mut blocks: Vec<QuotaBlock> = Vec::new();
+
+    for &(ts, weight) in prompts {
+        match blocks.last_mut() {
+            Some(block) if ts < block.end => {
+                block.prompts += 1;
+                block.weighted += weight;
+            }
+            _ => blocks.push(QuotaBlock {
+                start: ts,
+                end: ts + window,
+                prompts: 1,
+                weighted: weight,
+            }),
+        }
+    }
+
+    blocks
+}
+
+/// The quota block still running now, if any.
+/// `files` should cover at least the last `QUOTA_BLOCK_LOOKBACK_HOURS` (see `quota_block_files`).
+pub fn current_quota_block(files: &[PathBuf]) -> Option<QuotaBlock> {
+    let now = Utc::now();
+    let since = now - chrono::Duration::hours(QUOTA_BLOCK_LOOKBACK_HOURS);
+    let prompts = collect_weighted_prompts(files, since);
+    reconstruct_quota_blocks(&prompts)
+        .pop()
+        .filter(|block| now < block.end)
+}
+
+/// Reconstruct the quota state as it was at `at`: the block running then and the weighted
+/// prompt count of the 7 days before it
+pub fn quota_info_at(at: DateTime<Utc>) -> QuotaInfo {
+    let hours_back = (Utc::now() - at).num_hours().max(0) + 24 * 8;
+    let files = collect_jsonl_files(&own_data_dirs(), Some(hours_back));
+    let prompts: Vec<(DateTime<Utc>, f64)> =
+        collect_weighted_prompts(&files, at - chrono::Duration::days(7))
+            .into_iter()
+            .filter(|(ts, _)| *ts <= at)
+            .collect();
+    let week_weighted: f64 = prompts.iter().map(|(_, weight)| weight).sum();
+    let week_started = day_start_in(&Local, week_start(at.with_timezone(&Local).date_naive()));
+    let week_to_date_weighted: f64 = prompts
+        .iter()
+        .filter(|(ts, _)| *ts >= week_started)
+        .map(|(_, weight)| weight)
+        .sum();
+    let block = reconstruct_quota_blocks(&prompts)
+        .pop()
+        .filter(|block| at < block.end);
+    let custom_windows = custom_window_statuses(&quota_windows(), at);
+    build_quota_info_at(
+        block.as_ref(),
+        week_weighted,
+        week_to_date_weighted,
+        custom_windows,
+        at,
+    )
+}
+
+/// Files that may contain prompts needed to anchor the current quota block
+pub fn quota_block_files(data_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    collect_jsonl_files(data_dirs, Some(QUOTA_BLOCK_LOOKBACK_HOURS + 1))
+}
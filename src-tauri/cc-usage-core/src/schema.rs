@@ -0,0 +1,99 @@
+//! JSON Schemas of the serialized types, generated from their Rust definitions so frontend
+//! types and third-party consumers can be checked against them. The `schemas` test writes
+//! them with `write_schemas` (see the README's JSON Schemas section).
+
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use std::fs;
+use std::path::Path;
+
+use crate::types::*;
+
+macro_rules! schema_types {
+    ($($ty:ident),* $(,)?) => {
+        /// Names accepted by `type_schema`
+        pub const SCHEMA_TYPES: &[&str] = &[$(stringify!($ty)),*];
+
+        /// Schema of a serialized type by its Rust name, e.g. "UsageStats"
+        pub fn type_schema(type_name: &str) -> Option<RootSchema> {
+            match type_name {
+                $(stringify!($ty) => Some(schema_for!($ty)),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+schema_types!(
+    UsageStats,
+    TokenUsage,
+    ModelUsage,
+    UserUsage,
+    QuotaInfo,
+    QuotaMetric,
+    QuotaWindowStatus,
+    PaceStatus,
+    ThrottleAction,
+    ThrottleAdvice,
+    UsagePlan,
+    ActiveSession,
+    ContextBreakdown,
+    ContextPoint,
+    SessionContextHistory,
+    DailyActivity,
+    WeeklyUsage,
+    WeekDay,
+    GoalStatus,
+    BillingCycle,
+    OrgUtilization,
+    OrgSummary,
+    OrgAnalytics,
+    UserCodeMetrics,
+    ProductivityStats,
+    TaskProductivity,
+    PeerComparison,
+    PeerMetric,
+    DailyDigest,
+    DayTotals,
+    DailyDelta,
+    PeriodTotals,
+    UsageChange,
+    UsageComparison,
+    HourlyBucket,
+    SessionFilters,
+    SessionMatch,
+    ProjectWeekMatrix,
+    ProjectWeekRow,
+    WeeklyProjectSeries,
+    ProjectSeries,
+    ParseHealth,
+    PerfTiming,
+    TimezoneInfo,
+    AccessViolation,
+    SourceIssue,
+    DataDirCandidate,
+    OptimizationHint,
+    FailedRequests,
+    SessionFailedRequests,
+    DayFailedRequests,
+    FailedRequestReport,
+);
+
+/// Write `<Type>.schema.json` for every type in `SCHEMA_TYPES` to `dir`, leaving files whose
+/// content is unchanged alone. Returns how many files were written.
+pub fn write_schemas(dir: &Path) -> Result<usize, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    let mut written = 0;
+    for name in SCHEMA_TYPES {
+        let schema = type_schema(name).ok_or_else(|| format!("No schema for {name}"))?;
+        let json = serde_json::to_string_pretty(&schema)
+            .map_err(|e| format!("Failed to serialize schema for {name}: {e}"))?;
+        let path = dir.join(format!("{name}.schema.json"));
+        if fs::read_to_string(&path).is_ok_and(|current| current == json) {
+            continue;
+        }
+        fs::write(&path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+        written += 1;
+    }
+    Ok(written)
+}
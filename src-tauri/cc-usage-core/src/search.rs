@@ -0,0 +1,324 @@
+//! Search over all local sessions, by text (title, project, directory) and filters.
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use crate::history::load_session_histories;
+use crate::parser::{journal_lines, parse_usage_from_file, ParsedEntry};
+use crate::projects::project_name;
+use crate::sources::{
+    collect_jsonl_files, get_claude_data_dirs, is_session_excluded, own_data_dirs,
+    resolve_session_id,
+};
+use crate::types::{SessionFilters, SessionMatch};
+use crate::users::session_owners;
+
+/// Sessions with activity within this many minutes count as active, the rest as idle
+pub const ACTIVE_SESSION_MINUTES: i64 = 30;
+
+/// Results returned when `SessionFilters::limit` is unset
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// Title Claude Code gave the session: the last `summary` line of its journal
+pub fn read_session_title(path: &PathBuf) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let mut title = None;
+    for line in journal_lines(BufReader::new(file)) {
+        // Cheap pre-check; most lines are messages
+        if !line.contains("\"summary\"") {
+            continue;
+        }
+        let entry: serde_json::Value = match serde_json::from_str(&line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if entry.get("type").and_then(|t| t.as_str()) == Some("summary") {
+            if let Some(summary) = entry.get("summary").and_then(|s| s.as_str()) {
+                title = Some(summary.to_string());
+            }
+        }
+    }
+    title
+}
+
+/// Build one match per session from its entries (unfiltered, unscored)
+fn summarize_session(
+    session_id: &str,
+    entries: &[ParsedEntry],
+    title: Option<String>,
+    now: DateTime<Utc>,
+) -> SessionMatch {
+    let mut first_activity = String::new();
+    let mut last_activity = String::new();
+    let mut directory = String::new();
+    let mut models: Vec<String> = Vec::new();
+    let mut total_tokens: u64 = 0;
+    let mut cost_usd = 0.0;
+    for entry in entries {
+        if first_activity.is_empty() || entry.timestamp < first_activity {
+            first_activity = entry.timestamp.clone();
+        }
+        if entry.timestamp >= last_activity {
+            last_activity = entry.timestamp.clone();
+            if !entry.cwd.is_empty() {
+                directory = entry.cwd.clone();
+            }
+        }
+        if !models.contains(&entry.model) {
+            models.push(entry.model.clone());
+        }
+        total_tokens = total_tokens.saturating_add(entry.tokens.total());
+        cost_usd += entry.cost();
+    }
+
+    let active = DateTime::parse_from_rfc3339(&last_activity).is_ok_and(|ts| {
+        now - ts.with_timezone(&Utc) <= chrono::Duration::minutes(ACTIVE_SESSION_MINUTES)
+    });
+    SessionMatch {
+        session_id: session_id.to_string(),
+        title,
+        project: project_name(&directory),
+        directory,
+        first_activity,
+        last_activity,
+        models,
+        message_count: entries.len() as u32,
+        total_tokens,
+        cost_usd,
+        active,
+        excluded: is_session_excluded(session_id),
+        commands_executed: 0,
+        user: None,
+        score: 0.0,
+    }
+}
+
+fn local_date(timestamp: &str) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|ts| ts.with_timezone(&Local).date_naive())
+}
+
+fn passes_filters(session: &SessionMatch, filters: &SessionFilters) -> bool {
+    if let Some(ref model) = filters.model {
+        let model = model.to_lowercase();
+        if !session
+            .models
+            .iter()
+            .any(|m| m.to_lowercase().contains(&model))
+        {
+            return false;
+        }
+    }
+    if filters
+        .min_cost_usd
+        .is_some_and(|min| session.cost_usd < min)
+    {
+        return false;
+    }
+    // The session must overlap the date range
+    let parse = |date: &Option<String>| {
+        date.as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    };
+    if let Some(from) = parse(&filters.from) {
+        if local_date(&session.last_activity).is_none_or(|last| last < from) {
+            return false;
+        }
+    }
+    if let Some(to) = parse(&filters.to) {
+        if local_date(&session.first_activity).is_none_or(|first| first > to) {
+            return false;
+        }
+    }
+    if filters
+        .excluded
+        .is_some_and(|excluded| session.excluded != excluded)
+    {
+        return false;
+    }
+    match filters.status.as_deref() {
+        Some("active") => session.active,
+        Some("idle") => !session.active,
+        _ => true,
+    }
+}
+
+/// Relevance of a session for the query terms; None unless every term matches somewhere.
+/// Title matches weigh most, then the project name, then the rest of the path.
+fn score(session: &SessionMatch, terms: &[String]) -> Option<f64> {
+    let title = session.title.as_deref().unwrap_or("").to_lowercase();
+    let project = session.project.to_lowercase();
+    let directory = session.directory.to_lowercase();
+
+    let mut total = 0.0;
+    for term in terms {
+        let term_score = if project == *term {
+            4.0
+        } else if title.contains(term.as_str()) {
+            3.0
+        } else if project.contains(term.as_str()) {
+            2.0
+        } else if directory.contains(term.as_str()) {
+            1.0
+        } else {
+            return None;
+        };
+        total += term_score;
+    }
+    Some(total)
+}
+
+/// One unfiltered, unscored match per session in `entries`, for `rank_summaries`. `titles`
+/// maps session ids to their titles.
+pub fn summarize_sessions(
+    entries: Vec<ParsedEntry>,
+    titles: &HashMap<String, String>,
+) -> Vec<SessionMatch> {
+    let mut by_session: HashMap<String, Vec<ParsedEntry>> = HashMap::new();
+    for entry in entries {
+        if !entry.session_id.is_empty() {
+            by_session
+                .entry(entry.session_id.clone())
+                .or_default()
+                .push(entry);
+        }
+    }
+
+    let now = Utc::now();
+    by_session
+        .iter()
+        .map(|(id, entries)| summarize_session(id, entries, titles.get(id).cloned(), now))
+        .collect()
+}
+
+/// Rank summarized sessions against `query` (whitespace-separated terms, all required;
+/// empty matches everything) after applying `filters`. Best matches first, most recent
+/// first among equals.
+pub fn rank_summaries(
+    sessions: &[SessionMatch],
+    query: &str,
+    filters: &SessionFilters,
+) -> Vec<SessionMatch> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| term.to_lowercase())
+        .collect();
+    let mut matches: Vec<SessionMatch> = sessions
+        .iter()
+        .filter(|session| passes_filters(session, filters))
+        .filter_map(|session| {
+            let score = score(session, &terms)?;
+            Some(SessionMatch {
+                score,
+                ..session.clone()
+            })
+        })
+        .collect();
+
+    matches.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| b.last_activity.cmp(&a.last_activity))
+    });
+    matches.truncate(filters.limit.unwrap_or(DEFAULT_SEARCH_LIMIT));
+    matches
+}
+
+/// Rank sessions in `entries` against `query` after applying `filters` (see
+/// `rank_summaries`). `titles` maps session ids to their titles.
+pub fn rank_sessions(
+    entries: Vec<ParsedEntry>,
+    titles: &HashMap<String, String>,
+    query: &str,
+    filters: &SessionFilters,
+) -> Vec<SessionMatch> {
+    rank_summaries(&summarize_sessions(entries, titles), query, filters)
+}
+
+/// Full id and working directory of a session: the cwd of its latest entry. `session_id` is
+/// the full id or a prefix of at least `MIN_SESSION_PREFIX` characters (see
+/// `resolve_session_id`); only the session's own journals are read, from the entry cache when
+/// enabled.
+pub fn session_directory(session_id: &str) -> Result<(String, PathBuf), String> {
+    let (full_id, journals) = resolve_session_id(session_id, None)?;
+    let latest = journals
+        .iter()
+        .filter_map(|file| parse_usage_from_file(file).ok())
+        .flatten()
+        .filter(|entry| entry.session_id == full_id && !entry.cwd.is_empty())
+        .max_by(|a, b| a.timestamp.cmp(&b.timestamp))
+        .ok_or_else(|| format!("No working directory found for session {session_id}"))?;
+    Ok((full_id, PathBuf::from(latest.cwd)))
+}
+
+/// How far back journals can overlap the date range of `filters`: those last written before
+/// the range starts can't
+pub fn search_max_age_hours(filters: &SessionFilters) -> Option<i64> {
+    filters
+        .from
+        .as_deref()
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .map(|from| (Local::now().date_naive() - from).num_hours().max(0) + 24)
+}
+
+/// Search every local session (see `rank_summaries`)
+pub fn search_sessions(query: &str, filters: &SessionFilters) -> Vec<SessionMatch> {
+    let sessions = session_summaries(search_max_age_hours(filters));
+    rank_summaries(&sessions, query, filters)
+}
+
+/// Every local session in journals written within `max_age_hours`, summarized for
+/// `rank_summaries`; callers searching repeatedly can keep the result
+pub fn session_summaries(max_age_hours: Option<i64>) -> Vec<SessionMatch> {
+    let mut journals = Vec::new();
+    let mut titles = HashMap::new();
+    // Titles quote prompts, so those of other accounts read with `CoreSettings::all_users` stay
+    // private
+    let own_dirs = own_data_dirs();
+    for file in collect_jsonl_files(&get_claude_data_dirs(), max_age_hours) {
+        let parsed = match parse_usage_from_file(&file) {
+            Ok(parsed) => parsed,
+            Err(_) => continue,
+        };
+        let own = own_dirs.iter().any(|dir| file.starts_with(dir));
+        let title = if own { read_session_title(&file) } else { None };
+        if let Some(title) = title {
+            for entry in &parsed {
+                titles
+                    .entry(entry.session_id.clone())
+                    .or_insert_with(|| title.clone());
+            }
+        }
+        journals.push((file, parsed));
+    }
+    let owners = session_owners(
+        journals
+            .iter()
+            .map(|(file, parsed)| (file.as_path(), parsed.as_slice())),
+    );
+    let entries: Vec<ParsedEntry> = journals
+        .into_iter()
+        .flat_map(|(_, parsed)| parsed)
+        .collect();
+    // Sessions without a summary are titled with their first prompt
+    let histories = load_session_histories();
+    for (session_id, history) in histories.iter() {
+        if let Some(ref prompt) = history.first_prompt {
+            titles
+                .entry(session_id.clone())
+                .or_insert_with(|| prompt.clone());
+        }
+    }
+    let mut sessions = summarize_sessions(entries, &titles);
+    for session in &mut sessions {
+        session.commands_executed = histories
+            .get(&session.session_id)
+            .map_or(0, |history| history.commands);
+        session.user = owners.get(&session.session_id).cloned();
+    }
+    sessions
+}
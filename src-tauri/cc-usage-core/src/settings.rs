@@ -0,0 +1,121 @@
+//! Everything that configures the core, applied in one call: data discovery (extra and
+//! allowed roots, other accounts, change detection, the journal line limit), the entry cache,
+//! pricing and quota weights, custom quota windows, the calendar (week start, billing anchor,
+//! working hours, days off), project naming, excluded sessions, the usage source and
+//! low-memory mode.
+//!
+//! `configure` replaces the whole configuration, so a caller holding its settings in one
+//! place (e.g. a config file reloaded when edited) can't leave parts of the core stale.
+
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::access::set_allowed_roots;
+use crate::aggregate::set_low_memory;
+use crate::hours::{
+    set_billing_anchor_day, set_days_off, set_week_start, set_working_hours, WorkingHours,
+};
+use crate::otlp::{set_usage_source, UsageSource};
+use crate::parser::set_max_line_bytes;
+use crate::pricing::{set_provider_pricing, set_quota_weights, PricingProfile, QuotaWeights};
+use crate::projects::{set_group_by_remote, set_project_rules, ProjectRule};
+use crate::quota::{set_quota_windows, QuotaWindowDef};
+use crate::sources::{
+    set_change_detection, set_excluded_sessions, set_extra_roots, ChangeDetection,
+};
+use crate::users::set_all_users;
+
+/// Source label of the roots in `CoreSettings::granted_roots`
+const GRANTED_SOURCE: &str = "portal";
+
+/// Settings of the core. `Default` is the behavior without configuration.
+#[derive(Debug, Clone)]
+pub struct CoreSettings {
+    /// Data roots granted through the desktop portal, searched besides the usual locations
+    pub granted_roots: Vec<PathBuf>,
+    /// `CLAUDE_CONFIG_DIR` roots outside the home directory that may be read
+    pub allowed_roots: Vec<PathBuf>,
+    /// Directory of the parsed journal cache (feature `entry-cache`); None disables it
+    pub cache_dir: Option<PathBuf>,
+    /// Quota weight table; None uses the built-in weights
+    pub quota_weights: Option<QuotaWeights>,
+    /// Custom windows reported in `QuotaInfo.windows`
+    pub quota_windows: Vec<QuotaWindowDef>,
+    /// Bedrock/Vertex pricing by provider, merged over the built-in profiles
+    pub provider_pricing: HashMap<String, PricingProfile>,
+    /// Working hours for `off_hours_percent`; None uses the default schedule
+    pub working_hours: Option<WorkingHours>,
+    /// First day of the week, 1 = Monday ... 7 = Sunday; other values mean Sunday
+    pub week_starts_on: u32,
+    /// Day of the month billing cycles renew on (1-31); None uses calendar months
+    pub billing_anchor_day: Option<u32>,
+    /// Days left out of the weekly pace
+    pub days_off: Vec<NaiveDate>,
+    /// How recently changed journals are detected
+    pub change_detection: ChangeDetection,
+    /// Project path mapping rules, tried in order
+    pub project_rules: Vec<ProjectRule>,
+    /// Name checkouts after their git remote instead of their directory
+    pub group_by_remote: bool,
+    /// Journal lines longer than this are skipped; None uses `DEFAULT_MAX_LINE_BYTES`
+    pub max_line_bytes: Option<usize>,
+    /// Ids (or prefixes of at least `MIN_SESSION_PREFIX` characters) of sessions left out of
+    /// totals, quota and activity
+    pub excluded_sessions: Vec<String>,
+    /// Read usage from the journals or from OTLP events
+    pub usage_source: UsageSource,
+    /// Also read the Claude roots of every other account on the machine
+    pub all_users: bool,
+    /// Keep less history in memory (shorter activity, no sparklines, no prompt history)
+    pub low_memory: bool,
+}
+
+impl Default for CoreSettings {
+    fn default() -> Self {
+        Self {
+            granted_roots: Vec::new(),
+            allowed_roots: Vec::new(),
+            cache_dir: None,
+            quota_weights: None,
+            quota_windows: Vec::new(),
+            provider_pricing: HashMap::new(),
+            working_hours: None,
+            week_starts_on: 7,
+            billing_anchor_day: None,
+            days_off: Vec::new(),
+            change_detection: ChangeDetection::default(),
+            project_rules: Vec::new(),
+            group_by_remote: false,
+            max_line_bytes: None,
+            excluded_sessions: Vec::new(),
+            usage_source: UsageSource::default(),
+            all_users: false,
+            low_memory: false,
+        }
+    }
+}
+
+/// Apply `settings`, replacing the previous configuration. An invalid project rule pattern
+/// fails the call after everything else was applied, keeping the previous rules.
+pub fn configure(settings: &CoreSettings) -> Result<(), String> {
+    set_extra_roots(GRANTED_SOURCE, settings.granted_roots.clone());
+    set_allowed_roots(settings.allowed_roots.clone());
+    #[cfg(feature = "entry-cache")]
+    crate::cache::set_cache_dir(settings.cache_dir.clone());
+    set_quota_weights(settings.quota_weights.clone());
+    set_quota_windows(settings.quota_windows.clone());
+    set_provider_pricing(settings.provider_pricing.clone());
+    set_working_hours(settings.working_hours.clone());
+    set_week_start(settings.week_starts_on);
+    set_billing_anchor_day(settings.billing_anchor_day);
+    set_days_off(settings.days_off.clone());
+    set_change_detection(settings.change_detection);
+    set_group_by_remote(settings.group_by_remote);
+    set_max_line_bytes(settings.max_line_bytes);
+    set_excluded_sessions(settings.excluded_sessions.clone());
+    set_usage_source(settings.usage_source);
+    set_all_users(settings.all_users);
+    set_low_memory(settings.low_memory);
+    set_project_rules(&settings.project_rules)
+}
@@ -0,0 +1,550 @@
+//! Locating Claude Code data: config roots, project journals, todo files and settings.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::SystemTime;
+
+use crate::access::{record_violation, root_allowed, stays_within};
+use crate::parser::TodoItem;
+use crate::types::{DataDirCandidate, SourceIssue};
+use crate::users::{all_users, user_homes};
+
+/// How `collect_jsonl_files` decides whether a journal changed recently
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeDetection {
+    /// File modification time
+    #[default]
+    Mtime,
+    /// Size plus a hash of the first and last `FINGERPRINT_BYTES`; for network filesystems
+    /// (NFS, SMB) where mtimes are unreliable
+    ContentHash,
+}
+
+/// Bytes hashed from each end of a journal by `ChangeDetection::ContentHash`
+pub const FINGERPRINT_BYTES: u64 = 4096;
+
+static CHANGE_DETECTION: RwLock<ChangeDetection> = RwLock::new(ChangeDetection::Mtime);
+
+/// path -> (fingerprint, when the fingerprint was last seen changing)
+static FINGERPRINTS: Mutex<Option<HashMap<PathBuf, (u64, SystemTime)>>> = Mutex::new(None);
+
+/// Select how recently changed journals are detected
+pub(crate) fn set_change_detection(mode: ChangeDetection) {
+    if let Ok(mut current) = CHANGE_DETECTION.write() {
+        *current = mode;
+    }
+}
+
+pub fn change_detection() -> ChangeDetection {
+    CHANGE_DETECTION
+        .read()
+        .map(|mode| *mode)
+        .unwrap_or_default()
+}
+
+/// Hash of a file's size and its first and last `FINGERPRINT_BYTES`
+fn content_fingerprint(path: &Path) -> Option<u64> {
+    let mut file = File::open(path).ok()?;
+    let size = file.metadata().ok()?.len();
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    size.hash(&mut hasher);
+
+    let mut buf = Vec::with_capacity(FINGERPRINT_BYTES as usize);
+    file.by_ref()
+        .take(FINGERPRINT_BYTES)
+        .read_to_end(&mut buf)
+        .ok()?;
+    if size > FINGERPRINT_BYTES {
+        // Tail starts after the head so small files aren't hashed twice
+        let tail_start = size
+            .saturating_sub(FINGERPRINT_BYTES)
+            .max(FINGERPRINT_BYTES);
+        file.seek(SeekFrom::Start(tail_start)).ok()?;
+        file.take(FINGERPRINT_BYTES).read_to_end(&mut buf).ok()?;
+    }
+    buf.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// When the file last changed according to its content fingerprint. A file seen for the
+/// first time falls back to its mtime; later fingerprint changes count as changed now.
+fn content_changed_at(path: &Path) -> Option<SystemTime> {
+    let fingerprint = content_fingerprint(path)?;
+    let mut known = FINGERPRINTS.lock().ok()?;
+    let known = known.get_or_insert_with(HashMap::new);
+    let now = SystemTime::now();
+    match known.get_mut(path) {
+        Some((previous, changed_at)) => {
+            if *previous != fingerprint {
+                *previous = fingerprint;
+                *changed_at = now;
+            }
+            Some(*changed_at)
+        }
+        None => {
+            let changed_at = path.metadata().and_then(|m| m.modified()).unwrap_or(now);
+            known.insert(path.to_path_buf(), (fingerprint, changed_at));
+            Some(changed_at)
+        }
+    }
+}
+
+/// Roots supplied by the embedding application (e.g. granted through a desktop portal)
+static EXTRA_ROOTS: RwLock<Vec<(PathBuf, &'static str)>> = RwLock::new(Vec::new());
+
+/// Register additional config roots, tried after `CLAUDE_CONFIG_DIR` and before the defaults.
+/// `source` labels them in `get_data_dir_report`. Replaces any previously registered roots.
+pub(crate) fn set_extra_roots(source: &'static str, roots: Vec<PathBuf>) {
+    if let Ok(mut extra) = EXTRA_ROOTS.write() {
+        *extra = roots.into_iter().map(|root| (root, source)).collect();
+    }
+}
+
+/// Source label of the other accounts' roots read with `CoreSettings::all_users`
+const ALL_USERS_SOURCE: &str = "all_users";
+
+/// Shortest session id prefix accepted by `CoreSettings::excluded_sessions`, as shown for active
+/// sessions
+pub const MIN_SESSION_PREFIX: usize = 8;
+
+/// Ids (or prefixes) of sessions left out of totals, quota and activity
+static EXCLUDED_SESSIONS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Whether `id` can name a session: at least `MIN_SESSION_PREFIX` characters of a session id
+pub fn valid_session_prefix(id: &str) -> bool {
+    id.len() >= MIN_SESSION_PREFIX
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The session `id` names among `journals`, by file name (journals are named after their
+/// session): its full id and its journals. `id` is a full session id or a prefix of at least
+/// `MIN_SESSION_PREFIX` characters, like the ids of active sessions; any case.
+pub fn match_session_id(id: &str, journals: &[PathBuf]) -> Result<(String, Vec<PathBuf>), String> {
+    let prefix = id.trim().to_lowercase();
+    if !valid_session_prefix(&prefix) {
+        return Err(format!("Invalid session id '{id}'"));
+    }
+    let mut sessions: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for journal in journals {
+        let Some(stem) = journal.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if stem.to_lowercase().starts_with(&prefix) {
+            sessions
+                .entry(stem.to_string())
+                .or_default()
+                .push(journal.clone());
+        }
+    }
+    match sessions.len() {
+        0 => Err(format!("No session '{id}'")),
+        1 => Ok(sessions.into_iter().next().unwrap_or_default()),
+        count => Err(format!("'{id}' matches {count} sessions, use a longer id")),
+    }
+}
+
+/// `match_session_id` over the journals written within `max_age_hours`. Only lists files;
+/// nothing is parsed.
+pub fn resolve_session_id(
+    id: &str,
+    max_age_hours: Option<i64>,
+) -> Result<(String, Vec<PathBuf>), String> {
+    let journals = collect_jsonl_files(&get_claude_data_dirs(), max_age_hours);
+    match_session_id(id, &journals)
+}
+
+/// Leave sessions out of usage totals, quota windows and daily activity. Each id is a full
+/// session id or a prefix of one; invalid ids are ignored. Journals are never touched.
+pub(crate) fn set_excluded_sessions(ids: Vec<String>) {
+    if let Ok(mut excluded) = EXCLUDED_SESSIONS.write() {
+        *excluded = ids
+            .into_iter()
+            .map(|id| id.trim().to_lowercase())
+            .filter(|id| valid_session_prefix(id))
+            .collect();
+    }
+}
+
+pub fn is_session_excluded(session_id: &str) -> bool {
+    !session_id.is_empty()
+        && EXCLUDED_SESSIONS.read().is_ok_and(|excluded| {
+            excluded
+                .iter()
+                .any(|id| session_id.to_lowercase().starts_with(id.as_str()))
+        })
+}
+
+/// Whether a journal belongs to an excluded session (`<session_id>.jsonl`)
+pub fn is_excluded_journal(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(is_session_excluded)
+}
+
+/// Claude config roots (each containing `projects/` and `todos/`) in resolution order:
+/// 1. `CLAUDE_CONFIG_DIR` (comma-separated list allowed)
+/// 2. Roots registered with `CoreSettings::granted_roots` (e.g. granted through a desktop portal)
+/// 3. `$XDG_CONFIG_HOME/claude`
+/// 4. `~/.config/claude` (current default)
+/// 5. `~/.claude` (legacy)
+/// 6. macOS only: `~/Library/Application Support/{Claude,claude-code}`
+/// 7. With `CoreSettings::all_users`: `.config/claude` and `.claude` in every account's home
+///
+/// Works without a home directory as long as one of the environment variables is set.
+/// `CLAUDE_CONFIG_DIR` entries outside the allow-list are dropped (see `access`), and so are
+/// account roots resolving outside their account's home.
+/// Includes candidates that resolve to the same directory as an earlier one; see
+/// `claude_root_candidates` for the deduplicated list.
+fn all_root_candidates() -> Vec<(PathBuf, &'static str)> {
+    let mut roots: Vec<(PathBuf, &'static str)> = Vec::new();
+
+    if let Ok(value) = std::env::var("CLAUDE_CONFIG_DIR") {
+        for part in value.split(',') {
+            let part = part.trim();
+            if !part.is_empty() && root_allowed(Path::new(part)) {
+                roots.push((PathBuf::from(part), "CLAUDE_CONFIG_DIR"));
+            }
+        }
+    }
+
+    if let Ok(extra) = EXTRA_ROOTS.read() {
+        roots.extend(extra.iter().cloned());
+    }
+
+    if let Some(xdg) = std::env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        roots.push((PathBuf::from(xdg).join("claude"), "XDG_CONFIG_HOME"));
+    }
+
+    if let Some(home) = dirs::home_dir() {
+        roots.push((home.join(".config").join("claude"), "HOME/.config/claude"));
+        roots.push((home.join(".claude"), "HOME/.claude"));
+    }
+
+    // Where macOS apps keep their data, i.e. ~/Library/Application Support
+    #[cfg(target_os = "macos")]
+    if let Some(support) = dirs::config_dir() {
+        roots.push((support.join("Claude"), "Application Support/Claude"));
+        roots.push((
+            support.join("claude-code"),
+            "Application Support/claude-code",
+        ));
+    }
+
+    if all_users() {
+        for home in user_homes() {
+            for root in [home.join(".config").join("claude"), home.join(".claude")] {
+                // Any account can point its roots elsewhere, e.g. into another home
+                if stays_within(&root, &home) {
+                    roots.push((root, ALL_USERS_SOURCE));
+                } else {
+                    record_violation(
+                        "root_not_allowed",
+                        &root,
+                        format!("Resolves outside the home directory {}", home.display()),
+                    );
+                }
+            }
+        }
+    }
+
+    roots
+}
+
+/// Directory a candidate root really refers to, so symlinked roots (`~/.claude` pointing at
+/// `~/.config/claude`) and case variants on case-insensitive file systems are read once
+fn resolved_root(root: &Path) -> PathBuf {
+    std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf())
+}
+
+/// Claude config roots (each containing `projects/` and `todos/`) in resolution order, see
+/// `all_root_candidates`. Only the first candidate resolving to a given directory is kept.
+fn claude_root_candidates() -> Vec<(PathBuf, &'static str)> {
+    let mut roots = all_root_candidates();
+    // XDG_CONFIG_HOME usually equals ~/.config - keep only the first occurrence
+    let mut seen = std::collections::HashSet::new();
+    roots.retain(|(root, _)| seen.insert(resolved_root(root)));
+    roots
+}
+
+/// Every Claude config root in resolution order, whether or not it has data
+pub(crate) fn claude_roots() -> Vec<PathBuf> {
+    claude_root_candidates()
+        .into_iter()
+        .map(|(root, _)| root)
+        .collect()
+}
+
+pub fn get_claude_data_dirs() -> Vec<PathBuf> {
+    claude_root_candidates()
+        .into_iter()
+        .map(|(root, _)| root.join("projects"))
+        .filter(|projects| projects.exists())
+        .collect()
+}
+
+/// `get_claude_data_dirs` without the other accounts' roots read with `CoreSettings::all_users`
+pub fn own_data_dirs() -> Vec<PathBuf> {
+    claude_root_candidates()
+        .into_iter()
+        .filter(|(_, source)| *source != ALL_USERS_SOURCE)
+        .map(|(root, _)| root.join("projects"))
+        .filter(|projects| projects.exists())
+        .collect()
+}
+
+pub fn source_issue(kind: &str, path: &Path, detail: impl ToString) -> SourceIssue {
+    SourceIssue {
+        kind: kind.to_string(),
+        path: path.display().to_string(),
+        detail: detail.to_string(),
+    }
+}
+
+/// Data dirs that can't be read: `projects` dirs whose existence can't be checked (e.g. a
+/// disconnected mount) or that can't be listed. `get_claude_data_dirs` leaves the former
+/// out, the latter yield no journals.
+pub fn check_data_dirs() -> Vec<SourceIssue> {
+    let mut issues = Vec::new();
+    for (root, _) in claude_root_candidates() {
+        let projects = root.join("projects");
+        match projects.try_exists() {
+            Ok(false) => {}
+            Ok(true) => {
+                if let Err(e) = std::fs::read_dir(&projects) {
+                    issues.push(source_issue("unreadable_dir", &projects, e));
+                }
+            }
+            Err(e) => issues.push(source_issue("unreachable_root", &projects, e)),
+        }
+    }
+    issues
+}
+
+/// Report every candidate root in resolution order, where it resolves to and whether its
+/// data exists. Candidates resolving to an earlier one are marked with `duplicate_of`.
+pub fn get_data_dir_report() -> Vec<DataDirCandidate> {
+    let mut seen: Vec<(PathBuf, String)> = Vec::new();
+    all_root_candidates()
+        .into_iter()
+        .map(|(root, source)| {
+            let resolved = resolved_root(&root);
+            let duplicate_of = match seen.iter().find(|(r, _)| *r == resolved) {
+                Some((_, first)) => Some(first.clone()),
+                None => {
+                    seen.push((resolved.clone(), root.display().to_string()));
+                    None
+                }
+            };
+            DataDirCandidate {
+                projects_exists: root.join("projects").exists(),
+                todos_exists: root.join("todos").exists(),
+                resolved: (resolved != root).then(|| resolved.display().to_string()),
+                duplicate_of,
+                root: root.display().to_string(),
+                source: source.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Collect JSONL files, optionally filtering by modification time
+/// If max_age_hours is None, returns all files; otherwise only files modified within that window
+/// (judged by content fingerprint instead of mtime under `ChangeDetection::ContentHash`).
+/// Files whose real location (through symlinked files or directories) is outside their data
+/// dir are skipped and recorded as "symlink_escape".
+pub fn collect_jsonl_files(data_dirs: &[PathBuf], max_age_hours: Option<i64>) -> Vec<PathBuf> {
+    collect_jsonl_files_checked(data_dirs, max_age_hours).0
+}
+
+/// `collect_jsonl_files`, also returning the directories that couldn't be listed
+pub fn collect_jsonl_files_checked(
+    data_dirs: &[PathBuf],
+    max_age_hours: Option<i64>,
+) -> (Vec<PathBuf>, Vec<SourceIssue>) {
+    let mut files = Vec::new();
+    let mut issues = Vec::new();
+    let cutoff = max_age_hours.map(|hours| {
+        std::time::SystemTime::now() - std::time::Duration::from_secs((hours * 3600) as u64)
+    });
+    let by_content = change_detection() == ChangeDetection::ContentHash;
+
+    for dir in data_dirs {
+        let real_dir = std::fs::canonicalize(dir).unwrap_or_else(|_| dir.clone());
+        // Parent dir -> whether it resolves inside the data dir
+        let mut parents_inside: HashMap<PathBuf, bool> = HashMap::new();
+        if let Ok(entries) = glob::glob(&format!("{}/**/*.jsonl", dir.display())) {
+            for entry in entries {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        issues.push(source_issue("unreadable_dir", e.path(), e.error()));
+                        continue;
+                    }
+                };
+                let parent_inside = entry.parent().is_some_and(|parent| {
+                    *parents_inside
+                        .entry(parent.to_path_buf())
+                        .or_insert_with(|| stays_within(parent, &real_dir))
+                });
+                let is_symlink = entry.symlink_metadata().is_ok_and(|m| m.is_symlink());
+                if !parent_inside || (is_symlink && !stays_within(&entry, &real_dir)) {
+                    record_violation(
+                        "symlink_escape",
+                        &entry,
+                        format!("Resolves outside data dir {}", dir.display()),
+                    );
+                    continue;
+                }
+                // If we have a cutoff, filter by modification time
+                if let Some(cutoff_time) = cutoff {
+                    if by_content {
+                        if content_changed_at(&entry).is_some_and(|at| at < cutoff_time) {
+                            continue;
+                        }
+                    } else if let Ok(metadata) = entry.metadata() {
+                        if let Ok(modified) = metadata.modified() {
+                            if modified < cutoff_time {
+                                continue; // Skip files older than cutoff
+                            }
+                        }
+                    }
+                }
+                files.push(entry);
+            }
+        }
+    }
+
+    (files, issues)
+}
+
+/// Claude Code `settings.json` files that exist, in resolution order
+pub fn claude_settings_files() -> Vec<PathBuf> {
+    claude_root_candidates()
+        .into_iter()
+        .map(|(root, _)| root.join("settings.json"))
+        .filter(|settings| settings.exists())
+        .collect()
+}
+
+/// Claude Code's prompt history files (`history.jsonl`) in the Claude roots that have one.
+/// Other accounts' histories are never read with `CoreSettings::all_users`: they hold their
+/// prompts.
+pub fn claude_history_files() -> Vec<PathBuf> {
+    claude_root_candidates()
+        .into_iter()
+        .filter(|(_, source)| *source != ALL_USERS_SOURCE)
+        .map(|(root, _)| root.join("history.jsonl"))
+        .filter(|history| history.exists())
+        .collect()
+}
+
+/// Default model configured for Claude Code: `ANTHROPIC_MODEL`, else the first
+/// `settings.json` with a `model` key
+pub fn get_configured_model() -> Option<String> {
+    if let Ok(model) = std::env::var("ANTHROPIC_MODEL") {
+        if !model.trim().is_empty() {
+            return Some(model.trim().to_string());
+        }
+    }
+    claude_settings_files().iter().find_map(|path| {
+        let contents = std::fs::read_to_string(path).ok()?;
+        let settings: serde_json::Value = serde_json::from_str(&contents).ok()?;
+        settings
+            .get("model")
+            .and_then(|m| m.as_str())
+            .filter(|m| !m.is_empty())
+            .map(|m| m.to_string())
+    })
+}
+
+/// First string value of a `subscriptionType`/`billingType` key anywhere in `value`
+fn find_plan_value(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Object(map) => ["subscriptionType", "billingType"]
+            .iter()
+            .find_map(|key| map.get(*key).and_then(|v| v.as_str()))
+            .filter(|plan| !plan.is_empty())
+            .map(|plan| plan.to_string())
+            .or_else(|| map.values().find_map(find_plan_value)),
+        serde_json::Value::Array(items) => items.iter().find_map(find_plan_value),
+        _ => None,
+    }
+}
+
+/// Best-effort plan hint (e.g. "max", "pro") from Claude Code's account and feature flag
+/// caches (`~/.claude.json`, `statsig/`). Their layout is undocumented, so this may be None.
+pub fn get_plan_hint() -> Option<String> {
+    let mut files: Vec<PathBuf> = dirs::home_dir()
+        .map(|home| home.join(".claude.json"))
+        .into_iter()
+        .collect();
+    for (root, _) in claude_root_candidates() {
+        if let Ok(entries) = std::fs::read_dir(root.join("statsig")) {
+            files.extend(entries.flatten().map(|entry| entry.path()));
+        }
+    }
+
+    files.iter().find_map(|path| {
+        let contents = std::fs::read_to_string(path).ok()?;
+        let value: serde_json::Value = serde_json::from_str(&contents).ok()?;
+        find_plan_value(&value).or_else(|| {
+            // Statsig caches store evaluations as a JSON string inside JSON
+            value
+                .get("data")
+                .and_then(|d| d.as_str())
+                .and_then(|inner| serde_json::from_str(inner).ok())
+                .and_then(|inner: serde_json::Value| find_plan_value(&inner))
+        })
+    })
+}
+
+/// Read todo file and count pending todos for a session
+pub(crate) fn get_pending_todo_count(session_id: &str) -> u32 {
+    let paths: Vec<PathBuf> = claude_root_candidates()
+        .into_iter()
+        .map(|(root, _)| root.join("todos"))
+        .collect();
+
+    for todos_dir in &paths {
+        if !todos_dir.exists() {
+            continue;
+        }
+
+        // Look for files matching the session ID pattern
+        if let Ok(entries) = std::fs::read_dir(todos_dir) {
+            for entry in entries.flatten() {
+                let filename = entry.file_name().to_string_lossy().to_string();
+                if filename.starts_with(session_id) && filename.ends_with(".json") {
+                    if !stays_within(&entry.path(), todos_dir) {
+                        record_violation(
+                            "symlink_escape",
+                            &entry.path(),
+                            format!("Resolves outside {}", todos_dir.display()),
+                        );
+                        continue;
+                    }
+                    if let Ok(content) = std::fs::read_to_string(entry.path()) {
+                        if let Ok(todos) = serde_json::from_str::<Vec<TodoItem>>(&content) {
+                            return todos
+                                .iter()
+                                .filter(|t| {
+                                    t.status.as_deref() != Some("completed")
+                                })
+                                .count() as u32;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    0
+}
@@ -0,0 +1,859 @@
+//! Serializable result types shared by the aggregators and their consumers.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    /// Cache writes by TTL, when the journal or API reports the split; both are part of
+    /// `cache_creation_input_tokens`, which is priced at the 5 minute rate when unsplit
+    #[serde(default)]
+    pub cache_creation_5m_input_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_1h_input_tokens: u64,
+    /// Output tokens spent on extended thinking, part of `output_tokens`. Journals don't
+    /// report them, so they are estimated from the length of the thinking blocks.
+    #[serde(default)]
+    pub thinking_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Input, output, cache write and cache read tokens. Sums saturate, so corrupt counts
+    /// can't overflow.
+    pub fn total(&self) -> u64 {
+        self.input_tokens
+            .saturating_add(self.output_tokens)
+            .saturating_add(self.cache_creation_input_tokens)
+            .saturating_add(self.cache_read_input_tokens)
+    }
+
+    /// Tokens read as the request's context: fresh input, cache writes and cache reads
+    pub fn context_tokens(&self) -> u64 {
+        self.input_tokens
+            .saturating_add(self.cache_creation_input_tokens)
+            .saturating_add(self.cache_read_input_tokens)
+    }
+
+    /// Add `other` field by field, saturating
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens = self.input_tokens.saturating_add(other.input_tokens);
+        self.output_tokens = self.output_tokens.saturating_add(other.output_tokens);
+        self.cache_creation_input_tokens = self
+            .cache_creation_input_tokens
+            .saturating_add(other.cache_creation_input_tokens);
+        self.cache_read_input_tokens = self
+            .cache_read_input_tokens
+            .saturating_add(other.cache_read_input_tokens);
+        self.cache_creation_5m_input_tokens = self
+            .cache_creation_5m_input_tokens
+            .saturating_add(other.cache_creation_5m_input_tokens);
+        self.cache_creation_1h_input_tokens = self
+            .cache_creation_1h_input_tokens
+            .saturating_add(other.cache_creation_1h_input_tokens);
+        self.thinking_tokens = self.thinking_tokens.saturating_add(other.thinking_tokens);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ModelUsage {
+    pub model: String,
+    pub display_name: String,
+    pub tokens: TokenUsage,
+    pub cost_usd: f64,
+    /// "bedrock" or "vertex" for usage routed through a cloud provider; `model` is then the
+    /// canonical Anthropic API id
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Estimated output tokens/sec; None without enough consecutive entries to measure
+    #[serde(default)]
+    pub avg_output_tps: Option<f64>,
+    /// Share of `cost_usd` spent on `tokens.thinking_tokens`
+    #[serde(default)]
+    pub thinking_cost_usd: f64,
+    // Pre-formatted strings according to the user's format preferences
+    #[serde(default)]
+    pub display_tokens: String,
+    #[serde(default)]
+    pub display_cost: String,
+}
+
+/// Usage of one OS user's sessions, on a machine shared by several (see `CoreSettings::all_users`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UserUsage {
+    pub user: String,
+    pub tokens: TokenUsage,
+    pub cost_usd: f64,
+    pub message_count: u32,
+    pub session_count: u32,
+    /// The user's own 5hr window and rolling week as percents of the estimated limits, from
+    /// the prompts in the journals they own
+    #[serde(default)]
+    pub usage_percent: f64,
+    #[serde(default)]
+    pub week_usage_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct QuotaInfo {
+    pub messages_in_window: u32,
+    pub window_hours: u32,
+    pub estimated_limit: u32,
+    pub usage_percent: f64,
+    pub plan: String,
+    pub week_usage_percent: f64,
+    pub week_limit_hours: u32,
+    /// Start/end (RFC 3339) of the active 5hr block; None when no block is running
+    #[serde(default)]
+    pub window_started_at: Option<String>,
+    #[serde(default)]
+    pub window_ends_at: Option<String>,
+    /// Weighted usage since the start of the week (local 00:00 of its first day, see
+    /// `week_start`) as a percent of the weekly allowance; the pace fields judge this, where
+    /// `week_usage_percent` covers the rolling 7 days
+    #[serde(default)]
+    pub week_to_date_percent: f64,
+    /// Week-to-date usage against an even spread of the allowance over the week
+    #[serde(default)]
+    pub pace_status: PaceStatus,
+    /// `week_to_date_percent` minus `expected_week_percent` (percentage points)
+    #[serde(default)]
+    pub pace_delta_percent: f64,
+    /// Percent of the weekly allowance per hour that uses up exactly what's left by the reset
+    #[serde(default)]
+    pub safe_pace_percent_per_hour: f64,
+    /// Share of the week elapsed, not counting days off
+    #[serde(default)]
+    pub expected_week_percent: f64,
+    /// Weekly usage by the reset if the rest of the week (minus days off) goes like so far
+    #[serde(default)]
+    pub projected_week_usage_percent: f64,
+    /// Every quota window: the built-in 5hr block and week (mirroring the fields above),
+    /// then the custom windows from `CoreSettings::quota_windows`
+    #[serde(default)]
+    pub windows: Vec<QuotaWindowStatus>,
+}
+
+/// What a quota window counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaMetric {
+    /// User prompts (requests per window)
+    Prompts,
+    /// Input, output and cache write tokens; cache reads don't count toward rate limits
+    Tokens,
+    /// Prompts weighted by model (see `QuotaWeights`)
+    #[default]
+    Weighted,
+}
+
+/// Usage of one quota window as of now
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct QuotaWindowStatus {
+    pub name: String,
+    pub metric: QuotaMetric,
+    pub window_minutes: u32,
+    pub limit: f64,
+    pub used: f64,
+    pub usage_percent: f64, // used / limit, capped at 100
+    /// The built-in 5hr block or week rather than a configured window
+    pub builtin: bool,
+}
+
+/// Weekly usage pace relative to spreading the allowance evenly until the weekly reset
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema, Default)]
+pub enum PaceStatus {
+    /// Used more than the elapsed share of the week; the allowance runs out early at this rate
+    Ahead,
+    #[default]
+    OnTrack,
+    /// Used less than the elapsed share of the week; there is room for heavier use
+    Behind,
+}
+
+/// What an automation script driving Claude Code should do next (see `throttle_advice`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ThrottleAction {
+    /// Enough headroom at the current burn rate
+    Proceed,
+    /// At the current burn rate a limit is reached before it resets; pause or space out work
+    Slow,
+    /// A limit is (nearly) used up; wait for it to reset
+    Stop,
+}
+
+/// Throttling recommendation from the quota headroom and burn rate
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ThrottleAdvice {
+    pub action: ThrottleAction,
+    /// Seconds to wait before proceeding at full speed; 0 for `Proceed`
+    pub seconds_until_ok: u64,
+    /// The limit behind the advice, e.g. "5hr window at 96%"; empty for `Proceed`
+    pub reason: String,
+    pub window_percent: f64,
+    pub week_percent: f64,
+    /// Percent of the 5hr window used per hour since the block started
+    pub window_burn_percent_per_hour: f64,
+}
+
+/// Whether upcoming work fits the quota left, in Sonnet-equivalent (weighted) prompts
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UsagePlan {
+    pub prompts_needed: u32,
+    pub model: String,
+    /// Quota weight of one prompt of `model`
+    pub weight: f64,
+    pub weighted_needed: f64,
+    /// Left in the active 5hr block, or a whole window when none is active
+    pub window_remaining: f64,
+    /// Left of the rolling 7-day allowance
+    pub week_remaining: f64,
+    pub fits_window: bool,
+    pub fits_week: bool,
+    /// Earliest time (RFC 3339) the work fits both, now if it does already; None when it
+    /// needs more than a whole window or week
+    pub earliest_start: Option<String>,
+    /// Full 5hr windows the work takes at the window limit
+    pub windows_needed: u32,
+}
+
+/// Estimated composition of a session's current context. The fixed prefix (system prompt,
+/// tool definitions, memory files) is taken to be the context of the session's first request.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ContextBreakdown {
+    /// System prompt, tools and memory files, re-read from the prompt cache every turn
+    pub system_tokens: u64,
+    /// Earlier turns of the conversation, served from the prompt cache
+    pub history_tokens: u64,
+    /// Input new to this request: uncached tokens plus those written to the cache
+    pub fresh_input_tokens: u64,
+    pub context_limit: u64,
+    /// Shares of `context_limit`
+    pub system_percent: f64,
+    pub history_percent: f64,
+    pub fresh_input_percent: f64,
+}
+
+/// A session's context size as of one assistant message
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ContextPoint {
+    pub timestamp: String,
+    /// Input, cache write and cache read tokens of the request
+    pub context_tokens: u64,
+    /// Share of the model's context limit in use
+    pub used_percent: f64,
+    pub model: String,
+    /// The context shrank sharply since the previous message: a compaction or `/clear`
+    pub after_compaction: bool,
+}
+
+/// How a session's context grew, message by message, and when auto-compaction will likely
+/// hit at the pace since the last compaction
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SessionContextHistory {
+    pub session_id: String,
+    pub context_limit: u64,
+    /// Oldest first; subagent (sidechain) messages have their own context and are left out
+    pub points: Vec<ContextPoint>,
+    pub compactions: u32,
+    /// Average context growth per message since the last compaction
+    pub tokens_per_message: Option<f64>,
+    /// None while the context isn't growing
+    pub messages_until_compaction: Option<u32>,
+    /// RFC 3339, from the average time between messages since the last compaction
+    pub compaction_expected_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ActiveSession {
+    pub session_id: String,
+    pub project: String,
+    pub directory: String,
+    pub first_activity: String,
+    pub last_activity: String,
+    pub duration_minutes: u32,
+    pub message_count: u32,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    pub model: String,
+    pub model_display_name: String,
+    pub context_remaining_percent: f64,
+    pub todo_count: u32,
+    /// Tokens per `SPARKLINE_BUCKET_MINUTES` bucket over the last `SPARKLINE_WINDOW_MINUTES`,
+    /// oldest first
+    #[serde(default)]
+    pub recent_activity: Vec<u32>,
+    /// Estimated output tokens/sec over the last 24 hours (see `estimate_output_speeds`)
+    #[serde(default)]
+    pub avg_output_tps: Option<f64>,
+    /// Abandoned conversation branches (rewinds, edited prompts) in the last 24 hours
+    #[serde(default)]
+    pub branch_count: u32,
+    /// Tokens spent on abandoned branches in the last 24 hours
+    #[serde(default)]
+    pub abandoned_branch_tokens: u64,
+    /// Estimated make-up of the latest main-conversation request's context
+    #[serde(default)]
+    pub context_breakdown: Option<ContextBreakdown>,
+    /// Slash and shell (`!`) commands run, from Claude Code's prompt history
+    #[serde(default)]
+    pub commands_executed: u32,
+    /// OS user owning the session's journal; only with `CoreSettings::all_users`
+    #[serde(default)]
+    pub user: Option<String>,
+    /// A running Claude Code process was matched to the session (filled in by the app)
+    #[serde(default)]
+    pub process_running: bool,
+    #[serde(default)]
+    pub pid: Option<u32>,
+    // Pre-formatted strings according to the user's format preferences
+    #[serde(default)]
+    pub display_last_activity: String,
+    #[serde(default)]
+    pub display_tokens: String,
+    #[serde(default)]
+    pub display_cost: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DailyActivity {
+    pub date: String, // YYYY-MM-DD format
+    pub prompt_count: u32,
+    /// False for a day without prompts inside a gap that looks like missing journals
+    /// (deleted or rotated) rather than a break, see `mark_activity_gaps`
+    #[serde(default = "default_data_complete")]
+    pub data_complete: bool,
+}
+
+fn default_data_complete() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WeeklyUsage {
+    pub days: Vec<WeekDay>,           // 7 days starting from reset (see `week_start`)
+    pub week_start: String,           // YYYY-MM-DD of the first day of the week
+    pub estimated_weekly_limit: u32,  // Max prompts allowed per week
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WeekDay {
+    pub date: String,       // YYYY-MM-DD
+    pub day_name: String,   // "Sun", "Mon", etc.
+    pub prompt_count: u32,
+    pub is_today: bool,
+    pub is_future: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UsageStats {
+    pub total_tokens: TokenUsage,
+    pub total_cost_usd: f64,
+    pub by_model: Vec<ModelUsage>,
+    pub session_count: u32,
+    pub last_updated: String,
+    pub quota: QuotaInfo,
+    pub active_sessions: Vec<ActiveSession>,
+    pub daily_activity: Vec<DailyActivity>,
+    pub weekly_usage: WeeklyUsage,
+    #[serde(default)]
+    pub peer_comparison: Option<PeerComparison>,
+    #[serde(default)]
+    pub productivity: ProductivityStats,
+    /// Share of the period's tokens used outside working hours (see `WorkingHours`)
+    #[serde(default)]
+    pub off_hours_percent: f64,
+    /// Default model from Claude Code's settings (`model` / `ANTHROPIC_MODEL`)
+    #[serde(default)]
+    pub configured_model: Option<String>,
+    /// Plan inferred from Claude Code's account caches, e.g. "max"; best effort
+    #[serde(default)]
+    pub plan_hint: Option<String>,
+    /// Progress on the user's usage goals; filled in by the app from its config
+    #[serde(default)]
+    pub goals: Vec<GoalStatus>,
+    /// The current billing cycle and its cost forecast; "month" period with a billing anchor
+    /// day only
+    #[serde(default)]
+    pub billing_cycle: Option<BillingCycle>,
+    /// Organization-wide Claude Code metrics per user; API mode only
+    #[serde(default)]
+    pub org_analytics: Option<OrgAnalytics>,
+    /// Organization usage against the Team/Enterprise seat allowance; API mode only
+    #[serde(default)]
+    pub org_utilization: Option<OrgUtilization>,
+    /// Organization name, members and today's cost for the header; API mode only
+    #[serde(default)]
+    pub org_summary: Option<OrgSummary>,
+    /// Computation was cancelled or timed out; totals cover only part of the period
+    #[serde(default)]
+    pub truncated: bool,
+    /// Data sources that couldn't be read; totals leave their usage out
+    #[serde(default)]
+    pub degraded: Vec<SourceIssue>,
+    /// Usage per OS user, highest cost first; only with `CoreSettings::all_users`
+    #[serde(default)]
+    pub by_user: Vec<UserUsage>,
+    // Pre-formatted strings according to the user's format preferences
+    #[serde(default)]
+    pub display_last_updated: String,
+    #[serde(default)]
+    pub display_total_tokens: String,
+    #[serde(default)]
+    pub display_total_cost: String,
+}
+
+/// A usage goal's standing in the current period and its streak of completed periods met
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GoalStatus {
+    pub id: String,
+    pub kind: String, // e.g. "max_weekly_cost"
+    pub label: String,
+    pub target: f64,
+    /// Value so far in the current (day or week) period
+    pub current: f64,
+    /// Whether the current period is on track
+    pub met: bool,
+    /// Consecutive completed periods the goal was met, up to the last one
+    pub streak: u32,
+    pub best_streak: u32,
+}
+
+/// The billing cycle in progress and where it is heading if the rest goes like so far
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct BillingCycle {
+    pub start: String,     // YYYY-MM-DD (local), the last renewal
+    pub renews_on: String, // YYYY-MM-DD (local), the next renewal
+    /// Share of the cycle elapsed
+    pub elapsed_percent: f64,
+    pub days_left: u32,
+    pub cost_usd: f64,
+    pub projected_cost_usd: f64,
+    pub projected_tokens: u64,
+}
+
+/// Organization usage over the last 7 days against the combined allowance of its seats
+/// (seat count times the configured per-seat weekly allowance)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct OrgUtilization {
+    pub seat_count: u32,
+    /// Users with Claude Code activity today, when the analytics report is available
+    pub active_seats: Option<u32>,
+    pub week_tokens: u64,
+    pub week_cost_usd: f64,
+    /// None when no per-seat allowance of that kind is configured
+    pub capacity_tokens: Option<u64>,
+    pub capacity_cost_usd: Option<f64>,
+    /// Highest of the token and cost utilizations
+    pub utilization_percent: f64,
+    pub cost_per_seat_usd: f64,
+}
+
+/// The organization behind the Admin API key. Name, members, invites and workspaces are
+/// fetched once a day; the cost is today's (UTC), for the selected workspaces if any.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct OrgSummary {
+    pub name: String,
+    pub member_count: u32,
+    /// Invites sent but not yet accepted
+    pub pending_invites: u32,
+    pub workspace_count: u32,
+    pub today_cost_usd: f64,
+    /// When the organization details were last fetched (RFC 3339)
+    pub fetched_at: String,
+}
+
+/// One day of Claude Code activity across the organization, from the Admin API's Claude Code
+/// Analytics report
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct OrgAnalytics {
+    pub date: String, // YYYY-MM-DD (UTC)
+    /// Sorted by estimated cost, highest first
+    pub users: Vec<UserCodeMetrics>,
+    pub sessions: u32,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    pub commits: u32,
+    pub pull_requests: u32,
+    pub cost_usd: f64,
+}
+
+/// A user's (or API key's) Claude Code activity for the day
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct UserCodeMetrics {
+    pub user: String, // Email address, or the API key name for API actors
+    pub sessions: u32,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    pub commits: u32,
+    pub pull_requests: u32,
+    /// Edit/write tool suggestions accepted and rejected
+    pub edits_accepted: u32,
+    pub edits_rejected: u32,
+    pub cost_usd: f64,
+}
+
+/// Rough efficiency signal: spend per todo item completed (via `TodoWrite`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct ProductivityStats {
+    pub sessions: Vec<TaskProductivity>,
+    pub projects: Vec<TaskProductivity>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TaskProductivity {
+    pub key: String, // Short session id or project name
+    pub project: String,
+    pub completed_tasks: u32,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    pub cost_per_task: Option<f64>,
+    pub tokens_per_task: Option<f64>,
+}
+
+/// Personal weekly usage relative to a configured team baseline
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PeerComparison {
+    pub metrics: Vec<PeerMetric>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PeerMetric {
+    pub name: String, // "weekly_prompts", "weekly_tokens", "weekly_cost"
+    pub yours: f64,
+    pub team_median: f64,
+    pub ratio: f64,      // yours / team_median
+    pub summary: String, // e.g. "1.4× team median"
+}
+
+/// Summary of a single local calendar day, used by the daily digest notification
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DailyDigest {
+    pub date: String, // YYYY-MM-DD (local)
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    pub prompt_count: u32,
+    pub top_project: Option<String>,
+    pub top_project_cost_usd: f64,
+    pub peak_quota_percent: f64, // Highest 5hr window usage reached during the day
+}
+
+/// Totals for one local calendar day, compared by `DailyDelta`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct DayTotals {
+    pub date: String, // YYYY-MM-DD (local)
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    pub prompt_count: u32,
+    pub projects: Vec<String>, // Projects with usage that day, sorted
+    pub sessions_started: u32, // Sessions whose first entry falls on the day
+}
+
+/// Today so far against the whole of yesterday, from `compute_daily_delta`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DailyDelta {
+    pub today: DayTotals,
+    pub yesterday: DayTotals,
+    pub tokens_delta: i64,
+    pub cost_delta_usd: f64,
+    pub prompts_delta: i64,
+    pub sessions_started_delta: i64,
+    pub new_projects: Vec<String>, // Touched today but not yesterday
+    pub summary: String,           // e.g. "+$3.20, +41 prompts, 2 new projects"
+}
+
+/// Totals of one side of a `compare_usage` comparison
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct PeriodTotals {
+    pub period: String, // As requested, e.g. "last_week"
+    pub start: String,  // RFC 3339 UTC, inclusive
+    pub end: String,    // RFC 3339 UTC, exclusive; now for periods still running
+    pub days: f64,      // Length of the range, for normalizing partial periods
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    pub prompt_count: u32,
+}
+
+/// One model or project in both periods of a comparison
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UsageChange {
+    pub key: String, // Model id or project name
+    pub display_name: String,
+    pub tokens_a: u64,
+    pub tokens_b: u64,
+    pub cost_a_usd: f64,
+    pub cost_b_usd: f64,
+    /// Change from period b to period a; None when b had no usage
+    pub tokens_change_percent: Option<f64>,
+    pub cost_change_percent: Option<f64>,
+}
+
+/// Period a against period b (the baseline), from `compare_usage`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UsageComparison {
+    pub period_a: PeriodTotals,
+    pub period_b: PeriodTotals,
+    pub tokens_change_percent: Option<f64>,
+    pub cost_change_percent: Option<f64>,
+    pub prompts_change_percent: Option<f64>,
+    /// Every model or project used in either period, by higher cost of the two
+    pub by_model: Vec<UsageChange>,
+    pub by_project: Vec<UsageChange>,
+}
+
+/// One clock hour of usage, from `get_hourly_series`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HourlyBucket {
+    pub timestamp: String, // Start of the hour, RFC 3339 UTC
+    pub tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// Filters for `search_sessions`; unset fields don't filter
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SessionFilters {
+    /// Substring of a model used in the session, e.g. "opus"
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub min_cost_usd: Option<f64>,
+    #[serde(default)]
+    pub from: Option<String>, // YYYY-MM-DD (local), inclusive
+    #[serde(default)]
+    pub to: Option<String>, // YYYY-MM-DD (local), inclusive
+    /// "active" or "idle" (see `ACTIVE_SESSION_MINUTES`)
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Only sessions excluded from totals (true) or only counted ones (false); unset lists both
+    #[serde(default)]
+    pub excluded: Option<bool>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// A session found by `search_sessions`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionMatch {
+    pub session_id: String,
+    /// Summary title Claude Code gave the session, if any
+    pub title: Option<String>,
+    pub project: String,
+    pub directory: String,
+    pub first_activity: String,
+    pub last_activity: String,
+    pub models: Vec<String>,
+    pub message_count: u32,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    pub active: bool,
+    /// Left out of totals, quota and activity (see `CoreSettings::excluded_sessions`)
+    #[serde(default)]
+    pub excluded: bool,
+    /// Slash and shell (`!`) commands run, from Claude Code's prompt history
+    #[serde(default)]
+    pub commands_executed: u32,
+    /// OS user owning the session's journal; only with `CoreSettings::all_users`
+    #[serde(default)]
+    pub user: Option<String>,
+    pub score: f64, // Query relevance; 0 for an empty query
+}
+
+/// Cost per project per week, for a stacked or heat chart of where money went
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectWeekMatrix {
+    pub weeks: Vec<String>, // YYYY-MM-DD of each week's first day (local), oldest first
+    pub projects: Vec<ProjectWeekRow>, // Highest total cost first
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectWeekRow {
+    pub project: String,
+    pub costs_usd: Vec<f64>, // One per entry in `weeks`
+    pub total_cost_usd: f64,
+}
+
+/// Weekly cost and tokens of the top projects plus "other", for a stacked bar chart of where
+/// usage goes
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WeeklyProjectSeries {
+    pub weeks: Vec<String>, // YYYY-MM-DD of each week's first day (local), oldest first
+    /// Highest total cost first; the "other" series, if any, comes last
+    pub series: Vec<ProjectSeries>,
+    pub total_cost_usd: Vec<f64>, // All projects, one per entry in `weeks`
+    pub total_tokens: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectSeries {
+    pub project: String,
+    /// The projects outside the top ones, summed
+    pub other: bool,
+    /// Projects summed into this series: 1, or how many "other" holds
+    pub projects: u32,
+    pub costs_usd: Vec<f64>, // One per entry in `weeks`
+    pub tokens: Vec<u64>,    // Input, output and cache tokens, one per entry in `weeks`
+    pub total_cost_usd: f64,
+    pub total_tokens: u64,
+}
+
+/// Team medians to compare personal weekly usage against; a `None` median is skipped
+#[derive(Debug, Clone, Default)]
+pub struct PeerBaseline {
+    pub weekly_prompts_median: Option<f64>,
+    pub weekly_tokens_median: Option<f64>,
+    pub weekly_cost_median: Option<f64>,
+}
+
+/// Timing of one operation since startup, from `perf_stats`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct PerfTiming {
+    /// "get_usage", "parse_file", "daily_activity", "api_request", ...
+    pub op: String,
+    pub calls: u64,
+    pub last_ms: f64,
+    pub max_ms: f64,
+    pub total_ms: f64,
+    /// Files scanned by the latest run
+    pub last_files: u64,
+    /// Entries parsed or received by the latest run
+    pub last_entries: u64,
+    /// When the latest run finished (RFC 3339)
+    pub last_at: String,
+}
+
+/// Journal lines read and skipped, and timestamp problems, in the latest usage load, from
+/// `parse_health`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+pub struct ParseHealth {
+    pub lines_read: u64,
+    /// Longer than `max_line_bytes`
+    pub oversized_lines: u64,
+    pub invalid_utf8_lines: u64,
+    /// Not a JSON object of the expected shape
+    pub malformed_lines: u64,
+    pub max_line_bytes: u64,
+    /// Usage entries without a usable timestamp, dated with their file's mtime
+    #[serde(default)]
+    pub undated_entries: u64,
+    /// Entries dated more than `CLOCK_SKEW_TOLERANCE_SECS` before an earlier entry of the
+    /// same file (machine clock changes)
+    #[serde(default)]
+    pub non_monotonic_entries: u64,
+    #[serde(default)]
+    pub files_with_skew: u64,
+    /// Largest backwards jump seen
+    #[serde(default)]
+    pub max_skew_seconds: u64,
+    /// Usage entries dated more than `FUTURE_TOLERANCE_SECS` ahead of the clock, left out of
+    /// the stats (see `quarantine_future_entries`)
+    #[serde(default)]
+    pub future_entries: u64,
+    /// How far ahead the furthest of them was
+    #[serde(default)]
+    pub max_future_seconds: u64,
+}
+
+/// The local time zone that days, weeks and working hours are judged in, for diagnostics
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TimezoneInfo {
+    /// IANA name from `TZ` or `/etc/localtime`, when it can be told
+    pub name: Option<String>,
+    /// Current offset from UTC, e.g. "+02:00"
+    pub utc_offset: String,
+    pub utc_offset_seconds: i32,
+    /// Daylight saving time is in effect: the offset is above the year's lowest
+    pub dst_active: bool,
+    /// Next offset change within a year (RFC 3339), if any
+    pub next_transition: Option<String>,
+    pub next_utc_offset: Option<String>,
+}
+
+/// Filesystem access refused by the read-only guard (see `access`), for diagnostics
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AccessViolation {
+    /// "root_not_allowed", "symlink_escape" or "write_in_data_dir"
+    pub kind: String,
+    pub path: String,
+    pub detail: String,
+    pub detected_at: String,
+}
+
+/// A data dir or journal that couldn't be read while computing usage
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SourceIssue {
+    /// "unreachable_root", "unreadable_dir" or "unreadable_file"
+    pub kind: String,
+    pub path: String,
+    pub detail: String, // The I/O error
+}
+
+/// A Claude config root considered during data dir resolution, for diagnostics
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DataDirCandidate {
+    pub root: String,
+    pub source: String, // Where the candidate came from, e.g. "CLAUDE_CONFIG_DIR"
+    pub projects_exists: bool,
+    pub todos_exists: bool,
+    /// Canonical path, when it differs from `root` (symlinks, case on macOS)
+    #[serde(default)]
+    pub resolved: Option<String>,
+    /// Earlier candidate resolving to the same directory; this one is skipped
+    #[serde(default)]
+    pub duplicate_of: Option<String>,
+}
+
+/// Requests that failed with an API error or were interrupted, and the tokens they used
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct FailedRequests {
+    /// Turns that ended in an API error (overloaded, rate limited, network)
+    pub errors: u32,
+    /// Requests interrupted by the user (Esc), or tool calls refused
+    pub aborted: u32,
+    /// Tokens of the responses lost to these failures
+    pub wasted_tokens: TokenUsage,
+    pub wasted_cost_usd: f64,
+}
+
+/// Failed requests of one session
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionFailedRequests {
+    pub session_id: String,
+    pub project: String,
+    pub directory: String,
+    pub failed: FailedRequests,
+    /// RFC 3339 time of the latest failure
+    pub last_failed_at: String,
+}
+
+/// Failed requests of one local day
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DayFailedRequests {
+    pub date: String, // YYYY-MM-DD
+    pub failed: FailedRequests,
+}
+
+/// Failed requests over the last days, from `get_failed_requests`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct FailedRequestReport {
+    /// RFC 3339 start of the first day covered
+    pub since: String,
+    pub total: FailedRequests,
+    /// Oldest first; days without failures are left out
+    pub by_day: Vec<DayFailedRequests>,
+    /// Most wasted cost first
+    pub by_session: Vec<SessionFailedRequests>,
+}
+
+/// A suggestion from `get_optimization_hints`, with its estimated saving at API prices
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct OptimizationHint {
+    pub kind: String, // e.g. "opus_short_exchanges"
+    pub title: String,
+    pub detail: String,
+    pub estimated_savings_usd: f64,
+    pub affected_count: u32, // Responses or sessions the hint is based on
+}
@@ -0,0 +1,213 @@
+//! Usage per OS user on shared machines (`CoreSettings::all_users`): the Claude roots in every
+//! account's home are read too, and each session belongs to the owner of its journal. Unix
+//! only; elsewhere nothing is attributed.
+
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::parser::{future_cutoff, ParsedEntry};
+use crate::quota::{
+    count_week_weighted_usage, current_quota_block, quota_block_files, ESTIMATED_WEEKLY_LIMIT,
+    ESTIMATED_WINDOW_LIMIT,
+};
+use crate::sources::{collect_jsonl_files, is_session_excluded};
+use crate::types::{TokenUsage, UserUsage};
+
+static ALL_USERS: AtomicBool = AtomicBool::new(false);
+
+/// Read every account's Claude roots and attribute sessions to users; for a daemon on a
+/// multi-user dev server that can read the other homes (e.g. running as root)
+pub(crate) fn set_all_users(enabled: bool) {
+    ALL_USERS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn all_users() -> bool {
+    ALL_USERS.load(Ordering::Relaxed)
+}
+
+/// An account from `/etc/passwd`
+struct Account {
+    name: String,
+    uid: u32,
+    home: PathBuf,
+}
+
+#[cfg(unix)]
+fn accounts() -> Vec<Account> {
+    let passwd = std::fs::read_to_string("/etc/passwd").unwrap_or_default();
+    passwd
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            if fields.len() < 7 || fields[0].is_empty() {
+                return None;
+            }
+            Some(Account {
+                name: fields[0].to_string(),
+                uid: fields[2].parse().ok()?,
+                home: PathBuf::from(fields[5]),
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+fn accounts() -> Vec<Account> {
+    Vec::new()
+}
+
+/// Home directories of every account with an absolute home, for the root candidates
+pub(crate) fn user_homes() -> Vec<PathBuf> {
+    accounts()
+        .into_iter()
+        .map(|account| account.home)
+        .filter(|home| home.is_absolute() && home != Path::new("/"))
+        .collect()
+}
+
+#[cfg(unix)]
+fn file_uid(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    path.metadata().ok().map(|metadata| metadata.uid())
+}
+
+#[cfg(not(unix))]
+fn file_uid(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// Account names by uid
+fn account_names() -> HashMap<u32, String> {
+    accounts()
+        .into_iter()
+        .map(|account| (account.uid, account.name))
+        .collect()
+}
+
+/// User owning `path`; owners without an account are named by uid
+fn file_owner(path: &Path, names: &HashMap<u32, String>) -> Option<String> {
+    let uid = file_uid(path)?;
+    Some(names.get(&uid).cloned().unwrap_or_else(|| uid.to_string()))
+}
+
+/// Session id -> user owning its journal, for the sessions in `journals`; empty unless
+/// `CoreSettings::all_users` is on. Owners without an account are named by uid.
+pub fn session_owners<'a>(
+    journals: impl IntoIterator<Item = (&'a Path, &'a [ParsedEntry])>,
+) -> HashMap<String, String> {
+    let mut owners = HashMap::new();
+    if !all_users() {
+        return owners;
+    }
+    let names = account_names();
+    for (path, entries) in journals {
+        let Some(user) = file_owner(path, &names) else {
+            continue;
+        };
+        for entry in entries.iter().filter(|entry| !entry.session_id.is_empty()) {
+            owners
+                .entry(entry.session_id.clone())
+                .or_insert_with(|| user.clone());
+        }
+    }
+    owners
+}
+
+/// User -> (5hr window, rolling week) usage percent, each from the prompts in the journals
+/// the user owns in `data_dirs`; empty unless `CoreSettings::all_users` is on. Every account has
+/// limits of its own, so prompts of different users never share a window.
+pub fn quota_by_user(data_dirs: &[PathBuf]) -> HashMap<String, (f64, f64)> {
+    if !all_users() {
+        return HashMap::new();
+    }
+    let names = account_names();
+    let by_owner = |files: Vec<PathBuf>| {
+        let mut by_owner: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for file in files {
+            if let Some(user) = file_owner(&file, &names) {
+                by_owner.entry(user).or_default().push(file);
+            }
+        }
+        by_owner
+    };
+    let block_files = by_owner(quota_block_files(data_dirs));
+    by_owner(collect_jsonl_files(data_dirs, Some(24 * 8)))
+        .into_iter()
+        .map(|(user, week_files)| {
+            let window_weighted = block_files
+                .get(&user)
+                .and_then(|files| current_quota_block(files))
+                .map_or(0.0, |block| block.weighted);
+            let (week_weighted, _) = count_week_weighted_usage(&week_files);
+            let window = (window_weighted / ESTIMATED_WINDOW_LIMIT as f64 * 100.0).min(100.0);
+            let week = (week_weighted / ESTIMATED_WEEKLY_LIMIT as f64 * 100.0).min(100.0);
+            (user, (window, week))
+        })
+        .collect()
+}
+
+/// Tokens, cost and sessions per user since `since`, highest cost first. Entries of
+/// sessions without an owner (OTLP events), excluded sessions and future-dated entries are
+/// left out.
+pub fn usage_by_user(
+    entries: &[ParsedEntry],
+    owners: &HashMap<String, String>,
+    since: Option<DateTime<Utc>>,
+) -> Vec<UserUsage> {
+    if owners.is_empty() {
+        return Vec::new();
+    }
+    let cutoff = future_cutoff(Utc::now());
+    // user -> (usage, its sessions)
+    let mut by_user: HashMap<&str, (UserUsage, HashSet<&str>)> = HashMap::new();
+    for entry in entries {
+        let Some(user) = owners.get(&entry.session_id) else {
+            continue;
+        };
+        let Ok(timestamp) = DateTime::parse_from_rfc3339(&entry.timestamp) else {
+            continue;
+        };
+        let timestamp = timestamp.with_timezone(&Utc);
+        if since.is_some_and(|since| timestamp < since)
+            || timestamp > cutoff
+            || is_session_excluded(&entry.session_id)
+        {
+            continue;
+        }
+        let (usage, sessions) = by_user.entry(user).or_insert_with(|| {
+            (
+                UserUsage {
+                    user: user.clone(),
+                    tokens: TokenUsage::default(),
+                    cost_usd: 0.0,
+                    message_count: 0,
+                    session_count: 0,
+                    usage_percent: 0.0,
+                    week_usage_percent: 0.0,
+                },
+                HashSet::new(),
+            )
+        });
+        usage.tokens.add(&entry.tokens);
+        usage.cost_usd += entry.cost();
+        usage.message_count += 1;
+        sessions.insert(&entry.session_id);
+    }
+
+    let mut users: Vec<UserUsage> = by_user
+        .into_values()
+        .map(|(mut usage, sessions)| {
+            usage.session_count = sessions.len() as u32;
+            usage
+        })
+        .collect();
+    users.sort_by(|a, b| {
+        b.cost_usd
+            .total_cmp(&a.cost_usd)
+            .then_with(|| a.user.cmp(&b.user))
+    });
+    users
+}
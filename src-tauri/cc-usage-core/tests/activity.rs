@@ -0,0 +1,58 @@
+//! Gaps in daily activity: silent working days in a long enough run between active days are
+//! flagged as possibly missing journals; weekends and days off are never flagged.
+
+mod common;
+
+use cc_usage_core::aggregate::mark_activity_gaps;
+use cc_usage_core::{CoreSettings, DailyActivity};
+use chrono::NaiveDate;
+
+fn active(date: &str) -> DailyActivity {
+    DailyActivity {
+        date: date.to_string(),
+        prompt_count: 10,
+        data_complete: true,
+    }
+}
+
+fn missing(days: &[DailyActivity]) -> Vec<&str> {
+    days.iter()
+        .filter(|day| !day.data_complete)
+        .map(|day| day.date.as_str())
+        .collect()
+}
+
+#[test]
+fn silent_working_days_between_active_days_are_flagged() {
+    let settings = common::configure(CoreSettings::default());
+
+    // Friday to Monday: only a weekend in between
+    let weekend = mark_activity_gaps(vec![active("2025-03-07"), active("2025-03-10")]);
+    assert_eq!(weekend.len(), 2);
+
+    // Monday to the next Monday: four silent working days, the weekend between isn't flagged
+    let week = mark_activity_gaps(vec![active("2025-03-03"), active("2025-03-10")]);
+    assert_eq!(
+        missing(&week),
+        ["2025-03-04", "2025-03-05", "2025-03-06", "2025-03-07"]
+    );
+    assert!(week
+        .iter()
+        .all(|day| day.data_complete || day.prompt_count == 0));
+    assert!(week.windows(2).all(|pair| pair[0].date < pair[1].date));
+
+    // Without an active day after it, silence is just the present
+    let trailing = mark_activity_gaps(vec![active("2025-03-03")]);
+    assert!(missing(&trailing).is_empty());
+
+    // Days off leave too few working days for a gap
+    settings.apply(CoreSettings {
+        days_off: vec![
+            NaiveDate::from_ymd_opt(2025, 3, 5).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 3, 6).unwrap(),
+        ],
+        ..CoreSettings::default()
+    });
+    let vacation = mark_activity_gaps(vec![active("2025-03-03"), active("2025-03-10")]);
+    assert!(missing(&vacation).is_empty());
+}
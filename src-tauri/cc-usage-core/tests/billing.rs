@@ -0,0 +1,49 @@
+//! Billing cycles: months start on the anchor day, clamped to the end of shorter months,
+//! and calendar months without one.
+
+mod common;
+
+use cc_usage_core::hours::{billing_cycle_start, month_period_start, next_billing_cycle_start};
+use cc_usage_core::CoreSettings;
+use chrono::NaiveDate;
+
+fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+fn anchored_on(day: Option<u32>) -> CoreSettings {
+    CoreSettings {
+        billing_anchor_day: day,
+        ..CoreSettings::default()
+    }
+}
+
+#[test]
+fn cycles_follow_the_anchor_day() {
+    // Without an anchor: calendar months, and "month" is the last 30 days
+    let settings = common::configure(anchored_on(None));
+    assert_eq!(billing_cycle_start(date("2025-03-20")), date("2025-03-01"));
+    assert_eq!(month_period_start(date("2025-03-20")), date("2025-02-18"));
+
+    settings.apply(anchored_on(Some(15)));
+    assert_eq!(billing_cycle_start(date("2025-03-15")), date("2025-03-15"));
+    assert_eq!(billing_cycle_start(date("2025-03-14")), date("2025-02-15"));
+    assert_eq!(billing_cycle_start(date("2025-01-03")), date("2024-12-15"));
+    assert_eq!(month_period_start(date("2025-03-20")), date("2025-03-15"));
+
+    // The 31st renews on the last day of shorter months
+    settings.apply(anchored_on(Some(31)));
+    assert_eq!(billing_cycle_start(date("2025-03-10")), date("2025-02-28"));
+    assert_eq!(
+        next_billing_cycle_start(date("2025-02-28")),
+        date("2025-03-31")
+    );
+    assert_eq!(
+        next_billing_cycle_start(date("2024-01-31")),
+        date("2024-02-29")
+    );
+
+    // Out of range days are ignored
+    settings.apply(anchored_on(Some(32)));
+    assert_eq!(billing_cycle_start(date("2025-03-20")), date("2025-03-01"));
+}
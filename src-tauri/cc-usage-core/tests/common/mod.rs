@@ -0,0 +1,62 @@
+//! Helpers shared by the integration tests.
+
+// Each test crate uses only some of them
+#![allow(dead_code)]
+
+use cc_usage_core::{CoreSettings, ParsedEntry, TokenUsage};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Held by tests changing or reading the core's process-wide state (settings, parse health),
+/// so tests in one binary running in parallel don't see each other's
+static GLOBAL_STATE: Mutex<()> = Mutex::new(());
+
+pub fn lock_global_state() -> MutexGuard<'static, ()> {
+    GLOBAL_STATE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Core settings in effect until dropped, which restores the defaults
+pub struct Configured {
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Drop for Configured {
+    fn drop(&mut self) {
+        // Runs before the lock is released
+        cc_usage_core::configure(&CoreSettings::default()).unwrap();
+    }
+}
+
+impl Configured {
+    /// Replace the settings in effect
+    pub fn apply(&self, settings: CoreSettings) {
+        cc_usage_core::configure(&settings).unwrap();
+    }
+}
+
+/// Apply `settings` for as long as the returned guard lives, holding off other tests
+/// touching the core's global state meanwhile
+pub fn configure(settings: CoreSettings) -> Configured {
+    let lock = lock_global_state();
+    cc_usage_core::configure(&settings).unwrap();
+    Configured { _lock: lock }
+}
+
+/// Session id of tests about a single session
+pub const SESSION_ID: &str = "3f2a9c1e-0000-4000-8000-000000000001";
+
+/// A Sonnet usage entry of `session_id` at `timestamp` (RFC 3339) in `/home/user/project`.
+/// Tests needing other fields override them with struct update syntax.
+pub fn entry(session_id: &str, timestamp: &str, tokens: TokenUsage) -> ParsedEntry {
+    ParsedEntry {
+        model: "claude-sonnet-4-5-20250929".to_string(),
+        provider: None,
+        tokens,
+        timestamp: timestamp.to_string(),
+        session_id: session_id.to_string(),
+        cwd: "/home/user/project".to_string(),
+        completed_todos: Vec::new(),
+        uuid: String::new(),
+        parent_uuid: None,
+        is_sidechain: false,
+    }
+}
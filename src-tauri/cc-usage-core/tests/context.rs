@@ -0,0 +1,51 @@
+//! Context history: one point per main-thread message, compactions detected from sharp
+//! drops, and the auto-compaction forecast from the growth since the last one.
+
+mod common;
+
+use cc_usage_core::context::{context_history, AUTO_COMPACT_PERCENT};
+use cc_usage_core::{ParsedEntry, TokenUsage};
+
+fn message(minute: u32, context_tokens: u64, is_sidechain: bool) -> ParsedEntry {
+    let tokens = TokenUsage {
+        input_tokens: 10,
+        cache_read_input_tokens: context_tokens - 10,
+        output_tokens: 500,
+        ..TokenUsage::default()
+    };
+    let timestamp = format!("2025-03-10T10:{minute:02}:00Z");
+    ParsedEntry {
+        is_sidechain,
+        ..common::entry(common::SESSION_ID, &timestamp, tokens)
+    }
+}
+
+#[test]
+fn context_growth_and_compaction_forecast() {
+    let entries = vec![
+        message(0, 100_000, false),
+        message(1, 150_000, false),
+        message(2, 20_000, true),  // subagent: its own context
+        message(3, 30_000, false), // compacted
+        message(5, 40_000, false),
+        message(7, 50_000, false),
+    ];
+    let history = context_history("3f2a9c1e", &entries);
+
+    assert_eq!(history.session_id, common::SESSION_ID);
+    assert_eq!(history.points.len(), 5);
+    assert_eq!(history.compactions, 1);
+    assert!(history.points[2].after_compaction);
+    assert_eq!(history.points[4].used_percent, 25.0);
+
+    // 10K tokens per message since the compaction, 2 minutes apart
+    assert_eq!(history.tokens_per_message, Some(10_000.0));
+    let threshold = history.context_limit as f64 * AUTO_COMPACT_PERCENT / 100.0;
+    let expected = ((threshold - 50_000.0) / 10_000.0).ceil() as u32;
+    assert_eq!(history.messages_until_compaction, Some(expected));
+    let at =
+        chrono::DateTime::parse_from_rfc3339(history.compaction_expected_at.as_deref().unwrap())
+            .unwrap();
+    let last = chrono::DateTime::parse_from_rfc3339("2025-03-10T10:07:00Z").unwrap();
+    assert_eq!((at - last).num_minutes(), 2 * expected as i64);
+}
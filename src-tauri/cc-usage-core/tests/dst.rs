@@ -0,0 +1,119 @@
+//! Daylight saving transitions: local days of 23 and 25 hours, midnights that don't exist,
+//! and weekly pace over weeks containing a change. Zones are fixed with chrono-tz so the
+//! results don't depend on the machine's local time zone.
+
+use cc_usage_core::hours::{day_hours_in, day_start_in};
+use cc_usage_core::quota::apply_weekly_pace_in;
+use cc_usage_core::{PaceStatus, QuotaInfo};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::America::{New_York, Santiago};
+use chrono_tz::Europe::Berlin;
+
+fn date(value: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+}
+
+fn utc(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+fn quota(week_usage_percent: f64) -> QuotaInfo {
+    QuotaInfo {
+        messages_in_window: 0,
+        window_hours: 5,
+        estimated_limit: 500,
+        usage_percent: 0.0,
+        plan: String::new(),
+        week_usage_percent,
+        week_limit_hours: 210,
+        window_started_at: None,
+        window_ends_at: None,
+        week_to_date_percent: week_usage_percent,
+        pace_status: PaceStatus::OnTrack,
+        pace_delta_percent: 0.0,
+        safe_pace_percent_per_hour: 0.0,
+        expected_week_percent: 0.0,
+        projected_week_usage_percent: 0.0,
+        windows: Vec::new(),
+    }
+}
+
+#[test]
+fn day_lengths_follow_the_clock_changes() {
+    assert_eq!(day_hours_in(&New_York, date("2025-03-09")), 23.0);
+    assert_eq!(day_hours_in(&New_York, date("2025-03-10")), 24.0);
+    assert_eq!(day_hours_in(&New_York, date("2025-11-02")), 25.0);
+    assert_eq!(day_hours_in(&Berlin, date("2025-03-30")), 23.0);
+    assert_eq!(day_hours_in(&Berlin, date("2025-10-26")), 25.0);
+}
+
+#[test]
+fn day_bounds_use_the_offset_of_each_day() {
+    assert_eq!(
+        day_start_in(&New_York, date("2025-03-09")),
+        utc("2025-03-09T05:00:00Z")
+    );
+    assert_eq!(
+        day_start_in(&New_York, date("2025-03-10")),
+        utc("2025-03-10T04:00:00Z")
+    );
+    assert_eq!(
+        day_start_in(&New_York, date("2025-11-03")),
+        utc("2025-11-03T05:00:00Z")
+    );
+}
+
+#[test]
+fn skipped_midnight_starts_the_day_at_the_first_existing_time() {
+    // Chile moves clocks from 00:00 to 01:00, so the day starts at 01:00 -03
+    assert_eq!(
+        day_start_in(&Santiago, date("2024-09-08")),
+        utc("2024-09-08T04:00:00Z")
+    );
+    assert_eq!(day_hours_in(&Santiago, date("2024-09-08")), 23.0);
+}
+
+#[test]
+fn weekly_pace_counts_real_hours_in_a_short_week() {
+    // Weeks start on Sunday by default; this one loses an hour on its first day
+    let mut info = quota(50.0);
+    // Wednesday 00:00 EDT: 23 + 24 + 24 of 167 hours elapsed
+    apply_weekly_pace_in(&mut info, utc("2025-03-12T04:00:00Z"), &New_York);
+    let expected = 71.0 / 167.0 * 100.0;
+    assert!((info.expected_week_percent - expected).abs() < 1e-9);
+    assert!((info.pace_delta_percent - (50.0 - expected)).abs() < 1e-9);
+    let safe = 50.0 / (167.0 - 71.0);
+    assert!((info.safe_pace_percent_per_hour - safe).abs() < 1e-9);
+}
+
+#[test]
+fn weekly_pace_counts_real_hours_in_a_long_week() {
+    let mut info = quota(10.0);
+    // Monday 00:00 EST: the 25 hour Sunday of 169 hours elapsed
+    apply_weekly_pace_in(&mut info, utc("2025-11-03T05:00:00Z"), &New_York);
+    let expected = 25.0 / 169.0 * 100.0;
+    assert!((info.expected_week_percent - expected).abs() < 1e-9);
+    assert!((info.projected_week_usage_percent - 10.0 * 169.0 / 25.0).abs() < 1e-9);
+}
+
+#[test]
+fn weekly_pace_reaches_the_whole_week_at_the_reset() {
+    let mut info = quota(100.0);
+    // One second before the next Sunday, 00:00 EDT
+    apply_weekly_pace_in(&mut info, utc("2025-03-16T03:59:59Z"), &New_York);
+    assert!(info.expected_week_percent < 100.0);
+    assert!(info.expected_week_percent > 99.99);
+}
+
+#[test]
+fn weekly_pace_leaves_out_the_previous_weeks_tail() {
+    // The rolling 7 days still hold last week's heavy usage; the week so far doesn't
+    let mut info = quota(10.0);
+    info.week_usage_percent = 80.0;
+    apply_weekly_pace_in(&mut info, utc("2025-11-03T05:00:00Z"), &New_York);
+    let expected = 25.0 / 169.0 * 100.0;
+    assert!((info.pace_delta_percent - (10.0 - expected)).abs() < 1e-9);
+    assert_eq!(info.pace_status, PaceStatus::OnTrack);
+}
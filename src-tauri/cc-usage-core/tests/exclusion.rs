@@ -0,0 +1,47 @@
+//! Excluded sessions: left out of totals by id or prefix, journals matched by file name.
+
+mod common;
+
+use cc_usage_core::aggregate::{aggregate_usage, compute_weekly_usage};
+use cc_usage_core::quota::build_quota_info;
+use cc_usage_core::sources::{is_excluded_journal, is_session_excluded};
+use cc_usage_core::{CoreSettings, ParsedEntry, TokenUsage};
+use chrono::Utc;
+use std::path::Path;
+
+fn entry(session_id: &str, output_tokens: u64) -> ParsedEntry {
+    let tokens = TokenUsage {
+        output_tokens,
+        ..TokenUsage::default()
+    };
+    common::entry(session_id, &Utc::now().to_rfc3339(), tokens)
+}
+
+#[test]
+fn excluded_sessions_are_left_out_of_totals() {
+    let demo = common::SESSION_ID;
+    let real = "7b41d0aa-0000-4000-8000-000000000002";
+    let _settings = common::configure(CoreSettings {
+        excluded_sessions: vec![
+            "3F2A9C1E".to_string(), // prefix, any case
+            "short".to_string(),    // too short to name a session: ignored
+        ],
+        ..CoreSettings::default()
+    });
+
+    assert!(is_session_excluded(demo));
+    assert!(!is_session_excluded(real));
+    assert!(!is_session_excluded("shortcut-session-id"));
+    assert!(is_excluded_journal(Path::new(&format!(
+        "/home/user/.claude/projects/demo/{demo}.jsonl"
+    ))));
+
+    let stats = aggregate_usage(
+        vec![entry(demo, 1000), entry(real, 10)],
+        None,
+        build_quota_info(None, 0.0, 0.0, Vec::new()),
+        Vec::new(),
+        compute_weekly_usage(&[]),
+    );
+    assert_eq!(stats.total_tokens.output_tokens, 10);
+}
@@ -0,0 +1,55 @@
+//! Failed requests: API errors and interruptions are found in journal lines with the tokens
+//! of the responses they cut off, and copies in resumed sessions are counted once.
+
+use cc_usage_core::failures::{failed_request_report, parse_failed_requests, FailureKind};
+use chrono::{DateTime, Utc};
+
+const JOURNAL: &str = r#"{"type":"user","uuid":"u1","sessionId":"s1","cwd":"/work/app","timestamp":"2025-03-12T10:00:00Z","message":{"role":"user","content":"Refactor the parser"}}
+{"type":"assistant","uuid":"a1","sessionId":"s1","cwd":"/work/app","timestamp":"2025-03-12T10:00:05Z","message":{"model":"claude-sonnet-4-5-20250929","usage":{"input_tokens":100,"output_tokens":400,"cache_read_input_tokens":1000}}}
+{"type":"user","uuid":"u2","sessionId":"s1","cwd":"/work/app","timestamp":"2025-03-12T10:00:09Z","message":{"role":"user","content":[{"type":"text","text":"[Request interrupted by user]"}]}}
+{"type":"assistant","uuid":"a2","sessionId":"s1","cwd":"/work/app","timestamp":"2025-03-12T10:01:00Z","message":{"model":"claude-sonnet-4-5-20250929","usage":{"input_tokens":50,"output_tokens":80}}}
+{"type":"user","uuid":"u3","sessionId":"s1","cwd":"/work/app","timestamp":"2025-03-12T10:01:05Z","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"The user doesn't want to proceed with this tool use."}]}}
+{"type":"user","uuid":"u4","sessionId":"s1","cwd":"/work/app","timestamp":"2025-03-12T10:01:06Z","message":{"role":"user","content":[{"type":"text","text":"[Request interrupted by user for tool use]"}]}}
+{"type":"user","uuid":"u5","sessionId":"s1","cwd":"/work/app","timestamp":"2025-03-12T10:02:00Z","message":{"role":"user","content":"Try again"}}
+{"type":"assistant","uuid":"a3","sessionId":"s1","cwd":"/work/app","timestamp":"2025-03-12T10:02:30Z","isApiErrorMessage":true,"message":{"model":"<synthetic>","usage":{"input_tokens":0,"output_tokens":0},"content":[{"type":"text","text":"API Error: 529 Overloaded"}]}}
+{"type":"assistant","uuid":"a4","sessionId":"s1","cwd":"/work/app","timestamp":"2025-03-12T10:03:00Z","message":{"model":"claude-sonnet-4-5-20250929","usage":{"input_tokens":100,"output_tokens":300}}}
+"#;
+
+#[test]
+fn failures_are_found_with_their_wasted_tokens() {
+    let requests = parse_failed_requests(JOURNAL.as_bytes());
+    let kinds: Vec<FailureKind> = requests.iter().map(|r| r.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            FailureKind::Aborted,
+            FailureKind::Aborted,
+            FailureKind::Error
+        ]
+    );
+    // The interrupted response, the request of the refused tool call, nothing for the error
+    let output: Vec<u64> = requests
+        .iter()
+        .map(|r| r.wasted_tokens.output_tokens)
+        .collect();
+    assert_eq!(output, [400, 80, 0]);
+    assert!(requests[0].wasted_cost_usd > 0.0);
+
+    // A resumed session repeats the same lines in a second journal
+    let mut both = requests.clone();
+    both.extend(parse_failed_requests(JOURNAL.as_bytes()));
+    let since = DateTime::parse_from_rfc3339("2025-03-12T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let report = failed_request_report(&both, since);
+    assert_eq!(report.total.errors, 1);
+    assert_eq!(report.total.aborted, 2);
+    assert_eq!(report.total.wasted_tokens.output_tokens, 480);
+    assert_eq!(report.by_session.len(), 1);
+    assert_eq!(report.by_session[0].project, "app");
+    assert_eq!(report.by_day.len(), 1);
+
+    // Failures before `since` are left out
+    let later = since + chrono::Duration::days(1);
+    assert_eq!(failed_request_report(&both, later).total.aborted, 0);
+}
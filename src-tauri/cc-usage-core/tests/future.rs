@@ -0,0 +1,87 @@
+//! Entries dated in the future (a clock set ahead) are quarantined as journals are parsed:
+//! left out of the stats and counted in parse health, with `last_updated` never after now.
+
+mod common;
+
+use cc_usage_core::aggregate::{aggregate_usage, compute_weekly_usage};
+use cc_usage_core::parser::{
+    begin_parse_health, finish_parse_health, parse_user_prompt_timestamp, quarantine_future_entries,
+};
+use cc_usage_core::quota::build_quota_info;
+use cc_usage_core::{parse_health, parse_usage_from_reader, ParsedEntry, TokenUsage};
+use chrono::{DateTime, Duration, Utc};
+
+fn entry(timestamp: DateTime<Utc>, output_tokens: u64) -> ParsedEntry {
+    let tokens = TokenUsage {
+        output_tokens,
+        ..TokenUsage::default()
+    };
+    common::entry(common::SESSION_ID, &timestamp.to_rfc3339(), tokens)
+}
+
+#[test]
+fn future_entries_are_quarantined() {
+    let now = Utc::now();
+    let mut entries = vec![
+        entry(now - Duration::minutes(10), 10),
+        entry(now + Duration::minutes(2), 20), // within the tolerance
+        entry(now + Duration::days(3), 1000),
+    ];
+    let _state = common::lock_global_state();
+    begin_parse_health();
+    assert_eq!(quarantine_future_entries(&mut entries, now), 1);
+    assert_eq!(entries.len(), 2);
+    let health = finish_parse_health();
+    assert_eq!(health.future_entries, 1);
+    assert!(health.max_future_seconds >= 3 * 86_400 - 1);
+
+    // A second load counts afresh rather than adding to the first
+    begin_parse_health();
+    quarantine_future_entries(&mut vec![entry(now + Duration::days(1), 5)], now);
+    let health = finish_parse_health();
+    assert_eq!(health.future_entries, 1);
+    assert_eq!(parse_health().future_entries, 1);
+
+    let stats = aggregate_usage(
+        vec![
+            entry(now - Duration::minutes(10), 10),
+            entry(now + Duration::minutes(2), 20),
+            entry(now + Duration::days(3), 1000),
+        ],
+        None,
+        build_quota_info(None, 0.0, 0.0, Vec::new()),
+        Vec::new(),
+        compute_weekly_usage(&[]),
+    );
+    assert_eq!(stats.total_tokens.output_tokens, 30);
+    let last_updated = DateTime::parse_from_rfc3339(&stats.last_updated).unwrap();
+    assert!(last_updated <= Utc::now());
+}
+
+#[test]
+fn future_lines_are_dropped_while_parsing() {
+    let now = Utc::now();
+    let usage = |at: DateTime<Utc>| {
+        format!(
+            r#"{{"type":"assistant","timestamp":"{}","message":{{"model":"claude-sonnet-4-5-20250929","usage":{{"input_tokens":1,"output_tokens":2}}}}}}"#,
+            at.to_rfc3339()
+        )
+    };
+    let prompt = |at: DateTime<Utc>| {
+        format!(
+            r#"{{"type":"user","timestamp":"{}","message":{{"content":"Hello"}}}}"#,
+            at.to_rfc3339()
+        )
+    };
+
+    let journal = format!(
+        "{}\n{}\n",
+        usage(now - Duration::hours(1)),
+        usage(now + Duration::days(2))
+    );
+    assert_eq!(parse_usage_from_reader(journal.as_bytes()).len(), 1);
+
+    // Prompt scans (activity, quota) skip them too
+    assert!(parse_user_prompt_timestamp(&prompt(now - Duration::hours(1))).is_some());
+    assert!(parse_user_prompt_timestamp(&prompt(now + Duration::days(2))).is_none());
+}
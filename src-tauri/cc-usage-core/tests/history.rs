@@ -0,0 +1,51 @@
+//! Prompt history: commands told apart from prompts, sessions titled by their first prompt,
+//! and directories filled in for entries without one.
+
+mod common;
+
+use cc_usage_core::history::{fill_missing_directories, parse_history, session_histories};
+use cc_usage_core::{ParsedEntry, TokenUsage};
+
+const HISTORY: &str = r#"{"display":"/model opus","timestamp":1741600800000,"project":"/home/user/api","sessionId":"s1"}
+{"display":"Fix the failing login test\nIt times out on CI","timestamp":1741600860000,"project":"/home/user/api","sessionId":"s1"}
+{"display":"!cargo test","timestamp":1741600920000,"project":"/home/user/api","sessionId":"s1"}
+not json
+{"display":"   ","timestamp":1741600980000,"project":"/home/user/api","sessionId":"s1"}
+{"display":"Old prompt without a session","timestamp":1741600000000,"project":"/home/user/web"}
+"#;
+
+#[test]
+fn history_titles_commands_and_directories() {
+    let entries = parse_history(HISTORY.as_bytes());
+    assert_eq!(entries.len(), 4);
+
+    let histories = session_histories(&entries);
+    assert_eq!(histories.len(), 1);
+    let session = &histories["s1"];
+    assert_eq!(
+        session.first_prompt.as_deref(),
+        Some("Fix the failing login test")
+    );
+    assert_eq!(session.prompts, 1);
+    assert_eq!(session.commands, 2);
+    assert_eq!(session.project.as_deref(), Some("/home/user/api"));
+
+    // Prompts are placed by time, not by the order they are read in
+    let reversed: Vec<_> = entries.iter().rev().cloned().collect();
+    let session = &session_histories(&reversed)["s1"];
+    assert_eq!(
+        session.first_prompt.as_deref(),
+        Some("Fix the failing login test")
+    );
+    assert_eq!(session.commands, 2);
+
+    let entry = |session_id: &str, cwd: &str| ParsedEntry {
+        cwd: cwd.to_string(),
+        ..common::entry(session_id, "2025-03-10T10:00:00Z", TokenUsage::default())
+    };
+    let mut usage = vec![entry("s1", ""), entry("s1", "/tmp"), entry("s2", "")];
+    fill_missing_directories(&mut usage, &histories);
+    assert_eq!(usage[0].cwd, "/home/user/api");
+    assert_eq!(usage[1].cwd, "/tmp");
+    assert_eq!(usage[2].cwd, "");
+}
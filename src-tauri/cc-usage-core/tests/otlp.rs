@@ -0,0 +1,83 @@
+//! OTLP/JSON log export from Claude Code: API request events become entries, other events
+//! are ignored.
+
+use cc_usage_core::otlp::{ingest_otlp_logs, otlp_entries};
+use chrono::Utc;
+use serde_json::json;
+
+fn export(records: serde_json::Value) -> Vec<u8> {
+    json!({
+        "resourceLogs": [{
+            "resource": {
+                "attributes": [{"key": "service.name", "value": {"stringValue": "claude-code"}}]
+            },
+            "scopeLogs": [{"logRecords": records}]
+        }]
+    })
+    .to_string()
+    .into_bytes()
+}
+
+#[test]
+fn api_request_events_become_entries() {
+    let now = Utc::now().to_rfc3339();
+    let body = export(json!([
+        {
+            "body": {"stringValue": "claude_code.api_request"},
+            "attributes": [
+                {"key": "event.name", "value": {"stringValue": "api_request"}},
+                {"key": "event.timestamp", "value": {"stringValue": now}},
+                {"key": "session.id", "value": {"stringValue": "otlp-session"}},
+                {"key": "model", "value": {"stringValue": "claude-sonnet-4-5-20250929"}},
+                {"key": "input_tokens", "value": {"stringValue": "120"}},
+                {"key": "output_tokens", "value": {"intValue": "45"}},
+                {"key": "cache_read_tokens", "value": {"intValue": 3000}},
+                {"key": "cache_creation_tokens", "value": {"doubleValue": 500.0}}
+            ]
+        },
+        {
+            "body": {"stringValue": "claude_code.user_prompt"},
+            "attributes": [
+                {"key": "event.name", "value": {"stringValue": "user_prompt"}},
+                {"key": "event.timestamp", "value": {"stringValue": now}}
+            ]
+        }
+    ]));
+
+    let entries = ingest_otlp_logs(&body).unwrap();
+    assert_eq!(entries.len(), 1);
+    let entry = &entries[0];
+    assert_eq!(entry.session_id, "otlp-session");
+    assert_eq!(entry.model, "claude-sonnet-4-5-20250929");
+    assert_eq!(entry.tokens.input_tokens, 120);
+    assert_eq!(entry.tokens.output_tokens, 45);
+    assert_eq!(entry.tokens.cache_read_input_tokens, 3000);
+    assert_eq!(entry.tokens.cache_creation_input_tokens, 500);
+    assert!(entry.cwd.is_empty());
+
+    assert!(otlp_entries()
+        .iter()
+        .any(|e| e.session_id == "otlp-session"));
+}
+
+#[test]
+fn record_time_is_used_without_event_timestamp() {
+    let nanos = Utc::now().timestamp_nanos_opt().unwrap().to_string();
+    let body = export(json!([{
+        "timeUnixNano": nanos,
+        "attributes": [
+            {"key": "event.name", "value": {"stringValue": "api_request"}},
+            {"key": "model", "value": {"stringValue": "claude-opus-4-1"}},
+            {"key": "output_tokens", "value": {"stringValue": "10"}}
+        ]
+    }]));
+
+    let entries = ingest_otlp_logs(&body).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].tokens.output_tokens, 10);
+}
+
+#[test]
+fn invalid_json_is_rejected() {
+    assert!(ingest_otlp_logs(b"\x0a\x12protobuf").is_err());
+}
@@ -0,0 +1,131 @@
+//! Hardening tests for the journal parser: property-based adversarial lines plus the
+//! checked-in regression corpus under `tests/corpus` (shared with the fuzz targets).
+
+use cc_usage_core::parser::parse_user_prompt_timestamp;
+use cc_usage_core::quota::count_user_prompts_in_window;
+use cc_usage_core::{parse_usage_from_file, parse_usage_from_reader};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Generous upper bound for parsing one corpus file; catches quadratic blowups, not jitter
+const PARSE_BUDGET: Duration = Duration::from_secs(5);
+
+/// Window wide enough to include every corpus timestamp
+const ALL_TIME_HOURS: i64 = 24 * 365 * 100;
+
+fn corpus_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("corpus")
+        .join(name)
+}
+
+fn corpus_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(corpus_path(""))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn corpus_parses_without_panicking() {
+    for path in corpus_files() {
+        let start = Instant::now();
+        parse_usage_from_file(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        for line in bytes.split(|b| *b == b'\n') {
+            if let Ok(line) = std::str::from_utf8(line) {
+                parse_user_prompt_timestamp(line);
+            }
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed < PARSE_BUDGET, "{path:?} took {elapsed:?}");
+    }
+}
+
+#[test]
+fn out_of_range_token_counts_are_skipped() {
+    let entries = parse_usage_from_file(&corpus_path("huge_numbers.jsonl")).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].tokens.input_tokens, u64::MAX);
+    assert_eq!(entries[0].tokens.cache_read_input_tokens, u64::MAX);
+}
+
+#[test]
+fn invalid_utf8_lines_do_not_stop_parsing() {
+    let files = [corpus_path("broken_utf8.jsonl")];
+    assert_eq!(count_user_prompts_in_window(&files, ALL_TIME_HOURS), 1);
+}
+
+#[test]
+fn deeply_nested_content_is_rejected_not_recursed() {
+    let entries = parse_usage_from_file(&corpus_path("deep_nesting.jsonl")).unwrap();
+    assert!(entries.is_empty());
+}
+
+fn nested(depth: usize) -> String {
+    format!("{}{}", "[".repeat(depth), "]".repeat(depth))
+}
+
+/// Lines shaped like journal entries with adversarial values, mixed with arbitrary text
+fn journal_line() -> impl Strategy<Value = String> {
+    prop_oneof![
+        any::<String>(),
+        (any::<u64>(), any::<u64>(), "[a-z0-9.-]{0,24}").prop_map(|(input, output, model)| {
+            format!(
+                r#"{{"type":"assistant","timestamp":"2025-06-01T10:00:00Z","message":{{"model":"{model}","usage":{{"input_tokens":{input},"output_tokens":{output}}}}}}}"#
+            )
+        }),
+        (any::<i128>(), any::<f64>()).prop_map(|(big, float)| {
+            format!(
+                r#"{{"type":"assistant","message":{{"model":"claude-opus-4","usage":{{"input_tokens":{big},"output_tokens":{float}}}}}}}"#
+            )
+        }),
+        (1usize..2000).prop_map(|depth| {
+            format!(
+                r#"{{"type":"user","timestamp":"2025-06-01T10:00:00Z","message":{{"content":{}}}}}"#,
+                nested(depth)
+            )
+        }),
+        (1usize..2000).prop_map(|depth| {
+            format!(
+                r#"{{"type":"assistant","message":{{"model":"m","usage":{{}},"content":[{{"type":"tool_use","name":"TodoWrite","input":{{"todos":{}}}}}]}}}}"#,
+                nested(depth)
+            )
+        }),
+        "\\PC{0,64}".prop_map(|text| format!(r#"{{"type":"user","message":{{"content":"{text}"}}}}"#)),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_lines_never_panic(lines in vec(journal_line(), 0..16)) {
+        let input = lines.join("\n");
+        let entries = parse_usage_from_reader(Cursor::new(input.as_bytes()));
+        prop_assert!(entries.len() <= input.split('\n').count());
+        for line in input.split('\n') {
+            parse_user_prompt_timestamp(line);
+        }
+    }
+
+    #[test]
+    fn arbitrary_bytes_never_panic(bytes in vec(any::<u8>(), 0..4096)) {
+        parse_usage_from_reader(Cursor::new(bytes));
+    }
+
+    #[test]
+    fn token_counts_round_trip(input in any::<u64>(), output in any::<u64>()) {
+        let line = format!(
+            r#"{{"type":"assistant","timestamp":"2025-06-01T10:00:00Z","message":{{"model":"claude-sonnet-4","usage":{{"input_tokens":{input},"output_tokens":{output}}}}}}}"#
+        );
+        let entries = parse_usage_from_reader(Cursor::new(line.as_bytes()));
+        prop_assert_eq!(entries.len(), 1);
+        prop_assert_eq!(entries[0].tokens.input_tokens, input);
+        prop_assert_eq!(entries[0].tokens.output_tokens, output);
+    }
+}
@@ -0,0 +1,31 @@
+//! The "current-window" period: it starts with the active 5hr block, counts nothing while no
+//! block runs, and reads journals far enough back to cover the whole block.
+
+use cc_usage_core::aggregate::{period_file_hours, period_since};
+use cc_usage_core::quota::{QuotaBlock, QUOTA_WINDOW_HOURS};
+use chrono::{Duration, Utc};
+
+#[test]
+fn current_window_follows_the_active_block() {
+    let now = Utc::now();
+    let block = QuotaBlock {
+        start: now - Duration::minutes(290),
+        end: now + Duration::minutes(10),
+        prompts: 12,
+        weighted: 30.0,
+    };
+    assert_eq!(
+        period_since("current-window", Some(&block)),
+        Some(block.start)
+    );
+
+    // No block running: the window starts now, so nothing is counted
+    let since = period_since("current-window", None).unwrap();
+    assert!(since >= now && since - now < Duration::seconds(5));
+
+    // Journals written since the oldest possible block start are read
+    let hours = period_file_hours("current-window").unwrap();
+    assert!(hours > QUOTA_WINDOW_HOURS);
+    assert!(Duration::hours(hours) > now - block.start);
+    assert!(hours < period_file_hours("today").unwrap());
+}
@@ -0,0 +1,48 @@
+//! Planning work: it fits the quota left, waits for the active 5hr block to end, or waits for
+//! old prompts to leave the rolling week.
+
+use cc_usage_core::quota::plan_prompts;
+use chrono::{DateTime, Duration, Utc};
+
+#[test]
+fn plan_waits_for_the_window_and_the_week() {
+    let at = DateTime::parse_from_rfc3339("2025-03-12T20:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+
+    // 300 Sonnet prompts in a block started an hour ago: 200 of 500 left
+    let block_start = at - Duration::hours(1);
+    let prompts: Vec<(DateTime<Utc>, f64)> = (0..300)
+        .map(|i| (block_start + Duration::seconds(i), 1.0))
+        .collect();
+
+    // 50 Opus prompts weigh 137.5
+    let plan = plan_prompts(&prompts, 50, "claude-opus-4-1", at);
+    assert!(plan.fits_window && plan.fits_week);
+    assert_eq!(plan.weighted_needed, 137.5);
+    assert_eq!(plan.earliest_start, Some(at.to_rfc3339()));
+
+    // 80 Opus prompts weigh 220: start when the block ends
+    let plan = plan_prompts(&prompts, 80, "opus", at);
+    assert!(!plan.fits_window);
+    assert_eq!(plan.window_remaining, 200.0);
+    let block_end = block_start + Duration::hours(5);
+    assert_eq!(plan.earliest_start, Some(block_end.to_rfc3339()));
+
+    // More than a whole window never fits in one
+    let plan = plan_prompts(&prompts, 200, "opus", at);
+    assert_eq!(plan.earliest_start, None);
+    assert_eq!(plan.windows_needed, 2);
+
+    // A week nearly used up six days ago: start once those prompts leave the week
+    let six_days_ago = at - Duration::days(6);
+    let old: Vec<(DateTime<Utc>, f64)> = (0..2500)
+        .map(|i| (six_days_ago + Duration::seconds(i), 1.0))
+        .collect();
+    let plan = plan_prompts(&old, 100, "sonnet", at);
+    assert!(plan.fits_window && !plan.fits_week);
+    assert_eq!(plan.week_remaining, 90.0);
+    // 10 of the oldest prompts have to leave the week
+    let week_ok = six_days_ago + Duration::seconds(9) + Duration::days(7);
+    assert_eq!(plan.earliest_start, Some(week_ok.to_rfc3339()));
+}
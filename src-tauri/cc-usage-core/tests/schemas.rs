@@ -0,0 +1,19 @@
+//! JSON Schemas: one per serialized type, written to `SCHEMA_OUT_DIR` when set (the target
+//! dir otherwise), and written again only when they change.
+
+use cc_usage_core::{write_schemas, SCHEMA_TYPES};
+use std::path::PathBuf;
+
+#[test]
+fn schemas_are_written() {
+    let dir = std::env::var_os("SCHEMA_OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("schemas"));
+    write_schemas(&dir).unwrap();
+    for name in SCHEMA_TYPES {
+        let json = std::fs::read_to_string(dir.join(format!("{name}.schema.json"))).unwrap();
+        assert!(serde_json::from_str::<serde_json::Value>(&json).is_ok());
+    }
+    // Nothing changed since
+    assert_eq!(write_schemas(&dir), Ok(0));
+}
@@ -0,0 +1,40 @@
+//! Session ids given by the UI: the 8-character prefixes shown for active sessions resolve to
+//! the full id by journal name; short, unknown and ambiguous ids are refused.
+
+mod common;
+
+use cc_usage_core::sources::match_session_id;
+use std::path::PathBuf;
+
+fn journal(root: &str, session_id: &str) -> PathBuf {
+    PathBuf::from(format!(
+        "/home/user/{root}/projects/-home-user-project/{session_id}.jsonl"
+    ))
+}
+
+#[test]
+fn prefixes_resolve_to_one_session() {
+    let other = "7b41d0aa-0000-4000-8000-000000000002";
+    let sibling = "7b41d0aa-0000-4000-8000-000000000003";
+    let journals = [
+        journal(".claude", common::SESSION_ID),
+        journal(".config/claude", common::SESSION_ID),
+        journal(".claude", other),
+        journal(".claude", sibling),
+    ];
+
+    // The id shown for active sessions, in any case; every journal of the session comes along
+    let (id, files) = match_session_id("3F2A9C1E", &journals).unwrap();
+    assert_eq!(id, common::SESSION_ID);
+    assert_eq!(files, journals[..2]);
+
+    let (id, _) = match_session_id(other, &journals).unwrap();
+    assert_eq!(id, other);
+
+    assert!(match_session_id("7b41d0aa", &journals)
+        .unwrap_err()
+        .contains("matches 2 sessions"));
+    assert!(match_session_id("0123abcd", &journals).is_err());
+    assert!(match_session_id("3f2a9c", &journals).is_err());
+    assert!(match_session_id("../3f2a9c1e", &journals).is_err());
+}
@@ -0,0 +1,53 @@
+//! Throttling advice: proceed with headroom, slow down when the burn rate would use up the
+//! 5hr window before it ends, stop when it is full.
+
+use cc_usage_core::{throttle_advice, PaceStatus, QuotaInfo, ThrottleAction};
+use chrono::{DateTime, Duration, Utc};
+
+fn quota(usage_percent: f64, started_hours_ago: i64, at: DateTime<Utc>) -> QuotaInfo {
+    let start = at - Duration::hours(started_hours_ago);
+    QuotaInfo {
+        messages_in_window: 0,
+        window_hours: 5,
+        estimated_limit: 500,
+        usage_percent,
+        plan: "Max 5x".to_string(),
+        week_usage_percent: 30.0,
+        week_limit_hours: 210,
+        window_started_at: Some(start.to_rfc3339()),
+        window_ends_at: Some((start + Duration::hours(5)).to_rfc3339()),
+        week_to_date_percent: 30.0,
+        pace_status: PaceStatus::OnTrack,
+        pace_delta_percent: 0.0,
+        safe_pace_percent_per_hour: 0.0,
+        expected_week_percent: 30.0,
+        projected_week_usage_percent: 60.0,
+        windows: Vec::new(),
+    }
+}
+
+#[test]
+fn advice_follows_headroom_and_burn_rate() {
+    let at = DateTime::parse_from_rfc3339("2025-03-12T12:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+
+    // 10% per hour, 3 hours left: ends at 50%
+    let advice = throttle_advice(&quota(20.0, 2, at), at);
+    assert_eq!(advice.action, ThrottleAction::Proceed);
+    assert_eq!(advice.seconds_until_ok, 0);
+
+    // 50% per hour, 4 hours left: the remaining 50% lasts 1 hour, so pause for 3
+    let advice = throttle_advice(&quota(50.0, 1, at), at);
+    assert_eq!(advice.action, ThrottleAction::Slow);
+    assert_eq!(advice.seconds_until_ok, 3 * 3600);
+    assert_eq!(advice.window_burn_percent_per_hour, 50.0);
+
+    // Full window: wait for the block to end
+    let mut full = quota(96.0, 4, at);
+    full.window_ends_at = Some((at + Duration::minutes(30)).to_rfc3339());
+    let advice = throttle_advice(&full, at);
+    assert_eq!(advice.action, ThrottleAction::Stop);
+    assert_eq!(advice.seconds_until_ok, 30 * 60);
+    assert!(advice.reason.contains("5hr window"));
+}
@@ -0,0 +1,49 @@
+//! Usage per OS user: entries grouped by their session's owner, with sessions without an
+//! owner, earlier entries and future-dated ones left out.
+
+mod common;
+
+use cc_usage_core::users::usage_by_user;
+use cc_usage_core::{ParsedEntry, TokenUsage};
+use chrono::{Duration, SecondsFormat, Utc};
+use std::collections::HashMap;
+
+fn entry(session_id: &str, minutes_ago: i64, output_tokens: u64) -> ParsedEntry {
+    let timestamp =
+        (Utc::now() - Duration::minutes(minutes_ago)).to_rfc3339_opts(SecondsFormat::Secs, true);
+    let tokens = TokenUsage {
+        input_tokens: 100,
+        output_tokens,
+        ..TokenUsage::default()
+    };
+    common::entry(session_id, &timestamp, tokens)
+}
+
+#[test]
+fn usage_grouped_by_session_owner() {
+    let owners: HashMap<String, String> = [("a1", "alice"), ("a2", "alice"), ("b1", "bob")]
+        .into_iter()
+        .map(|(session, user)| (session.to_string(), user.to_string()))
+        .collect();
+    let entries = vec![
+        entry("a1", 30, 1_000),
+        entry("a2", 20, 1_000),
+        entry("b1", 10, 100),
+        entry("b1", 600, 5_000),     // before `since`
+        entry("b1", -60, 5_000),     // an hour in the future
+        entry("otlp", 5, 1_000_000), // no owner
+    ];
+
+    let users = usage_by_user(&entries, &owners, Some(Utc::now() - Duration::hours(2)));
+
+    assert_eq!(users.len(), 2);
+    assert_eq!(users[0].user, "alice");
+    assert_eq!(users[0].session_count, 2);
+    assert_eq!(users[0].message_count, 2);
+    assert_eq!(users[0].tokens.output_tokens, 2_000);
+    assert_eq!(users[1].user, "bob");
+    assert_eq!(users[1].message_count, 1);
+    assert!(users[0].cost_usd > users[1].cost_usd);
+
+    assert!(usage_by_user(&entries, &HashMap::new(), None).is_empty());
+}
@@ -0,0 +1,409 @@
+//! User-defined alert rules ("when metric X over the last Y minutes exceeds Z, do A"),
+//! evaluated by the scheduler once a minute. A rule fires when its metric goes above the
+//! threshold and fires again only after dropping back to or below it.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::config::AppConfig;
+use crate::crash::log_line;
+use crate::notifications::{self, Notifier};
+use crate::usage::{custom_window_statuses, QuotaMetric, QuotaWindowDef};
+
+/// What a rule measures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    /// Input, output and cache write tokens within the window
+    Tokens,
+    /// User prompts within the window
+    Prompts,
+    /// Model-weighted prompts within the window, as the quota counts them
+    Weighted,
+    /// API-equivalent cost within the window
+    CostUsd,
+    /// Estimated usage of the current 5hr quota block; the window is ignored
+    WindowPercent,
+    /// Estimated weekly quota usage; the window is ignored
+    WeekPercent,
+}
+
+impl AlertMetric {
+    fn windowed(self) -> bool {
+        !matches!(self, AlertMetric::WindowPercent | AlertMetric::WeekPercent)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertAction {
+    /// Desktop notification, honoring mute and quiet hours
+    Notify,
+    /// POST the alert as JSON to `webhook_url`
+    Webhook,
+    /// `AlertFired` signal on the D-Bus interface (Linux)
+    DbusSignal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    pub metric: AlertMetric,
+    /// Minutes back from now the metric is measured over
+    #[serde(default = "default_window_minutes")]
+    pub window_minutes: u32,
+    pub threshold: f64,
+    pub action: AlertAction,
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_window_minutes() -> u32 {
+    60
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// A rule's current value, from `get_alert_status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertStatus {
+    pub name: String,
+    pub metric: AlertMetric,
+    pub value: f64,
+    pub threshold: f64,
+    /// Above the threshold; the action ran when it got there
+    pub firing: bool,
+}
+
+/// Payload of webhooks and the `alert-fired` event
+#[derive(Debug, Clone, Serialize)]
+struct FiredAlert<'a> {
+    name: &'a str,
+    metric: AlertMetric,
+    window_minutes: u32,
+    value: f64,
+    threshold: f64,
+    fired_at: String,
+}
+
+/// Check rules before saving them
+pub fn validate_rules(rules: &[AlertRule]) -> Result<(), String> {
+    let mut names = HashSet::new();
+    for rule in rules {
+        if rule.name.trim().is_empty() {
+            return Err("Alert rules need a name".to_string());
+        }
+        // Firing state is kept per name
+        if !names.insert(rule.name.as_str()) {
+            return Err(format!("Two alert rules are named '{}'", rule.name));
+        }
+        if !rule.threshold.is_finite() {
+            return Err(format!("Alert '{}' has no valid threshold", rule.name));
+        }
+        if rule.metric.windowed() && rule.window_minutes == 0 {
+            return Err(format!(
+                "Alert '{}' needs a window of at least 1 minute",
+                rule.name
+            ));
+        }
+        if rule.action == AlertAction::Webhook {
+            let url = rule.webhook_url.as_deref().unwrap_or_default();
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(format!(
+                    "Alert '{}' needs an http(s) webhook_url",
+                    rule.name
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Current value of every enabled rule's metric at `now`
+fn measure(rules: &[&AlertRule], now: DateTime<Utc>) -> Vec<f64> {
+    // Windowed counts come from the same computation as custom quota windows
+    let defs: Vec<QuotaWindowDef> = rules
+        .iter()
+        .filter_map(|rule| {
+            let metric = match rule.metric {
+                AlertMetric::Tokens => QuotaMetric::Tokens,
+                AlertMetric::Prompts => QuotaMetric::Prompts,
+                AlertMetric::Weighted => QuotaMetric::Weighted,
+                _ => return None,
+            };
+            Some(QuotaWindowDef {
+                name: rule.name.clone(),
+                window_minutes: rule.window_minutes,
+                metric,
+                limit: rule.threshold,
+            })
+        })
+        .collect();
+    let mut windows = custom_window_statuses(&defs, now).into_iter();
+    let quota = rules
+        .iter()
+        .any(|rule| !rule.metric.windowed())
+        .then(|| crate::state::get().quota());
+
+    rules
+        .iter()
+        .map(|rule| match rule.metric {
+            AlertMetric::Tokens | AlertMetric::Prompts | AlertMetric::Weighted => {
+                windows.next().map(|window| window.used).unwrap_or(0.0)
+            }
+            AlertMetric::CostUsd => cost_in_window(rule.window_minutes, now),
+            AlertMetric::WindowPercent => quota.as_ref().map_or(0.0, |q| q.usage_percent),
+            AlertMetric::WeekPercent => quota.as_ref().map_or(0.0, |q| q.week_usage_percent),
+        })
+        .collect()
+}
+
+fn cost_in_window(window_minutes: u32, now: DateTime<Utc>) -> f64 {
+    let since = now - chrono::Duration::minutes(window_minutes as i64);
+    let hours_back = window_minutes as i64 / 60 + 1;
+    crate::state::get()
+        .recent_entries(hours_back)
+        .iter()
+        .filter(|entry| {
+            DateTime::parse_from_rfc3339(&entry.timestamp).is_ok_and(|ts| ts >= since && ts <= now)
+        })
+        .map(|entry| entry.cost())
+        .sum()
+}
+
+/// Status of every enabled rule; `firing` holds the names of rules currently above their
+/// threshold
+pub fn alert_status(cfg: &AppConfig, firing: &[String]) -> Vec<AlertStatus> {
+    let rules: Vec<&AlertRule> = cfg.alerts.iter().filter(|rule| rule.enabled).collect();
+    let values = measure(&rules, Utc::now());
+    rules
+        .iter()
+        .zip(values)
+        .map(|(rule, value)| AlertStatus {
+            name: rule.name.clone(),
+            metric: rule.metric,
+            value,
+            threshold: rule.threshold,
+            firing: firing.contains(&rule.name),
+        })
+        .collect()
+}
+
+/// Evaluate the enabled rules, running the action of each rule that crossed its threshold
+/// since the last call. `firing` is updated; returns whether it changed.
+pub fn evaluate(notifier: &Notifier, cfg: &AppConfig, firing: &mut Vec<String>) -> bool {
+    let rules: Vec<&AlertRule> = cfg.alerts.iter().filter(|rule| rule.enabled).collect();
+    let before = firing.clone();
+    // Forget rules that were removed or disabled
+    firing.retain(|name| rules.iter().any(|rule| &rule.name == name));
+    if rules.is_empty() {
+        return *firing != before;
+    }
+
+    let now = Utc::now();
+    for (rule, value) in rules.iter().zip(measure(&rules, now)) {
+        let was_firing = firing.contains(&rule.name);
+        if value <= rule.threshold {
+            if was_firing {
+                firing.retain(|name| name != &rule.name);
+            }
+            continue;
+        }
+        if was_firing {
+            continue;
+        }
+        firing.push(rule.name.clone());
+        let alert = FiredAlert {
+            name: &rule.name,
+            metric: rule.metric,
+            window_minutes: rule.window_minutes,
+            value,
+            threshold: rule.threshold,
+            fired_at: now.to_rfc3339(),
+        };
+        log_line!(
+            "Alert '{}' fired: {value:.2} > {}",
+            rule.name,
+            rule.threshold
+        );
+        if let Some(app_handle) = notifier.app_handle() {
+            crate::events::emit(app_handle, "alert-fired", &alert);
+        }
+        run_action(notifier, rule, &alert);
+    }
+    *firing != before
+}
+
+fn describe(alert: &FiredAlert) -> String {
+    let metric = serde_json::to_value(alert.metric)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default();
+    if alert.metric.windowed() {
+        format!(
+            "{metric} over the last {} min is {:.2}, above {}",
+            alert.window_minutes, alert.value, alert.threshold
+        )
+    } else {
+        format!("{metric} is {:.1}, above {}", alert.value, alert.threshold)
+    }
+}
+
+fn run_action(notifier: &Notifier, rule: &AlertRule, alert: &FiredAlert) {
+    match rule.action {
+        AlertAction::Notify => notifier.send(
+            notifications::ALERT,
+            &format!("Claude Code alert: {}", rule.name),
+            &describe(alert),
+        ),
+        AlertAction::Webhook => {
+            let url = rule.webhook_url.clone().unwrap_or_default();
+            let body = match serde_json::to_value(alert) {
+                Ok(body) => body,
+                Err(e) => {
+                    log_line!("Failed to serialize alert '{}': {e}", rule.name);
+                    return;
+                }
+            };
+            let name = rule.name.clone();
+            tauri::async_runtime::spawn(async move {
+                let result = reqwest::Client::new()
+                    .post(&url)
+                    .timeout(std::time::Duration::from_secs(10))
+                    .json(&body)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+                if let Err(e) = result {
+                    log_line!("Webhook for alert '{name}' failed: {e}");
+                }
+            });
+        }
+        AlertAction::DbusSignal => {
+            #[cfg(target_os = "linux")]
+            crate::dbus_service::emit_alert_fired(
+                &rule.name,
+                &describe(alert),
+                alert.value,
+                alert.threshold,
+            );
+            #[cfg(not(target_os = "linux"))]
+            log_line!(
+                "Alert '{}' asks for a D-Bus signal, which is only available on Linux",
+                rule.name
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, metric: AlertMetric, action: AlertAction) -> AlertRule {
+        AlertRule {
+            name: name.to_string(),
+            metric,
+            window_minutes: 60,
+            threshold: 10.0,
+            action,
+            webhook_url: None,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn rules_are_read_with_defaults() {
+        let rule: AlertRule = serde_json::from_value(serde_json::json!({
+            "name": "Spend",
+            "metric": "cost_usd",
+            "threshold": 5,
+            "action": "dbus_signal",
+        }))
+        .unwrap();
+        assert_eq!(rule.metric, AlertMetric::CostUsd);
+        assert_eq!(rule.action, AlertAction::DbusSignal);
+        assert_eq!(rule.window_minutes, 60);
+        assert!(rule.enabled);
+
+        let unknown = serde_json::json!({
+            "name": "Spend",
+            "metric": "dollars",
+            "threshold": 5,
+            "action": "notify",
+        });
+        assert!(serde_json::from_value::<AlertRule>(unknown).is_err());
+    }
+
+    #[test]
+    fn invalid_rules_are_refused() {
+        let valid = rule("Tokens", AlertMetric::Tokens, AlertAction::Notify);
+        assert!(validate_rules(&[]).is_ok());
+        assert!(validate_rules(std::slice::from_ref(&valid)).is_ok());
+
+        let unnamed = rule(" ", AlertMetric::Tokens, AlertAction::Notify);
+        assert!(validate_rules(&[unnamed]).is_err());
+        let err = validate_rules(&[valid.clone(), valid.clone()]).unwrap_err();
+        assert!(err.contains("Two alert rules"), "{err}");
+
+        let mut no_threshold = valid.clone();
+        no_threshold.threshold = f64::NAN;
+        assert!(validate_rules(&[no_threshold]).is_err());
+
+        // Only windowed metrics need a window
+        let mut no_window = valid.clone();
+        no_window.window_minutes = 0;
+        assert!(validate_rules(&[no_window]).is_err());
+        let mut week = rule("Week", AlertMetric::WeekPercent, AlertAction::Notify);
+        week.window_minutes = 0;
+        assert!(validate_rules(&[week]).is_ok());
+    }
+
+    #[test]
+    fn webhooks_need_an_http_url() {
+        let mut webhook = rule("Hook", AlertMetric::Prompts, AlertAction::Webhook);
+        assert!(validate_rules(std::slice::from_ref(&webhook)).is_err());
+        for url in ["", "ftp://example.com/hook", "example.com/hook"] {
+            webhook.webhook_url = Some(url.to_string());
+            assert!(
+                validate_rules(std::slice::from_ref(&webhook)).is_err(),
+                "{url}"
+            );
+        }
+        for url in ["http://localhost:9000/hook", "https://example.com/hook"] {
+            webhook.webhook_url = Some(url.to_string());
+            assert!(
+                validate_rules(std::slice::from_ref(&webhook)).is_ok(),
+                "{url}"
+            );
+        }
+    }
+
+    #[test]
+    fn fired_alerts_are_described_by_metric() {
+        let alert = FiredAlert {
+            name: "Spend",
+            metric: AlertMetric::CostUsd,
+            window_minutes: 30,
+            value: 12.345,
+            threshold: 10.0,
+            fired_at: String::new(),
+        };
+        assert_eq!(
+            describe(&alert),
+            "cost_usd over the last 30 min is 12.35, above 10"
+        );
+        let alert = FiredAlert {
+            metric: AlertMetric::WindowPercent,
+            value: 91.26,
+            threshold: 90.0,
+            ..alert
+        };
+        assert_eq!(describe(&alert), "window_percent is 91.3, above 90");
+    }
+}
@@ -1,13 +1,25 @@
-use chrono::Utc;
+use chrono::{Local, NaiveDate, Utc};
 use reqwest::header::{HeaderMap, HeaderValue};
-use serde::Deserialize;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::{mask_api_key, TeamSeats};
+use crate::crash::log_line;
 
 use crate::usage::{
-    build_active_sessions, collect_daily_activity, collect_jsonl_files, compute_weekly_usage,
-    count_user_prompts_in_window, count_weighted_usage_in_window, get_claude_data_dirs,
-    get_model_display_name, parse_usage_from_file, ActiveSession, DailyActivity, ModelUsage,
-    QuotaInfo, TokenUsage, UsageStats, WeeklyUsage,
+    build_active_sessions, build_quota_info, check_data_dirs, collect_daily_activity,
+    collect_jsonl_files, collect_jsonl_files_checked, compute_off_hours_percent,
+    compute_peer_comparison, compute_productivity, compute_weekly_usage, count_week_weighted_usage,
+    current_quota_block, custom_window_statuses, estimate_output_speeds, get_claude_data_dirs,
+    get_configured_model, get_model_display_name, get_plan_hint, local_day_start, own_data_dirs,
+    parse_usage_from_file, quota_block_files, quota_windows, source_issue, ActiveSession,
+    DailyActivity, ModelUsage, OrgAnalytics, OrgSummary, OrgUtilization, PeerComparison, PerfSpan,
+    ProductivityStats, QuotaInfo, SourceIssue, TokenUsage, UsageStats, UserCodeMetrics,
+    WeeklyUsage,
 };
 
 const BASE_URL: &str = "https://api.anthropic.com";
@@ -60,6 +72,9 @@ pub struct CostResult {
     pub currency: Option<String>,
     pub model: Option<String>,
     pub cost_type: Option<String>,
+    /// Only present when grouped by workspace; None is the default workspace
+    #[serde(default)]
+    pub workspace_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -78,12 +93,202 @@ pub struct CostReportResponse {
     pub next_page: Option<String>,
 }
 
+// --- Claude Code Analytics types ---
+
+#[derive(Debug, Deserialize)]
+pub struct CodeActor {
+    #[serde(rename = "type")]
+    pub actor_type: String, // "user_actor" or "api_actor"
+    #[serde(default)]
+    pub email_address: Option<String>,
+    #[serde(default)]
+    pub api_key_name: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LinesOfCode {
+    #[serde(default)]
+    pub added: u64,
+    #[serde(default)]
+    pub removed: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CodeCoreMetrics {
+    #[serde(default)]
+    pub num_sessions: u32,
+    #[serde(default)]
+    pub lines_of_code: LinesOfCode,
+    #[serde(default)]
+    pub commits_by_claude_code: u32,
+    #[serde(default)]
+    pub pull_requests_by_claude_code: u32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ToolActions {
+    #[serde(default)]
+    pub accepted: u32,
+    #[serde(default)]
+    pub rejected: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EstimatedCost {
+    /// Cents
+    #[serde(default)]
+    pub amount: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CodeModelBreakdown {
+    #[serde(default)]
+    pub estimated_cost: Option<EstimatedCost>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+pub struct CodeAnalyticsRecord {
+    pub date: String,
+    pub actor: CodeActor,
+    #[serde(default)]
+    pub core_metrics: CodeCoreMetrics,
+    /// Per tool ("edit_tool", "write_tool", ...)
+    #[serde(default)]
+    pub tool_actions: HashMap<String, ToolActions>,
+    #[serde(default)]
+    pub model_breakdown: Vec<CodeModelBreakdown>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CodeAnalyticsResponse {
+    pub data: Vec<CodeAnalyticsRecord>,
+    pub has_more: bool,
+    pub next_page: Option<String>,
+}
+
+// --- Workspace types ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub display_color: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub archived_at: Option<String>,
+}
+
+// --- Organization types ---
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+pub struct Organization {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+pub struct OrgUser {
+    pub id: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    #[serde(default)]
+    pub role: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+pub struct Invite {
+    pub id: String,
+    #[serde(default)]
+    pub email: Option<String>,
+    /// "pending", "accepted", "expired" or "deleted"
+    #[serde(default)]
+    pub status: Option<String>,
+}
+
+/// A page of an `after_id`-paginated list (workspaces, users, invites)
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+pub struct ListResponse<T> {
+    pub data: Vec<T>,
+    pub has_more: bool,
+    pub first_id: Option<String>,
+    pub last_id: Option<String>,
+}
+
+/// Key that last authenticated successfully, tried first by new clients
+static ACTIVE_KEY: Mutex<Option<String>> = Mutex::new(None);
+
+/// The Admin API key currently in use, if any request has succeeded yet
+pub fn active_api_key() -> Option<String> {
+    ACTIVE_KEY.lock().ok().and_then(|key| key.clone())
+}
+
+/// Organization details by the UTC day and the (sorted) keys they were fetched with
+type CachedOrgDetails = (NaiveDate, Vec<String>, OrgSummary);
+
+/// Organization name, members, invites and workspaces; these rarely change, so they are
+/// fetched once a day rather than on every refresh
+static ORG_DETAILS: Mutex<Option<CachedOrgDetails>> = Mutex::new(None);
+
+/// When fetching the organization details last failed, how many times in a row, with which
+/// keys, and the error
+type OrgDetailsFailure = (Instant, u32, Vec<String>, String);
+
+/// Keys without org read access fail every time, so retries back off
+static ORG_DETAILS_FAILURE: Mutex<Option<OrgDetailsFailure>> = Mutex::new(None);
+
+/// Wait after the first failure to fetch the organization details, doubled per failure
+const ORG_DETAILS_RETRY: Duration = Duration::from_secs(60);
+
+/// Longest wait between attempts to fetch the organization details
+const ORG_DETAILS_MAX_RETRY: Duration = Duration::from_secs(6 * 3600);
+
+/// Admin API client over one or more keys. Requests go to the active key; a 401 falls
+/// through to the next key so org key rotations don't interrupt the widget.
 pub struct AdminApiClient {
-    client: reqwest::Client,
+    // (api key, client with that key's headers)
+    clients: Vec<(String, reqwest::Client)>,
 }
 
 impl AdminApiClient {
     pub fn new(api_key: &str) -> Result<Self, String> {
+        Self::with_keys(&[api_key.to_string()])
+    }
+
+    /// Client over several keys in priority order; the last key that worked goes first
+    pub fn with_keys(api_keys: &[String]) -> Result<Self, String> {
+        let mut keys: Vec<&String> = api_keys.iter().collect();
+        if let Some(active) = active_api_key() {
+            if let Some(pos) = keys.iter().position(|k| **k == active) {
+                let key = keys.remove(pos);
+                keys.insert(0, key);
+            }
+        }
+
+        let clients = keys
+            .into_iter()
+            .map(|key| Ok((key.clone(), Self::build_client(key)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        if clients.is_empty() {
+            return Err("No Admin API key configured".to_string());
+        }
+        Ok(Self { clients })
+    }
+
+    /// The client's keys, sorted; a different set of keys may be a different organization
+    fn sorted_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.clients.iter().map(|(key, _)| key.clone()).collect();
+        keys.sort();
+        keys
+    }
+
+    fn build_client(api_key: &str) -> Result<reqwest::Client, String> {
         let mut headers = HeaderMap::new();
         headers.insert(
             "x-api-key",
@@ -94,12 +299,37 @@ impl AdminApiClient {
             HeaderValue::from_static("2023-06-01"),
         );
 
-        let client = reqwest::Client::builder()
+        reqwest::Client::builder()
             .default_headers(headers)
             .build()
-            .map_err(|e| format!("Failed to build HTTP client: {e}"))?;
+            .map_err(|e| format!("Failed to build HTTP client: {e}"))
+    }
 
-        Ok(Self { client })
+    /// GET `url` with the first key that isn't rejected with 401
+    async fn get(&self, url: &str, what: &str) -> Result<reqwest::Response, String> {
+        let _perf = PerfSpan::start("api_request");
+        let last = self.clients.len() - 1;
+        for (i, (key, client)) in self.clients.iter().enumerate() {
+            let resp = client
+                .get(url)
+                .send()
+                .await
+                .map_err(|e| format!("{what} request failed: {e}"))?;
+            if resp.status() == StatusCode::UNAUTHORIZED && i < last {
+                log_line!(
+                    "Admin API key {} rejected (401), trying the next key",
+                    mask_api_key(key)
+                );
+                continue;
+            }
+            if resp.status().is_success() {
+                if let Ok(mut active) = ACTIVE_KEY.lock() {
+                    *active = Some(key.clone());
+                }
+            }
+            return Ok(resp);
+        }
+        Err(format!("{what} request failed: no Admin API key accepted"))
     }
 
     pub async fn fetch_usage_report(
@@ -108,6 +338,7 @@ impl AdminApiClient {
         ending_at: Option<&str>,
         bucket_width: &str,
         group_by: &[&str],
+        workspace_ids: &[String],
     ) -> Result<UsageReportResponse, String> {
         let mut url = format!(
             "{BASE_URL}/v1/organizations/usage_report/messages?starting_at={starting_at}&bucket_width={bucket_width}"
@@ -118,13 +349,11 @@ impl AdminApiClient {
         for g in group_by {
             url.push_str(&format!("&group_by[]={g}"));
         }
+        for id in workspace_ids {
+            url.push_str(&format!("&workspace_ids[]={id}"));
+        }
 
-        let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| format!("Usage report request failed: {e}"))?;
+        let resp = self.get(&url, "Usage report").await?;
 
         if !resp.status().is_success() {
             let status = resp.status();
@@ -137,10 +366,13 @@ impl AdminApiClient {
             .map_err(|e| format!("Failed to parse usage report: {e}"))
     }
 
+    /// Cost report grouped by description; with `workspace_ids`, only costs from those
+    /// workspaces are kept
     pub async fn fetch_cost_report(
         &self,
         starting_at: &str,
         ending_at: Option<&str>,
+        workspace_ids: &[String],
     ) -> Result<CostReportResponse, String> {
         let mut url = format!(
             "{BASE_URL}/v1/organizations/cost_report?starting_at={starting_at}&bucket_width=1d&group_by[]=description"
@@ -148,13 +380,12 @@ impl AdminApiClient {
         if let Some(end) = ending_at {
             url.push_str(&format!("&ending_at={end}"));
         }
+        // The cost report has no workspace filter, so group by workspace and filter here
+        if !workspace_ids.is_empty() {
+            url.push_str("&group_by[]=workspace_id");
+        }
 
-        let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| format!("Cost report request failed: {e}"))?;
+        let resp = self.get(&url, "Cost report").await?;
 
         if !resp.status().is_success() {
             let status = resp.status();
@@ -162,9 +393,123 @@ impl AdminApiClient {
             return Err(format!("Cost report API error {status}: {body}"));
         }
 
-        resp.json::<CostReportResponse>()
+        let mut report = resp
+            .json::<CostReportResponse>()
             .await
-            .map_err(|e| format!("Failed to parse cost report: {e}"))
+            .map_err(|e| format!("Failed to parse cost report: {e}"))?;
+        if !workspace_ids.is_empty() {
+            for bucket in &mut report.data {
+                bucket.results.retain(|r| {
+                    r.workspace_id
+                        .as_ref()
+                        .is_some_and(|id| workspace_ids.contains(id))
+                });
+            }
+        }
+        Ok(report)
+    }
+
+    /// Claude Code Analytics for one UTC day (`YYYY-MM-DD`): a record per user and API key
+    pub async fn fetch_code_analytics(
+        &self,
+        day: &str,
+    ) -> Result<Vec<CodeAnalyticsRecord>, String> {
+        let mut records = Vec::new();
+        let mut page: Option<String> = None;
+        loop {
+            let mut url = format!(
+                "{BASE_URL}/v1/organizations/usage_report/claude_code?starting_at={day}&limit=1000"
+            );
+            if let Some(ref cursor) = page {
+                url.push_str(&format!("&page={cursor}"));
+            }
+
+            let resp = self.get(&url, "Claude Code analytics").await?;
+
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("Claude Code analytics API error {status}: {body}"));
+            }
+
+            let response = resp
+                .json::<CodeAnalyticsResponse>()
+                .await
+                .map_err(|e| format!("Failed to parse Claude Code analytics: {e}"))?;
+            records.extend(response.data);
+            match response.next_page {
+                Some(next) if response.has_more => page = Some(next),
+                _ => break,
+            }
+        }
+        Ok(records)
+    }
+
+    /// Every item of an `after_id`-paginated organization list, e.g. `workspaces`
+    async fn list_all<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        what: &str,
+    ) -> Result<Vec<T>, String> {
+        let mut items = Vec::new();
+        let mut after_id: Option<String> = None;
+        loop {
+            let mut url = format!("{BASE_URL}/v1/organizations/{path}?limit=100");
+            if let Some(ref id) = after_id {
+                url.push_str(&format!("&after_id={id}"));
+            }
+
+            let resp = self.get(&url, what).await?;
+
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                return Err(format!("{what} API error {status}: {body}"));
+            }
+
+            let page = resp
+                .json::<ListResponse<T>>()
+                .await
+                .map_err(|e| format!("Failed to parse {}: {e}", what.to_lowercase()))?;
+            items.extend(page.data);
+            match page.last_id {
+                Some(last_id) if page.has_more => after_id = Some(last_id),
+                _ => break,
+            }
+        }
+        Ok(items)
+    }
+
+    /// All active workspaces in the organization
+    pub async fn list_workspaces(&self) -> Result<Vec<Workspace>, String> {
+        self.list_all("workspaces", "Workspace list").await
+    }
+
+    /// Members of the organization
+    pub async fn list_users(&self) -> Result<Vec<OrgUser>, String> {
+        self.list_all("users", "User list").await
+    }
+
+    /// Invites of the organization, in any status
+    pub async fn list_invites(&self) -> Result<Vec<Invite>, String> {
+        self.list_all("invites", "Invite list").await
+    }
+
+    /// The organization the key belongs to
+    pub async fn fetch_organization(&self) -> Result<Organization, String> {
+        let resp = self
+            .get(&format!("{BASE_URL}/v1/organizations/me"), "Organization")
+            .await?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!("Organization API error {status}: {body}"));
+        }
+
+        resp.json::<Organization>()
+            .await
+            .map_err(|e| format!("Failed to parse organization: {e}"))
     }
 
     /// Validate the API key by making a minimal usage report request
@@ -178,12 +523,7 @@ impl AdminApiClient {
             "{BASE_URL}/v1/organizations/usage_report/messages?starting_at={starting_at}&bucket_width=1h&limit=1"
         );
 
-        let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| format!("Validation request failed: {e}"))?;
+        let resp = self.get(&url, "Validation").await?;
 
         if resp.status().is_success() {
             Ok(())
@@ -202,18 +542,28 @@ struct LocalSupplementalData {
     daily_activity: Vec<DailyActivity>,
     weekly_usage: WeeklyUsage,
     last_updated: String,
+    peer_comparison: Option<PeerComparison>,
+    productivity: ProductivityStats,
+    /// Output tokens/sec per model over the last 24 hours, estimated from local journals
+    model_output_tps: HashMap<String, f64>,
+    /// Share of today's local tokens used outside working hours
+    off_hours_percent: f64,
+    /// Local data dirs and journals that couldn't be read
+    degraded: Vec<SourceIssue>,
 }
 
 /// Gather local JSONL data for sessions, quota calculations, and activity heatmap
 fn get_local_supplemental_data() -> LocalSupplementalData {
     let data_dirs = get_claude_data_dirs();
+    let mut degraded = check_data_dirs();
 
     // Sessions from last 25 hours of files
     let session_files = collect_jsonl_files(&data_dirs, Some(25));
     let mut session_entries = Vec::new();
     for file in &session_files {
-        if let Ok(entries) = parse_usage_from_file(file) {
-            session_entries.extend(entries);
+        match parse_usage_from_file(file) {
+            Ok(entries) => session_entries.extend(entries),
+            Err(e) => degraded.push(source_issue("unreadable_file", file, e)),
         }
     }
 
@@ -225,81 +575,312 @@ fn get_local_supplemental_data() -> LocalSupplementalData {
         .unwrap_or("")
         .to_string();
 
+    let day_ago = Utc::now() - chrono::Duration::hours(24);
+    let productivity = compute_productivity(&session_entries, Some(day_ago));
+    let model_output_tps = estimate_output_speeds(&session_entries, Some(day_ago)).by_model;
+    let today_start = Utc::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+    let off_hours_percent = compute_off_hours_percent(&session_entries, Some(today_start));
     let active_sessions = build_active_sessions(session_entries);
 
-    // Quota: 5hr rolling window
-    let five_hr_files = collect_jsonl_files(&data_dirs, Some(6));
-    let quota_window_prompts = count_user_prompts_in_window(&five_hr_files, 5);
-    let quota_window_weighted = count_weighted_usage_in_window(&five_hr_files, 5);
+    // Quota: current 5hr block reconstructed from prompt history. Only the running
+    // account's, since other accounts read with `all_users` have limits of their own.
+    let quota_dirs = own_data_dirs();
+    let quota_block = current_quota_block(&quota_block_files(&quota_dirs));
 
     // Weekly quota
-    let week_files = collect_jsonl_files(&data_dirs, Some(24 * 8));
-    let week_weighted = count_weighted_usage_in_window(&week_files, 24 * 7);
-
-    let estimated_limit: u32 = 500;
-    let usage_percent = (quota_window_weighted / estimated_limit as f64 * 100.0).min(100.0);
-    let week_estimated_prompts: u32 = 2590;
-    let week_usage_percent = (week_weighted / week_estimated_prompts as f64 * 100.0).min(100.0);
-
-    let quota = QuotaInfo {
-        messages_in_window: quota_window_prompts,
-        window_hours: 5,
-        estimated_limit,
-        usage_percent,
-        plan: "Max 5x".to_string(),
-        week_usage_percent,
-        week_limit_hours: 210,
-    };
+    let week_files = collect_jsonl_files(&quota_dirs, Some(24 * 8));
+    let (week_weighted, week_to_date_weighted) = count_week_weighted_usage(&week_files);
+
+    let custom_windows = custom_window_statuses(&quota_windows(), Utc::now());
+    let quota = build_quota_info(
+        quota_block.as_ref(),
+        week_weighted,
+        week_to_date_weighted,
+        custom_windows,
+    );
 
     // Daily activity heatmap
-    let activity_files = collect_jsonl_files(&data_dirs, Some(24 * 85));
+    let (activity_files, dir_issues) = collect_jsonl_files_checked(&data_dirs, Some(24 * 85));
+    degraded.extend(dir_issues);
     let daily_activity = collect_daily_activity(&activity_files);
     let weekly_usage = compute_weekly_usage(&daily_activity);
 
+    let peer_comparison = compute_peer_comparison(&crate::config::load_config().team_baseline);
+
     LocalSupplementalData {
         active_sessions,
         quota,
         daily_activity,
         weekly_usage,
         last_updated,
+        peer_comparison,
+        productivity,
+        model_output_tps,
+        off_hours_percent,
+        degraded,
+    }
+}
+
+/// Per-user totals for a day of Claude Code Analytics records
+fn summarize_code_analytics(day: &str, records: Vec<CodeAnalyticsRecord>) -> OrgAnalytics {
+    let mut users: HashMap<String, UserCodeMetrics> = HashMap::new();
+    for record in records {
+        let name = match record.actor.actor_type.as_str() {
+            "api_actor" => record.actor.api_key_name,
+            _ => record.actor.email_address,
+        }
+        .unwrap_or_else(|| "unknown".to_string());
+        let metrics = &record.core_metrics;
+        let user = users
+            .entry(name.clone())
+            .or_insert_with(|| UserCodeMetrics {
+                user: name,
+                ..Default::default()
+            });
+        user.sessions += metrics.num_sessions;
+        user.lines_added += metrics.lines_of_code.added;
+        user.lines_removed += metrics.lines_of_code.removed;
+        user.commits += metrics.commits_by_claude_code;
+        user.pull_requests += metrics.pull_requests_by_claude_code;
+        for actions in record.tool_actions.values() {
+            user.edits_accepted += actions.accepted;
+            user.edits_rejected += actions.rejected;
+        }
+        user.cost_usd += record
+            .model_breakdown
+            .iter()
+            .filter_map(|m| m.estimated_cost.as_ref())
+            .map(|cost| cost.amount / 100.0)
+            .sum::<f64>();
+    }
+
+    let mut users: Vec<UserCodeMetrics> = users.into_values().collect();
+    users.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+    OrgAnalytics {
+        date: day.to_string(),
+        sessions: users.iter().map(|u| u.sessions).sum(),
+        lines_added: users.iter().map(|u| u.lines_added).sum(),
+        lines_removed: users.iter().map(|u| u.lines_removed).sum(),
+        commits: users.iter().map(|u| u.commits).sum(),
+        pull_requests: users.iter().map(|u| u.pull_requests).sum(),
+        cost_usd: users.iter().map(|u| u.cost_usd).sum(),
+        users,
     }
 }
 
-/// Build UsageStats by combining API token/cost data with local session/quota data
-pub async fn build_usage_stats_from_api(client: &AdminApiClient) -> Result<UsageStats, String> {
+/// Organization tokens and cost over the last 7 days against the seats' combined allowance
+async fn fetch_org_utilization(
+    client: &AdminApiClient,
+    workspace_ids: &[String],
+    seats: &TeamSeats,
+    active_seats: Option<u32>,
+) -> Result<OrgUtilization, String> {
     let now = Utc::now();
-    let today_start = now
-        .date_naive()
-        .and_hms_opt(0, 0, 0)
-        .unwrap()
-        .and_utc()
+    let starting_at = (now - chrono::Duration::days(7))
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string();
+    let ending_at = now.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+    let usage_report = client
+        .fetch_usage_report(&starting_at, Some(&ending_at), "1d", &[], workspace_ids)
+        .await?;
+    let week_tokens: u64 = usage_report
+        .data
+        .iter()
+        .flat_map(|bucket| &bucket.results)
+        .fold(0u64, |sum, r| {
+            let cache_creation = r.cache_creation.as_ref().map_or(0, |c| {
+                c.ephemeral_5m_input_tokens
+                    .saturating_add(c.ephemeral_1h_input_tokens)
+            });
+            sum.saturating_add(r.uncached_input_tokens)
+                .saturating_add(r.output_tokens)
+                .saturating_add(r.cache_read_input_tokens)
+                .saturating_add(cache_creation)
+        });
+
+    let cost_report = client
+        .fetch_cost_report(&starting_at, Some(&ending_at), workspace_ids)
+        .await?;
+    // Amounts are in cents as decimal strings
+    let week_cost_usd: f64 = cost_report
+        .data
+        .iter()
+        .flat_map(|bucket| &bucket.results)
+        .filter_map(|r| r.amount.as_deref()?.parse::<f64>().ok())
+        .sum::<f64>()
+        / 100.0;
+
+    let seat_count = seats.seat_count.max(1);
+    let capacity_tokens = seats
+        .per_seat_weekly_tokens
+        .map(|tokens| tokens.saturating_mul(seat_count as u64));
+    let capacity_cost_usd = seats
+        .per_seat_weekly_cost_usd
+        .map(|cost| cost * seat_count as f64);
+    let utilization_percent = [
+        capacity_tokens.map(|cap| week_tokens as f64 / cap as f64),
+        capacity_cost_usd.map(|cap| week_cost_usd / cap),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|ratio| ratio.is_finite())
+    .fold(0.0, f64::max)
+        * 100.0;
+
+    Ok(OrgUtilization {
+        seat_count,
+        active_seats,
+        week_tokens,
+        week_cost_usd,
+        capacity_tokens,
+        capacity_cost_usd,
+        utilization_percent,
+        cost_per_seat_usd: week_cost_usd / seat_count as f64,
+    })
+}
+
+/// Organization name, member and invite counts and workspace count, from `ORG_DETAILS` when
+/// already fetched today; after a failure, the error is returned until the backoff passes.
+/// `today_cost_usd` is left at zero for the caller.
+async fn fetch_org_details(client: &AdminApiClient) -> Result<OrgSummary, String> {
+    let today = Utc::now().date_naive();
+    let keys = client.sorted_keys();
+    let cached = ORG_DETAILS
+        .lock()
+        .ok()
+        .and_then(|cached| cached.clone())
+        .filter(|(day, cached_keys, _)| *day == today && *cached_keys == keys);
+    if let Some((_, _, details)) = cached {
+        return Ok(details);
+    }
+    let failure = ORG_DETAILS_FAILURE
+        .lock()
+        .ok()
+        .and_then(|failure| failure.clone())
+        .filter(|(_, _, failed_keys, _)| *failed_keys == keys);
+    if let Some((at, failures, _, error)) = failure {
+        let retry = ORG_DETAILS_RETRY
+            .saturating_mul(1 << failures.saturating_sub(1).min(16))
+            .min(ORG_DETAILS_MAX_RETRY);
+        if at.elapsed() < retry {
+            return Err(error);
+        }
+    }
+
+    let result = fetch_org_details_uncached(client).await;
+    if let Ok(mut failure) = ORG_DETAILS_FAILURE.lock() {
+        *failure = match &result {
+            Ok(_) => None,
+            Err(e) => {
+                let failures = match failure.take() {
+                    Some((_, failures, failed_keys, _)) if failed_keys == keys => failures + 1,
+                    _ => 1,
+                };
+                Some((Instant::now(), failures, keys.clone(), e.clone()))
+            }
+        };
+    }
+    let details = result?;
+    if let Ok(mut cached) = ORG_DETAILS.lock() {
+        *cached = Some((today, keys, details.clone()));
+    }
+    Ok(details)
+}
+
+async fn fetch_org_details_uncached(client: &AdminApiClient) -> Result<OrgSummary, String> {
+    let organization = client.fetch_organization().await?;
+    let members = client.list_users().await?;
+    let invites = client.list_invites().await?;
+    let workspaces = client.list_workspaces().await?;
+    Ok(OrgSummary {
+        name: organization.name,
+        member_count: members.len() as u32,
+        pending_invites: invites
+            .iter()
+            .filter(|invite| invite.status.as_deref() == Some("pending"))
+            .count() as u32,
+        workspace_count: workspaces.len() as u32,
+        today_cost_usd: 0.0,
+        fetched_at: Utc::now().to_rfc3339(),
+    })
+}
+
+/// Build UsageStats by combining API token/cost data with local session/quota data.
+/// A non-empty `workspace_ids` limits the API data to those workspaces.
+pub async fn build_usage_stats_from_api(
+    client: &AdminApiClient,
+    workspace_ids: &[String],
+    team_seats: &TeamSeats,
+) -> Result<UsageStats, String> {
+    let now = Utc::now();
+    // "Today" is the user's local day, as for local stats
+    let today_start = local_day_start(now.with_timezone(&Local).date_naive())
         .format("%Y-%m-%dT%H:%M:%SZ")
         .to_string();
     let ending_at = now.format("%Y-%m-%dT%H:%M:%SZ").to_string();
 
     // Fetch usage grouped by model for today
     let usage_report = client
-        .fetch_usage_report(&today_start, Some(&ending_at), "1d", &["model"])
+        .fetch_usage_report(
+            &today_start,
+            Some(&ending_at),
+            "1d",
+            &["model"],
+            workspace_ids,
+        )
         .await?;
 
     // Fetch cost report for today
     let cost_report = client
-        .fetch_cost_report(&today_start, Some(&ending_at))
+        .fetch_cost_report(&today_start, Some(&ending_at), workspace_ids)
         .await?;
 
+    // Claude Code metrics per user; needs analytics access, so a failure only drops the section
+    let today = now.format("%Y-%m-%d").to_string();
+    let org_analytics = match client.fetch_code_analytics(&today).await {
+        Ok(records) => Some(summarize_code_analytics(&today, records)),
+        Err(e) => {
+            log_line!("Claude Code analytics unavailable: {e}");
+            None
+        }
+    };
+
+    let org_utilization = if team_seats.enabled && team_seats.seat_count > 0 {
+        let active_seats = org_analytics.as_ref().map(|a| a.users.len() as u32);
+        match fetch_org_utilization(client, workspace_ids, team_seats, active_seats).await {
+            Ok(utilization) => Some(utilization),
+            Err(e) => {
+                log_line!("Org utilization unavailable: {e}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
     // Aggregate usage by model from API data
     let mut model_tokens: HashMap<String, TokenUsage> = HashMap::new();
     for bucket in &usage_report.data {
         for result in &bucket.results {
             let model_name = result.model.as_deref().unwrap_or("unknown").to_string();
             let entry = model_tokens.entry(model_name).or_default();
-            entry.input_tokens += result.uncached_input_tokens;
-            entry.output_tokens += result.output_tokens;
-            entry.cache_read_input_tokens += result.cache_read_input_tokens;
-            if let Some(ref cache) = result.cache_creation {
-                entry.cache_creation_input_tokens +=
-                    cache.ephemeral_5m_input_tokens + cache.ephemeral_1h_input_tokens;
-            }
+            let cache = result.cache_creation.as_ref();
+            let cache_5m = cache.map_or(0, |c| c.ephemeral_5m_input_tokens);
+            let cache_1h = cache.map_or(0, |c| c.ephemeral_1h_input_tokens);
+            entry.add(&TokenUsage {
+                input_tokens: result.uncached_input_tokens,
+                output_tokens: result.output_tokens,
+                cache_creation_input_tokens: cache_5m.saturating_add(cache_1h),
+                cache_read_input_tokens: result.cache_read_input_tokens,
+                cache_creation_5m_input_tokens: cache_5m,
+                cache_creation_1h_input_tokens: cache_1h,
+                thinking_tokens: 0,
+            });
         }
     }
 
@@ -323,10 +904,7 @@ pub async fn build_usage_stats_from_api(client: &AdminApiClient) -> Result<Usage
     let mut by_model: Vec<ModelUsage> = model_tokens
         .into_iter()
         .map(|(model, tokens)| {
-            total.input_tokens += tokens.input_tokens;
-            total.output_tokens += tokens.output_tokens;
-            total.cache_creation_input_tokens += tokens.cache_creation_input_tokens;
-            total.cache_read_input_tokens += tokens.cache_read_input_tokens;
+            total.add(&tokens);
 
             let cost = model_costs.get(&model).copied().unwrap_or(0.0);
             total_cost += cost;
@@ -337,6 +915,11 @@ pub async fn build_usage_stats_from_api(client: &AdminApiClient) -> Result<Usage
                 display_name,
                 tokens,
                 cost_usd: cost,
+                provider: None,
+                avg_output_tps: None,
+                thinking_cost_usd: 0.0,
+                display_tokens: String::new(),
+                display_cost: String::new(),
             }
         })
         .collect();
@@ -347,16 +930,29 @@ pub async fn build_usage_stats_from_api(client: &AdminApiClient) -> Result<Usage
         b_total.cmp(&a_total)
     });
 
+    // Header line for admins; the organization details are cached for the day
+    let org_summary = match fetch_org_details(client).await {
+        Ok(details) => Some(OrgSummary {
+            today_cost_usd: total_cost,
+            ..details
+        }),
+        Err(e) => {
+            log_line!("Organization summary unavailable: {e}");
+            None
+        }
+    };
+
     // Get supplemental data from local JSONL (sessions, quota, activity)
     let local = tokio::task::spawn_blocking(get_local_supplemental_data)
         .await
         .map_err(|e| format!("Failed to get local data: {e}"))?;
+    for model in &mut by_model {
+        model.avg_output_tps = local.model_output_tps.get(&model.model).copied();
+    }
 
     let session_count = by_model
         .iter()
-        .map(|m| {
-            (m.tokens.input_tokens + m.tokens.output_tokens + m.tokens.cache_creation_input_tokens + m.tokens.cache_read_input_tokens) as u32
-        })
+        .map(|m| m.tokens.total() as u32)
         .sum::<u32>();
 
     Ok(UsageStats {
@@ -369,5 +965,21 @@ pub async fn build_usage_stats_from_api(client: &AdminApiClient) -> Result<Usage
         active_sessions: local.active_sessions,
         daily_activity: local.daily_activity,
         weekly_usage: local.weekly_usage,
+        peer_comparison: local.peer_comparison,
+        productivity: local.productivity,
+        off_hours_percent: local.off_hours_percent,
+        configured_model: get_configured_model(),
+        plan_hint: get_plan_hint(),
+        goals: Vec::new(),
+        billing_cycle: None,
+        org_analytics,
+        org_utilization,
+        org_summary,
+        truncated: false,
+        degraded: local.degraded,
+        by_user: Vec::new(),
+        display_last_updated: String::new(),
+        display_total_tokens: String::new(),
+        display_total_cost: String::new(),
     })
 }
@@ -0,0 +1,91 @@
+//! Version, build and environment details for bug reports, and for frontend behavior that
+//! depends on how the app was built or configured.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config;
+use crate::renderer;
+use crate::usage::UsageSource;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppInfo {
+    pub version: String,
+    /// Short commit hash of the build, "unknown" outside a git checkout
+    pub git_hash: String,
+    /// RFC 3339 (UTC); `SOURCE_DATE_EPOCH` when set at build time
+    pub build_date: Option<String>,
+    pub debug_build: bool,
+    pub tauri_version: String,
+    /// Cargo features compiled in
+    pub features: Vec<String>,
+    /// Optional services and modes turned on in config
+    pub services: Vec<String>,
+    pub os: String,
+    pub os_version: Option<String>,
+    pub arch: String,
+    /// WebKit workarounds applied at startup (Linux)
+    pub active_workarounds: Vec<String>,
+}
+
+/// Cargo features of this build
+fn enabled_features() -> Vec<String> {
+    let features = [
+        ("simd-json", cfg!(feature = "simd-json")),
+        ("simulate", cfg!(feature = "simulate")),
+    ];
+    features
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Optional services and modes enabled in config
+fn enabled_services(cfg: &config::AppConfig) -> Vec<String> {
+    let services = [
+        ("http_server", cfg.http_server.enabled),
+        ("otlp_receiver", cfg.usage_source == UsageSource::Otlp),
+        ("daily_digest", cfg.daily_digest.enabled),
+        ("weekly_report", cfg.weekly_report.enabled),
+        ("summary_file", cfg.summary_file.enabled),
+        ("alerts", cfg.alerts.iter().any(|rule| rule.enabled)),
+        ("privacy_mode", cfg.privacy_mode),
+        ("low_memory", cfg.low_memory),
+        ("entries_stream", cfg.entries_stream),
+        ("all_users", cfg.all_users),
+        ("update_check", cfg.update_check),
+    ];
+    services
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+pub fn get_app_info() -> AppInfo {
+    let build_date = env!("CC_USAGE_BUILD_TIMESTAMP")
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0))
+        .map(|date| date.to_rfc3339());
+
+    AppInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_hash: env!("CC_USAGE_GIT_HASH").to_string(),
+        build_date,
+        debug_build: cfg!(debug_assertions),
+        tauri_version: tauri::VERSION.to_string(),
+        features: enabled_features(),
+        services: enabled_services(&config::load_config()),
+        os: std::env::consts::OS.to_string(),
+        os_version: sysinfo::System::long_os_version(),
+        arch: std::env::consts::ARCH.to_string(),
+        active_workarounds: renderer::get_renderer_info()
+            .workarounds
+            .into_iter()
+            .filter(|w| w.applied)
+            .map(|w| w.name)
+            .collect(),
+    }
+}
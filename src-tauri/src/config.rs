@@ -1,21 +1,722 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+use crate::alerts::AlertRule;
+use crate::usage::{
+    ChangeDetection, PricingProfile, ProjectRule, QuotaWeights, QuotaWindowDef, UsageSource,
+    WorkingHours,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     #[serde(default)]
     pub admin_api_key: Option<String>,
+    /// Additional Admin API keys for rotation, tried in order after `admin_api_key`
+    #[serde(default)]
+    pub admin_api_keys: Vec<ApiKeyEntry>,
+    /// Workspaces included in API-mode stats; empty includes every workspace
+    #[serde(default)]
+    pub api_workspace_ids: Vec<String>,
+    #[serde(default)]
+    pub daily_digest: DailyDigestConfig,
+    /// Claude roots granted through the desktop portal when running sandboxed (Flatpak)
+    #[serde(default)]
+    pub granted_data_roots: Vec<String>,
+    /// Directories outside the home directory that `CLAUDE_CONFIG_DIR` may point into
+    #[serde(default)]
+    pub allowed_data_roots: Vec<String>,
+    #[serde(default)]
+    pub format: FormatPrefs,
+    #[serde(default)]
+    pub webkit: WebkitWorkarounds,
+    #[serde(default)]
+    pub team_baseline: TeamBaseline,
+    #[serde(default)]
+    pub team_seats: TeamSeats,
+    #[serde(default)]
+    pub http_server: HttpServerConfig,
+    /// Where token usage comes from: "journals" (default) or "otlp", Claude Code's
+    /// OpenTelemetry log export received by `otlp_receiver`. Changes take effect on the
+    /// next start.
+    #[serde(default)]
+    pub usage_source: UsageSource,
+    #[serde(default)]
+    pub otlp_receiver: OtlpReceiverConfig,
+    /// Time budget for computing local usage stats; unset uses the default, 0 disables it
+    #[serde(default)]
+    pub usage_timeout_secs: Option<u64>,
+    /// Custom per-model quota weights; unset uses the built-in calibrated table
+    #[serde(default)]
+    pub quota_weights: Option<QuotaWeights>,
+    /// Custom rolling quota windows (e.g. enterprise tokens per minute), reported after the
+    /// built-in 5hr block and week
+    #[serde(default)]
+    pub quota_windows: Vec<QuotaWindowDef>,
+    /// Pricing profiles by provider ("bedrock", "vertex") for usage billed through a cloud
+    /// marketplace; providers without one use Anthropic API prices
+    #[serde(default)]
+    pub provider_pricing: HashMap<String, PricingProfile>,
+    #[serde(default)]
+    pub summary_file: SummaryFileConfig,
+    #[serde(default)]
+    pub terminal: TerminalConfig,
+    #[serde(default)]
+    pub tray_icon: TrayIconConfig,
+    /// Replace project names and directories with stable pseudonyms in all outputs
+    #[serde(default)]
+    pub privacy_mode: bool,
+    /// Working schedule for `off_hours_percent`; unset uses weekdays 09:00-18:00
+    #[serde(default)]
+    pub working_hours: Option<WorkingHours>,
+    /// First day of the week for the weekly reset, charts, goals and week buckets,
+    /// 1 = Monday ... 7 = Sunday
+    #[serde(default = "default_week_starts_on")]
+    pub week_starts_on: u32,
+    /// Day of the month (1-31) the subscription renews on, for the "month" period, monthly
+    /// goals and the cycle forecast; unset keeps "month" at the last 30 days
+    #[serde(default)]
+    pub billing_cycle_anchor_day: Option<u32>,
+    #[serde(default)]
+    pub off_hours_alert: OffHoursAlertConfig,
+    /// User-defined alert rules evaluated by the scheduler, see `alerts`
+    #[serde(default)]
+    pub alerts: Vec<AlertRule>,
+    /// Alert when a live session's cost (last 24hr) exceeds this many USD; unset disables it
+    #[serde(default)]
+    pub session_cost_ceiling_usd: Option<f64>,
+    /// Allow `terminate_session_process` to stop running Claude Code processes
+    #[serde(default)]
+    pub allow_session_termination: bool,
+    /// "mtime" (default) or "content_hash" for journals on network filesystems
+    #[serde(default)]
+    pub change_detection: ChangeDetection,
+    /// How long journal changes are batched before the widget refreshes
+    #[serde(default)]
+    pub watcher_debounce: DebounceConfig,
+    /// Journal watcher backend, latency and depth, with per-platform overrides
+    #[serde(default)]
+    pub watcher: WatcherConfig,
+    /// Path mapping rules (regex -> project name) applied before the directory name
+    #[serde(default)]
+    pub project_rules: Vec<ProjectRule>,
+    /// Name git checkouts after their remote (`owner/repo`), merging clones of one repository
+    #[serde(default)]
+    pub group_projects_by_remote: bool,
+    /// Planned days off left out of the weekly pace and projection: "YYYY-MM-DD" or
+    /// "YYYY-MM-DD..YYYY-MM-DD" (inclusive)
+    #[serde(default)]
+    pub days_off: Vec<String>,
+    /// Sessions (full ids or prefixes of at least 8 characters) left out of totals, quota and
+    /// activity, e.g. demo runs; their journals stay untouched
+    #[serde(default)]
+    pub excluded_sessions: Vec<String>,
+    /// Keep only the quota gauge lean on constrained devices: 7 days of daily activity, no
+    /// session sparklines, no imported history, and "all" capped to the last 30 days
+    #[serde(default)]
+    pub low_memory: bool,
+    /// Emit newly parsed usage entries as `entries-appended` events, for custom frontends
+    #[serde(default)]
+    pub entries_stream: bool,
+    /// Read the Claude data of every account on the machine and report usage per OS user,
+    /// for daemon mode on a shared dev server
+    #[serde(default)]
+    pub all_users: bool,
+    /// Look for a newer release on GitHub at startup; off unless the user opts in
+    #[serde(default)]
+    pub update_check: bool,
+    /// Longest journal line parsed, in bytes (default 16 MiB); longer lines are skipped
+    #[serde(default)]
+    pub max_journal_line_bytes: Option<usize>,
+    #[serde(default)]
+    pub notifications: NotificationPrefs,
+    /// Period shown by the widget ("current-window", "today", "week", "month" or "all"); unset
+    /// is "today"
+    #[serde(default)]
+    pub display_period: Option<String>,
+    #[serde(default)]
+    pub weekly_report: WeeklyReportConfig,
+    /// Usage goals tracked in `UsageStats::goals` (see `goals`)
+    #[serde(default)]
+    pub goals: Vec<GoalConfig>,
+}
+
+// Not derived: a fresh install must get the same values as a config file without the fields
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            admin_api_key: None,
+            admin_api_keys: Vec::new(),
+            api_workspace_ids: Vec::new(),
+            daily_digest: DailyDigestConfig::default(),
+            granted_data_roots: Vec::new(),
+            allowed_data_roots: Vec::new(),
+            format: FormatPrefs::default(),
+            webkit: WebkitWorkarounds::default(),
+            team_baseline: TeamBaseline::default(),
+            team_seats: TeamSeats::default(),
+            http_server: HttpServerConfig::default(),
+            usage_source: UsageSource::default(),
+            otlp_receiver: OtlpReceiverConfig::default(),
+            usage_timeout_secs: None,
+            quota_weights: None,
+            quota_windows: Vec::new(),
+            provider_pricing: HashMap::new(),
+            summary_file: SummaryFileConfig::default(),
+            terminal: TerminalConfig::default(),
+            tray_icon: TrayIconConfig::default(),
+            privacy_mode: false,
+            working_hours: None,
+            week_starts_on: default_week_starts_on(),
+            billing_cycle_anchor_day: None,
+            off_hours_alert: OffHoursAlertConfig::default(),
+            alerts: Vec::new(),
+            session_cost_ceiling_usd: None,
+            allow_session_termination: false,
+            change_detection: ChangeDetection::default(),
+            watcher_debounce: DebounceConfig::default(),
+            watcher: WatcherConfig::default(),
+            project_rules: Vec::new(),
+            group_projects_by_remote: false,
+            days_off: Vec::new(),
+            excluded_sessions: Vec::new(),
+            low_memory: false,
+            entries_stream: false,
+            all_users: false,
+            update_check: false,
+            max_journal_line_bytes: None,
+            notifications: NotificationPrefs::default(),
+            display_period: None,
+            weekly_report: WeeklyReportConfig::default(),
+            goals: Vec::new(),
+        }
+    }
+}
+
+/// An Admin API key with an optional validity window ("YYYY-MM-DD" or RFC 3339, UTC)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyEntry {
+    pub key: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub valid_from: Option<String>,
+    #[serde(default)]
+    pub valid_until: Option<String>,
+}
+
+impl ApiKeyEntry {
+    pub fn valid_from_at(&self) -> Option<DateTime<Utc>> {
+        self.valid_from.as_deref().and_then(parse_validity_date)
+    }
+
+    pub fn valid_until_at(&self) -> Option<DateTime<Utc>> {
+        self.valid_until.as_deref().and_then(parse_validity_date)
+    }
+
+    pub fn is_valid_at(&self, now: DateTime<Utc>) -> bool {
+        !self.key.is_empty()
+            && self.valid_from_at().is_none_or(|from| from <= now)
+            && self.valid_until_at().is_none_or(|until| now < until)
+    }
+}
+
+fn parse_validity_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|dt| dt.and_utc())
+        })
+}
+
+/// Admin API keys to try, in order: the primary key, then rotation keys valid right now
+pub fn usable_api_keys(config: &AppConfig) -> Vec<String> {
+    let now = Utc::now();
+    let mut keys: Vec<String> = config
+        .admin_api_key
+        .iter()
+        .filter(|key| !key.is_empty())
+        .cloned()
+        .collect();
+    for entry in &config.admin_api_keys {
+        if entry.is_valid_at(now) && !keys.contains(&entry.key) {
+            keys.push(entry.key.clone());
+        }
+    }
+    keys
+}
+
+/// Small JSON summary rewritten on every refresh for shell prompts, conky and scripts
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SummaryFileConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Defaults to `summary.json` in the config dir
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// Terminal opened by `open_session_in_terminal`, as program and arguments; "{dir}" and
+/// "{session_id}" are substituted in each argument
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TerminalConfig {
+    /// e.g. `["wezterm", "start", "--cwd", "{dir}"]`; empty tries the platform's usual terminals
+    #[serde(default)]
+    pub command: Vec<String>,
+    /// Appended to run the session in the new terminal, e.g.
+    /// `["--", "claude", "--resume", "{session_id}"]`; empty only suggests the command
+    #[serde(default)]
+    pub resume_args: Vec<String>,
+}
+
+/// Panel the tray icon is drawn for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PanelTheme {
+    /// Follow the desktop's color scheme
+    #[default]
+    Auto,
+    Dark,
+    Light,
+}
+
+/// Tray icon drawn from the current quota usage (see `icon`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrayIconConfig {
+    #[serde(default)]
+    pub theme: PanelTheme,
+    /// Rings for the 5hr block and weekly usage; false shows the static app icon
+    #[serde(default = "default_show_usage")]
+    pub show_usage: bool,
+}
+
+impl Default for TrayIconConfig {
+    fn default() -> Self {
+        Self {
+            theme: PanelTheme::Auto,
+            show_usage: default_show_usage(),
+        }
+    }
+}
+
+fn default_show_usage() -> bool {
+    true
+}
+
+/// Read-only HTTP dashboard. Changes take effect on the next start.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpServerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// "127.0.0.1" keeps it local; "0.0.0.0" exposes it on the LAN
+    #[serde(default = "default_http_bind_address")]
+    pub bind_address: String,
+    #[serde(default = "default_http_port")]
+    pub port: u16,
+    /// When set, requests must carry `Authorization: Bearer <token>` or `?token=<token>`
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Host names the dashboard may be reached by besides localhost and the bind address,
+    /// e.g. the machine's LAN name; requests addressed to any other name are refused
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+}
+
+impl Default for HttpServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: default_http_bind_address(),
+            port: default_http_port(),
+            token: None,
+            allowed_hosts: Vec::new(),
+        }
+    }
+}
+
+fn default_week_starts_on() -> u32 {
+    7
+}
+
+/// Address of the OTLP/HTTP receiver used with `usage_source: "otlp"`. Changes take effect
+/// on the next start.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtlpReceiverConfig {
+    #[serde(default = "default_http_bind_address")]
+    pub bind_address: String,
+    /// 4318 is the standard OTLP/HTTP port
+    #[serde(default = "default_otlp_port")]
+    pub port: u16,
+}
+
+impl Default for OtlpReceiverConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: default_http_bind_address(),
+            port: default_otlp_port(),
+        }
+    }
+}
+
+fn default_otlp_port() -> u16 {
+    4318
+}
+
+fn default_http_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_http_port() -> u16 {
+    8765
+}
+
+/// Opt-in, user-provided team medians to compare personal weekly usage against.
+/// Nothing is sent anywhere; unset medians are skipped.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TeamBaseline {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub weekly_prompts_median: Option<f64>,
+    #[serde(default)]
+    pub weekly_tokens_median: Option<f64>,
+    #[serde(default)]
+    pub weekly_cost_median: Option<f64>,
+}
+
+/// Team/Enterprise seats sharing the organization's usage; in API mode the widget compares
+/// org usage over the last 7 days against `seat_count` times the per-seat allowances
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TeamSeats {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub seat_count: u32,
+    #[serde(default)]
+    pub per_seat_weekly_tokens: Option<u64>,
+    #[serde(default)]
+    pub per_seat_weekly_cost_usd: Option<f64>,
+}
+
+/// Linux WebKitGTK workarounds. `None` = auto (applied only when the environment needs it).
+/// Changes take effect on the next start.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WebkitWorkarounds {
+    #[serde(default)]
+    pub disable_compositing: Option<bool>,
+    #[serde(default)]
+    pub disable_dmabuf: Option<bool>,
+    #[serde(default)]
+    pub disable_sandbox: Option<bool>,
+    #[serde(default)]
+    pub single_web_process: Option<bool>,
+    #[serde(default)]
+    pub disable_gpu: Option<bool>,
+}
+
+/// How token counts are abbreviated in backend-produced display strings
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenUnit {
+    /// K/M chosen by magnitude (e.g. "950", "12.3K", "4.1M")
+    #[default]
+    Auto,
+    /// Full number with thousands separators
+    Raw,
+    K,
+    M,
+}
+
+/// Display formatting preferences applied to strings the backend produces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatPrefs {
+    #[serde(default)]
+    pub token_unit: TokenUnit,
+    #[serde(default = "default_cost_decimals")]
+    pub cost_decimals: u8,
+    #[serde(default = "default_clock_24h")]
+    pub clock_24h: bool,
+}
+
+impl Default for FormatPrefs {
+    fn default() -> Self {
+        Self {
+            token_unit: TokenUnit::Auto,
+            cost_decimals: default_cost_decimals(),
+            clock_24h: default_clock_24h(),
+        }
+    }
+}
+
+fn default_cost_decimals() -> u8 {
+    2
+}
+
+fn default_clock_24h() -> bool {
+    true
+}
+
+/// Opt-in daily summary notification, sent once per day at `time` (local, "HH:MM")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyDigestConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_digest_time")]
+    pub time: String,
+}
+
+impl Default for DailyDigestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            time: default_digest_time(),
+        }
+    }
+}
+
+fn default_digest_time() -> String {
+    "09:00".to_string()
+}
+
+/// A usage goal. Kinds: "max_daily_cost", "max_weekly_cost" and "max_monthly_cost" (USD,
+/// months follow the billing cycle), and "min_cache_hit_rate" (percent of context tokens
+/// read from the prompt cache, per week).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalConfig {
+    /// Stable identifier; streak history is kept per id
+    pub id: String,
+    pub kind: String,
+    pub target: f64,
+}
+
+/// Opt-in weekly usage report emailed over SMTP. The SMTP password is kept in the OS
+/// keyring (see `email_report`), never in this file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeeklyReportConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub smtp_host: String,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    /// TLS from the first byte (usually port 465) instead of STARTTLS (usually 587)
+    #[serde(default)]
+    pub implicit_tls: bool,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub from: String,
+    #[serde(default)]
+    pub to: Vec<String>,
+    /// Day to send on, 1 = Monday ... 7 = Sunday
+    #[serde(default = "default_report_weekday")]
+    pub weekday: u32,
+    /// Local time to send at, "HH:MM"
+    #[serde(default = "default_digest_time")]
+    pub time: String,
+}
+
+impl Default for WeeklyReportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            smtp_host: String::new(),
+            smtp_port: default_smtp_port(),
+            implicit_tls: false,
+            username: String::new(),
+            from: String::new(),
+            to: Vec::new(),
+            weekday: default_report_weekday(),
+            time: default_digest_time(),
+        }
+    }
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_report_weekday() -> u32 {
+    1
+}
+
+/// Notification sounds, do-not-disturb hours and a global mute
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationPrefs {
+    #[serde(default)]
+    pub muted: bool,
+    /// Sound per alert kind (see `notifications::KINDS`): a sound theme name on Linux
+    /// (e.g. "message-new-instant"), a system sound on macOS. Missing means silent.
+    #[serde(default)]
+    pub sounds: HashMap<String, String>,
+    /// Notifications are dropped during these hours
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+}
+
+/// Local do-not-disturb window; `end` before `start` spans midnight
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: String, // "HH:MM"
+    pub end: String,   // "HH:MM"
+}
+
+impl QuietHours {
+    /// Whether `time` (local) is inside the window; false if either bound is unparsable
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        let parse = |value: &str| NaiveTime::parse_from_str(value, "%H:%M").ok();
+        match (parse(&self.start), parse(&self.end)) {
+            (Some(start), Some(end)) if start <= end => start <= time && time < end,
+            (Some(start), Some(end)) => time >= start || time < end,
+            _ => false,
+        }
+    }
+}
+
+/// Adaptive debounce of journal change events (see `debounce`). Changes saved with
+/// `set_watcher_debounce` apply on the watcher's next wakeup (`debounce::request_reload`);
+/// edits to `config.json` take effect on the next start.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebounceConfig {
+    /// Quiet time before refreshing while writes are sparse
+    #[serde(default = "default_debounce_min_ms")]
+    pub min_ms: u64,
+    /// Quiet time before refreshing during a write storm
+    #[serde(default = "default_debounce_max_ms")]
+    pub max_ms: u64,
+    /// Longest a change waits for a refresh while writes keep coming
+    #[serde(default = "default_debounce_max_wait_ms")]
+    pub max_wait_ms: u64,
+}
+
+impl Default for DebounceConfig {
+    fn default() -> Self {
+        Self {
+            min_ms: default_debounce_min_ms(),
+            max_ms: default_debounce_max_ms(),
+            max_wait_ms: default_debounce_max_wait_ms(),
+        }
+    }
+}
+
+fn default_debounce_min_ms() -> u64 {
+    250
+}
+
+fn default_debounce_max_ms() -> u64 {
+    2000
+}
+
+fn default_debounce_max_wait_ms() -> u64 {
+    5000
+}
+
+/// How journals are watched: "native" events (inotify, FSEvents, ReadDirectoryChangesW) or
+/// "poll"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatcherBackend {
+    Native,
+    Poll,
+}
+
+/// Journal watcher settings; unset fields fall back to the platform default (see `watcher`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatcherSettings {
+    #[serde(default)]
+    pub backend: Option<WatcherBackend>,
+    /// Interval of the poll backend
+    #[serde(default)]
+    pub poll_interval_ms: Option<u64>,
+    /// Quiet time before refreshing while writes are sparse; overrides
+    /// `watcher_debounce.min_ms`
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+    /// Directory levels watched below each `projects` dir (1 = project dirs); unset watches
+    /// everything
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+}
+
+/// Watcher settings for every platform, and overrides for one; changes take effect on the
+/// next start
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatcherConfig {
+    #[serde(flatten)]
+    pub all: WatcherSettings,
+    #[serde(default)]
+    pub linux: Option<WatcherSettings>,
+    #[serde(default)]
+    pub macos: Option<WatcherSettings>,
+    #[serde(default)]
+    pub windows: Option<WatcherSettings>,
+}
+
+/// Opt-in notification when heavy usage starts outside working hours (e.g. a forgotten agent)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OffHoursAlertConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Tokens within `window_minutes` that count as heavy usage
+    #[serde(default = "default_off_hours_tokens")]
+    pub tokens_threshold: u64,
+    #[serde(default = "default_off_hours_window")]
+    pub window_minutes: u32,
+}
+
+impl Default for OffHoursAlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tokens_threshold: default_off_hours_tokens(),
+            window_minutes: default_off_hours_window(),
+        }
+    }
+}
+
+fn default_off_hours_tokens() -> u64 {
+    500_000
+}
+
+fn default_off_hours_window() -> u32 {
+    30
+}
+
+pub fn config_dir() -> PathBuf {
+    // dirs::config_dir() honors XDG_CONFIG_HOME; without a home dir fall back to the temp dir
+    dirs::config_dir()
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("cc-usage-widget")
 }
 
 pub fn config_path() -> PathBuf {
-    let config_dir = dirs::config_dir()
-        .unwrap_or_else(|| dirs::home_dir().unwrap().join(".config"))
-        .join("cc-usage-widget");
-    config_dir.join("config.json")
+    config_dir().join("config.json")
 }
 
+/// The current config, kept in memory by the app state
 pub fn load_config() -> AppConfig {
+    crate::state::get().config()
+}
+
+/// Save `config` as the current config. Prefer `update_config` when modifying the loaded
+/// config, so changes made by others meanwhile aren't lost.
+pub fn save_config(config: &AppConfig) -> Result<(), String> {
+    crate::state::get().save_config(config)
+}
+
+/// Load, modify and save the config in one step
+pub fn update_config<T>(change: impl FnOnce(&mut AppConfig) -> T) -> Result<T, String> {
+    crate::state::get().update_config(change)
+}
+
+pub(crate) fn read_config_file() -> AppConfig {
     let path = config_path();
     if !path.exists() {
         return AppConfig::default();
@@ -26,8 +727,9 @@ pub fn load_config() -> AppConfig {
     }
 }
 
-pub fn save_config(config: &AppConfig) -> Result<(), String> {
+pub(crate) fn write_config_file(config: &AppConfig) -> Result<(), String> {
     let path = config_path();
+    crate::usage::ensure_outside_data_dirs(&path)?;
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(|e| format!("Failed to create config dir: {e}"))?;
     }
@@ -45,3 +747,18 @@ pub fn mask_api_key(key: &str) -> String {
     let suffix = &key[key.len().saturating_sub(4)..];
     format!("{prefix}...{suffix}")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matches_an_empty_config_file() {
+        let parsed: AppConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(AppConfig::default().week_starts_on, 7);
+        assert_eq!(
+            serde_json::to_value(AppConfig::default()).unwrap(),
+            serde_json::to_value(parsed).unwrap()
+        );
+    }
+}
@@ -0,0 +1,55 @@
+//! Per-session cost ceiling: flags live sessions whose 24hr cost crosses
+//! `session_cost_ceiling_usd`, catching runaway agent sessions.
+//!
+//! Each session is reported once per app run.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use crate::usage::{
+    build_active_sessions, collect_jsonl_files, get_claude_data_dirs, parse_usage_from_file,
+};
+
+/// Sessions whose journal changed within this many hours count as live
+const LIVE_SESSION_HOURS: i64 = 1;
+
+/// Sessions already reported, by short session id
+static REPORTED: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionCostExceeded {
+    pub session_id: String,
+    pub project: String,
+    pub directory: String,
+    pub cost_usd: f64,
+    pub ceiling_usd: f64,
+}
+
+/// Live sessions over `ceiling_usd` that have not been reported yet
+pub fn new_sessions_over_ceiling(ceiling_usd: f64) -> Vec<SessionCostExceeded> {
+    let mut entries = Vec::new();
+    for file in collect_jsonl_files(&get_claude_data_dirs(), Some(LIVE_SESSION_HOURS)) {
+        if let Ok(parsed) = parse_usage_from_file(&file) {
+            entries.extend(parsed);
+        }
+    }
+
+    let mut reported = match REPORTED.lock() {
+        Ok(reported) => reported,
+        Err(_) => return Vec::new(),
+    };
+    let reported = reported.get_or_insert_with(HashSet::new);
+
+    build_active_sessions(entries)
+        .into_iter()
+        .filter(|s| s.cost_usd > ceiling_usd && reported.insert(s.session_id.clone()))
+        .map(|s| SessionCostExceeded {
+            session_id: s.session_id,
+            project: s.project,
+            directory: s.directory,
+            cost_usd: s.cost_usd,
+            ceiling_usd,
+        })
+        .collect()
+}
@@ -0,0 +1,117 @@
+//! Panic reporting: a panic hook writes a crash report (message, backtrace, recent log
+//! lines, app version) to the config dir so it can be surfaced on the next start.
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::config;
+
+/// Number of log lines kept for crash reports
+const RECENT_LOG_CAPACITY: usize = 200;
+
+static RECENT_LOG: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// `eprintln!` that also keeps the line in the in-memory log attached to crash reports
+macro_rules! log_line {
+    ($($arg:tt)*) => {{
+        let line = format!($($arg)*);
+        eprintln!("{line}");
+        $crate::crash::record_log_line(line);
+    }};
+}
+pub(crate) use log_line;
+
+pub fn record_log_line(line: String) {
+    if let Ok(mut log) = RECENT_LOG.lock() {
+        if log.len() >= RECENT_LOG_CAPACITY {
+            log.pop_front();
+        }
+        log.push_back(format!("{} {}", Local::now().format("%H:%M:%S"), line));
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub timestamp: String,
+    pub app_version: String,
+    pub os: String,
+    pub thread: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub backtrace: String,
+    pub recent_log: Vec<String>,
+}
+
+fn last_crash_path() -> PathBuf {
+    config::config_dir().join("last_crash.json")
+}
+
+/// Install a panic hook that writes a crash report before running the default hook
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = if let Some(s) = info.payload().downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = info.payload().downcast_ref::<String>() {
+            s.clone()
+        } else {
+            "unknown panic payload".to_string()
+        };
+
+        // try_lock: the panicking thread may already hold the log lock
+        let recent_log = RECENT_LOG
+            .try_lock()
+            .map(|log| log.iter().cloned().collect())
+            .unwrap_or_default();
+
+        let report = CrashReport {
+            timestamp: Local::now().to_rfc3339(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            thread: std::thread::current()
+                .name()
+                .unwrap_or("<unnamed>")
+                .to_string(),
+            message,
+            location: info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            recent_log,
+        };
+
+        if let Err(e) = write_crash_report(&report) {
+            eprintln!("Failed to write crash report: {e}");
+        }
+
+        default_hook(info);
+    }));
+}
+
+fn write_crash_report(report: &CrashReport) -> Result<(), String> {
+    let path = last_crash_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| e.to_string())
+}
+
+/// The crash report left by the previous run, if any
+pub fn load_last_crash() -> Option<CrashReport> {
+    let contents = fs::read_to_string(last_crash_path()).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Remove the stored crash report once the user has seen it
+pub fn clear_last_crash() -> Result<(), String> {
+    let path = last_crash_path();
+    if !path.exists() {
+        return Ok(());
+    }
+    fs::remove_file(&path).map_err(|e| format!("Failed to remove crash report: {e}"))
+}
@@ -0,0 +1,164 @@
+//! `--daemon`: headless mode for boxes without a display (e.g. a Raspberry Pi where Claude
+//! Code runs over SSH). Runs the backend subsystems only, with no Tauri app or webview:
+//! the journal watcher (or the OTLP receiver), limit history, the cost ceiling check, the summary file, D-Bus (or
+//! the named pipe / local socket service), the HTTP dashboard and the scheduler. Desktop
+//! notifications (daily digest, off-hours usage, alerts) need the app and are logged instead.
+
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::config;
+use crate::cost_guard;
+use crate::crash::log_line;
+use crate::http_server;
+use crate::limits;
+use crate::notifications::Notifier;
+use crate::otlp_receiver;
+use crate::privacy;
+use crate::scheduler;
+use crate::usage::{self, ParsedEntry};
+
+#[cfg(target_os = "linux")]
+use crate::dbus_service::{self, DbusServiceHandle};
+#[cfg(not(target_os = "linux"))]
+use crate::ipc_service;
+
+/// Whether the process was started with `--daemon`
+pub fn requested() -> bool {
+    std::env::args().skip(1).any(|arg| arg == "--daemon")
+}
+
+/// Run the headless backend until the process is killed
+pub fn run() {
+    log_line!("Starting in daemon mode (no window)");
+    let cfg = config::load_config();
+
+    #[cfg(target_os = "linux")]
+    let dbus_handle = dbus_service::spawn_dbus_service(None);
+    #[cfg(not(target_os = "linux"))]
+    let ipc_handle = ipc_service::spawn_ipc_service(None);
+
+    let provider: http_server::StatsProvider = Arc::new(|period: &str| {
+        tauri::async_runtime::block_on(crate::load_usage(crate::state::get(), period.to_string()))
+    });
+    http_server::spawn_http_server(cfg.http_server, provider);
+
+    // Daily digest, off-hours alert, alert rules and the weekly email report
+    scheduler::spawn_scheduler(Notifier::Log);
+
+    // OTLP events are pushed to the receiver; there are no journals to watch
+    if usage::usage_source() == usage::UsageSource::Otlp {
+        otlp_receiver::restore_saved_events();
+        #[cfg(target_os = "linux")]
+        let service = dbus_handle;
+        #[cfg(not(target_os = "linux"))]
+        let service = ipc_handle;
+        let on_ingest: otlp_receiver::IngestHook = Arc::new(move |_: &[ParsedEntry]| {
+            refresh(1);
+            #[cfg(target_os = "linux")]
+            if let Some(ref handle) = service {
+                tauri::async_runtime::block_on(handle.notify_usage_changed());
+            }
+            #[cfg(not(target_os = "linux"))]
+            if let Some(ref handle) = service {
+                handle.notify_usage_changed();
+            }
+        });
+        otlp_receiver::spawn_otlp_receiver(cfg.otlp_receiver, on_ingest);
+        refresh(24);
+        loop {
+            std::thread::park();
+        }
+    }
+
+    let (tx, rx) = channel();
+    let mut watcher = match crate::watcher::create_watcher(tx) {
+        Ok(w) => w,
+        Err(e) => {
+            log_line!("Failed to create watcher: {:?}", e);
+            return;
+        }
+    };
+    let mut watched_dirs = Vec::new();
+    crate::watcher::watch_new_data_dirs(&mut watcher, &mut watched_dirs);
+    let mut last_rescan = Instant::now();
+
+    // Catch limit hits from while the daemon wasn't running
+    refresh(24);
+    #[cfg(target_os = "linux")]
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .ok();
+
+    // Debounce: only refresh after no events for the latency (500ms by default)
+    let latency = crate::watcher::daemon_latency(&config::load_config());
+    let mut last_event = Instant::now();
+    loop {
+        match rx.recv_timeout(latency) {
+            Ok(_) => {
+                last_event = Instant::now();
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                if last_rescan.elapsed() >= crate::watcher::DATA_DIR_RESCAN_INTERVAL {
+                    crate::watcher::watch_new_data_dirs(&mut watcher, &mut watched_dirs);
+                    last_rescan = Instant::now();
+                }
+
+                if last_event.elapsed() >= latency && last_event.elapsed() < latency * 2 {
+                    refresh(1);
+                    #[cfg(target_os = "linux")]
+                    notify_dbus(&rt, &dbus_handle);
+                    #[cfg(not(target_os = "linux"))]
+                    if let Some(ref handle) = ipc_handle {
+                        handle.notify_usage_changed();
+                    }
+                }
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+/// Update everything derived from the journals after they changed
+fn refresh(limit_lookback_hours: i64) {
+    crate::state::get().invalidate();
+    for event in limits::record_new_limit_events(limit_lookback_hours) {
+        log_line!("Limit reached: {} ({})", event.message, event.timestamp);
+    }
+
+    let cfg = config::load_config();
+    if let Some(ceiling) = cfg.session_cost_ceiling_usd.filter(|c| *c > 0.0) {
+        let mut alerts = cost_guard::new_sessions_over_ceiling(ceiling);
+        if cfg.privacy_mode {
+            privacy::apply_to_cost_alerts(&mut alerts);
+        }
+        for alert in alerts {
+            log_line!(
+                "Session {} ({}) cost ${:.2}, over the ${:.2} ceiling",
+                alert.session_id,
+                alert.project,
+                alert.cost_usd,
+                alert.ceiling_usd
+            );
+        }
+    }
+
+    // Loading usage rewrites the summary file when it is enabled
+    if cfg.summary_file.enabled {
+        if let Err(e) = tauri::async_runtime::block_on(crate::load_usage(
+            crate::state::get(),
+            "today".to_string(),
+        )) {
+            log_line!("Failed to refresh usage: {e}");
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn notify_dbus(rt: &Option<tokio::runtime::Runtime>, dbus_handle: &Option<DbusServiceHandle>) {
+    if let (Some(rt), Some(handle)) = (rt, dbus_handle) {
+        rt.block_on(handle.notify_usage_changed());
+    }
+}
@@ -1,56 +1,54 @@
 //! D-Bus service for exposing CC Usage Widget data to external consumers like GNOME extensions.
 //!
 //! Exposes the `com.shane.CCUsageWidget1` interface at `/com/shane/CCUsageWidget`.
+//! The well-known bus name doubles as the single-instance lock (see
+//! `forward_to_running_instance`).
 
-use crate::usage::get_current_usage;
-use chrono::{Datelike, Utc};
-use std::sync::Arc;
+use crate::crash::log_line;
+use crate::usage::{
+    get_hourly_series, should_throttle, week_usage_summary, ThrottleAction, MAX_SERIES_HOURS,
+};
+use std::sync::{Arc, OnceLock};
+use tauri::AppHandle;
 use tokio::sync::Mutex;
-use zbus::{interface, Connection, Result};
+use zbus::object_server::SignalEmitter;
+use zbus::{fdo, interface, Connection, Result};
+
+const BUS_NAME: &str = "com.shane.CCUsageWidget";
+const OBJECT_PATH: &str = "/com/shane/CCUsageWidget";
+const INTERFACE_NAME: &str = "com.shane.CCUsageWidget1";
+
+/// Connection of the running service, for signals sent from other threads
+static CONNECTION: OnceLock<Connection> = OnceLock::new();
 
 /// D-Bus service providing usage summary data.
 pub struct UsageService {
     /// Cached usage data to avoid recomputing on every D-Bus call
     cache: Arc<Mutex<Option<(f64, u32)>>>,
+    /// Used by `Refresh` and `ShowWindow` to reach the widget window; None in daemon mode
+    app: Option<AppHandle>,
 }
 
 impl UsageService {
-    pub fn new() -> Self {
+    pub fn new(app: Option<AppHandle>) -> Self {
         Self {
             cache: Arc::new(Mutex::new(None)),
+            app,
         }
     }
 
     /// Update the cached usage data (called when file watcher detects changes)
     pub async fn update_cache(&self) {
-        let data = Self::compute_usage_summary();
+        let data = Self::compute_usage_summary().await;
         let mut cache = self.cache.lock().await;
         *cache = Some(data);
     }
 
-    /// Compute usage summary from current data
-    fn compute_usage_summary() -> (f64, u32) {
-        match get_current_usage("week") {
-            Ok(stats) => {
-                let week_usage_percent = stats.quota.week_usage_percent;
-                let days_left = Self::compute_days_until_reset();
-                (week_usage_percent, days_left)
-            }
-            Err(_) => (0.0, Self::compute_days_until_reset()),
-        }
-    }
-
-    /// Compute days until the weekly reset (Sunday at midnight)
-    fn compute_days_until_reset() -> u32 {
-        let today = Utc::now().date_naive();
-        let days_since_sunday = today.weekday().num_days_from_sunday();
-        // Days until next Sunday (if today is Sunday, returns 7)
-        let days_left = if days_since_sunday == 0 {
-            7
-        } else {
-            7 - days_since_sunday
-        };
-        days_left
+    /// Compute usage summary from current data, off the D-Bus runtime
+    async fn compute_usage_summary() -> (f64, u32) {
+        tauri::async_runtime::spawn_blocking(week_usage_summary)
+            .await
+            .unwrap_or((0.0, 0))
     }
 }
 
@@ -66,7 +64,96 @@ impl UsageService {
         drop(cache);
 
         // Cache miss - compute fresh data
-        Self::compute_usage_summary()
+        Self::compute_usage_summary().await
+    }
+
+    /// Tokens and cost per hour for the last `hours` hours, oldest first, as
+    /// (hour start in RFC 3339, tokens, cost_usd) for panel graphs
+    async fn get_hourly_series(&self, hours: u32) -> fdo::Result<Vec<(String, u64, f64)>> {
+        let hours = hours.clamp(1, MAX_SERIES_HOURS);
+        let series = tauri::async_runtime::spawn_blocking(move || get_hourly_series(hours))
+            .await
+            .map_err(|e| fdo::Error::Failed(format!("Task join error: {e}")))?;
+        Ok(series
+            .into_iter()
+            .map(|bucket| (bucket.timestamp, bucket.tokens, bucket.cost_usd))
+            .collect())
+    }
+
+    /// The full `UsageStats` for `period` ("current-window", "today", "week", "month" or
+    /// "all") as JSON, the same data the widget and the HTTP dashboard show
+    async fn get_stats_json(&self, period: &str) -> fdo::Result<String> {
+        crate::load_usage_json(period.to_string())
+            .await
+            .map_err(fdo::Error::Failed)
+    }
+
+    /// Whether automation driving Claude Code should proceed, slow down or stop, as
+    /// (action: "proceed", "slow" or "stop", seconds until it may proceed, reason)
+    async fn should_throttle(&self) -> fdo::Result<(String, u64, String)> {
+        let advice = tauri::async_runtime::spawn_blocking(should_throttle)
+            .await
+            .map_err(|e| fdo::Error::Failed(format!("Task join error: {e}")))?
+            .map_err(fdo::Error::Failed)?;
+        let action = match advice.action {
+            ThrottleAction::Proceed => "proceed",
+            ThrottleAction::Slow => "slow",
+            ThrottleAction::Stop => "stop",
+        };
+        Ok((action.to_string(), advice.seconds_until_ok, advice.reason))
+    }
+
+    /// Revision of this interface; added since revision 1: `GetStatsJson` (2), the
+    /// `AlertFired` signal (3) and `ShouldThrottle` (4)
+    #[zbus(property)]
+    async fn interface_version(&self) -> u32 {
+        crate::INTERFACE_VERSION
+    }
+
+    /// Recompute the cached summary and make the widget reload its stats
+    async fn refresh(&self) {
+        crate::state::get().invalidate();
+        self.update_cache().await;
+        if let Some(ref app) = self.app {
+            crate::events::emit(app, "usage-updated", ());
+        }
+    }
+
+    /// Show, unminimize and focus the widget window
+    async fn show_window(&self) -> fdo::Result<()> {
+        let app = self
+            .app
+            .as_ref()
+            .ok_or_else(|| fdo::Error::Failed("Widget window not found".to_string()))?;
+        crate::tray::show_widget(app).map_err(fdo::Error::Failed)
+    }
+
+    /// An alert rule with the `dbus_signal` action crossed its threshold
+    #[zbus(signal)]
+    async fn alert_fired(
+        emitter: &SignalEmitter<'_>,
+        name: &str,
+        description: &str,
+        value: f64,
+        threshold: f64,
+    ) -> Result<()>;
+}
+
+/// Send the `AlertFired` signal, logging failures; a no-op when the service isn't running
+pub fn emit_alert_fired(name: &str, description: &str, value: f64, threshold: f64) {
+    let connection = match CONNECTION.get() {
+        Some(connection) => connection,
+        None => {
+            log_line!("D-Bus service not running; alert '{name}' not signalled");
+            return;
+        }
+    };
+    let result = tauri::async_runtime::block_on(async {
+        let emitter = SignalEmitter::new(connection, OBJECT_PATH)?;
+        UsageService::alert_fired(&emitter, name, description, value, threshold).await
+    });
+    if let Err(e) = result {
+        log_line!("Failed to signal alert '{name}': {e}");
     }
 }
 
@@ -87,8 +174,8 @@ impl DbusServiceHandle {
 
 /// Initialize and run the D-Bus service on the session bus.
 /// Returns a handle for updating the service cache.
-async fn init_dbus_service() -> Result<DbusServiceHandle> {
-    let service = Arc::new(UsageService::new());
+async fn init_dbus_service(app: Option<AppHandle>) -> Result<DbusServiceHandle> {
+    let service = Arc::new(UsageService::new(app));
 
     // Pre-populate the cache
     service.update_cache().await;
@@ -96,16 +183,16 @@ async fn init_dbus_service() -> Result<DbusServiceHandle> {
     let connection = Connection::session().await?;
 
     // Request the well-known bus name
-    connection
-        .request_name("com.shane.CCUsageWidget")
-        .await?;
+    connection.request_name(BUS_NAME).await?;
 
     // Register the object at the expected path
     connection
         .object_server()
-        .at("/com/shane/CCUsageWidget", (*service).clone())
+        .at(OBJECT_PATH, (*service).clone())
         .await?;
 
+    let _ = CONNECTION.set(connection.clone());
+
     // Keep the connection alive by storing it in the handle
     Ok(DbusServiceHandle {
         service,
@@ -115,7 +202,8 @@ async fn init_dbus_service() -> Result<DbusServiceHandle> {
 
 /// Spawn the D-Bus service on a dedicated thread with its own tokio runtime.
 /// This ensures the runtime stays alive to handle D-Bus method calls.
-pub fn spawn_dbus_service() -> Option<DbusServiceHandle> {
+/// `app` is None in daemon mode, where there is no window to refresh or show.
+pub fn spawn_dbus_service(app: Option<AppHandle>) -> Option<DbusServiceHandle> {
     use std::sync::mpsc;
     use std::thread;
 
@@ -128,14 +216,14 @@ pub fn spawn_dbus_service() -> Option<DbusServiceHandle> {
         {
             Ok(rt) => rt,
             Err(e) => {
-                eprintln!("Failed to create D-Bus runtime: {:?}", e);
+                log_line!("Failed to create D-Bus runtime: {:?}", e);
                 let _ = tx.send(None);
                 return;
             }
         };
 
         rt.block_on(async {
-            match init_dbus_service().await {
+            match init_dbus_service(app).await {
                 Ok(handle) => {
                     let _ = tx.send(Some(handle.clone()));
                     // Keep the runtime alive by running forever
@@ -144,7 +232,7 @@ pub fn spawn_dbus_service() -> Option<DbusServiceHandle> {
                     }
                 }
                 Err(e) => {
-                    eprintln!("Failed to initialize D-Bus service: {:?}", e);
+                    log_line!("Failed to initialize D-Bus service: {:?}", e);
                     let _ = tx.send(None);
                 }
             }
@@ -155,10 +243,59 @@ pub fn spawn_dbus_service() -> Option<DbusServiceHandle> {
     rx.recv_timeout(std::time::Duration::from_millis(500)).ok().flatten()
 }
 
+/// Single instance: if another copy already owns the bus name, ask it to show its window
+/// (when `show_window`) and return true, so this process exits instead of starting a
+/// second watcher. False when no instance is running or the session bus is unavailable.
+pub fn forward_to_running_instance(show_window: bool) -> bool {
+    let rt = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(rt) => rt,
+        Err(_) => return false,
+    };
+
+    rt.block_on(async {
+        let connection = match Connection::session().await {
+            Ok(connection) => connection,
+            Err(_) => return false,
+        };
+        let running = match fdo::DBusProxy::new(&connection).await {
+            Ok(proxy) => match BUS_NAME.try_into() {
+                Ok(name) => proxy.name_has_owner(name).await.unwrap_or(false),
+                Err(_) => false,
+            },
+            Err(_) => false,
+        };
+        if !running {
+            return false;
+        }
+
+        log_line!("Another instance is already running; forwarding to it");
+        if show_window {
+            // Fails when the running instance is a headless daemon with no window
+            if let Err(e) = connection
+                .call_method(
+                    Some(BUS_NAME),
+                    OBJECT_PATH,
+                    Some(INTERFACE_NAME),
+                    "ShowWindow",
+                    &(),
+                )
+                .await
+            {
+                log_line!("Failed to show the running instance's window: {e}");
+            }
+        }
+        true
+    })
+}
+
 impl Clone for UsageService {
     fn clone(&self) -> Self {
         Self {
             cache: Arc::clone(&self.cache),
+            app: self.app.clone(),
         }
     }
 }
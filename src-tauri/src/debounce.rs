@@ -0,0 +1,226 @@
+//! Adaptive debounce for journal change events. A lone write refreshes the widget after a
+//! short quiet time; during write storms (agents streaming into several journals) the quiet
+//! time grows with the event rate, so refreshes are batched, while `max_wait_ms` bounds how
+//! stale the widget can get when writes never pause.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::config::DebounceConfig;
+
+/// Time constant of the event rate average
+const RATE_WINDOW_SECS: f64 = 5.0;
+
+/// Events per second at which the quiet time reaches `max_ms`
+const STORM_RATE: f64 = 20.0;
+
+/// How long to wait for events when nothing is pending, so the watcher loop still runs its
+/// periodic work
+const IDLE_TIMEOUT: Duration = Duration::from_millis(500);
+
+static EVENTS: AtomicU64 = AtomicU64::new(0);
+static REFRESHES: AtomicU64 = AtomicU64::new(0);
+static SUPPRESSED: AtomicU64 = AtomicU64::new(0);
+static FORCED: AtomicU64 = AtomicU64::new(0);
+static RATE_MILLI: AtomicU64 = AtomicU64::new(0);
+static QUIET_MS: AtomicU64 = AtomicU64::new(0);
+static RELOAD: AtomicBool = AtomicBool::new(false);
+
+/// Have the watcher loop pick up new `watcher_debounce` settings on its next wakeup
+pub fn request_reload() {
+    RELOAD.store(true, Ordering::Relaxed);
+}
+
+/// Whether settings changed since the last call
+pub fn take_reload_request() -> bool {
+    RELOAD.swap(false, Ordering::Relaxed)
+}
+
+/// Counters since startup, from `get_watcher_stats`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatcherStats {
+    pub events: u64,
+    pub refreshes: u64,
+    /// Events folded into another event's refresh
+    pub suppressed_events: u64,
+    /// Refreshes forced by `max_wait_ms` while writes kept coming
+    pub forced_refreshes: u64,
+    pub events_per_sec: f64,
+    /// Quiet time currently required before a refresh
+    pub quiet_ms: u64,
+}
+
+pub fn watcher_stats() -> WatcherStats {
+    WatcherStats {
+        events: EVENTS.load(Ordering::Relaxed),
+        refreshes: REFRESHES.load(Ordering::Relaxed),
+        suppressed_events: SUPPRESSED.load(Ordering::Relaxed),
+        forced_refreshes: FORCED.load(Ordering::Relaxed),
+        events_per_sec: RATE_MILLI.load(Ordering::Relaxed) as f64 / 1000.0,
+        quiet_ms: QUIET_MS.load(Ordering::Relaxed),
+    }
+}
+
+pub struct AdaptiveDebounce {
+    min: Duration,
+    max: Duration,
+    max_wait: Duration,
+    /// Exponentially weighted events per second
+    rate: f64,
+    last_event: Option<Instant>,
+    /// First event not yet refreshed for, and how many arrived since
+    pending: Option<(Instant, u64)>,
+}
+
+impl AdaptiveDebounce {
+    pub fn new(settings: &DebounceConfig) -> Self {
+        let mut debounce = Self {
+            min: Duration::ZERO,
+            max: Duration::ZERO,
+            max_wait: Duration::ZERO,
+            rate: 0.0,
+            last_event: None,
+            pending: None,
+        };
+        debounce.reconfigure(settings);
+        debounce
+    }
+
+    /// Switch to new settings, keeping the event rate and pending events
+    pub fn reconfigure(&mut self, settings: &DebounceConfig) {
+        self.min = Duration::from_millis(settings.min_ms);
+        self.max = Duration::from_millis(settings.max_ms).max(self.min);
+        self.max_wait = Duration::from_millis(settings.max_wait_ms).max(self.max);
+        QUIET_MS.store(self.quiet_period().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Current event rate, decayed to `now`
+    fn rate_at(&self, now: Instant) -> f64 {
+        match self.last_event {
+            Some(last) => {
+                let elapsed = now.duration_since(last).as_secs_f64();
+                self.rate * (-elapsed / RATE_WINDOW_SECS).exp()
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Quiet time before a refresh: `min` when idle, growing to `max` at `STORM_RATE`
+    fn quiet_period(&self) -> Duration {
+        let intensity = (self.rate / STORM_RATE).min(1.0);
+        self.min + (self.max - self.min).mul_f64(intensity)
+    }
+
+    pub fn record_event(&mut self) {
+        let now = Instant::now();
+        self.rate = self.rate_at(now) + 1.0 / RATE_WINDOW_SECS;
+        self.last_event = Some(now);
+        let (_, count) = self.pending.get_or_insert((now, 0));
+        *count += 1;
+        EVENTS.fetch_add(1, Ordering::Relaxed);
+        RATE_MILLI.store((self.rate * 1000.0) as u64, Ordering::Relaxed);
+        QUIET_MS.store(self.quiet_period().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// How long to wait for the next event before checking `ready` again
+    pub fn timeout(&self) -> Duration {
+        let (first, last) = match (self.pending, self.last_event) {
+            (Some((first, _)), Some(last)) => (first, last),
+            _ => return IDLE_TIMEOUT,
+        };
+        let until_quiet = self.quiet_period().saturating_sub(last.elapsed());
+        let until_forced = self.max_wait.saturating_sub(first.elapsed());
+        until_quiet.min(until_forced).max(Duration::from_millis(10))
+    }
+
+    /// Whether pending events should be refreshed for now: writes paused for the quiet
+    /// period, or the first of them has waited `max_wait`
+    pub fn ready(&mut self) -> bool {
+        let (first, count) = match self.pending {
+            Some(pending) => pending,
+            None => return false,
+        };
+        let quiet = self
+            .last_event
+            .is_some_and(|last| last.elapsed() >= self.quiet_period());
+        let forced = first.elapsed() >= self.max_wait;
+        if !quiet && !forced {
+            return false;
+        }
+        self.pending = None;
+        REFRESHES.fetch_add(1, Ordering::Relaxed);
+        SUPPRESSED.fetch_add(count.saturating_sub(1), Ordering::Relaxed);
+        if forced && !quiet {
+            FORCED.fetch_add(1, Ordering::Relaxed);
+        }
+        let rate = self.rate_at(Instant::now());
+        RATE_MILLI.store((rate * 1000.0) as u64, Ordering::Relaxed);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn debounce(min_ms: u64, max_ms: u64, max_wait_ms: u64) -> AdaptiveDebounce {
+        AdaptiveDebounce::new(&DebounceConfig {
+            min_ms,
+            max_ms,
+            max_wait_ms,
+        })
+    }
+
+    fn ago(ms: u64) -> Instant {
+        Instant::now() - Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn settings_are_kept_in_order() {
+        // max below min and max_wait below max are raised to match
+        let debounce = debounce(500, 100, 50);
+        assert_eq!(debounce.min, Duration::from_millis(500));
+        assert_eq!(debounce.max, Duration::from_millis(500));
+        assert_eq!(debounce.max_wait, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn quiet_time_grows_with_the_event_rate() {
+        let mut debounce = debounce(100, 1_000, 5_000);
+        assert_eq!(debounce.quiet_period(), Duration::from_millis(100));
+        debounce.rate = STORM_RATE / 2.0;
+        assert_eq!(debounce.quiet_period(), Duration::from_millis(550));
+        debounce.rate = STORM_RATE * 10.0;
+        assert_eq!(debounce.quiet_period(), Duration::from_millis(1_000));
+
+        // The rate decays once events stop
+        debounce.last_event = Some(ago(10_000));
+        assert!(debounce.rate_at(Instant::now()) < STORM_RATE * 10.0 / 2.0);
+    }
+
+    #[test]
+    fn pending_events_refresh_once_quiet_or_overdue() {
+        let mut debounce = debounce(100, 1_000, 5_000);
+        assert!(!debounce.ready());
+        assert_eq!(debounce.timeout(), IDLE_TIMEOUT);
+
+        debounce.record_event();
+        assert!(!debounce.ready());
+        // A single event barely raises the quiet time above min
+        assert!(debounce.quiet_period() < Duration::from_millis(150));
+        assert!(debounce.timeout() <= debounce.quiet_period());
+
+        // Writes paused for the quiet time
+        debounce.pending = Some((ago(200), 3));
+        debounce.last_event = Some(ago(200));
+        assert!(debounce.ready());
+        assert!(!debounce.ready());
+
+        // Writes never paused, but the first has waited max_wait
+        debounce.rate = STORM_RATE;
+        debounce.pending = Some((ago(6_000), 50));
+        debounce.last_event = Some(Instant::now());
+        assert!(debounce.ready());
+    }
+}
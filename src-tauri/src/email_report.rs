@@ -0,0 +1,135 @@
+//! Weekly usage report sent by email over SMTP, for people who want the numbers without
+//! opening the widget. Scheduled by `scheduler`; the SMTP password lives in the OS keyring.
+
+use chrono::Local;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+
+use crate::config::{self, WeeklyReportConfig};
+use crate::format::{format_cost, format_tokens};
+use crate::privacy;
+use crate::scheduler::format_digest;
+use crate::usage::compute_daily_digest;
+
+const KEYRING_SERVICE: &str = "cc-usage-widget";
+
+/// Keyring entry for the SMTP account of `settings`
+fn keyring_entry(settings: &WeeklyReportConfig) -> Result<keyring::Entry, String> {
+    let user = format!("smtp:{}@{}", settings.username, settings.smtp_host);
+    keyring::Entry::new(KEYRING_SERVICE, &user).map_err(|e| format!("Keyring unavailable: {e}"))
+}
+
+/// Store (or with None, remove) the SMTP password for the account in `settings`
+pub fn set_smtp_password(
+    settings: &WeeklyReportConfig,
+    password: Option<&str>,
+) -> Result<(), String> {
+    let entry = keyring_entry(settings)?;
+    match password {
+        Some(password) => entry
+            .set_password(password)
+            .map_err(|e| format!("Failed to store SMTP password: {e}")),
+        None => match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Failed to remove SMTP password: {e}")),
+        },
+    }
+}
+
+pub fn has_smtp_password(settings: &WeeklyReportConfig) -> bool {
+    keyring_entry(settings).is_ok_and(|entry| entry.get_password().is_ok())
+}
+
+/// Subject and plain-text body covering the 7 days before today
+pub fn render_weekly_report() -> (String, String) {
+    let cfg = config::load_config();
+    let today = Local::now().date_naive();
+    let first = today - chrono::Duration::days(7);
+    let last = today - chrono::Duration::days(1);
+
+    let mut total_tokens: u64 = 0;
+    let mut total_cost = 0.0;
+    let mut total_prompts = 0;
+    let mut days = Vec::new();
+    for offset in 0..7 {
+        let mut digest = compute_daily_digest(first + chrono::Duration::days(offset));
+        if cfg.privacy_mode {
+            privacy::apply_to_digest(&mut digest);
+        }
+        total_tokens = total_tokens.saturating_add(digest.total_tokens);
+        total_cost += digest.cost_usd;
+        total_prompts += digest.prompt_count;
+        days.push(format_digest(&digest, &cfg.format));
+    }
+
+    let range = format!(
+        "{} to {}",
+        first.format("%Y-%m-%d"),
+        last.format("%Y-%m-%d")
+    );
+    let subject = format!(
+        "Claude Code usage {}: {}",
+        range,
+        format_cost(total_cost, &cfg.format)
+    );
+    let body = format!(
+        "Claude Code usage, {}\n\nTotal: {} tokens · {} · {} prompts\n\n{}\n\n-- \n\
+         Sent by CC Usage Widget. Costs are estimates at API prices.\n",
+        range,
+        format_tokens(total_tokens, &cfg.format),
+        format_cost(total_cost, &cfg.format),
+        total_prompts,
+        days.join("\n\n")
+    );
+    (subject, body)
+}
+
+/// Render the report and send it to every recipient in `settings`
+pub fn send_weekly_report(settings: &WeeklyReportConfig) -> Result<(), String> {
+    if settings.smtp_host.is_empty() || settings.from.is_empty() || settings.to.is_empty() {
+        return Err("SMTP host, sender and at least one recipient are required".to_string());
+    }
+
+    let (subject, body) = render_weekly_report();
+    let mut builder = Message::builder()
+        .from(
+            settings
+                .from
+                .parse()
+                .map_err(|e| format!("Invalid sender '{}': {e}", settings.from))?,
+        )
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN);
+    for to in &settings.to {
+        builder = builder.to(to
+            .parse()
+            .map_err(|e| format!("Invalid recipient '{to}': {e}"))?);
+    }
+    let email = builder
+        .body(body)
+        .map_err(|e| format!("Failed to build email: {e}"))?;
+
+    let relay = if settings.implicit_tls {
+        SmtpTransport::relay(&settings.smtp_host)
+    } else {
+        SmtpTransport::starttls_relay(&settings.smtp_host)
+    }
+    .map_err(|e| format!("Invalid SMTP host '{}': {e}", settings.smtp_host))?
+    .port(settings.smtp_port);
+    let mailer = if settings.username.is_empty() {
+        relay.build()
+    } else {
+        let password = keyring_entry(settings)?
+            .get_password()
+            .map_err(|e| format!("No SMTP password in the keyring: {e}"))?;
+        relay
+            .credentials(Credentials::new(settings.username.clone(), password))
+            .build()
+    };
+
+    mailer
+        .send(&email)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to send weekly report: {e}"))
+}
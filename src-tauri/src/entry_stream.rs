@@ -0,0 +1,170 @@
+//! Opt-in stream of raw usage entries (`entries_stream` in config): newly parsed
+//! `ParsedEntry` values are emitted as `entries-appended` events, so custom frontends and
+//! local plugins can build their own views without reading the journals themselves.
+//!
+//! The journal watcher sends the entries of the lines appended to journals since its
+//! previous batch, and the OTLP receiver sends the entries it ingested. Entries are deduplicated by uuid (or by
+//! content when there is none), since resumed sessions copy earlier messages into their new
+//! journal. Only entries dated after the stream started are sent, so turning it on doesn't
+//! replay the history.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::AppHandle;
+
+use crate::config;
+use crate::events;
+use crate::privacy;
+use crate::usage::{
+    collect_jsonl_files, get_claude_data_dirs, parse_usage_from_reader, read_appended_lines,
+    ParsedEntry,
+};
+
+/// Keys of sent entries are kept this long, which covers any journal still being written
+const SENT_RETENTION_HOURS: i64 = 24;
+
+/// Journals modified within this many hours are checked for new entries
+const ACTIVE_JOURNAL_HOURS: i64 = 1;
+
+/// Larger batches are split over several events
+const MAX_ENTRIES_PER_EVENT: usize = 500;
+
+struct Stream {
+    /// Entries dated earlier are history, not new
+    started: DateTime<Utc>,
+    /// Journals not modified since the previous scan are skipped
+    last_scan: SystemTime,
+    /// Entry key -> entry timestamp, for every entry sent
+    sent: HashMap<String, DateTime<Utc>>,
+    /// Active journal -> bytes of it already read
+    offsets: HashMap<PathBuf, u64>,
+}
+
+static STREAM: Mutex<Option<Stream>> = Mutex::new(None);
+
+/// Start or stop the stream to match the config. Call at startup and when the setting
+/// changes; a restarted stream only sends entries dated from then on.
+pub fn sync_entries_stream() {
+    let enabled = config::load_config().entries_stream;
+    if let Ok(mut stream) = STREAM.lock() {
+        match (enabled, stream.is_some()) {
+            (true, false) => {
+                *stream = Some(Stream {
+                    started: Utc::now(),
+                    last_scan: SystemTime::now(),
+                    sent: HashMap::new(),
+                    offsets: HashMap::new(),
+                })
+            }
+            (false, true) => *stream = None,
+            _ => {}
+        }
+    }
+}
+
+/// Identifies an entry across journals; old journals without uuids fall back to content
+fn entry_key(entry: &ParsedEntry) -> String {
+    if !entry.uuid.is_empty() {
+        return entry.uuid.clone();
+    }
+    format!(
+        "{}|{}|{}|{}|{}",
+        entry.session_id,
+        entry.timestamp,
+        entry.model,
+        entry.tokens.input_tokens,
+        entry.tokens.output_tokens
+    )
+}
+
+/// Of `entries`, those dated after the stream started and not sent before, oldest first.
+/// Entries older than `SENT_RETENTION_HOURS` are left out too, since their keys may have
+/// been forgotten already.
+fn unsent(stream: &mut Stream, entries: Vec<ParsedEntry>) -> Vec<ParsedEntry> {
+    let forget_before = Utc::now() - Duration::hours(SENT_RETENTION_HOURS);
+    let since = stream.started.max(forget_before);
+    let mut fresh: Vec<ParsedEntry> = entries
+        .into_iter()
+        .filter(|entry| {
+            let Ok(timestamp) = DateTime::parse_from_rfc3339(&entry.timestamp) else {
+                return false;
+            };
+            let timestamp = timestamp.with_timezone(&Utc);
+            timestamp >= since && stream.sent.insert(entry_key(entry), timestamp).is_none()
+        })
+        .collect();
+    fresh.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+    stream
+        .sent
+        .retain(|_, timestamp| *timestamp >= forget_before);
+    fresh
+}
+
+/// Entries of the complete lines appended to `path` since `offset`, which is moved past
+/// them. A journal shorter than `offset` was replaced and is read from the start. Entries
+/// whose parent is in an earlier read have no `parent_uuid`.
+fn read_appended(path: &Path, offset: &mut u64) -> Vec<ParsedEntry> {
+    parse_usage_from_reader(read_appended_lines(path, offset).as_slice())
+}
+
+/// Emit the entries appended to journals since the previous call; does nothing while the
+/// stream is off
+pub fn emit_journal_entries(app: &AppHandle) {
+    let entries = {
+        let Ok(mut guard) = STREAM.lock() else {
+            return;
+        };
+        let Some(stream) = guard.as_mut() else {
+            return;
+        };
+        // Taken before scanning, so writes during the scan are picked up next time
+        let scan_started = SystemTime::now();
+        let active = collect_jsonl_files(&get_claude_data_dirs(), Some(ACTIVE_JOURNAL_HOURS));
+        stream.offsets.retain(|path, _| active.contains(path));
+        let mut parsed = Vec::new();
+        for file in active {
+            let modified = std::fs::metadata(&file)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            if modified.is_some_and(|modified| modified < stream.last_scan) {
+                continue;
+            }
+            let offset = stream.offsets.entry(file.clone()).or_default();
+            parsed.extend(read_appended(&file, offset));
+        }
+        stream.last_scan = scan_started;
+        unsent(stream, parsed)
+    };
+    send(app, entries);
+}
+
+/// Emit entries ingested from another source (the OTLP receiver); does nothing while the
+/// stream is off
+pub fn emit_ingested_entries(app: &AppHandle, entries: &[ParsedEntry]) {
+    let entries = match STREAM.lock() {
+        Ok(mut guard) => match guard.as_mut() {
+            Some(stream) => unsent(stream, entries.to_vec()),
+            None => return,
+        },
+        Err(_) => return,
+    };
+    send(app, entries);
+}
+
+fn send(app: &AppHandle, mut entries: Vec<ParsedEntry>) {
+    if entries.is_empty() {
+        return;
+    }
+    if config::load_config().privacy_mode {
+        for entry in &mut entries {
+            entry.cwd = privacy::pseudonym(&entry.cwd);
+        }
+    }
+    for batch in entries.chunks(MAX_ENTRIES_PER_EVENT) {
+        events::emit(app, "entries-appended", batch.to_vec());
+    }
+}
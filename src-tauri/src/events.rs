@@ -0,0 +1,179 @@
+//! Events emitted to the frontend, with a rolling log of the most recent ones so a widget
+//! that missed an update can be checked against what the backend actually sent
+//! (`get_event_log`).
+//!
+//! Every event's payload is wrapped as `{seq, payload}`, with a sequence number increasing
+//! by one per event. A frontend that sees a gap, or comes back from a reload or suspend,
+//! calls `resync` with the last number it handled to get the events it missed.
+
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+/// Number of events kept in the log
+const EVENT_LOG_CAPACITY: usize = 200;
+
+/// Number of events kept with their full payload for `resync`
+const REPLAY_CAPACITY: usize = 100;
+
+/// Total size of the payloads kept for `resync`, as JSON; older events are dropped sooner
+/// when large payloads (e.g. `entries-appended` batches) would exceed it
+const REPLAY_MAX_BYTES: usize = 1024 * 1024;
+
+/// Payload summaries are cut to this many characters
+const PAYLOAD_SUMMARY_CHARS: usize = 200;
+
+static EVENT_LOG: Mutex<VecDeque<EventRecord>> = Mutex::new(VecDeque::new());
+
+static REPLAY: Mutex<Replay> = Mutex::new(Replay {
+    events: VecDeque::new(),
+    bytes: 0,
+    latest: 0,
+});
+
+/// Events kept for `resync`
+struct Replay {
+    /// Oldest first, each with the size of its payload as JSON
+    events: VecDeque<(SequencedEvent, usize)>,
+    /// Sum of the payload sizes
+    bytes: usize,
+    /// Sequence number of the latest event
+    latest: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventRecord {
+    /// Sequence number; none for warnings, which aren't emitted
+    pub seq: Option<u64>,
+    pub timestamp: String,
+    /// Event name, e.g. "usage-updated", or "warning" for backend warnings
+    pub event: String,
+    /// Payload as JSON (or the warning text), shortened
+    pub payload: String,
+    /// Whether the event reached the webview; always true for warnings
+    pub delivered: bool,
+}
+
+/// What an event's payload is sent as
+#[derive(Clone, Serialize)]
+struct Envelope<S> {
+    seq: u64,
+    payload: S,
+}
+
+/// An emitted event, as replayed by `resync`
+#[derive(Debug, Clone, Serialize)]
+pub struct SequencedEvent {
+    pub seq: u64,
+    pub event: String,
+    pub payload: serde_json::Value,
+}
+
+/// Events a frontend missed, and the state they would have changed
+#[derive(Debug, Clone, Serialize)]
+pub struct Resync {
+    /// Sequence number of the latest event emitted; 0 before the first
+    pub seq: u64,
+    /// Events after the requested sequence number, oldest first
+    pub events: Vec<SequencedEvent>,
+    /// False when some missed events are no longer kept, or the backend restarted since
+    /// the requested number; reload everything then
+    pub complete: bool,
+    /// The period to show, as last sent by `period-changed`
+    pub display_period: String,
+}
+
+fn record(seq: Option<u64>, event: &str, payload: String, delivered: bool) {
+    let payload = match payload.char_indices().nth(PAYLOAD_SUMMARY_CHARS) {
+        Some((end, _)) => format!("{}…", &payload[..end]),
+        None => payload,
+    };
+    if let Ok(mut log) = EVENT_LOG.lock() {
+        if log.len() >= EVENT_LOG_CAPACITY {
+            log.pop_front();
+        }
+        log.push_back(EventRecord {
+            seq,
+            timestamp: Utc::now().to_rfc3339(),
+            event: event.to_string(),
+            payload,
+            delivered,
+        });
+    }
+}
+
+/// Emit `event` to all webviews with the next sequence number, and record it in the event
+/// log and for `resync`
+pub fn emit<S: Serialize + Clone>(app_handle: &AppHandle, event: &str, payload: S) {
+    // Whatever makes the widget reload also makes the cached stats stale
+    if event == "usage-updated" {
+        crate::state::get().invalidate();
+    }
+    let value = serde_json::to_value(&payload).unwrap_or_default();
+    let summary = value.to_string();
+    let seq = match REPLAY.lock() {
+        Ok(mut replay) => {
+            replay.latest += 1;
+            let sequenced = SequencedEvent {
+                seq: replay.latest,
+                event: event.to_string(),
+                payload: value,
+            };
+            replay.bytes += summary.len();
+            replay.events.push_back((sequenced, summary.len()));
+            while replay.events.len() > REPLAY_CAPACITY || replay.bytes > REPLAY_MAX_BYTES {
+                match replay.events.pop_front() {
+                    Some((_, size)) => replay.bytes -= size,
+                    None => break,
+                }
+            }
+            replay.latest
+        }
+        Err(_) => 0,
+    };
+    let delivered = app_handle.emit(event, Envelope { seq, payload }).is_ok();
+    record(Some(seq), event, summary, delivered);
+}
+
+/// Record a backend warning the user may want to correlate with missing updates
+pub fn warning(message: &str) {
+    record(None, "warning", message.to_string(), true);
+}
+
+/// Events emitted after `since_seq`; without it just the latest sequence number, for a
+/// frontend starting to count
+pub fn resync(since_seq: Option<u64>) -> Resync {
+    let (events, latest, oldest) = match REPLAY.lock() {
+        Ok(replay) => {
+            let kept = replay.events.iter().map(|(e, _)| e);
+            let events = match since_seq {
+                Some(since) => kept.filter(|e| e.seq > since).cloned().collect(),
+                None => Vec::new(),
+            };
+            let oldest = replay.events.front().map(|(e, _)| e.seq);
+            (events, replay.latest, oldest)
+        }
+        Err(_) => (Vec::new(), 0, None),
+    };
+    let complete = match since_seq {
+        Some(since) if since > latest => false,
+        Some(since) if since < latest => oldest.is_some_and(|oldest| oldest <= since + 1),
+        _ => true,
+    };
+    Resync {
+        seq: latest,
+        events,
+        complete,
+        display_period: crate::tray::display_period(),
+    }
+}
+
+/// Logged events, oldest first
+pub fn event_log() -> Vec<EventRecord> {
+    EVENT_LOG
+        .lock()
+        .map(|log| log.iter().cloned().collect())
+        .unwrap_or_default()
+}
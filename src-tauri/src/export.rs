@@ -0,0 +1,192 @@
+//! Export of session activity as time-tracker import files (Toggl Track, Clockify).
+//!
+//! Each time entry is one activity block: a run of assistant messages in a session with no
+//! gap longer than `IDLE_GAP_MINUTES`.
+
+use chrono::{DateTime, Local, Utc};
+
+use crate::privacy;
+use crate::usage::{collect_activity_blocks, ActivityBlock};
+
+/// Inactivity that ends one time entry and starts the next
+const IDLE_GAP_MINUTES: i64 = 15;
+/// Minimum entry length so single-message blocks still register
+const MIN_ENTRY_MINUTES: i64 = 1;
+
+#[derive(Debug, Clone, Copy)]
+pub enum TimeTrackerFormat {
+    Toggl,
+    Clockify,
+}
+
+impl TimeTrackerFormat {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "toggl" => Ok(Self::Toggl),
+            "clockify" => Ok(Self::Clockify),
+            other => Err(format!(
+                "Unknown time tracker format '{other}' (expected toggl or clockify)"
+            )),
+        }
+    }
+}
+
+/// Build a CSV of time entries for the last `days` days in the given tracker's import format.
+/// With `privacy_mode`, project names are replaced with pseudonyms.
+pub fn export_time_entries(
+    format: TimeTrackerFormat,
+    days: u32,
+    email: &str,
+    privacy_mode: bool,
+) -> String {
+    let since = Utc::now() - chrono::Duration::days(days as i64);
+    let mut blocks = collect_activity_blocks(since, chrono::Duration::minutes(IDLE_GAP_MINUTES));
+    if privacy_mode {
+        privacy::apply_to_blocks(&mut blocks);
+    }
+
+    match format {
+        TimeTrackerFormat::Toggl => to_toggl_csv(&blocks, email),
+        TimeTrackerFormat::Clockify => to_clockify_csv(&blocks, email),
+    }
+}
+
+/// Toggl Track import: Email, Project, Description, Start date, Start time, Duration
+fn to_toggl_csv(blocks: &[ActivityBlock], email: &str) -> String {
+    let mut csv = String::from("Email,Project,Description,Start date,Start time,Duration\n");
+    for block in blocks {
+        let (start, end) = entry_bounds(block);
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            csv_field(email),
+            csv_field(&block.project),
+            csv_field(&description(block)),
+            start.format("%Y-%m-%d"),
+            start.format("%H:%M:%S"),
+            format_hms(end - start),
+        ));
+    }
+    csv
+}
+
+/// Clockify import: Project, Description, Email, Start/End date and time, Duration (h)
+fn to_clockify_csv(blocks: &[ActivityBlock], email: &str) -> String {
+    let mut csv = String::from(
+        "Project,Description,Email,Start Date,Start Time,End Date,End Time,Duration (h)\n",
+    );
+    for block in blocks {
+        let (start, end) = entry_bounds(block);
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{:.2}\n",
+            csv_field(&block.project),
+            csv_field(&description(block)),
+            csv_field(email),
+            start.format("%Y-%m-%d"),
+            start.format("%H:%M:%S"),
+            end.format("%Y-%m-%d"),
+            end.format("%H:%M:%S"),
+            (end - start).num_seconds() as f64 / 3600.0,
+        ));
+    }
+    csv
+}
+
+/// Entry start/end in local time, padded to the minimum entry length
+fn entry_bounds(block: &ActivityBlock) -> (DateTime<Local>, DateTime<Local>) {
+    let end = block
+        .end
+        .max(block.start + chrono::Duration::minutes(MIN_ENTRY_MINUTES));
+    (block.start.with_timezone(&Local), end.with_timezone(&Local))
+}
+
+fn description(block: &ActivityBlock) -> String {
+    let short_id: String = block.session_id.chars().take(8).collect();
+    format!(
+        "Claude Code session {} ({} messages, ${:.2})",
+        short_id, block.message_count, block.cost_usd
+    )
+}
+
+fn format_hms(duration: chrono::Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    format!("{:02}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+}
+
+/// Quote a CSV field when it contains separators, quotes or newlines
+fn csv_field(value: &str) -> String {
+    if value.contains(|c| matches!(c, ',' | '"' | '\n')) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(project: &str, start: &str, end: &str) -> ActivityBlock {
+        ActivityBlock {
+            session_id: "3f2a9c1e-0000-4000-8000-000000000001".to_string(),
+            project: project.to_string(),
+            start: start.parse().unwrap(),
+            end: end.parse().unwrap(),
+            message_count: 3,
+            cost_usd: 1.5,
+        }
+    }
+
+    #[test]
+    fn formats_are_parsed_in_any_case() {
+        assert!(matches!(
+            TimeTrackerFormat::parse("Toggl"),
+            Ok(TimeTrackerFormat::Toggl)
+        ));
+        assert!(matches!(
+            TimeTrackerFormat::parse("CLOCKIFY"),
+            Ok(TimeTrackerFormat::Clockify)
+        ));
+        assert!(TimeTrackerFormat::parse("harvest").is_err());
+        assert!(TimeTrackerFormat::parse("").is_err());
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("api"), "api");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("api, web"), "\"api, web\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn durations_are_padded_and_never_negative() {
+        assert_eq!(format_hms(chrono::Duration::seconds(0)), "00:00:00");
+        assert_eq!(format_hms(chrono::Duration::seconds(3_725)), "01:02:05");
+        assert_eq!(format_hms(chrono::Duration::hours(30)), "30:00:00");
+        assert_eq!(format_hms(chrono::Duration::seconds(-5)), "00:00:00");
+    }
+
+    #[test]
+    fn entries_last_at_least_a_minute() {
+        // A single message starts and ends a block at the same time
+        let single = block("api", "2025-03-10T09:00:00Z", "2025-03-10T09:00:00Z");
+        let csv = to_toggl_csv(&[single], "me@example.com");
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("Email,Project,Description,Start date,Start time,Duration")
+        );
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("me@example.com,api,"), "{row}");
+        assert!(row.contains("Claude Code session 3f2a9c1e (3 messages, $1.50)"));
+        assert!(row.ends_with(",00:01:00"), "{row}");
+        assert_eq!(lines.next(), None);
+
+        let long = block("api, web", "2025-03-10T09:00:00Z", "2025-03-10T10:30:00Z");
+        let csv = to_clockify_csv(&[long], "me@example.com");
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.starts_with("\"api, web\","), "{row}");
+        assert!(row.ends_with(",1.50"), "{row}");
+    }
+}
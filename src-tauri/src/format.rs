@@ -0,0 +1,140 @@
+//! Display formatting shared by every backend output (Tauri commands, notifications).
+//!
+//! Raw numeric/RFC 3339 fields stay untouched; `apply_format_prefs` fills the `display_*`
+//! companions so the frontend doesn't need its own formatting rules.
+
+use chrono::{DateTime, Local};
+
+use crate::config::{FormatPrefs, TokenUnit};
+use crate::usage::UsageStats;
+
+pub fn format_tokens(tokens: u64, prefs: &FormatPrefs) -> String {
+    match prefs.token_unit {
+        TokenUnit::Auto => {
+            if tokens >= 1_000_000 {
+                format!("{:.1}M", tokens as f64 / 1_000_000.0)
+            } else if tokens >= 1_000 {
+                format!("{:.1}K", tokens as f64 / 1_000.0)
+            } else {
+                tokens.to_string()
+            }
+        }
+        TokenUnit::Raw => group_thousands(tokens),
+        TokenUnit::K => format!("{:.1}K", tokens as f64 / 1_000.0),
+        TokenUnit::M => format!("{:.2}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+pub fn format_cost(cost_usd: f64, prefs: &FormatPrefs) -> String {
+    format!("${:.*}", prefs.cost_decimals as usize, cost_usd)
+}
+
+/// Format an RFC 3339 timestamp as a local time of day. Unparseable input is returned as-is.
+pub fn format_timestamp(timestamp: &str, prefs: &FormatPrefs) -> String {
+    match DateTime::parse_from_rfc3339(timestamp) {
+        Ok(ts) => {
+            let local = ts.with_timezone(&Local);
+            if prefs.clock_24h {
+                local.format("%H:%M:%S").to_string()
+            } else {
+                local.format("%-I:%M:%S %p").to_string()
+            }
+        }
+        Err(_) => timestamp.to_string(),
+    }
+}
+
+/// Fill every `display_*` field of the stats from the raw values
+pub fn apply_format_prefs(stats: &mut UsageStats, prefs: &FormatPrefs) {
+    let total = &stats.total_tokens;
+    let total_tokens = total.total();
+    stats.display_total_tokens = format_tokens(total_tokens, prefs);
+    stats.display_total_cost = format_cost(stats.total_cost_usd, prefs);
+    stats.display_last_updated = format_timestamp(&stats.last_updated, prefs);
+
+    for model in &mut stats.by_model {
+        let t = &model.tokens;
+        let model_tokens = t.total();
+        model.display_tokens = format_tokens(model_tokens, prefs);
+        model.display_cost = format_cost(model.cost_usd, prefs);
+    }
+
+    for session in &mut stats.active_sessions {
+        session.display_last_activity = format_timestamp(&session.last_activity, prefs);
+        session.display_tokens = format_tokens(session.total_tokens, prefs);
+        session.display_cost = format_cost(session.cost_usd, prefs);
+    }
+}
+
+fn group_thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(ch);
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prefs(token_unit: TokenUnit) -> FormatPrefs {
+        FormatPrefs {
+            token_unit,
+            ..FormatPrefs::default()
+        }
+    }
+
+    #[test]
+    fn tokens_are_formatted_in_the_chosen_unit() {
+        let auto = prefs(TokenUnit::Auto);
+        assert_eq!(format_tokens(0, &auto), "0");
+        assert_eq!(format_tokens(999, &auto), "999");
+        assert_eq!(format_tokens(1_000, &auto), "1.0K");
+        assert_eq!(format_tokens(12_345, &auto), "12.3K");
+        assert_eq!(format_tokens(4_100_000, &auto), "4.1M");
+
+        let raw = prefs(TokenUnit::Raw);
+        assert_eq!(format_tokens(0, &raw), "0");
+        assert_eq!(format_tokens(999, &raw), "999");
+        assert_eq!(format_tokens(1_000, &raw), "1,000");
+        assert_eq!(format_tokens(123_456, &raw), "123,456");
+        assert_eq!(format_tokens(1_234_567, &raw), "1,234,567");
+
+        assert_eq!(format_tokens(500, &prefs(TokenUnit::K)), "0.5K");
+        assert_eq!(format_tokens(1_234_567, &prefs(TokenUnit::M)), "1.23M");
+    }
+
+    #[test]
+    fn costs_use_the_configured_decimals() {
+        let mut prefs = FormatPrefs::default();
+        assert_eq!(format_cost(1.234, &prefs), "$1.23");
+        prefs.cost_decimals = 0;
+        assert_eq!(format_cost(12.6, &prefs), "$13");
+        prefs.cost_decimals = 4;
+        assert_eq!(format_cost(0.0, &prefs), "$0.0000");
+    }
+
+    #[test]
+    fn timestamps_follow_the_clock_preference() {
+        let mut prefs = FormatPrefs {
+            clock_24h: true,
+            ..FormatPrefs::default()
+        };
+        assert_eq!(format_timestamp("not a time", &prefs), "not a time");
+        assert_eq!(format_timestamp("", &prefs), "");
+        let time = format_timestamp("2025-03-10T14:05:09Z", &prefs);
+        assert_eq!(time.len(), 8);
+        // Minutes shift in half-hour time zones; seconds never do
+        assert!(time.ends_with(":09"), "{time}");
+
+        prefs.clock_24h = false;
+        let time = format_timestamp("2025-03-10T14:05:09Z", &prefs);
+        assert!(time.ends_with(" AM") || time.ends_with(" PM"), "{time}");
+        assert!(!time.starts_with('0'), "{time}");
+    }
+}
@@ -0,0 +1,426 @@
+//! Usage goals ("stay under $50/week", "keep the cache hit rate above 60%") and streaks.
+//!
+//! Each completed period (day, week or billing month) is judged once and recorded in the
+//! store; after that only the current period is recomputed on refresh. Periods without usage
+//! are recorded as idle: they neither extend nor break a streak. Monthly records that no
+//! longer start a billing cycle (the anchor day changed) are judged again.
+
+use chrono::{DateTime, Local, NaiveDate};
+use std::collections::HashMap;
+
+use crate::config::GoalConfig;
+use crate::crash::log_line;
+use crate::store::{self, GoalRecord};
+use crate::usage::{
+    billing_cycle_start, collect_jsonl_files, get_claude_data_dirs, next_billing_cycle_start,
+    parse_usage_from_file, week_start, GoalStatus, TokenUsage,
+};
+
+pub const KINDS: [&str; 4] = [
+    "max_daily_cost",
+    "max_weekly_cost",
+    "max_monthly_cost",
+    "min_cache_hit_rate",
+];
+
+/// How far back completed periods are evaluated when a goal is new (or its target changed)
+const LOOKBACK_DAYS: i64 = 84;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    fn of(kind: &str) -> Option<Period> {
+        match kind {
+            "max_daily_cost" => Some(Period::Day),
+            "max_weekly_cost" | "min_cache_hit_rate" => Some(Period::Week),
+            "max_monthly_cost" => Some(Period::Month),
+            _ => None,
+        }
+    }
+
+    /// First day of the period containing `date` (weeks start on `week_starts_on`, months
+    /// on the billing anchor day)
+    fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => date,
+            Period::Week => week_start(date),
+            Period::Month => billing_cycle_start(date),
+        }
+    }
+
+    /// First day of the period after the one starting on `start`
+    fn next(self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => start + chrono::Duration::days(1),
+            Period::Week => start + chrono::Duration::days(7),
+            Period::Month => next_billing_cycle_start(start),
+        }
+    }
+
+    /// First day of the period before the one starting on `start`
+    fn previous(self, start: NaiveDate) -> NaiveDate {
+        self.start(start - chrono::Duration::days(1))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Totals {
+    cost_usd: f64,
+    cache_read_tokens: u64,
+    context_tokens: u64,
+}
+
+impl Totals {
+    fn add(&mut self, tokens: &TokenUsage, cost_usd: f64) {
+        self.cost_usd += cost_usd;
+        self.cache_read_tokens = self
+            .cache_read_tokens
+            .saturating_add(tokens.cache_read_input_tokens);
+        self.context_tokens = self.context_tokens.saturating_add(tokens.context_tokens());
+    }
+}
+
+/// Usage per local day from `from` on: journals, plus imported history for days the
+/// journals don't cover
+fn daily_totals(from: NaiveDate) -> HashMap<NaiveDate, Totals> {
+    let hours_back = (Local::now().date_naive() - from).num_hours().max(0) + 24;
+    let mut days: HashMap<NaiveDate, Totals> = HashMap::new();
+    for file in collect_jsonl_files(&get_claude_data_dirs(), Some(hours_back)) {
+        for entry in parse_usage_from_file(&file).unwrap_or_default() {
+            let date = match DateTime::parse_from_rfc3339(&entry.timestamp) {
+                Ok(ts) => ts.with_timezone(&Local).date_naive(),
+                Err(_) => continue,
+            };
+            if date >= from {
+                days.entry(date)
+                    .or_default()
+                    .add(&entry.tokens, entry.cost());
+            }
+        }
+    }
+
+    let mut imported: HashMap<NaiveDate, Totals> = HashMap::new();
+    for record in store::load_history() {
+        if let Ok(date) = NaiveDate::parse_from_str(&record.date, "%Y-%m-%d") {
+            if date >= from && !days.contains_key(&date) {
+                imported
+                    .entry(date)
+                    .or_default()
+                    .add(&record.tokens, record.cost_usd);
+            }
+        }
+    }
+    days.extend(imported);
+    days
+}
+
+/// The goal's measure over `totals`; None when nothing was used
+fn measure(kind: &str, totals: &Totals) -> Option<f64> {
+    if totals.cost_usd == 0.0 && totals.context_tokens == 0 {
+        return None;
+    }
+    match kind {
+        "min_cache_hit_rate" if totals.context_tokens == 0 => None,
+        "min_cache_hit_rate" => {
+            Some(totals.cache_read_tokens as f64 / totals.context_tokens as f64 * 100.0)
+        }
+        _ => Some(totals.cost_usd),
+    }
+}
+
+fn is_met(kind: &str, value: f64, target: f64) -> bool {
+    if kind.starts_with("min_") {
+        value >= target
+    } else {
+        value <= target
+    }
+}
+
+fn label(goal: &GoalConfig) -> String {
+    match goal.kind.as_str() {
+        "max_daily_cost" => format!("Under ${:.2} a day", goal.target),
+        "max_weekly_cost" => format!("Under ${:.2} a week", goal.target),
+        "max_monthly_cost" => format!("Under ${:.2} a month", goal.target),
+        _ => format!("Cache hit rate above {:.0}%", goal.target),
+    }
+}
+
+fn period_totals(days: &HashMap<NaiveDate, Totals>, start: NaiveDate, period: Period) -> Totals {
+    let mut totals = Totals::default();
+    let end = period.next(start);
+    for date in start.iter_days().take_while(|date| *date < end) {
+        if let Some(day) = days.get(&date) {
+            totals.cost_usd += day.cost_usd;
+            totals.cache_read_tokens = totals
+                .cache_read_tokens
+                .saturating_add(day.cache_read_tokens);
+            totals.context_tokens = totals.context_tokens.saturating_add(day.context_tokens);
+        }
+    }
+    totals
+}
+
+/// Completed periods within the lookback window, oldest first
+fn completed_periods(period: Period, today: NaiveDate) -> Vec<NaiveDate> {
+    let current = period.start(today);
+    let mut start = period.start(today - chrono::Duration::days(LOOKBACK_DAYS));
+    let mut periods = Vec::new();
+    while start < current {
+        periods.push(start);
+        start = period.next(start);
+    }
+    periods
+}
+
+/// Current standing and streaks of every configured goal, recording newly completed periods
+pub fn goal_statuses(goals: &[GoalConfig]) -> Vec<GoalStatus> {
+    let today = Local::now().date_naive();
+    let goals: Vec<(&GoalConfig, Period)> = goals
+        .iter()
+        .filter_map(|goal| Period::of(&goal.kind).map(|period| (goal, period)))
+        .collect();
+    if goals.is_empty() {
+        return Vec::new();
+    }
+
+    let mut records = store::load_goal_records();
+    // Months recorded under another billing anchor day don't line up with the current
+    // cycles; drop them so the cycles within the lookback are judged again
+    let monthly: Vec<&str> = goals
+        .iter()
+        .filter(|(_, period)| *period == Period::Month)
+        .map(|(goal, _)| goal.id.as_str())
+        .collect();
+    let misaligned = |record: &GoalRecord| {
+        monthly.contains(&record.goal_id.as_str())
+            && NaiveDate::parse_from_str(&record.period_start, "%Y-%m-%d")
+                .is_ok_and(|start| Period::Month.start(start) != start)
+    };
+    let mut changed = records.iter().any(misaligned);
+    records.retain(|record| !misaligned(record));
+    let recorded = |records: &[GoalRecord], goal: &GoalConfig, start: &str| {
+        records
+            .iter()
+            .any(|r| r.goal_id == goal.id && r.period_start == start && r.target == goal.target)
+    };
+
+    // Only read journals as far back as the oldest period still to be judged
+    let mut from = today;
+    for (goal, period) in &goals {
+        from = from.min(period.start(today));
+        if let Some(missing) = completed_periods(*period, today)
+            .into_iter()
+            .find(|start| !recorded(&records, goal, &start.format("%Y-%m-%d").to_string()))
+        {
+            from = from.min(missing);
+        }
+    }
+    let days = daily_totals(from);
+
+    for (goal, period) in &goals {
+        for start in completed_periods(*period, today) {
+            let key = start.format("%Y-%m-%d").to_string();
+            if start < from || recorded(&records, goal, &key) {
+                continue;
+            }
+            let value = measure(&goal.kind, &period_totals(&days, start, *period));
+            records.retain(|r| !(r.goal_id == goal.id && r.period_start == key));
+            records.push(GoalRecord {
+                goal_id: goal.id.clone(),
+                period_start: key,
+                target: goal.target,
+                value: value.unwrap_or(0.0),
+                met: value.is_some_and(|value| is_met(&goal.kind, value, goal.target)),
+                idle: value.is_none(),
+            });
+            changed = true;
+        }
+    }
+    if changed {
+        records.sort_by(|a, b| a.period_start.cmp(&b.period_start));
+        if let Err(e) = store::save_goal_records(&records) {
+            log_line!("Failed to save goal history: {e}");
+        }
+    }
+
+    goals
+        .iter()
+        .map(|(goal, period)| {
+            let current = measure(
+                &goal.kind,
+                &period_totals(&days, period.start(today), *period),
+            );
+
+            // Walk back from the last completed period while periods are contiguous and met.
+            // Idle periods are skipped: only periods with usage that met the goal count.
+            let history: HashMap<NaiveDate, &GoalRecord> = records
+                .iter()
+                .filter(|r| r.goal_id == goal.id && r.target == goal.target)
+                .filter_map(|r| {
+                    NaiveDate::parse_from_str(&r.period_start, "%Y-%m-%d")
+                        .ok()
+                        .map(|start| (start, r))
+                })
+                .collect();
+            let mut streak = 0;
+            let mut start = period.previous(period.start(today));
+            while let Some(record) = history.get(&start) {
+                if !record.idle {
+                    if !record.met {
+                        break;
+                    }
+                    streak += 1;
+                }
+                start = period.previous(start);
+            }
+            let mut starts: Vec<&NaiveDate> = history.keys().collect();
+            starts.sort();
+            let (mut best_streak, mut run, mut previous) = (0, 0, None::<NaiveDate>);
+            for start in starts {
+                let record = history[start];
+                let contiguous = previous.is_some_and(|p| period.next(p) == *start);
+                run = match (record.idle, record.met, contiguous) {
+                    (true, _, true) => run,
+                    (true, _, false) => 0,
+                    (false, true, true) => run + 1,
+                    (false, true, false) => 1,
+                    (false, false, _) => 0,
+                };
+                best_streak = best_streak.max(run);
+                previous = Some(*start);
+            }
+
+            GoalStatus {
+                id: goal.id.clone(),
+                kind: goal.kind.clone(),
+                label: label(goal),
+                target: goal.target,
+                current: current.unwrap_or(0.0),
+                // Nothing used yet this period keeps the goal on track
+                met: current.is_none_or(|value| is_met(&goal.kind, value, goal.target)),
+                streak,
+                best_streak,
+            }
+        })
+        .collect()
+}
+
+/// Check goal definitions before saving them
+pub fn validate(goals: &[GoalConfig]) -> Result<(), String> {
+    let mut ids = std::collections::HashSet::new();
+    for goal in goals {
+        if goal.id.is_empty() || !ids.insert(goal.id.as_str()) {
+            return Err(format!(
+                "Goal ids must be unique and non-empty: '{}'",
+                goal.id
+            ));
+        }
+        if !KINDS.contains(&goal.kind.as_str()) {
+            return Err(format!(
+                "Unknown goal kind '{}', expected one of {}",
+                goal.kind,
+                KINDS.join(", ")
+            ));
+        }
+        if !goal.target.is_finite() || goal.target < 0.0 {
+            return Err(format!("Invalid target for goal '{}'", goal.id));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn goal(id: &str, kind: &str, target: f64) -> GoalConfig {
+        GoalConfig {
+            id: id.to_string(),
+            kind: kind.to_string(),
+            target,
+        }
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn totals(cost_usd: f64, cache_read_tokens: u64, context_tokens: u64) -> Totals {
+        Totals {
+            cost_usd,
+            cache_read_tokens,
+            context_tokens,
+        }
+    }
+
+    #[test]
+    fn invalid_goals_are_refused() {
+        assert!(validate(&[goal("a", "max_daily_cost", 10.0)]).is_ok());
+        assert!(validate(&[goal("a", "min_cache_hit_rate", 0.0)]).is_ok());
+        assert!(validate(&[goal("", "max_daily_cost", 10.0)]).is_err());
+        assert!(validate(&[
+            goal("a", "max_daily_cost", 10.0),
+            goal("a", "max_weekly_cost", 50.0)
+        ])
+        .is_err());
+        assert!(validate(&[goal("a", "max_yearly_cost", 10.0)]).is_err());
+        assert!(validate(&[goal("a", "max_daily_cost", -1.0)]).is_err());
+        assert!(validate(&[goal("a", "max_daily_cost", f64::INFINITY)]).is_err());
+    }
+
+    #[test]
+    fn idle_periods_have_no_measure() {
+        assert_eq!(measure("max_daily_cost", &Totals::default()), None);
+        assert_eq!(measure("min_cache_hit_rate", &Totals::default()), None);
+        assert_eq!(measure("max_daily_cost", &totals(2.5, 0, 100)), Some(2.5));
+        // Cost without context tokens (e.g. imported history) has no hit rate
+        assert_eq!(measure("min_cache_hit_rate", &totals(2.5, 0, 0)), None);
+        assert_eq!(
+            measure("min_cache_hit_rate", &totals(2.5, 60, 100)),
+            Some(60.0)
+        );
+    }
+
+    #[test]
+    fn targets_are_limits_or_minimums_by_kind() {
+        assert!(is_met("max_daily_cost", 10.0, 10.0));
+        assert!(!is_met("max_daily_cost", 10.01, 10.0));
+        assert!(is_met("min_cache_hit_rate", 60.0, 60.0));
+        assert!(!is_met("min_cache_hit_rate", 59.9, 60.0));
+
+        assert_eq!(
+            label(&goal("a", "max_weekly_cost", 50.0)),
+            "Under $50.00 a week"
+        );
+        assert_eq!(
+            label(&goal("a", "min_cache_hit_rate", 60.0)),
+            "Cache hit rate above 60%"
+        );
+    }
+
+    #[test]
+    fn daily_periods_add_up_their_own_day() {
+        let days = HashMap::from([
+            (date("2025-03-09"), totals(1.0, 10, 100)),
+            (date("2025-03-10"), totals(2.0, 20, 200)),
+        ]);
+        let day = period_totals(&days, date("2025-03-10"), Period::Day);
+        assert_eq!(day.cost_usd, 2.0);
+        assert_eq!(day.context_tokens, 200);
+        assert_eq!(
+            period_totals(&days, date("2025-03-11"), Period::Day).cost_usd,
+            0.0
+        );
+
+        let today = date("2025-03-10");
+        let periods = completed_periods(Period::Day, today);
+        assert_eq!(periods.len(), LOOKBACK_DAYS as usize);
+        assert_eq!(periods.last(), Some(&date("2025-03-09")));
+        assert_eq!(Period::Day.previous(today), date("2025-03-09"));
+    }
+}
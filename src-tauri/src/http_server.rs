@@ -0,0 +1,517 @@
+//! Optional read-only HTTP dashboard.
+//!
+//! Serves the same `UsageStats` the widget shows as JSON (`/api/usage?period=...`) plus a
+//! minimal HTML page at `/`. Disabled by default, binds to localhost unless configured
+//! otherwise, and can require a bearer token (`Authorization: Bearer <token>` or
+//! `?token=<token>`, percent-encoded). Only GET is accepted; nothing can be changed over HTTP.
+//! Requests must name the server in their `Host` header (localhost, the bind address or one
+//! of `allowed_hosts`), so a web page can't reach it through a domain rebound to this machine.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::HttpServerConfig;
+use crate::crash::log_line;
+use crate::usage::UsageStats;
+
+/// Fetches stats for a period ("current-window", "today", "week", "month", "all")
+pub type StatsProvider = Arc<dyn Fn(&str) -> Result<UsageStats, String> + Send + Sync>;
+
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// The whole request must arrive within this long, so a client sending a byte at a time
+/// can't keep a connection slot however short each read is
+const REQUEST_DEADLINE: Duration = Duration::from_secs(10);
+const MAX_HEADER_LINES: usize = 64;
+/// Requests are cut off after this many bytes of request line and headers
+const MAX_REQUEST_BYTES: u64 = 16 * 1024;
+/// Connections handled at once; more are answered with 503 until one finishes
+const MAX_CONNECTIONS: usize = 16;
+
+static OPEN_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// A connection being handled, counted in `open` until dropped
+pub(crate) struct ConnectionSlot(&'static AtomicUsize);
+
+impl ConnectionSlot {
+    /// A slot unless `max` connections are open already
+    pub(crate) fn acquire(open: &'static AtomicUsize, max: usize) -> Option<Self> {
+        open.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+            (count < max).then_some(count + 1)
+        })
+        .ok()
+        .map(|_| ConnectionSlot(open))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    bearer: Option<String>,
+    host: Option<String>,
+}
+
+/// Who may use the dashboard
+struct Access {
+    /// Names requests may be addressed to besides localhost: the bind address and
+    /// `allowed_hosts`
+    hosts: Vec<String>,
+    token: Option<String>,
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+pub fn spawn_http_server(settings: HttpServerConfig, provider: StatsProvider) {
+    if !settings.enabled {
+        return;
+    }
+
+    thread::spawn(move || {
+        let addr = format!("{}:{}", settings.bind_address, settings.port);
+        let listener = match TcpListener::bind(&addr) {
+            Ok(l) => l,
+            Err(e) => {
+                log_line!("Failed to start HTTP dashboard on {addr}: {e}");
+                return;
+            }
+        };
+        log_line!("HTTP dashboard listening on http://{addr}/");
+
+        let mut hosts = settings.allowed_hosts;
+        hosts.push(settings.bind_address);
+        let access = Arc::new(Access {
+            hosts,
+            token: settings.token.filter(|t| !t.is_empty()),
+        });
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+            let Some(slot) = ConnectionSlot::acquire(&OPEN_CONNECTIONS, MAX_CONNECTIONS) else {
+                let busy = text_response("503 Service Unavailable", "Too many connections");
+                write_response(stream, &busy);
+                continue;
+            };
+            let provider = Arc::clone(&provider);
+            let access = Arc::clone(&access);
+            thread::spawn(move || {
+                let _slot = slot;
+                handle_connection(stream, &provider, &access);
+            });
+        }
+    });
+}
+
+fn handle_connection(stream: TcpStream, provider: &StatsProvider, access: &Access) {
+    let reader = DeadlineReader {
+        stream: &stream,
+        deadline: Instant::now() + REQUEST_DEADLINE,
+    };
+    let response = match read_request(reader) {
+        Some(request) => route(&request, provider, access),
+        None => text_response("400 Bad Request", "Bad request"),
+    };
+    write_response(stream, &response);
+}
+
+/// Reads from a stream until `deadline`, each read waiting at most `READ_TIMEOUT`
+struct DeadlineReader<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream
+            .set_read_timeout(Some(remaining.min(READ_TIMEOUT)))?;
+        self.stream.read(buf)
+    }
+}
+
+fn write_response(mut stream: TcpStream, response: &Response) {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    );
+    let _ = stream.write_all(head.as_bytes());
+    let _ = stream.write_all(response.body.as_bytes());
+}
+
+fn read_request(stream: impl Read) -> Option<Request> {
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?;
+    let (path, query_string) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut bearer = None;
+    let mut host = None;
+    for _ in 0..MAX_HEADER_LINES {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("authorization") {
+                bearer = value.trim().strip_prefix("Bearer ").map(|t| t.to_string());
+            } else if name.eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let query = query_string
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (percent_decode(k), percent_decode(v)))
+        .collect();
+
+    Some(Request {
+        method,
+        path: path.to_string(),
+        query,
+        bearer,
+        host,
+    })
+}
+
+/// Decode the `%XX` escapes and `+` (space) of a query string part; malformed escapes are
+/// kept as they are
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| bytes[i] == b'%' && hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[i], escaped) {
+            (_, Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', None) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, None) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Compare in time independent of where they differ, so response times don't tell how
+/// much of a guessed token is right
+fn tokens_match(supplied: &str, expected: &str) -> bool {
+    let (supplied, expected) = (supplied.as_bytes(), expected.as_bytes());
+    supplied.len() == expected.len()
+        && supplied
+            .iter()
+            .zip(expected)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// The name in a `Host` header, without the port or the brackets around an IPv6 address
+fn host_name(host: &str) -> &str {
+    if let Some(rest) = host.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest);
+    }
+    match host.rsplit_once(':') {
+        Some((name, port)) if !name.contains(':') && port.bytes().all(|b| b.is_ascii_digit()) => {
+            name
+        }
+        _ => host,
+    }
+}
+
+/// Whether a `Host` header names this server: localhost, a loopback address or one of
+/// `hosts`. Bound to all interfaces, any IP address is accepted too: only domain names can
+/// be rebound, and clients on the LAN may use any of the machine's addresses.
+fn host_allowed(host: Option<&str>, hosts: &[String]) -> bool {
+    let Some(host) = host else {
+        return false;
+    };
+    let name = host_name(host).trim_end_matches('.').to_ascii_lowercase();
+    let mut all_interfaces = false;
+    for allowed in hosts {
+        let allowed = allowed.trim_start_matches('[').trim_end_matches(']');
+        if allowed.eq_ignore_ascii_case(&name) {
+            return true;
+        }
+        all_interfaces |= allowed
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_unspecified());
+    }
+    name == "localhost"
+        || name
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback() || all_interfaces)
+}
+
+fn query_param<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request
+        .query
+        .iter()
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.as_str())
+}
+
+fn route(request: &Request, provider: &StatsProvider, access: &Access) -> Response {
+    if !host_allowed(request.host.as_deref(), &access.hosts) {
+        return text_response("403 Forbidden", "Unknown host; see allowed_hosts");
+    }
+    if request.method != "GET" {
+        return text_response("405 Method Not Allowed", "Read-only: only GET is supported");
+    }
+
+    if let Some(expected) = access.token.as_deref() {
+        let supplied = request
+            .bearer
+            .as_deref()
+            .or_else(|| query_param(request, "token"));
+        if !supplied.is_some_and(|supplied| tokens_match(supplied, expected)) {
+            return text_response("401 Unauthorized", "Missing or invalid token");
+        }
+    }
+
+    match request.path.as_str() {
+        "/" | "/index.html" => Response {
+            status: "200 OK",
+            content_type: "text/html; charset=utf-8",
+            body: DASHBOARD_HTML.to_string(),
+        },
+        "/api/usage" => {
+            let period = query_param(request, "period").unwrap_or("today");
+            if !matches!(
+                period,
+                "current-window" | "today" | "week" | "month" | "all"
+            ) {
+                return text_response("400 Bad Request", "Unknown period");
+            }
+            match provider(period).and_then(|stats| {
+                serde_json::to_string(&stats).map_err(|e| format!("Failed to serialize: {e}"))
+            }) {
+                Ok(json) => Response {
+                    status: "200 OK",
+                    content_type: "application/json",
+                    body: json,
+                },
+                Err(e) => text_response("500 Internal Server Error", &e),
+            }
+        }
+        _ => text_response("404 Not Found", "Not found"),
+    }
+}
+
+fn text_response(status: &'static str, message: &str) -> Response {
+    Response {
+        status,
+        content_type: "text/plain; charset=utf-8",
+        body: message.to_string(),
+    }
+}
+
+const DASHBOARD_HTML: &str = r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Claude Code Usage</title>
+<style>
+  body { font-family: system-ui, sans-serif; background: #14141e; color: #e5e5e5; margin: 1.5rem; }
+  h1 { font-size: 1.2rem; }
+  .card { background: #1f1f2e; border-radius: 8px; padding: 0.75rem 1rem; margin-bottom: 0.75rem; }
+  .muted { color: #888; }
+  td { padding: 0.15rem 0.75rem 0.15rem 0; }
+</style>
+</head>
+<body>
+<h1>Claude Code Usage</h1>
+<div id="content" class="muted">Loading…</div>
+<script>
+const params = new URLSearchParams(location.search);
+const token = params.get("token");
+const period = params.get("period") || "today";
+async function load() {
+  const url = `/api/usage?period=${encodeURIComponent(period)}` + (token ? `&token=${encodeURIComponent(token)}` : "");
+  const el = document.getElementById("content");
+  try {
+    const res = await fetch(url);
+    if (!res.ok) { el.textContent = `Error ${res.status}: ${await res.text()}`; return; }
+    const s = await res.json();
+    const esc = (v) => String(v).replace(/[&<>"]/g, (c) => ({"&": "&amp;", "<": "&lt;", ">": "&gt;", '"': "&quot;"}[c]));
+    const sessions = s.active_sessions.map((a) =>
+      `<tr>${a.user ? `<td>${esc(a.user)}</td>` : ""}<td>${esc(a.project)}</td><td>${esc(a.model_display_name)}</td><td>${a.context_remaining_percent.toFixed(0)}%</td><td>${esc(a.display_cost || "$" + a.cost_usd.toFixed(2))}</td></tr>`).join("");
+    const users = (s.by_user || []).map((u) =>
+      `<tr><td>${esc(u.user)}</td><td>$${u.cost_usd.toFixed(2)}</td><td>${u.session_count} sessions</td><td>5hr ${(u.usage_percent || 0).toFixed(0)}% · week ${(u.week_usage_percent || 0).toFixed(0)}%</td></tr>`).join("");
+    el.className = "";
+    el.innerHTML = `
+      <div class="card">5hr window: <b>${s.quota.usage_percent.toFixed(1)}%</b> (${s.quota.messages_in_window}/${s.quota.estimated_limit})<br>
+      Week: <b>${s.quota.week_usage_percent.toFixed(1)}%</b></div>
+      <div class="card">${esc(period)}: <b>${esc(s.display_total_cost || "$" + s.total_cost_usd.toFixed(2))}</b> · ${esc(s.display_total_tokens)} tokens</div>
+      ${users ? `<div class="card"><table>${users}</table></div>` : ""}
+      <div class="card"><table>${sessions || '<tr><td class="muted">No active sessions</td></tr>'}</table></div>
+      <div class="muted">Updated ${esc(s.display_last_updated || s.last_updated)}</div>`;
+  } catch (e) {
+    el.textContent = `Error: ${e}`;
+  }
+}
+load();
+setInterval(load, 30000);
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hosts(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn request(raw: &str) -> Request {
+        read_request(raw.as_bytes()).unwrap()
+    }
+
+    fn provider() -> StatsProvider {
+        Arc::new(|_: &str| Err("No stats".to_string()))
+    }
+
+    #[test]
+    fn query_parts_are_percent_decoded() {
+        assert_eq!(percent_decode("a%20b+c"), "a b c");
+        assert_eq!(percent_decode("%2Btoken%3D"), "+token=");
+        assert_eq!(percent_decode("caf%C3%A9"), "café");
+        // Malformed escapes are kept
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%4"), "%zz%4");
+        assert_eq!(percent_decode(""), "");
+    }
+
+    #[test]
+    fn tokens_must_match_exactly() {
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secreT", "secret"));
+        assert!(!tokens_match("secret2", "secret"));
+        assert!(!tokens_match("secre", "secret"));
+        assert!(!tokens_match("", "secret"));
+    }
+
+    #[test]
+    fn requests_need_the_token_when_one_is_set() {
+        let access = Access {
+            hosts: hosts(&["127.0.0.1"]),
+            token: Some("s3cr=t".to_string()),
+        };
+        let status = |raw: &str| route(&request(raw), &provider(), &access).status;
+        assert_eq!(
+            status("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+            "401 Unauthorized"
+        );
+        assert_eq!(
+            status("GET /?token=s3cr HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+            "401 Unauthorized"
+        );
+        assert_eq!(
+            status("GET /?token=s3cr%3Dt HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+            "200 OK"
+        );
+        assert_eq!(
+            status("GET / HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer s3cr=t\r\n\r\n"),
+            "200 OK"
+        );
+        assert_eq!(
+            status("GET / HTTP/1.1\r\nHost: localhost\r\nAuthorization: Basic s3cr=t\r\n\r\n"),
+            "401 Unauthorized"
+        );
+        assert_eq!(
+            status("POST /nowhere HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+            "405 Method Not Allowed"
+        );
+        // Which paths exist isn't told without the token
+        assert_eq!(
+            status("GET /nowhere HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+            "401 Unauthorized"
+        );
+        assert!(read_request("\r\n".as_bytes()).is_none());
+    }
+
+    #[test]
+    fn hosts_must_name_this_server() {
+        let local = hosts(&["127.0.0.1"]);
+        for host in [
+            "localhost:8765",
+            "LOCALHOST",
+            "localhost.",
+            "127.0.0.1",
+            "[::1]:8765",
+        ] {
+            assert!(host_allowed(Some(host), &local), "{host}");
+        }
+        // A domain rebound to this machine, another address, or no Host at all
+        for host in [Some("evil.example:8765"), Some("192.168.1.20:8765"), None] {
+            assert!(!host_allowed(host, &local), "{host:?}");
+        }
+
+        // Bound to all interfaces: any address, and names from allowed_hosts
+        let lan = hosts(&["workstation.lan", "0.0.0.0"]);
+        for host in [
+            "192.168.1.20:8765",
+            "[fe80::1]:8765",
+            "Workstation.lan:8765",
+        ] {
+            assert!(host_allowed(Some(host), &lan), "{host}");
+        }
+        assert!(!host_allowed(Some("evil.example"), &lan));
+    }
+
+    #[test]
+    fn requests_to_other_hosts_are_refused() {
+        let access = Access {
+            hosts: hosts(&["127.0.0.1"]),
+            token: None,
+        };
+        let rebound = request("GET / HTTP/1.1\r\nHost: evil.example:8765\r\n\r\n");
+        assert_eq!(
+            route(&rebound, &provider(), &access).status,
+            "403 Forbidden"
+        );
+        let local = request("GET / HTTP/1.1\r\nhost: localhost:8765\r\n\r\n");
+        assert_eq!(route(&local, &provider(), &access).status, "200 OK");
+    }
+}
@@ -0,0 +1,225 @@
+//! Tray/status icon rendered from the current quota: an outer ring for the 5hr block, an
+//! inner ring for the week and the pulse glyph of the app logo, drawn in light or dark
+//! strokes to suit the panel (`AppConfig::tray_icon`).
+
+use image::{Rgba, RgbaImage};
+use std::f64::consts::TAU;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::PanelTheme;
+
+/// Rendered size in pixels; the panel scales it down
+pub const ICON_SIZE: u32 = 64;
+
+/// How long a detected panel theme is reused before asking the desktop again
+const THEME_CACHE: Duration = Duration::from_secs(60);
+
+static DETECTED_THEME: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
+
+/// Strokes on dark panels
+const LIGHT_STROKE: [u8; 3] = [240, 240, 240];
+/// Strokes on light panels
+const DARK_STROKE: [u8; 3] = [40, 40, 40];
+
+const GREEN: [u8; 3] = [74, 222, 128];
+const AMBER: [u8; 3] = [251, 191, 36];
+const RED: [u8; 3] = [248, 113, 113];
+
+/// Pulse glyph in the middle, as points in a 64 pixel icon
+const PULSE: [(f64, f64); 7] = [
+    (18.0, 32.0),
+    (26.0, 32.0),
+    (29.0, 24.0),
+    (33.0, 41.0),
+    (36.0, 28.0),
+    (38.5, 32.0),
+    (46.0, 32.0),
+];
+
+/// Whether the icon is drawn for a dark panel: the configured theme, or the desktop's when
+/// `Auto`
+pub fn dark_panel(theme: PanelTheme) -> bool {
+    match theme {
+        PanelTheme::Dark => true,
+        PanelTheme::Light => false,
+        PanelTheme::Auto => {
+            let mut cached = DETECTED_THEME.lock().unwrap_or_else(|e| e.into_inner());
+            match *cached {
+                Some((at, dark)) if at.elapsed() < THEME_CACHE => dark,
+                _ => {
+                    let dark = detect_dark_panel();
+                    *cached = Some((Instant::now(), dark));
+                    dark
+                }
+            }
+        }
+    }
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let mut command = Command::new(program);
+    command.args(args);
+    // Console programs like `reg` would flash a console window from the GUI app
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = command.output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Ask the desktop whether its panel is dark. Panels are dark unless the desktop says the
+/// theme is light, since most Linux panels and the Windows taskbar default to dark.
+fn detect_dark_panel() -> bool {
+    if cfg!(target_os = "macos") {
+        // Unset (an error) in light mode
+        return command_output("defaults", &["read", "-g", "AppleInterfaceStyle"])
+            .is_some_and(|style| style.eq_ignore_ascii_case("dark"));
+    }
+    if cfg!(target_os = "windows") {
+        let key = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize";
+        return command_output("reg", &["query", key, "/v", "SystemUsesLightTheme"])
+            .is_none_or(|output| !output.trim_end().ends_with("0x1"));
+    }
+    if let Some(scheme) = command_output(
+        "gsettings",
+        &["get", "org.gnome.desktop.interface", "color-scheme"],
+    ) {
+        if scheme.contains("prefer-light") {
+            return false;
+        }
+    }
+    !std::env::var("GTK_THEME").is_ok_and(|theme| theme.to_ascii_lowercase().contains("light"))
+}
+
+/// Ring color by how much of the allowance is used
+fn severity_color(percent: f64) -> [u8; 3] {
+    if percent >= 90.0 {
+        RED
+    } else if percent >= 70.0 {
+        AMBER
+    } else {
+        GREEN
+    }
+}
+
+/// Paint `color` over the pixel with coverage `alpha` (0-1)
+fn blend(img: &mut RgbaImage, x: u32, y: u32, color: [u8; 3], alpha: f64) {
+    if alpha <= 0.0 {
+        return;
+    }
+    let alpha = alpha.min(1.0);
+    let dst = img.get_pixel(x, y).0;
+    let dst_alpha = dst[3] as f64 / 255.0;
+    let out_alpha = alpha + dst_alpha * (1.0 - alpha);
+    let channel = |i: usize| {
+        let value =
+            (color[i] as f64 * alpha + dst[i] as f64 * dst_alpha * (1.0 - alpha)) / out_alpha;
+        value.round() as u8
+    };
+    img.put_pixel(
+        x,
+        y,
+        Rgba([
+            channel(0),
+            channel(1),
+            channel(2),
+            (out_alpha * 255.0).round() as u8,
+        ]),
+    );
+}
+
+/// Ring of radius `radius` and width `width` around the center: `percent` of it, clockwise
+/// from 12 o'clock, in `color`, the rest as a faint track in `track`
+fn draw_ring(
+    img: &mut RgbaImage,
+    radius: f64,
+    width: f64,
+    percent: f64,
+    color: [u8; 3],
+    track: [u8; 3],
+) {
+    let center = (img.width() as f64 - 1.0) / 2.0;
+    let filled = (percent / 100.0).clamp(0.0, 1.0);
+    for y in 0..img.height() {
+        for x in 0..img.width() {
+            let (dx, dy) = (x as f64 - center, y as f64 - center);
+            let distance = (dx * dx + dy * dy).sqrt();
+            let coverage = (width / 2.0 + 0.5 - (distance - radius).abs()).clamp(0.0, 1.0);
+            if coverage == 0.0 {
+                continue;
+            }
+            // Clockwise angle from 12 o'clock, as a share of the full turn
+            let angle = dx.atan2(-dy).rem_euclid(TAU) / TAU;
+            if angle <= filled {
+                blend(img, x, y, color, coverage);
+            } else {
+                blend(img, x, y, track, coverage * 0.25);
+            }
+        }
+    }
+}
+
+/// Distance from `p` to the segment `a`-`b`
+fn segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (abx, aby) = (b.0 - a.0, b.1 - a.1);
+    let length = abx * abx + aby * aby;
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * abx + (p.1 - a.1) * aby) / length).clamp(0.0, 1.0)
+    };
+    let (cx, cy) = (a.0 + t * abx - p.0, a.1 + t * aby - p.1);
+    (cx * cx + cy * cy).sqrt()
+}
+
+fn draw_pulse(img: &mut RgbaImage, color: [u8; 3], width: f64) {
+    let scale = img.width() as f64 / 64.0;
+    let points: Vec<(f64, f64)> = PULSE.iter().map(|(x, y)| (x * scale, y * scale)).collect();
+    for y in 0..img.height() {
+        for x in 0..img.width() {
+            let p = (x as f64, y as f64);
+            let distance = points
+                .windows(2)
+                .map(|segment| segment_distance(p, segment[0], segment[1]))
+                .fold(f64::INFINITY, f64::min);
+            blend(img, x, y, color, width / 2.0 + 0.5 - distance);
+        }
+    }
+}
+
+/// Icon of `ICON_SIZE` pixels showing the 5hr block and weekly usage
+pub fn render_usage_icon(window_percent: f64, week_percent: f64, dark_panel: bool) -> RgbaImage {
+    let stroke = if dark_panel {
+        LIGHT_STROKE
+    } else {
+        DARK_STROKE
+    };
+    let scale = ICON_SIZE as f64 / 64.0;
+    let mut img = RgbaImage::new(ICON_SIZE, ICON_SIZE);
+    draw_ring(
+        &mut img,
+        28.0 * scale,
+        5.0 * scale,
+        window_percent,
+        severity_color(window_percent),
+        stroke,
+    );
+    draw_ring(
+        &mut img,
+        20.5 * scale,
+        4.0 * scale,
+        week_percent,
+        severity_color(week_percent),
+        stroke,
+    );
+    draw_pulse(&mut img, stroke, 3.0 * scale);
+    img
+}
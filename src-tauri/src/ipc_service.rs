@@ -0,0 +1,320 @@
+//! Local IPC service for Windows and macOS, the counterpart of the Linux D-Bus service
+//! (`dbus_service`), so external integrations (status bar tools, scripts) work everywhere.
+//!
+//! Listens on the named pipe `\\.\pipe\cc-usage-widget` on Windows and on the Unix socket
+//! `cc-usage-widget.sock` in the config dir elsewhere. The protocol is one JSON object per
+//! line: requests are `{"method": "GetHourlySeries", "params": {"hours": 24}}`, responses
+//! `{"result": ...}` or `{"error": "..."}`. Methods mirror the D-Bus interface:
+//! `GetUsageSummary`, `GetHourlySeries`, `GetStatsJson`, `ShouldThrottle`, `Refresh` and
+//! `ShowWindow`, plus `GetInterfaceVersion` for its `InterfaceVersion` property. The endpoint
+//! doubles as the single-instance lock (see `forward_to_running_instance`).
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::crash::log_line;
+use crate::events;
+use crate::usage::{get_hourly_series, should_throttle, week_usage_summary, MAX_SERIES_HOURS};
+
+#[cfg(windows)]
+const PIPE_NAME: &str = r"\\.\pipe\cc-usage-widget";
+
+#[cfg(unix)]
+fn socket_path() -> std::path::PathBuf {
+    crate::config::config_dir().join("cc-usage-widget.sock")
+}
+
+/// Wait after a failed connection, doubled for each failure in a row up to `MAX_RETRY_DELAY`,
+/// so a persistent error doesn't spin the listen loop
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Request handling shared by all connections
+struct IpcService {
+    /// Cached (week_usage_percent, days_left) to avoid recomputing on every call
+    cache: Mutex<Option<(f64, u32)>>,
+    /// Used by `Refresh` and `ShowWindow` to reach the widget window; None in daemon mode
+    app: Option<AppHandle>,
+}
+
+impl IpcService {
+    fn update_cache(&self) {
+        let summary = week_usage_summary();
+        if let Ok(mut cache) = self.cache.lock() {
+            *cache = Some(summary);
+        }
+    }
+
+    fn handle(&self, line: &str) -> Value {
+        let request: Request = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return json!({ "error": format!("Invalid request: {e}") }),
+        };
+        match request.method.as_str() {
+            "GetUsageSummary" => {
+                let cached = self.cache.lock().ok().and_then(|cache| *cache);
+                let (percent, days_left) = cached.unwrap_or_else(week_usage_summary);
+                json!({ "result": [percent, days_left] })
+            }
+            "GetHourlySeries" => {
+                let hours = request
+                    .params
+                    .get("hours")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(24)
+                    .clamp(1, MAX_SERIES_HOURS as u64) as u32;
+                let series: Vec<(String, u64, f64)> = get_hourly_series(hours)
+                    .into_iter()
+                    .map(|bucket| (bucket.timestamp, bucket.tokens, bucket.cost_usd))
+                    .collect();
+                json!({ "result": series })
+            }
+            "GetStatsJson" => {
+                let period = request
+                    .params
+                    .get("period")
+                    .and_then(Value::as_str)
+                    .unwrap_or("today")
+                    .to_string();
+                // Runs on a blocking thread (see `serve_client`)
+                match tauri::async_runtime::block_on(crate::load_usage_json(period)) {
+                    Ok(stats) => json!({ "result": stats }),
+                    Err(e) => json!({ "error": e }),
+                }
+            }
+            "ShouldThrottle" => match should_throttle() {
+                Ok(advice) => {
+                    json!({ "result": [advice.action, advice.seconds_until_ok, advice.reason] })
+                }
+                Err(e) => json!({ "error": e }),
+            },
+            "GetInterfaceVersion" => json!({ "result": crate::INTERFACE_VERSION }),
+            "Refresh" => {
+                crate::state::get().invalidate();
+                self.update_cache();
+                if let Some(ref app) = self.app {
+                    events::emit(app, "usage-updated", ());
+                }
+                json!({ "result": null })
+            }
+            "ShowWindow" => match self.app.as_ref() {
+                Some(app) => match crate::tray::show_widget(app) {
+                    Ok(()) => json!({ "result": null }),
+                    Err(e) => json!({ "error": e }),
+                },
+                None => json!({ "error": "Widget window not found" }),
+            },
+            other => json!({ "error": format!("Unknown method: {other}") }),
+        }
+    }
+}
+
+/// Handle to the running IPC service for updating its cache
+#[derive(Clone)]
+pub struct IpcServiceHandle {
+    service: Arc<IpcService>,
+}
+
+impl IpcServiceHandle {
+    /// Notify the IPC service that usage data has changed
+    pub fn notify_usage_changed(&self) {
+        self.service.update_cache();
+    }
+}
+
+async fn serve_client<S: AsyncRead + AsyncWrite>(stream: S, service: Arc<IpcService>) {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let service = Arc::clone(&service);
+        let response = tokio::task::spawn_blocking(move || service.handle(&line))
+            .await
+            .unwrap_or_else(|e| json!({ "error": format!("Task join error: {e}") }));
+        let mut response = response.to_string();
+        response.push('\n');
+        if writer.write_all(response.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(windows)]
+async fn listen(service: Arc<IpcService>, ready: impl FnOnce(bool)) {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let mut server = match ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(PIPE_NAME)
+    {
+        Ok(server) => server,
+        Err(e) => {
+            log_line!("Failed to create named pipe {PIPE_NAME}: {e}");
+            ready(false);
+            return;
+        }
+    };
+    ready(true);
+    let mut delay = RETRY_DELAY;
+    loop {
+        if let Err(e) = server.connect().await {
+            log_line!("Named pipe connection failed: {e}");
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RETRY_DELAY);
+            continue;
+        }
+        delay = RETRY_DELAY;
+        // A new instance must exist before the connected one is handed off
+        let next = match ServerOptions::new().create(PIPE_NAME) {
+            Ok(next) => next,
+            Err(e) => {
+                log_line!("Failed to create named pipe {PIPE_NAME}: {e}");
+                return;
+            }
+        };
+        let connected = std::mem::replace(&mut server, next);
+        tokio::spawn(serve_client(connected, Arc::clone(&service)));
+    }
+}
+
+#[cfg(unix)]
+async fn listen(service: Arc<IpcService>, ready: impl FnOnce(bool)) {
+    use tokio::net::UnixListener;
+
+    let path = socket_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    // Left behind by a previous run that didn't exit cleanly (a live instance would have
+    // been found by `forward_to_running_instance`)
+    let _ = std::fs::remove_file(&path);
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log_line!("Failed to listen on {}: {e}", path.display());
+            ready(false);
+            return;
+        }
+    };
+    ready(true);
+    let mut delay = RETRY_DELAY;
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                delay = RETRY_DELAY;
+                tokio::spawn(serve_client(stream, Arc::clone(&service)));
+            }
+            Err(e) => {
+                // e.g. out of file descriptors; accepting again right away fails the same way
+                log_line!("Socket connection failed: {e}");
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+}
+
+/// Spawn the IPC service on a dedicated thread with its own tokio runtime.
+/// `app` is None in daemon mode, where there is no window to refresh or show.
+pub fn spawn_ipc_service(app: Option<AppHandle>) -> Option<IpcServiceHandle> {
+    use std::sync::mpsc;
+    use std::thread;
+
+    let service = Arc::new(IpcService {
+        cache: Mutex::new(None),
+        app,
+    });
+    let handle = IpcServiceHandle {
+        service: Arc::clone(&service),
+    };
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let rt = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(rt) => rt,
+            Err(e) => {
+                log_line!("Failed to create IPC runtime: {:?}", e);
+                let _ = tx.send(false);
+                return;
+            }
+        };
+        service.update_cache();
+        rt.block_on(listen(service, |listening| {
+            let _ = tx.send(listening);
+        }));
+    });
+
+    // Wait for the service to start (with short timeout to avoid blocking app startup)
+    match rx.recv_timeout(std::time::Duration::from_millis(500)) {
+        Ok(true) => Some(handle),
+        _ => None,
+    }
+}
+
+/// Send one request to a running instance and return its response line
+async fn call_running_instance(request: &str) -> Option<String> {
+    #[cfg(windows)]
+    let stream = tokio::net::windows::named_pipe::ClientOptions::new()
+        .open(PIPE_NAME)
+        .ok()?;
+    #[cfg(unix)]
+    let stream = tokio::net::UnixStream::connect(socket_path()).await.ok()?;
+
+    let (reader, mut writer) = tokio::io::split(stream);
+    writer
+        .write_all(format!("{request}\n").as_bytes())
+        .await
+        .ok()?;
+    BufReader::new(reader).lines().next_line().await.ok()?
+}
+
+/// Single instance: if another copy is already listening, ask it to show its window (when
+/// `show_window`) and return true, so this process exits instead of starting a second
+/// watcher. False when no instance is running.
+pub fn forward_to_running_instance(show_window: bool) -> bool {
+    let rt = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(rt) => rt,
+        Err(_) => return false,
+    };
+
+    rt.block_on(async {
+        let request = if show_window {
+            r#"{"method": "ShowWindow"}"#
+        } else {
+            r#"{"method": "GetUsageSummary"}"#
+        };
+        let response = match call_running_instance(request).await {
+            Some(response) => response,
+            None => return false,
+        };
+        log_line!("Another instance is already running; forwarding to it");
+        // Fails when the running instance is a headless daemon with no window
+        if let Some(error) = serde_json::from_str::<Value>(&response)
+            .ok()
+            .and_then(|value| value.get("error").cloned())
+        {
+            log_line!("Failed to show the running instance's window: {error}");
+        }
+        true
+    })
+}
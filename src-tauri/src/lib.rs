@@ -1,86 +1,1205 @@
+mod alerts;
 mod api;
+mod app_info;
 mod config;
+mod cost_guard;
+mod crash;
+mod daemon;
 #[cfg(target_os = "linux")]
 mod dbus_service;
+mod debounce;
+mod email_report;
+mod entry_stream;
+mod events;
+mod export;
+mod format;
+mod goals;
+mod http_server;
+mod icon;
+#[cfg(not(target_os = "linux"))]
+mod ipc_service;
+mod limits;
+mod notifications;
+mod otlp_receiver;
+#[cfg(target_os = "linux")]
+mod portal;
+mod privacy;
+mod processes;
+mod renderer;
+mod scheduler;
+#[cfg(feature = "simulate")]
+mod simulate;
+mod state;
+mod store;
+mod summary;
+mod terminal;
+mod tray;
+mod updates;
 mod usage;
+mod watcher;
+
+use crash::log_line;
+use std::collections::HashMap;
+use std::sync::mpsc::{channel, sync_channel, SyncSender};
+use std::sync::Arc;
+use std::thread;
+use tauri::image::Image;
+use tauri::Manager;
+use usage::{get_claude_data_dirs, get_data_dir_report, DataDirCandidate, UsageStats};
+
+#[cfg(target_os = "linux")]
+use dbus_service::DbusServiceHandle;
+#[cfg(not(target_os = "linux"))]
+use ipc_service::IpcServiceHandle;
+
+
+/// Async command to fetch usage stats.
+/// If an Admin API key is configured, fetches from the API first with local fallback.
+/// File I/O is offloaded to a blocking thread pool.
+#[tauri::command]
+async fn get_usage(
+    state: tauri::State<'_, Arc<state::AppState>>,
+    period: String,
+) -> Result<UsageStats, String> {
+    load_usage(&state, period).await
+}
+
+/// Revision of the D-Bus interface and its IPC counterpart, for feature detection by clients;
+/// incremented when methods or signals are added
+const INTERFACE_VERSION: u32 = 4;
+
+/// `load_usage` serialized for D-Bus and IPC clients (`GetStatsJson`)
+async fn load_usage_json(period: String) -> Result<String, String> {
+    if !matches!(
+        period.as_str(),
+        "current-window" | "today" | "week" | "month" | "all"
+    ) {
+        return Err(format!("Unknown period '{period}'"));
+    }
+    let stats = load_usage(state::get(), period).await?;
+    serde_json::to_string(&stats).map_err(|e| format!("Failed to serialize: {e}"))
+}
+
+/// Fetch stats (API or local), fill display strings and refresh the summary file; shared by
+/// commands, the D-Bus/IPC services, the HTTP dashboard and daemon mode
+async fn load_usage(state: &Arc<state::AppState>, period: String) -> Result<UsageStats, String> {
+    let _perf = usage::PerfSpan::start("load_usage");
+    let cfg = state.config();
+    let mut stats = fetch_usage(state, &cfg, period.clone()).await?;
+    let goals = load_goals(state, &cfg).await;
+    let processes = load_processes().await;
+    finish_stats(&mut stats, &cfg, goals, &processes);
+    if cfg.summary_file.enabled {
+        if let Err(e) = summary::write_summary(&cfg.summary_file, &stats, &period) {
+            log_line!("Failed to write summary file: {e}");
+        }
+    }
+    Ok(stats)
+}
+
+/// Stats for several periods in one call (e.g. all of the widget's period tabs), keyed by
+/// period. Local journals are parsed once for all of them. The first period is the one
+/// displayed and refreshes the summary file.
+#[tauri::command]
+async fn get_usage_multi(
+    state: tauri::State<'_, Arc<state::AppState>>,
+    periods: Vec<String>,
+) -> Result<HashMap<String, UsageStats>, String> {
+    let _perf = usage::PerfSpan::start("load_usage");
+    let cfg = state.config();
+    let displayed = periods.first().cloned().unwrap_or_default();
+    let mut all = fetch_usage_multi(&state, &cfg, periods).await?;
+    let goals = load_goals(&state, &cfg).await;
+    let processes = load_processes().await;
+    for stats in all.values_mut() {
+        finish_stats(stats, &cfg, goals.clone(), &processes);
+    }
+    if let (true, Some(stats)) = (cfg.summary_file.enabled, all.get(&displayed)) {
+        if let Err(e) = summary::write_summary(&cfg.summary_file, stats, &displayed) {
+            log_line!("Failed to write summary file: {e}");
+        }
+    }
+    Ok(all)
+}
+
+/// Goal statuses, computed off the async runtime once per journal or settings change; empty
+/// without configured goals
+async fn load_goals(
+    state: &Arc<state::AppState>,
+    cfg: &config::AppConfig,
+) -> Vec<usage::GoalStatus> {
+    if cfg.goals.is_empty() {
+        return Vec::new();
+    }
+    let goals = cfg.goals.clone();
+    let state = state.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        state.cached("goals", || goals::goal_statuses(&goals))
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Running Claude Code processes, scanned off the async runtime
+async fn load_processes() -> Vec<processes::ClaudeProcess> {
+    tauri::async_runtime::spawn_blocking(processes::running_claude_processes)
+        .await
+        .unwrap_or_default()
+}
+
+/// Add goals, running processes, display strings and privacy pseudonyms to fetched stats
+fn finish_stats(
+    stats: &mut UsageStats,
+    cfg: &config::AppConfig,
+    goals: Vec<usage::GoalStatus>,
+    processes: &[processes::ClaudeProcess],
+) {
+    stats.goals = goals;
+    // Before privacy mode replaces the directories matched against
+    processes::annotate_sessions(&mut stats.active_sessions, processes);
+    format::apply_format_prefs(stats, &cfg.format);
+    if cfg.privacy_mode {
+        privacy::apply_to_stats(stats);
+    }
+}
+
+async fn fetch_usage(
+    state: &Arc<state::AppState>,
+    cfg: &config::AppConfig,
+    period: String,
+) -> Result<UsageStats, String> {
+    fetch_usage_multi(state, cfg, vec![period.clone()])
+        .await?
+        .remove(&period)
+        .ok_or_else(|| format!("No stats computed for {period}"))
+}
+
+async fn fetch_usage_multi(
+    state: &Arc<state::AppState>,
+    cfg: &config::AppConfig,
+    periods: Vec<String>,
+) -> Result<HashMap<String, UsageStats>, String> {
+    #[cfg(feature = "simulate")]
+    if let Some(simulated) = simulate::simulated_usage(&periods) {
+        return Ok(simulated.into_iter().collect());
+    }
+
+    // Check if API keys are configured
+    let api_keys = config::usable_api_keys(cfg);
+    if !api_keys.is_empty() {
+        match get_usage_from_api(&api_keys, &cfg.api_workspace_ids, &cfg.team_seats).await {
+            // API stats cover a fixed range whatever the period
+            Ok(stats) => {
+                return Ok(periods
+                    .into_iter()
+                    .map(|period| (period, stats.clone()))
+                    .collect())
+            }
+            Err(e) => {
+                log_line!("API fetch failed, falling back to local: {e}");
+                events::warning(&format!("API fetch failed, falling back to local: {e}"));
+            }
+        }
+    }
+
+    // Fall back to local JSONL parsing, reusing stats computed for another caller
+    let state = state.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || state.local_usage(&periods))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?;
+
+    // Inside Flatpak an empty result usually means ~/.claude hasn't been shared yet
+    #[cfg(target_os = "linux")]
+    if result.is_err() && portal::is_flatpak() && get_claude_data_dirs().is_empty() {
+        return result.map_err(|e| {
+            format!("{e}. Running inside Flatpak: grant access to ~/.claude with request_data_dir_access")
+        });
+    }
+
+    result
+}
+
+async fn get_usage_from_api(
+    api_keys: &[String],
+    workspace_ids: &[String],
+    team_seats: &config::TeamSeats,
+) -> Result<UsageStats, String> {
+    let client = api::AdminApiClient::with_keys(api_keys)?;
+    api::build_usage_stats_from_api(&client, workspace_ids, team_seats).await
+}
+
+/// Stop in-flight local usage computations; they return partial results marked `truncated`.
+/// Returns how many were running.
+#[tauri::command]
+fn cancel_usage() -> usize {
+    usage::cancel_usage_computations()
+}
+
+#[tauri::command]
+async fn set_api_key(key: String) -> Result<String, String> {
+    config::update_config(|cfg| {
+        cfg.admin_api_key = Some(key.clone());
+    })?;
+    Ok(config::mask_api_key(&key))
+}
+
+/// Days before a rotation key's `valid_until` at which `get_api_key_status` starts warning
+const API_KEY_EXPIRY_WARNING_DAYS: i64 = 7;
+
+#[tauri::command]
+fn get_api_key_status() -> HashMap<String, String> {
+    let cfg = config::load_config();
+    let mut status = HashMap::new();
+    match cfg.admin_api_key {
+        Some(ref key) if !key.is_empty() => {
+            status.insert("configured".to_string(), "true".to_string());
+            status.insert("masked_key".to_string(), config::mask_api_key(key));
+        }
+        _ => {
+            let configured = !config::usable_api_keys(&cfg).is_empty();
+            status.insert("configured".to_string(), configured.to_string());
+        }
+    }
+
+    // Key rotation: which key is in use and which keys expire soon
+    status.insert(
+        "usable_keys".to_string(),
+        config::usable_api_keys(&cfg).len().to_string(),
+    );
+    if let Some(active) = api::active_api_key() {
+        status.insert("active_key".to_string(), config::mask_api_key(&active));
+    }
+    let now = chrono::Utc::now();
+    let warnings: Vec<String> = cfg
+        .admin_api_keys
+        .iter()
+        .filter_map(|entry| {
+            let until = entry.valid_until_at()?;
+            let name = entry
+                .label
+                .clone()
+                .unwrap_or_else(|| config::mask_api_key(&entry.key));
+            if until <= now {
+                Some(format!("{name} expired on {}", until.format("%Y-%m-%d")))
+            } else if until - now <= chrono::Duration::days(API_KEY_EXPIRY_WARNING_DAYS) {
+                Some(format!("{name} expires on {}", until.format("%Y-%m-%d")))
+            } else {
+                None
+            }
+        })
+        .collect();
+    if !warnings.is_empty() {
+        status.insert("expiry_warning".to_string(), warnings.join("; "));
+    }
+    status
+}
+
+/// Workspaces in the organization of the configured Admin API key
+#[tauri::command]
+async fn list_workspaces() -> Result<Vec<api::Workspace>, String> {
+    let cfg = config::load_config();
+    let client = api::AdminApiClient::with_keys(&config::usable_api_keys(&cfg))?;
+    client.list_workspaces().await
+}
+
+#[tauri::command]
+fn get_selected_workspaces() -> Vec<String> {
+    config::load_config().api_workspace_ids
+}
+
+/// Limit API-mode stats to these workspace ids; an empty list includes all workspaces
+#[tauri::command]
+async fn set_selected_workspaces(workspace_ids: Vec<String>) -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.api_workspace_ids = workspace_ids
+            .into_iter()
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .collect();
+    })
+}
+
+/// Replace the rotation keys tried after the primary key
+#[tauri::command]
+async fn set_api_keys(keys: Vec<config::ApiKeyEntry>) -> Result<usize, String> {
+    config::update_config(|cfg| {
+        cfg.admin_api_keys = keys;
+        config::usable_api_keys(cfg).len()
+    })
+}
+
+#[tauri::command]
+async fn clear_api_key() -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.admin_api_key = None;
+    })
+}
+
+#[tauri::command]
+async fn validate_api_key(key: String) -> Result<(), String> {
+    let client = api::AdminApiClient::new(&key)?;
+    client.validate().await
+}
+
+#[tauri::command]
+fn get_format_prefs() -> config::FormatPrefs {
+    config::load_config().format
+}
+
+#[tauri::command]
+async fn set_format_prefs(prefs: config::FormatPrefs) -> Result<(), String> {
+    if prefs.cost_decimals > 6 {
+        return Err("cost_decimals must be between 0 and 6".to_string());
+    }
+    config::update_config(|cfg| {
+        cfg.format = prefs;
+    })
+}
+
+#[tauri::command]
+fn get_notification_prefs() -> config::NotificationPrefs {
+    config::load_config().notifications
+}
+
+#[tauri::command]
+async fn set_notification_prefs(
+    app: tauri::AppHandle,
+    prefs: config::NotificationPrefs,
+) -> Result<(), String> {
+    if let Some(kind) = prefs
+        .sounds
+        .keys()
+        .find(|kind| !notifications::KINDS.contains(&kind.as_str()))
+    {
+        return Err(format!("Unknown notification kind: {kind}"));
+    }
+    if let Some(ref quiet) = prefs.quiet_hours {
+        for value in [&quiet.start, &quiet.end] {
+            chrono::NaiveTime::parse_from_str(value, "%H:%M")
+                .map_err(|_| format!("Invalid quiet hours time '{value}', expected HH:MM"))?;
+        }
+    }
+    config::update_config(|cfg| {
+        cfg.notifications = prefs;
+    })?;
+    tray::sync_menu(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn show_widget(app: tauri::AppHandle) -> Result<(), String> {
+    tray::show_widget(&app)
+}
+
+/// Make the widget reload its stats, as after a journal change
+#[tauri::command]
+fn refresh_now(app: tauri::AppHandle) {
+    tray::refresh_now(&app);
+}
+
+#[tauri::command]
+fn set_notifications_paused(app: tauri::AppHandle, paused: bool) -> Result<(), String> {
+    tray::set_notifications_paused(&app, paused)
+}
+
+#[tauri::command]
+fn get_display_period() -> String {
+    tray::display_period()
+}
+
+/// Switch the period shown by the widget ("current-window", "today", "week", "month" or "all")
+#[tauri::command]
+fn set_display_period(app: tauri::AppHandle, period: String) -> Result<(), String> {
+    tray::set_display_period(&app, &period)
+}
+
+#[tauri::command]
+fn quit_app(app: tauri::AppHandle) {
+    app.exit(0);
+}
+
+#[tauri::command]
+fn get_goals() -> Vec<config::GoalConfig> {
+    config::load_config().goals
+}
+
+/// Replace the usage goals. Streak history is kept per goal id and restarts when a goal's
+/// target changes.
+#[tauri::command]
+async fn set_goals(goals: Vec<config::GoalConfig>) -> Result<(), String> {
+    goals::validate(&goals)?;
+    config::update_config(|cfg| {
+        cfg.goals = goals;
+    })
+}
+
+#[tauri::command]
+fn get_alert_rules() -> Vec<alerts::AlertRule> {
+    config::load_config().alerts
+}
+
+/// Replace the user-defined alert rules evaluated by the scheduler
+#[tauri::command]
+async fn set_alert_rules(rules: Vec<alerts::AlertRule>) -> Result<(), String> {
+    alerts::validate_rules(&rules)?;
+    config::update_config(|cfg| {
+        cfg.alerts = rules;
+    })
+}
+
+/// Current value of every enabled alert rule's metric and whether it is firing
+#[tauri::command]
+async fn get_alert_status() -> Result<Vec<alerts::AlertStatus>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        alerts::alert_status(&config::load_config(), &scheduler::firing_alerts())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))
+}
+
+/// Send a sample notification of `kind` with its configured sound, so the setup can be
+/// checked. Fails with the reason when notifications are muted or in quiet hours.
+#[tauri::command]
+fn test_notification(app: tauri::AppHandle, kind: String) -> Result<(), String> {
+    if !notifications::KINDS.contains(&kind.as_str()) {
+        return Err(format!("Unknown notification kind: {kind}"));
+    }
+    let prefs = config::load_config().notifications;
+    if let Some(reason) = notifications::suppressed_reason(&prefs) {
+        return Err(format!("Not shown: {reason}"));
+    }
+    notifications::show(
+        &app,
+        &prefs,
+        &kind,
+        "CC Usage Widget test notification",
+        &format!(
+            "This is how {} alerts will look and sound",
+            kind.replace('_', " ")
+        ),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+fn get_team_baseline() -> config::TeamBaseline {
+    config::load_config().team_baseline
+}
+
+#[tauri::command]
+async fn set_team_baseline(baseline: config::TeamBaseline) -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.team_baseline = baseline;
+    })
+}
+
+#[tauri::command]
+fn get_team_seats() -> config::TeamSeats {
+    config::load_config().team_seats
+}
+
+/// Largest seat count `set_team_seats` accepts
+const MAX_TEAM_SEATS: u32 = 100_000;
+
+/// Configure the seat count and per-seat weekly allowances for the org utilization summary
+#[tauri::command]
+async fn set_team_seats(seats: config::TeamSeats) -> Result<(), String> {
+    if seats.enabled && !(1..=MAX_TEAM_SEATS).contains(&seats.seat_count) {
+        return Err(format!("Seat count must be 1 to {MAX_TEAM_SEATS}"));
+    }
+    if seats.per_seat_weekly_tokens == Some(0) {
+        return Err("Per-seat tokens must be at least 1".to_string());
+    }
+    if seats
+        .per_seat_weekly_cost_usd
+        .is_some_and(|cost| !cost.is_finite() || cost < 0.0)
+    {
+        return Err("Per-seat cost must be finite and non-negative".to_string());
+    }
+    config::update_config(|cfg| {
+        cfg.team_seats = seats;
+    })
+}
+
+/// The quota estimation model: weight table, limits and the exact formula
+#[tauri::command]
+fn get_quota_model() -> usage::QuotaModel {
+    usage::quota_model()
+}
+
+/// Replace the per-model quota weight table; `None` restores the built-in weights
+#[tauri::command]
+async fn set_quota_weights(weights: Option<usage::QuotaWeights>) -> Result<(), String> {
+    if let Some(ref w) = weights {
+        let invalid = std::iter::once(w.default_weight)
+            .chain(w.rules.iter().map(|rule| rule.weight))
+            .any(|weight| !weight.is_finite() || weight < 0.0);
+        if invalid {
+            return Err("Quota weights must be finite and non-negative".to_string());
+        }
+    }
+    config::update_config(|cfg| {
+        cfg.quota_weights = weights;
+    })
+}
+
+#[tauri::command]
+fn get_quota_windows() -> Vec<usage::QuotaWindowDef> {
+    config::load_config().quota_windows
+}
+
+/// Replace the custom quota windows; an empty list leaves only the built-in 5hr block and week
+#[tauri::command]
+async fn set_quota_windows(windows: Vec<usage::QuotaWindowDef>) -> Result<(), String> {
+    for (i, window) in windows.iter().enumerate() {
+        if window.name.trim().is_empty() {
+            return Err("Quota windows need a name".to_string());
+        }
+        if windows[..i].iter().any(|other| other.name == window.name) {
+            return Err(format!("Duplicate quota window '{}'", window.name));
+        }
+        if window.window_minutes == 0 || !window.limit.is_finite() || window.limit <= 0.0 {
+            return Err(format!(
+                "Quota window '{}' needs a positive length and limit",
+                window.name
+            ));
+        }
+    }
+    config::update_config(|cfg| {
+        cfg.quota_windows = windows;
+    })
+}
+
+#[tauri::command]
+fn get_provider_pricing() -> HashMap<String, usage::PricingProfile> {
+    usage::provider_pricing()
+}
+
+/// Replace the pricing profiles by provider ("bedrock", "vertex"); an empty map prices
+/// everything at Anthropic API rates
+#[tauri::command]
+async fn set_provider_pricing(
+    profiles: HashMap<String, usage::PricingProfile>,
+) -> Result<(), String> {
+    let invalid = profiles.values().any(|profile| {
+        std::iter::once(profile.multiplier)
+            .chain(
+                profile
+                    .models
+                    .iter()
+                    .flat_map(|m| [m.input, m.output, m.cache_write, m.cache_read]),
+            )
+            .any(|price| !price.is_finite() || price < 0.0)
+    });
+    if invalid {
+        return Err("Prices and multipliers must be finite and non-negative".to_string());
+    }
+    config::update_config(|cfg| {
+        cfg.provider_pricing = profiles;
+    })
+}
+
+/// Suggestions for cutting costs based on the last `days` (default 7) of local usage
+#[tauri::command]
+async fn get_optimization_hints(days: Option<u32>) -> Result<Vec<usage::OptimizationHint>, String> {
+    let days = days.unwrap_or(7).max(1);
+    tauri::async_runtime::spawn_blocking(move || usage::get_optimization_hints(days))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))
+}
+
+/// API errors and interrupted requests of the last `days` (default 7) local days, with the
+/// tokens they wasted, per day and per session
+#[tauri::command]
+async fn get_failed_requests(
+    state: tauri::State<'_, Arc<state::AppState>>,
+    days: Option<u32>,
+) -> Result<usage::FailedRequestReport, String> {
+    let days = days.unwrap_or(7).clamp(1, 90);
+    let shared = state.inner().clone();
+    let mut report = tauri::async_runtime::spawn_blocking(move || {
+        shared.cached(&format!("failed_requests:{days}"), || {
+            usage::get_failed_requests(days)
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
+    if state.config().privacy_mode {
+        privacy::apply_to_failed_requests(&mut report);
+    }
+    Ok(report)
+}
+
+/// Whether `prompts_needed` prompts of `model` (e.g. "opus") fit the 5hr window and the week
+/// left, and the earliest time they would if not
+#[tauri::command]
+async fn plan_usage(prompts_needed: u32, model: String) -> Result<usage::UsagePlan, String> {
+    tauri::async_runtime::spawn_blocking(move || usage::plan_usage(prompts_needed, &model))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))
+}
+
+/// Tokens and cost per hour for the last `hours` (default 24, at most a week) hours
+#[tauri::command]
+async fn get_hourly_series(hours: Option<u32>) -> Result<Vec<usage::HourlyBucket>, String> {
+    let hours = hours.unwrap_or(24).clamp(1, 24 * 7);
+    tauri::async_runtime::spawn_blocking(move || usage::get_hourly_series(hours))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))
+}
+
+/// Today so far against yesterday, with a one-line summary for the header
+#[tauri::command]
+async fn get_daily_delta() -> Result<usage::DailyDelta, String> {
+    let today = chrono::Local::now().date_naive();
+    let mut delta = tauri::async_runtime::spawn_blocking(move || usage::compute_daily_delta(today))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?;
+    if config::load_config().privacy_mode {
+        privacy::apply_to_daily_delta(&mut delta);
+    }
+    Ok(delta)
+}
+
+/// Usage in `period_a` against `period_b` (e.g. "this_week" vs "last_week"), per model and
+/// per project
+#[tauri::command]
+async fn compare_usage(
+    period_a: String,
+    period_b: String,
+) -> Result<usage::UsageComparison, String> {
+    let mut comparison =
+        tauri::async_runtime::spawn_blocking(move || usage::compare_usage(&period_a, &period_b))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??;
+    if config::load_config().privacy_mode {
+        privacy::apply_to_comparison(&mut comparison);
+    }
+    Ok(comparison)
+}
+
+/// Search all local sessions by title/project/path, with optional filters
+#[tauri::command]
+async fn search_sessions(
+    state: tauri::State<'_, Arc<state::AppState>>,
+    query: String,
+    filters: Option<usage::SessionFilters>,
+) -> Result<Vec<usage::SessionMatch>, String> {
+    let filters = filters.unwrap_or_default();
+    let shared = state.inner().clone();
+    let mut sessions = tauri::async_runtime::spawn_blocking(move || {
+        // Sessions are summarized once per journal change; each query only ranks them
+        let max_age_hours = usage::search_max_age_hours(&filters);
+        let summaries = shared.cached(&format!("search:{max_age_hours:?}"), || {
+            Arc::new(usage::session_summaries(max_age_hours))
+        });
+        usage::rank_summaries(&summaries, &query, &filters)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
+    if state.config().privacy_mode {
+        privacy::apply_to_session_matches(&mut sessions);
+    }
+    Ok(sessions)
+}
+
+#[tauri::command]
+fn get_excluded_sessions() -> Vec<String> {
+    config::load_config().excluded_sessions
+}
+
+/// Leave a session out of cost totals, quota and activity without touching its journal. Takes
+/// a full session id or a prefix of at least 8 characters.
+#[tauri::command]
+async fn exclude_session(session_id: String) -> Result<(), String> {
+    let id = session_id.trim().to_lowercase();
+    if !usage::valid_session_prefix(&id) {
+        return Err(format!(
+            "Invalid session id '{session_id}': use the full id or at least {} characters",
+            usage::MIN_SESSION_PREFIX
+        ));
+    }
+    config::update_config(|cfg| {
+        if !cfg.excluded_sessions.contains(&id) {
+            cfg.excluded_sessions.push(id);
+        }
+    })
+}
+
+/// Count a previously excluded session again; removes every exclusion that matches it
+#[tauri::command]
+async fn include_session(session_id: String) -> Result<(), String> {
+    let id = session_id.trim().to_lowercase();
+    if !usage::valid_session_prefix(&id) {
+        return Err(format!("Invalid session id '{session_id}'"));
+    }
+    config::update_config(|cfg| {
+        cfg.excluded_sessions
+            .retain(|excluded| !(id.starts_with(excluded.as_str()) || excluded.starts_with(&id)));
+    })
+}
+
+/// Context size after each assistant message of an active session, with compactions and the
+/// auto-compaction forecast; `session_id` may be the 8-character id of an active session
+#[tauri::command]
+async fn get_session_context_history(
+    session_id: String,
+) -> Result<usage::SessionContextHistory, String> {
+    tauri::async_runtime::spawn_blocking(move || usage::session_context_history(&session_id))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Open a terminal in a session's working directory; with `resume`, run `claude --resume` in it
+/// when `terminal.resume_args` is configured
+#[tauri::command]
+async fn open_session_in_terminal(
+    session_id: String,
+    resume: Option<bool>,
+) -> Result<terminal::TerminalLaunch, String> {
+    let (session_id, dir) =
+        tauri::async_runtime::spawn_blocking(move || usage::session_directory(&session_id))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??;
+    let settings = config::load_config().terminal;
+    let resume = resume.unwrap_or(false);
+    let launch = terminal::open_in_terminal(&settings, &dir, &session_id, resume)?;
+    log_line!(
+        "Opened a terminal for session {session_id} in {}",
+        launch.directory
+    );
+    Ok(launch)
+}
+
+/// First step of the emergency stop for a runaway session: find its Claude Code process and
+/// get a confirmation token for `terminate_session_process`. Requires
+/// `allow_session_termination`.
+#[tauri::command]
+async fn request_session_termination(
+    session_id: String,
+) -> Result<processes::TerminationRequest, String> {
+    if !config::load_config().allow_session_termination {
+        return Err("Session termination is disabled (allow_session_termination)".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || processes::request_termination(&session_id))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Send SIGTERM (or SIGINT with `signal: "int"`) to a session's Claude Code process, confirmed
+/// with a token from `request_session_termination`. Returns the pid.
+#[tauri::command]
+async fn terminate_session_process(
+    session_id: String,
+    confirmation_token: String,
+    signal: Option<String>,
+) -> Result<u32, String> {
+    if !config::load_config().allow_session_termination {
+        return Err("Session termination is disabled (allow_session_termination)".to_string());
+    }
+    let id = session_id.clone();
+    let signal = signal.unwrap_or_else(|| "term".to_string());
+    let pid = tauri::async_runtime::spawn_blocking(move || {
+        processes::terminate_session(&id, &confirmation_token, &signal)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))??;
+    log_line!("Terminated Claude Code process {pid} of session {session_id}");
+    Ok(pid)
+}
+
+/// Project x week cost matrix for the last `weeks` (default 12) weeks
+#[tauri::command]
+async fn get_project_week_matrix(weeks: Option<u32>) -> Result<usage::ProjectWeekMatrix, String> {
+    let weeks = weeks.unwrap_or(12).clamp(1, 52);
+    let mut matrix =
+        tauri::async_runtime::spawn_blocking(move || usage::get_project_week_matrix(weeks))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?;
+    if config::load_config().privacy_mode {
+        privacy::apply_to_project_weeks(&mut matrix);
+    }
+    Ok(matrix)
+}
+
+/// Weekly cost and tokens of the top `top` (default 5) projects plus "other" for the last
+/// `weeks` (default 12) weeks
+#[tauri::command]
+async fn get_weekly_project_series(
+    weeks: Option<u32>,
+    top: Option<usize>,
+) -> Result<usage::WeeklyProjectSeries, String> {
+    let weeks = weeks.unwrap_or(12).clamp(1, 52);
+    let top = top.unwrap_or(5).clamp(1, 20);
+    let mut series =
+        tauri::async_runtime::spawn_blocking(move || usage::get_weekly_project_series(weeks, top))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?;
+    if config::load_config().privacy_mode {
+        privacy::apply_to_project_series(&mut series);
+    }
+    Ok(series)
+}
+
+/// Backfill history from another tool's export ("ccusage" or "claude_csv")
+#[tauri::command]
+async fn import_history(path: String, format: String) -> Result<store::ImportSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        store::import_history(std::path::Path::new(&path), &format)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+fn get_imported_history() -> Vec<store::HistoryRecord> {
+    store::load_history()
+}
+
+/// Check the parsed journal cache against a re-parse of `sample` (default 50, 0 for all)
+/// journals and imported history against the journals; `repair` rebuilds what drifted
+#[tauri::command]
+async fn verify_store(
+    sample: Option<usize>,
+    repair: Option<bool>,
+) -> Result<store::StoreVerification, String> {
+    let sample = sample.unwrap_or(50);
+    let repair = repair.unwrap_or(false);
+    let mut report =
+        tauri::async_runtime::spawn_blocking(move || store::verify_store(sample, repair))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??;
+    if config::load_config().privacy_mode {
+        privacy::apply_to_store_verification(&mut report);
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+fn get_project_rules() -> Vec<usage::ProjectRule> {
+    config::load_config().project_rules
+}
+
+/// Replace the rules mapping working directories to project names, tried in order
+#[tauri::command]
+async fn set_project_rules(rules: Vec<usage::ProjectRule>) -> Result<(), String> {
+    usage::check_project_rules(&rules)?;
+    config::update_config(|cfg| {
+        cfg.project_rules = rules;
+    })
+}
+
+/// Group projects by git remote instead of directory, so clones of one repository merge
+#[tauri::command]
+async fn set_group_projects_by_remote(enabled: bool) -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.group_projects_by_remote = enabled;
+    })
+}
+
+#[tauri::command]
+fn get_allowed_data_roots() -> Vec<String> {
+    config::load_config().allowed_data_roots
+}
+
+/// Set the directories outside the home directory that `CLAUDE_CONFIG_DIR` may point into
+#[tauri::command]
+async fn set_allowed_data_roots(roots: Vec<String>) -> Result<(), String> {
+    let roots: Vec<String> = roots
+        .into_iter()
+        .map(|root| root.trim().to_string())
+        .filter(|root| !root.is_empty())
+        .collect();
+    if let Some(root) = roots
+        .iter()
+        .find(|root| !std::path::Path::new(root).is_absolute())
+    {
+        return Err(format!("Allowed data roots must be absolute paths: {root}"));
+    }
+    config::update_config(|cfg| {
+        cfg.allowed_data_roots = roots;
+    })
+}
+
+#[tauri::command]
+fn get_week_starts_on() -> u32 {
+    config::load_config().week_starts_on
+}
+
+/// Set the first day of the week, 1 = Monday ... 7 = Sunday
+#[tauri::command]
+async fn set_week_starts_on(day: u32) -> Result<(), String> {
+    if !(1..=7).contains(&day) {
+        return Err("week_starts_on must be 1 (Monday) to 7 (Sunday)".to_string());
+    }
+    config::update_config(|cfg| {
+        cfg.week_starts_on = day;
+    })
+}
+
+#[tauri::command]
+fn get_billing_cycle_anchor_day() -> Option<u32> {
+    config::load_config().billing_cycle_anchor_day
+}
+
+/// Set the day of the month (1-31) the subscription renews on; None goes back to the last
+/// 30 days for the "month" period and calendar months for monthly goals
+#[tauri::command]
+async fn set_billing_cycle_anchor_day(
+    app: tauri::AppHandle,
+    day: Option<u32>,
+) -> Result<(), String> {
+    if day.is_some_and(|day| !(1..=31).contains(&day)) {
+        return Err("billing_cycle_anchor_day must be 1 to 31".to_string());
+    }
+    config::update_config(|cfg| {
+        cfg.billing_cycle_anchor_day = day;
+    })?;
+    tray::sync_menu(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_days_off() -> Vec<String> {
+    config::load_config().days_off
+}
+
+/// Replace the planned days off ("YYYY-MM-DD" or "YYYY-MM-DD..YYYY-MM-DD") left out of the
+/// weekly pace and projection
+#[tauri::command]
+async fn set_days_off(days: Vec<String>) -> Result<(), String> {
+    for spec in &days {
+        usage::parse_days_off(spec)?;
+    }
+    config::update_config(|cfg| {
+        cfg.days_off = days;
+    })
+}
+
+/// Add the days covered by the events of an .ics calendar to the days off; returns how many
+/// days were new
+#[tauri::command]
+async fn import_days_off(path: String) -> Result<usize, String> {
+    let contents = tauri::async_runtime::spawn_blocking(move || {
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {path}: {e}"))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))??;
+    let count = config::update_config(|cfg| {
+        let known: std::collections::HashSet<chrono::NaiveDate> = cfg
+            .days_off
+            .iter()
+            .filter_map(|spec| usage::parse_days_off(spec).ok())
+            .flatten()
+            .collect();
+        let added: Vec<String> = usage::parse_ics_days(&contents)
+            .into_iter()
+            .filter(|day| !known.contains(day))
+            .map(|day| day.format("%Y-%m-%d").to_string())
+            .collect();
+        let count = added.len();
+        cfg.days_off.extend(added);
+        count
+    })?;
+    Ok(count)
+}
+
+#[tauri::command]
+fn get_low_memory() -> bool {
+    config::load_config().low_memory
+}
+
+/// Turn low-memory mode on or off; takes effect on the next refresh
+#[tauri::command]
+async fn set_low_memory(enabled: bool) -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.low_memory = enabled;
+    })
+}
+
+#[tauri::command]
+fn get_entries_stream() -> bool {
+    config::load_config().entries_stream
+}
+
+/// Turn the `entries-appended` event on or off; entries dated before turning it on aren't sent
+#[tauri::command]
+async fn set_entries_stream(enabled: bool) -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.entries_stream = enabled;
+    })?;
+    entry_stream::sync_entries_stream();
+    Ok(())
+}
+
+#[tauri::command]
+fn get_all_users() -> bool {
+    config::load_config().all_users
+}
+
+/// Read every account's Claude data and report usage per OS user; the watcher picks up the
+/// other homes on its next rescan of the data dirs
+#[tauri::command]
+async fn set_all_users(enabled: bool) -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.all_users = enabled;
+    })
+}
+
+#[tauri::command]
+fn get_working_hours() -> usage::WorkingHours {
+    usage::working_hours()
+}
+
+/// Replace the working schedule used for `off_hours_percent` and the off-hours alert;
+/// `None` restores weekdays 09:00-18:00
+#[tauri::command]
+async fn set_working_hours(hours: Option<usage::WorkingHours>) -> Result<(), String> {
+    if let Some(ref h) = hours {
+        for time in [&h.start, &h.end] {
+            chrono::NaiveTime::parse_from_str(time, "%H:%M")
+                .map_err(|_| format!("Invalid time '{time}', expected HH:MM"))?;
+        }
+        if h.days.iter().any(|day| !(1..=7).contains(day)) {
+            return Err("Working days must be 1 (Monday) to 7 (Sunday)".to_string());
+        }
+    }
+    config::update_config(|cfg| {
+        cfg.working_hours = hours;
+    })
+}
+
+#[tauri::command]
+fn get_tray_icon_settings() -> config::TrayIconConfig {
+    config::load_config().tray_icon
+}
+
+/// Change the panel theme the tray icon is drawn for and whether it shows usage; the icon is
+/// redrawn right away
+#[tauri::command]
+async fn set_tray_icon_settings(
+    app: tauri::AppHandle,
+    settings: config::TrayIconConfig,
+) -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.tray_icon = settings;
+    })?;
+    tauri::async_runtime::spawn_blocking(move || tray::update_icon(&app))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))
+}
+
+#[tauri::command]
+fn get_privacy_mode() -> bool {
+    config::load_config().privacy_mode
+}
+
+/// Toggle pseudonymized project names; the frontend should refresh afterwards
+#[tauri::command]
+async fn set_privacy_mode(enabled: bool) -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.privacy_mode = enabled;
+    })
+}
+
+#[tauri::command]
+fn get_summary_file_settings() -> config::SummaryFileConfig {
+    config::load_config().summary_file
+}
 
-use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
-use std::collections::HashMap;
-use std::sync::mpsc::channel;
-use std::thread;
-use std::time::Duration;
-use tauri::image::Image;
-use tauri::{Emitter, Manager};
-use usage::{get_claude_data_dirs, get_current_usage, UsageStats};
+#[tauri::command]
+async fn set_summary_file_settings(settings: config::SummaryFileConfig) -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.summary_file = settings;
+    })
+}
 
-#[cfg(target_os = "linux")]
-use dbus_service::DbusServiceHandle;
+#[tauri::command]
+fn get_http_server_settings() -> config::HttpServerConfig {
+    config::load_config().http_server
+}
 
+/// Update the HTTP dashboard settings. Applies on next start.
+#[tauri::command]
+async fn set_http_server_settings(settings: config::HttpServerConfig) -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.http_server = settings;
+    })
+}
 
-/// Async command to fetch usage stats.
-/// If an Admin API key is configured, fetches from the API first with local fallback.
-/// File I/O is offloaded to a blocking thread pool.
 #[tauri::command]
-async fn get_usage(period: String) -> Result<UsageStats, String> {
-    // Check if API key is configured
-    let cfg = config::load_config();
-    if let Some(ref api_key) = cfg.admin_api_key {
-        if !api_key.is_empty() {
-            match get_usage_from_api(api_key).await {
-                Ok(stats) => return Ok(stats),
-                Err(e) => {
-                    eprintln!("API fetch failed, falling back to local: {e}");
-                }
-            }
-        }
-    }
+fn get_usage_source() -> usage::UsageSource {
+    config::load_config().usage_source
+}
 
-    // Fall back to local JSONL parsing
-    tauri::async_runtime::spawn_blocking(move || get_current_usage(&period))
-        .await
-        .map_err(|e| format!("Task join error: {}", e))?
+/// Count usage from the journals or from Claude Code's OTLP log export. Applies on next
+/// start.
+#[tauri::command]
+async fn set_usage_source(source: usage::UsageSource) -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.usage_source = source;
+    })
 }
 
-async fn get_usage_from_api(api_key: &str) -> Result<UsageStats, String> {
-    let client = api::AdminApiClient::new(api_key)?;
-    api::build_usage_stats_from_api(&client).await
+#[tauri::command]
+fn get_daily_digest_settings() -> config::DailyDigestConfig {
+    config::load_config().daily_digest
 }
 
 #[tauri::command]
-async fn set_api_key(key: String) -> Result<String, String> {
-    let mut cfg = config::load_config();
-    cfg.admin_api_key = Some(key.clone());
-    config::save_config(&cfg)?;
-    Ok(config::mask_api_key(&key))
+async fn set_daily_digest(enabled: bool, time: String) -> Result<(), String> {
+    chrono::NaiveTime::parse_from_str(&time, "%H:%M")
+        .map_err(|e| format!("Invalid digest time '{time}' (expected HH:MM): {e}"))?;
+    config::update_config(|cfg| {
+        cfg.daily_digest = config::DailyDigestConfig { enabled, time };
+    })
 }
 
+/// Weekly report settings, plus whether an SMTP password is stored in the keyring
 #[tauri::command]
-fn get_api_key_status() -> HashMap<String, String> {
-    let cfg = config::load_config();
-    let mut status = HashMap::new();
-    match cfg.admin_api_key {
-        Some(ref key) if !key.is_empty() => {
-            status.insert("configured".to_string(), "true".to_string());
-            status.insert("masked_key".to_string(), config::mask_api_key(key));
-        }
-        _ => {
-            status.insert("configured".to_string(), "false".to_string());
-        }
-    }
-    status
+fn get_weekly_report_settings() -> HashMap<String, serde_json::Value> {
+    let settings = config::load_config().weekly_report;
+    let mut result = HashMap::new();
+    result.insert(
+        "has_password".to_string(),
+        serde_json::Value::Bool(email_report::has_smtp_password(&settings)),
+    );
+    result.insert(
+        "settings".to_string(),
+        serde_json::to_value(&settings).unwrap_or_default(),
+    );
+    result
 }
 
+/// Save weekly report settings. `password` is stored in the keyring when given; an empty
+/// string removes it.
 #[tauri::command]
-async fn clear_api_key() -> Result<(), String> {
-    let mut cfg = config::load_config();
-    cfg.admin_api_key = None;
-    config::save_config(&cfg)
+async fn set_weekly_report(
+    settings: config::WeeklyReportConfig,
+    password: Option<String>,
+) -> Result<(), String> {
+    let time = &settings.time;
+    chrono::NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|e| format!("Invalid report time '{time}' (expected HH:MM): {e}"))?;
+    if !(1..=7).contains(&settings.weekday) {
+        return Err("weekday must be between 1 (Monday) and 7 (Sunday)".to_string());
+    }
+    if let Some(password) = password {
+        let password = Some(password.as_str()).filter(|p| !p.is_empty());
+        email_report::set_smtp_password(&settings, password)?;
+    }
+    config::update_config(|cfg| {
+        cfg.weekly_report = settings;
+    })
 }
 
+/// Send the weekly report now with the saved settings, to check the SMTP setup
 #[tauri::command]
-async fn validate_api_key(key: String) -> Result<(), String> {
-    let client = api::AdminApiClient::new(&key)?;
-    client.validate().await
+async fn send_test_report() -> Result<(), String> {
+    let settings = config::load_config().weekly_report;
+    tauri::async_runtime::spawn_blocking(move || email_report::send_weekly_report(&settings))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
 }
 
 #[tauri::command]
@@ -91,6 +1210,264 @@ fn get_data_dirs() -> Vec<String> {
         .collect()
 }
 
+/// Export recent session activity as a Toggl/Clockify CSV import file.
+/// Writes the CSV to `path` when given and always returns its contents.
+#[tauri::command]
+async fn export_time_entries(
+    format: String,
+    days: u32,
+    email: Option<String>,
+    path: Option<String>,
+) -> Result<String, String> {
+    let format = export::TimeTrackerFormat::parse(&format)?;
+    let privacy_mode = config::load_config().privacy_mode;
+    let csv = tauri::async_runtime::spawn_blocking(move || {
+        export::export_time_entries(format, days, email.as_deref().unwrap_or(""), privacy_mode)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
+
+    if let Some(path) = path {
+        usage::ensure_outside_data_dirs(std::path::Path::new(&path))?;
+        std::fs::write(&path, &csv).map_err(|e| format!("Failed to write {path}: {e}"))?;
+    }
+    Ok(csv)
+}
+
+/// Sandbox detection and whether any Claude data dir is currently reachable
+#[tauri::command]
+fn get_sandbox_status() -> HashMap<String, String> {
+    #[cfg(target_os = "linux")]
+    let sandbox = portal::sandbox_kind();
+    #[cfg(not(target_os = "linux"))]
+    let sandbox = "none";
+
+    let mut status = HashMap::new();
+    status.insert("sandbox".to_string(), sandbox.to_string());
+    status.insert(
+        "has_data_access".to_string(),
+        (!get_claude_data_dirs().is_empty()).to_string(),
+    );
+    status
+}
+
+/// Ask the user to share their Claude data directory through the desktop portal.
+/// The granted path is persisted and picked up by the file watcher.
+#[tauri::command]
+async fn request_data_dir_access(app_handle: tauri::AppHandle) -> Result<String, String> {
+    #[cfg(target_os = "linux")]
+    {
+        let root = portal::pick_claude_dir().await?;
+        let root_str = root.display().to_string();
+        config::update_config(|cfg| {
+            if !cfg.granted_data_roots.contains(&root_str) {
+                cfg.granted_data_roots.push(root_str.clone());
+            }
+        })?;
+        events::emit(&app_handle, "usage-updated", ());
+        Ok(root_str)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = app_handle;
+        Err("Portal access is only available on Linux".to_string())
+    }
+}
+
+/// Debug command listing every Claude data dir candidate in resolution order
+#[tauri::command]
+fn get_data_dir_resolution() -> Vec<DataDirCandidate> {
+    get_data_dir_report()
+}
+
+/// Serve a fixture instead of real usage (see `simulate::FIXTURES`, or a path to a JSON file
+/// of `UsageStats`); no name returns to real data. Needs a build with the `simulate` feature.
+#[tauri::command]
+fn load_fixture(name: Option<String>) -> Result<(), String> {
+    #[cfg(feature = "simulate")]
+    {
+        simulate::load_fixture(name.as_deref())
+    }
+    #[cfg(not(feature = "simulate"))]
+    {
+        let _ = name;
+        Err("Built without the simulate feature".to_string())
+    }
+}
+
+/// Delete the persistent cache of parsed journals; they are re-parsed on the next refresh
+#[tauri::command]
+async fn clear_cache() -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(usage::clear_cache)
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Debug command listing filesystem access refused by the read-only guard: data roots
+/// outside the allow-list, journals symlinked out of their data dir and writes into it
+#[tauri::command]
+fn get_access_violations() -> Vec<usage::AccessViolation> {
+    usage::access_violations()
+}
+
+#[tauri::command]
+fn get_watcher_debounce() -> config::DebounceConfig {
+    config::load_config().watcher_debounce
+}
+
+/// Replace the adaptive debounce settings; the watcher picks them up without a restart
+#[tauri::command]
+async fn set_watcher_debounce(settings: config::DebounceConfig) -> Result<(), String> {
+    if settings.min_ms == 0 {
+        return Err("min_ms must be at least 1".to_string());
+    }
+    if settings.max_ms < settings.min_ms || settings.max_wait_ms < settings.max_ms {
+        return Err("watcher_debounce needs min_ms <= max_ms <= max_wait_ms".to_string());
+    }
+    config::update_config(|cfg| {
+        cfg.watcher_debounce = settings;
+    })?;
+    debounce::request_reload();
+    Ok(())
+}
+
+/// Debug command counting journal change events, refreshes and the events the adaptive
+/// debounce folded into them since startup
+#[tauri::command]
+fn get_watcher_stats() -> debounce::WatcherStats {
+    debounce::watcher_stats()
+}
+
+/// Debug command reporting the journal watcher's backend (native or polling), its settings,
+/// the watched directories and any fallback, for tracking down missed updates
+#[tauri::command]
+fn get_watcher_status() -> Option<watcher::WatcherStatus> {
+    watcher::watcher_status()
+}
+
+/// Debug command counting journal lines read and skipped (oversized, invalid UTF-8,
+/// malformed) by the latest usage refresh
+#[tauri::command]
+fn get_parse_health() -> usage::ParseHealth {
+    usage::parse_health()
+}
+
+/// Debug command reporting the local time zone days and weeks are judged in, its current
+/// UTC offset and the next daylight saving change
+#[tauri::command]
+fn get_timezone_info() -> usage::TimezoneInfo {
+    usage::timezone_info(chrono::Utc::now())
+}
+
+/// JSON Schema of a serialized type, e.g. "UsageStats", generated from its Rust definition
+#[tauri::command]
+fn get_schema(type_name: String) -> Result<serde_json::Value, String> {
+    let schema = usage::type_schema(&type_name).ok_or_else(|| {
+        format!(
+            "Unknown type '{type_name}' (expected one of: {})",
+            usage::SCHEMA_TYPES.join(", ")
+        )
+    })?;
+    serde_json::to_value(schema).map_err(|e| format!("Failed to serialize schema: {}", e))
+}
+
+/// The last backend events emitted to the frontend (and backend warnings), oldest first,
+/// to check whether an update the widget missed was sent at all
+#[tauri::command]
+fn get_event_log() -> Vec<events::EventRecord> {
+    events::event_log()
+}
+
+/// Events emitted after `since_seq` that the widget missed (during a reload or suspend),
+/// with the current display period; without `since_seq` just the latest sequence number
+#[tauri::command]
+fn resync(since_seq: Option<u64>) -> events::Resync {
+    events::resync(since_seq)
+}
+
+/// Crash report left by a previous run (panic message, backtrace, recent log lines)
+#[tauri::command]
+fn get_last_crash() -> Option<crash::CrashReport> {
+    crash::load_last_crash()
+}
+
+#[tauri::command]
+fn dismiss_last_crash() -> Result<(), String> {
+    crash::clear_last_crash()
+}
+
+/// Every recorded quota-limit hit (journal messages and estimated 5hr limit), oldest first
+#[tauri::command]
+async fn get_limit_history() -> Result<Vec<limits::LimitEvent>, String> {
+    let mut events = tauri::async_runtime::spawn_blocking(limits::load_limit_history)
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?;
+    if config::load_config().privacy_mode {
+        privacy::apply_to_limit_events(&mut events);
+    }
+    Ok(events)
+}
+
+/// Timings of usage loading, journal parsing and API requests since startup (or the last
+/// reset), for reporting a slow widget
+#[tauri::command]
+fn get_perf_stats() -> Vec<usage::PerfTiming> {
+    usage::perf_stats()
+}
+
+#[tauri::command]
+fn reset_perf_stats() {
+    usage::reset_perf_stats()
+}
+
+/// Version, build, features, platform and active workarounds, for bug reports
+#[tauri::command]
+fn get_app_info() -> app_info::AppInfo {
+    app_info::get_app_info()
+}
+
+/// Debug command reporting detected GPU/session and which WebKit workarounds are active
+#[tauri::command]
+fn get_renderer_info() -> renderer::RendererInfo {
+    renderer::get_renderer_info()
+}
+
+/// Force a WebKit workaround on/off, or back to auto with `None`. Applies on next start.
+#[tauri::command]
+async fn set_webkit_workaround(name: String, enabled: Option<bool>) -> Result<(), String> {
+    config::update_config(|cfg| {
+        let slot = match name.as_str() {
+            "disable_compositing" => &mut cfg.webkit.disable_compositing,
+            "disable_dmabuf" => &mut cfg.webkit.disable_dmabuf,
+            "disable_sandbox" => &mut cfg.webkit.disable_sandbox,
+            "single_web_process" => &mut cfg.webkit.single_web_process,
+            "disable_gpu" => &mut cfg.webkit.disable_gpu,
+            other => return Err(format!("Unknown WebKit workaround '{other}'")),
+        };
+        *slot = enabled;
+        Ok(())
+    })?
+}
+
+/// Compare the running version with the latest GitHub release; never installs anything
+#[tauri::command]
+async fn check_for_updates() -> Result<updates::UpdateInfo, String> {
+    updates::check_for_updates().await
+}
+
+#[tauri::command]
+fn get_update_check() -> bool {
+    config::load_config().update_check
+}
+
+/// Turn the startup update check on or off; `check_for_updates` works either way
+#[tauri::command]
+async fn set_update_check(enabled: bool) -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.update_check = enabled;
+    })
+}
+
 /// Debug command to check WebKit environment variables
 #[tauri::command]
 fn get_webkit_env() -> std::collections::HashMap<String, String> {
@@ -107,100 +1484,201 @@ fn get_webkit_env() -> std::collections::HashMap<String, String> {
         .collect()
 }
 
+/// Append newly detected limit hits to the history and tell the frontend about them
+fn record_limit_events(app_handle: &tauri::AppHandle, lookback_hours: i64) {
+    let mut events = limits::record_new_limit_events(lookback_hours);
+    if !events.is_empty() {
+        if config::load_config().privacy_mode {
+            privacy::apply_to_limit_events(&mut events);
+        }
+        events::emit(app_handle, "limit-reached", events);
+    }
+}
+
+/// Warn (event + notification) about live sessions that crossed the configured cost ceiling
+fn check_session_cost_ceiling(app_handle: &tauri::AppHandle) {
+    let cfg = config::load_config();
+    let ceiling = match cfg.session_cost_ceiling_usd {
+        Some(ceiling) if ceiling > 0.0 => ceiling,
+        _ => return,
+    };
+    let mut alerts = cost_guard::new_sessions_over_ceiling(ceiling);
+    if alerts.is_empty() {
+        return;
+    }
+    if cfg.privacy_mode {
+        privacy::apply_to_cost_alerts(&mut alerts);
+    }
+    for alert in &alerts {
+        let body = format!(
+            "Session {} ({}) has cost {}, over the {} ceiling",
+            alert.session_id,
+            alert.project,
+            format::format_cost(alert.cost_usd, &cfg.format),
+            format::format_cost(alert.ceiling_usd, &cfg.format)
+        );
+        notifications::send(
+            app_handle,
+            notifications::SESSION_COST,
+            "Claude Code session cost ceiling exceeded",
+            &body,
+        );
+    }
+    events::emit(app_handle, "session-cost-exceeded", alerts);
+}
+
+/// Receive Claude Code's OTLP log export and refresh the widget as events arrive
+fn setup_otlp_receiver(app_handle: tauri::AppHandle) {
+    otlp_receiver::restore_saved_events();
+    let on_ingest: otlp_receiver::IngestHook = Arc::new(move |entries: &[usage::ParsedEntry]| {
+        entry_stream::emit_ingested_entries(&app_handle, entries);
+        events::emit(&app_handle, "usage-updated", ());
+        check_session_cost_ceiling(&app_handle);
+        tray::spawn_icon_update(&app_handle);
+    });
+    otlp_receiver::spawn_otlp_receiver(config::load_config().otlp_receiver, on_ingest);
+}
+
+/// Run `refresh` on a worker thread each time the returned sender is signalled, so the
+/// journal scan doesn't hold up the watcher's event loop. A signal sent while a refresh is
+/// already pending is folded into it.
+fn spawn_refresh_worker(mut refresh: impl FnMut() + Send + 'static) -> SyncSender<()> {
+    let (tx, rx) = sync_channel(1);
+    thread::spawn(move || {
+        for () in rx {
+            refresh();
+        }
+    });
+    tx
+}
+
+/// What the watcher does once writes pause: stream the new entries and have everything
+/// that shows usage refresh
+fn refresh_after_writes(app_handle: &tauri::AppHandle) {
+    entry_stream::emit_journal_entries(app_handle);
+    events::emit(app_handle, "usage-updated", ());
+    record_limit_events(app_handle, 1);
+    check_session_cost_ceiling(app_handle);
+    tray::spawn_icon_update(app_handle);
+}
+
 #[cfg(target_os = "linux")]
 fn setup_file_watcher(app_handle: tauri::AppHandle, dbus_handle: Option<DbusServiceHandle>) {
+    // OTLP events are pushed to the receiver; there are no journals to watch
+    if usage::usage_source() == usage::UsageSource::Otlp {
+        setup_otlp_receiver(app_handle);
+        return;
+    }
     thread::spawn(move || {
         let (tx, rx) = channel();
 
-        let config = Config::default().with_poll_interval(Duration::from_secs(2));
-
-        let mut watcher: RecommendedWatcher = match Watcher::new(tx, config) {
+        let mut watcher = match watcher::create_watcher(tx) {
             Ok(w) => w,
             Err(e) => {
-                eprintln!("Failed to create watcher: {:?}", e);
+                log_line!("Failed to create watcher: {:?}", e);
                 return;
             }
         };
 
-        let data_dirs = get_claude_data_dirs();
-        for dir in &data_dirs {
-            if let Err(e) = watcher.watch(dir, RecursiveMode::Recursive) {
-                eprintln!("Failed to watch {:?}: {:?}", dir, e);
-            }
-        }
+        let mut watched_dirs = Vec::new();
+        watcher::watch_new_data_dirs(&mut watcher, &mut watched_dirs);
+        // Catch limit hits from while the app wasn't running
+        record_limit_events(&app_handle, 24);
+        let mut last_rescan = std::time::Instant::now();
 
         // Create a tokio runtime for async D-Bus updates
         let rt = tokio::runtime::Builder::new_current_thread()
             .enable_all()
             .build()
             .ok();
+        let refresh = spawn_refresh_worker(move || {
+            refresh_after_writes(&app_handle);
 
-        // Debounce: only emit after no events for 500ms
-        let mut last_event = std::time::Instant::now();
+            // Notify D-Bus service of the change
+            if let (Some(ref rt), Some(ref handle)) = (&rt, &dbus_handle) {
+                let handle = handle.clone();
+                rt.block_on(async {
+                    handle.notify_usage_changed().await;
+                });
+            }
+        });
+
+        // Refresh once writes pause, batching more during write storms (see `debounce`)
+        let mut debounce =
+            debounce::AdaptiveDebounce::new(&watcher::debounce_config(&config::load_config()));
         loop {
-            match rx.recv_timeout(Duration::from_millis(500)) {
-                Ok(_) => {
-                    last_event = std::time::Instant::now();
-                }
-                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
-                    if last_event.elapsed() >= Duration::from_millis(500)
-                        && last_event.elapsed() < Duration::from_millis(1000)
-                    {
-                        let _ = app_handle.emit("usage-updated", ());
-
-                        // Notify D-Bus service of the change
-                        if let (Some(ref rt), Some(ref handle)) = (&rt, &dbus_handle) {
-                            let handle = handle.clone();
-                            rt.block_on(async {
-                                handle.notify_usage_changed().await;
-                            });
-                        }
-                    }
-                }
+            match rx.recv_timeout(debounce.timeout()) {
+                Ok(_) => debounce.record_event(),
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
                 Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
             }
+            if debounce::take_reload_request() {
+                debounce.reconfigure(&watcher::debounce_config(&config::load_config()));
+            }
+            if last_rescan.elapsed() >= watcher::DATA_DIR_RESCAN_INTERVAL {
+                watcher::watch_new_data_dirs(&mut watcher, &mut watched_dirs);
+                last_rescan = std::time::Instant::now();
+            }
+
+            if debounce.ready() {
+                let _ = refresh.try_send(());
+            }
         }
     });
 }
 
 #[cfg(not(target_os = "linux"))]
-fn setup_file_watcher(app_handle: tauri::AppHandle) {
+fn setup_file_watcher(app_handle: tauri::AppHandle, ipc_handle: Option<IpcServiceHandle>) {
+    // OTLP events are pushed to the receiver; there are no journals to watch
+    if usage::usage_source() == usage::UsageSource::Otlp {
+        setup_otlp_receiver(app_handle);
+        return;
+    }
     thread::spawn(move || {
         let (tx, rx) = channel();
 
-        let config = Config::default().with_poll_interval(Duration::from_secs(2));
-
-        let mut watcher: RecommendedWatcher = match Watcher::new(tx, config) {
+        let mut watcher = match watcher::create_watcher(tx) {
             Ok(w) => w,
             Err(e) => {
-                eprintln!("Failed to create watcher: {:?}", e);
+                log_line!("Failed to create watcher: {:?}", e);
                 return;
             }
         };
 
-        let data_dirs = get_claude_data_dirs();
-        for dir in &data_dirs {
-            if let Err(e) = watcher.watch(dir, RecursiveMode::Recursive) {
-                eprintln!("Failed to watch {:?}: {:?}", dir, e);
+        let mut watched_dirs = Vec::new();
+        watcher::watch_new_data_dirs(&mut watcher, &mut watched_dirs);
+        // Catch limit hits from while the app wasn't running
+        record_limit_events(&app_handle, 24);
+        let mut last_rescan = std::time::Instant::now();
+        let refresh = spawn_refresh_worker(move || {
+            refresh_after_writes(&app_handle);
+
+            // Notify the IPC service of the change
+            if let Some(ref handle) = ipc_handle {
+                handle.notify_usage_changed();
             }
-        }
+        });
 
-        // Debounce: only emit after no events for 500ms
-        let mut last_event = std::time::Instant::now();
+        // Refresh once writes pause, batching more during write storms (see `debounce`)
+        let mut debounce =
+            debounce::AdaptiveDebounce::new(&watcher::debounce_config(&config::load_config()));
         loop {
-            match rx.recv_timeout(Duration::from_millis(500)) {
-                Ok(_) => {
-                    last_event = std::time::Instant::now();
-                }
-                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
-                    if last_event.elapsed() >= Duration::from_millis(500)
-                        && last_event.elapsed() < Duration::from_millis(1000)
-                    {
-                        let _ = app_handle.emit("usage-updated", ());
-                    }
-                }
+            match rx.recv_timeout(debounce.timeout()) {
+                Ok(_) => debounce.record_event(),
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
                 Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
             }
+            if debounce::take_reload_request() {
+                debounce.reconfigure(&watcher::debounce_config(&config::load_config()));
+            }
+            if last_rescan.elapsed() >= watcher::DATA_DIR_RESCAN_INTERVAL {
+                watcher::watch_new_data_dirs(&mut watcher, &mut watched_dirs);
+                last_rescan = std::time::Instant::now();
+            }
+
+            if debounce.ready() {
+                let _ = refresh.try_send(());
+            }
         }
     });
 }
@@ -226,7 +1704,7 @@ fn setup_suspend_monitor(app_handle: tauri::AppHandle) {
         {
             Ok(rt) => rt,
             Err(e) => {
-                eprintln!("Failed to create tokio runtime: {:?}", e);
+                log_line!("Failed to create tokio runtime: {:?}", e);
                 return;
             }
         };
@@ -235,7 +1713,7 @@ fn setup_suspend_monitor(app_handle: tauri::AppHandle) {
             let conn = match zbus::Connection::system().await {
                 Ok(c) => c,
                 Err(e) => {
-                    eprintln!("Failed to connect to system D-Bus: {:?}", e);
+                    log_line!("Failed to connect to system D-Bus: {:?}", e);
                     return;
                 }
             };
@@ -252,7 +1730,7 @@ fn setup_suspend_monitor(app_handle: tauri::AppHandle) {
             let mut stream = match zbus::MessageStream::for_match_rule(rule, &conn, None).await {
                 Ok(s) => s,
                 Err(e) => {
-                    eprintln!("Failed to subscribe to D-Bus signal: {:?}", e);
+                    log_line!("Failed to subscribe to D-Bus signal: {:?}", e);
                     return;
                 }
             };
@@ -263,7 +1741,7 @@ fn setup_suspend_monitor(app_handle: tauri::AppHandle) {
                     if let Ok(body) = msg.body().deserialize::<bool>() {
                         if !body {
                             // System just resumed - emit event to trigger recovery
-                            let _ = app_handle.emit("system-resumed", ());
+                            events::emit(&app_handle, "system-resumed", ());
                         }
                     }
                 }
@@ -272,52 +1750,298 @@ fn setup_suspend_monitor(app_handle: tauri::AppHandle) {
     });
 }
 
+/// NetworkManager `NM_STATE_CONNECTED_GLOBAL`: full internet connectivity
+#[cfg(target_os = "linux")]
+const NM_STATE_CONNECTED_GLOBAL: u32 = 70;
+
+/// Refresh as soon as the user is back: on screen unlock (logind `Session.Unlock`) and when
+/// NetworkManager reports full connectivity again. `usage-updated` makes the frontend
+/// re-run `get_usage`, which re-fetches from the Admin API when a key is configured.
+#[cfg(target_os = "linux")]
+fn setup_wake_refresh_monitor(app_handle: tauri::AppHandle) {
+    thread::spawn(move || {
+        let rt = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(rt) => rt,
+            Err(e) => {
+                log_line!("Failed to create tokio runtime: {:?}", e);
+                return;
+            }
+        };
+
+        rt.block_on(async {
+            let conn = match zbus::Connection::system().await {
+                Ok(c) => c,
+                Err(e) => {
+                    log_line!("Failed to connect to system D-Bus: {:?}", e);
+                    return;
+                }
+            };
+
+            let unlock_rule = zbus::MatchRule::builder()
+                .msg_type(zbus::message::Type::Signal)
+                .interface("org.freedesktop.login1.Session")
+                .unwrap()
+                .member("Unlock")
+                .unwrap()
+                .build();
+            let network_rule = zbus::MatchRule::builder()
+                .msg_type(zbus::message::Type::Signal)
+                .interface("org.freedesktop.NetworkManager")
+                .unwrap()
+                .member("StateChanged")
+                .unwrap()
+                .build();
+
+            let unlock_stream =
+                match zbus::MessageStream::for_match_rule(unlock_rule, &conn, None).await {
+                    Ok(s) => s,
+                    Err(e) => {
+                        log_line!("Failed to subscribe to D-Bus signal: {:?}", e);
+                        return;
+                    }
+                };
+            let network_stream =
+                match zbus::MessageStream::for_match_rule(network_rule, &conn, None).await {
+                    Ok(s) => s,
+                    Err(e) => {
+                        log_line!("Failed to subscribe to D-Bus signal: {:?}", e);
+                        return;
+                    }
+                };
+
+            let mut stream = futures_util::stream::select(unlock_stream, network_stream);
+            while let Some(msg) = futures_util::StreamExt::next(&mut stream).await {
+                let Ok(msg) = msg else { continue };
+                let header = msg.header();
+                let refresh = match header.member().map(|m| m.as_str()) {
+                    Some("Unlock") => true,
+                    // StateChanged(u32) - only refresh once fully connected again
+                    Some("StateChanged") => msg
+                        .body()
+                        .deserialize::<u32>()
+                        .is_ok_and(|state| state == NM_STATE_CONNECTED_GLOBAL),
+                    _ => false,
+                };
+                if refresh {
+                    events::emit(&app_handle, "usage-updated", ());
+                }
+            }
+        });
+    });
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Workarounds for WebKitGTK issues on Linux
+    // Write a crash report to the config dir if anything panics
+    crash::install_panic_hook();
+
+    // Loading the config configures the core from it (data roots granted through the portal
+    // in earlier runs and the allow-list, quota weights and windows, pricing, the calendar,
+    // the usage source, excluded sessions, ...) before anything reads the journals
+    state::get();
+    entry_stream::sync_entries_stream();
+    usage::prune_entry_cache();
+    #[cfg(feature = "simulate")]
+    simulate::load_fixture_from_env();
+    log_line!("Claude data dirs: {:?}", get_claude_data_dirs());
+    usage::log_rejected_data_roots();
+    let timezone = usage::timezone_info(chrono::Utc::now());
+    log_line!(
+        "Time zone: {} (UTC{})",
+        timezone.name.as_deref().unwrap_or("unknown"),
+        timezone.utc_offset
+    );
+
+    // Single instance: a second launch raises the running widget and exits, rather than
+    // starting duplicate file watchers and clashing over the D-Bus name
     #[cfg(target_os = "linux")]
-    {
-        // Fix transparent window rendering bug
-        // https://github.com/tauri-apps/tauri/issues/10626
-        std::env::set_var("WEBKIT_DISABLE_COMPOSITING_MODE", "1");
-        std::env::set_var("WEBKIT_DISABLE_DMABUF_RENDERER", "1");
-        // Disable WebKit sandbox to prevent "Could not connect to localhost"
-        // errors after system suspend/resume. WebKit's multi-process architecture
-        // uses IPC that can break when child processes become stale.
-        std::env::set_var("WEBKIT_DISABLE_SANDBOX_THIS_IS_DANGEROUS", "1");
-        // Force single web process to avoid IPC issues between multiple web processes
-        std::env::set_var("WEBKIT_USE_SINGLE_WEB_PROCESS", "1");
-        // Disable hardware acceleration which can cause issues after suspend/resume
-        std::env::set_var("WEBKIT_DISABLE_GPU", "1");
+    if dbus_service::forward_to_running_instance(!daemon::requested()) {
+        return;
+    }
+    #[cfg(not(target_os = "linux"))]
+    if ipc_service::forward_to_running_instance(!daemon::requested()) {
+        return;
     }
 
+    // Headless backend only, without creating the app or any webview
+    if daemon::requested() {
+        daemon::run();
+        return;
+    }
+
+    // Workarounds for WebKitGTK issues on Linux, applied per config or when the
+    // detected GPU/session is known to need them
+    #[cfg(target_os = "linux")]
+    renderer::apply_webkit_workarounds(&config::load_config().webkit);
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
+        // Shared with the watcher, D-Bus/IPC and HTTP threads through `state::get()`
+        .manage(state::get().clone())
         .invoke_handler(tauri::generate_handler![
             get_usage,
+            get_usage_multi,
+            cancel_usage,
             get_data_dirs,
+            get_data_dir_resolution,
+            get_parse_health,
+            get_timezone_info,
+            get_watcher_debounce,
+            set_watcher_debounce,
+            get_watcher_stats,
+            get_watcher_status,
+            get_schema,
+            get_access_violations,
+            clear_cache,
+            load_fixture,
+            get_event_log,
+            resync,
+            get_sandbox_status,
+            request_data_dir_access,
             get_webkit_env,
+            get_app_info,
+            get_perf_stats,
+            reset_perf_stats,
+            get_renderer_info,
+            check_for_updates,
+            get_update_check,
+            set_update_check,
+            get_last_crash,
+            dismiss_last_crash,
+            set_webkit_workaround,
             set_api_key,
             get_api_key_status,
+            set_api_keys,
+            list_workspaces,
+            get_selected_workspaces,
+            set_selected_workspaces,
             clear_api_key,
             validate_api_key,
+            get_daily_digest_settings,
+            set_daily_digest,
+            get_weekly_report_settings,
+            set_weekly_report,
+            send_test_report,
+            export_time_entries,
+            get_format_prefs,
+            set_format_prefs,
+            get_notification_prefs,
+            set_notification_prefs,
+            test_notification,
+            get_alert_rules,
+            set_alert_rules,
+            get_alert_status,
+            get_goals,
+            set_goals,
+            show_widget,
+            refresh_now,
+            set_notifications_paused,
+            get_display_period,
+            set_display_period,
+            quit_app,
+            get_team_baseline,
+            set_team_baseline,
+            get_team_seats,
+            set_team_seats,
+            get_http_server_settings,
+            set_http_server_settings,
+            get_usage_source,
+            set_usage_source,
+            get_limit_history,
+            get_quota_model,
+            set_quota_weights,
+            get_quota_windows,
+            set_quota_windows,
+            get_provider_pricing,
+            set_provider_pricing,
+            get_optimization_hints,
+            plan_usage,
+            get_failed_requests,
+            get_project_week_matrix,
+            get_weekly_project_series,
+            get_hourly_series,
+            get_daily_delta,
+            compare_usage,
+            search_sessions,
+            get_excluded_sessions,
+            exclude_session,
+            include_session,
+            get_session_context_history,
+            open_session_in_terminal,
+            request_session_termination,
+            terminate_session_process,
+            import_history,
+            get_imported_history,
+            verify_store,
+            get_project_rules,
+            set_project_rules,
+            set_group_projects_by_remote,
+            get_working_hours,
+            set_working_hours,
+            get_allowed_data_roots,
+            set_allowed_data_roots,
+            get_week_starts_on,
+            set_week_starts_on,
+            get_billing_cycle_anchor_day,
+            set_billing_cycle_anchor_day,
+            get_days_off,
+            set_days_off,
+            import_days_off,
+            get_low_memory,
+            set_low_memory,
+            get_entries_stream,
+            set_entries_stream,
+            get_all_users,
+            set_all_users,
+            get_summary_file_settings,
+            set_summary_file_settings,
+            get_privacy_mode,
+            set_privacy_mode,
+            get_tray_icon_settings,
+            set_tray_icon_settings,
         ])
         .setup(move |app| {
             // Initialize D-Bus service on Linux
             // Runs on a dedicated thread with its own tokio runtime to keep the connection alive
             #[cfg(target_os = "linux")]
-            let dbus_handle = dbus_service::spawn_dbus_service();
+            let dbus_handle = dbus_service::spawn_dbus_service(Some(app.handle().clone()));
 
             #[cfg(target_os = "linux")]
             setup_file_watcher(app.handle().clone(), dbus_handle);
 
+            // Named pipe (Windows) or local socket (macOS) equivalent elsewhere
             #[cfg(not(target_os = "linux"))]
-            setup_file_watcher(app.handle().clone());
+            let ipc_handle = ipc_service::spawn_ipc_service(Some(app.handle().clone()));
+
+            #[cfg(not(target_os = "linux"))]
+            setup_file_watcher(app.handle().clone(), ipc_handle);
 
             // Monitor system suspend/resume to handle WebKit process recovery
             #[cfg(target_os = "linux")]
             setup_suspend_monitor(app.handle().clone());
 
+            // Refresh immediately on screen unlock and network reconnect
+            #[cfg(target_os = "linux")]
+            setup_wake_refresh_monitor(app.handle().clone());
+
+            // Periodic jobs (daily digest)
+            scheduler::spawn_scheduler(notifications::Notifier::App(app.handle().clone()));
+
+            // Optional read-only HTTP dashboard
+            let provider: http_server::StatsProvider = Arc::new(|period: &str| {
+                tauri::async_runtime::block_on(load_usage(state::get(), period.to_string()))
+            });
+            http_server::spawn_http_server(config::load_config().http_server, provider);
+
+            // Tray icon with quick actions
+            if let Err(e) = tray::setup_tray(app) {
+                log_line!("Failed to create tray icon: {:?}", e);
+            }
+
             // Set window icon for Linux
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.set_icon(load_icon());
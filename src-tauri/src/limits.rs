@@ -0,0 +1,227 @@
+//! Append-only audit log of quota-limit hits.
+//!
+//! Two kinds of events are recorded in `limit_history.jsonl` under the config dir:
+//! - "journal": Claude Code wrote a limit-reached API error into a session journal
+//!   (e.g. "Claude AI usage limit reached|1718000000", "5-hour limit reached ∙ resets 3pm")
+//! - "estimate": the reconstructed 5hr block crossed the estimated limit
+//!
+//! Each event carries the quota state at that moment. Records are only ever appended.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use crate::config;
+use crate::crash::log_line;
+use crate::usage::{
+    collect_jsonl_files, ensure_outside_data_dirs, get_claude_data_dirs, journal_lines,
+    project_name, quota_info_at, QuotaInfo, ESTIMATED_WINDOW_LIMIT,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitEvent {
+    pub timestamp: String,
+    pub source: String, // "journal" or "estimate"
+    pub kind: String,   // "five_hour", "weekly" or "usage_limit"
+    pub message: String,
+    pub session_id: String,
+    pub project: String,
+    pub resets_at: Option<String>,
+    // Usage state at the time of the event
+    pub window_usage_percent: f64,
+    pub week_usage_percent: f64,
+    pub messages_in_window: u32,
+}
+
+impl LimitEvent {
+    fn key(&self) -> String {
+        format!("{}|{}|{}", self.source, self.timestamp, self.session_id)
+    }
+}
+
+fn history_path() -> PathBuf {
+    config::config_dir().join("limit_history.jsonl")
+}
+
+/// All recorded events, oldest first
+pub fn load_limit_history() -> Vec<LimitEvent> {
+    let file = match File::open(history_path()) {
+        Ok(f) => f,
+        Err(_) => return Vec::new(),
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+fn append_events(events: &[LimitEvent]) -> Result<(), String> {
+    let path = history_path();
+    ensure_outside_data_dirs(&path)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open limit history: {e}"))?;
+    for event in events {
+        let line = serde_json::to_string(event).map_err(|e| e.to_string())?;
+        writeln!(file, "{line}").map_err(|e| format!("Failed to append limit history: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Classify a limit message and extract its reset time (`...|<unix seconds>` form)
+fn classify_limit_message(text: &str) -> (String, Option<String>) {
+    let lower = text.to_lowercase();
+    let kind = if lower.contains("weekly") {
+        "weekly"
+    } else if lower.contains("5-hour") {
+        "five_hour"
+    } else {
+        "usage_limit"
+    };
+    let resets_at = text
+        .rsplit_once('|')
+        .and_then(|(_, epoch)| epoch.trim().parse::<i64>().ok())
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .map(|dt| dt.to_rfc3339());
+    (kind.to_string(), resets_at)
+}
+
+/// Limit-reached messages written by Claude Code into journals modified in the lookback
+fn scan_journal_events(lookback_hours: i64) -> Vec<LimitEvent> {
+    let files = collect_jsonl_files(&get_claude_data_dirs(), Some(lookback_hours));
+    let mut events = Vec::new();
+
+    for path in &files {
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(_) => continue,
+        };
+        for line in journal_lines(BufReader::new(file)) {
+            // Cheap prefilter before parsing JSON
+            if !line.contains("limit reached") {
+                continue;
+            }
+            let entry: serde_json::Value = match serde_json::from_str(&line) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+            let text = entry
+                .get("message")
+                .and_then(|m| m.get("content"))
+                .and_then(|c| c.as_array())
+                .and_then(|blocks| {
+                    blocks
+                        .iter()
+                        .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                        .find(|t| t.to_lowercase().contains("limit reached"))
+                })
+                .map(|t| t.to_string());
+            let (text, timestamp) = match (text, entry.get("timestamp").and_then(|t| t.as_str())) {
+                (Some(text), Some(ts)) => (text, ts.to_string()),
+                _ => continue,
+            };
+
+            let cwd = entry.get("cwd").and_then(|c| c.as_str()).unwrap_or("");
+            let (kind, resets_at) = classify_limit_message(&text);
+            events.push(LimitEvent {
+                timestamp,
+                source: "journal".to_string(),
+                kind,
+                message: text,
+                session_id: entry
+                    .get("sessionId")
+                    .and_then(|s| s.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+                project: project_name(cwd),
+                resets_at,
+                window_usage_percent: 0.0,
+                week_usage_percent: 0.0,
+                messages_in_window: 0,
+            });
+        }
+    }
+
+    events
+}
+
+/// One event per 5hr block whose weighted usage reached the estimated limit, from the shared
+/// quota
+fn estimate_event(quota: &QuotaInfo) -> Option<LimitEvent> {
+    if quota.usage_percent < 100.0 {
+        return None;
+    }
+    let start = quota.window_started_at.clone()?;
+    Some(LimitEvent {
+        // Keyed by block start so each block is logged once
+        timestamp: start,
+        source: "estimate".to_string(),
+        kind: "five_hour".to_string(),
+        message: format!(
+            "Estimated 5hr limit reached ({} weighted prompts)",
+            ESTIMATED_WINDOW_LIMIT
+        ),
+        session_id: String::new(),
+        project: String::new(),
+        resets_at: quota.window_ends_at.clone(),
+        window_usage_percent: 0.0,
+        week_usage_percent: 0.0,
+        messages_in_window: 0,
+    })
+}
+
+/// Detect limit hits in journals modified within `lookback_hours`, append the ones not yet
+/// logged (with the quota state at their timestamp) and return them
+pub fn record_new_limit_events(lookback_hours: i64) -> Vec<LimitEvent> {
+    let known: HashSet<String> = load_limit_history().iter().map(LimitEvent::key).collect();
+
+    let current = crate::state::get().quota();
+    let mut candidates = scan_journal_events(lookback_hours);
+    candidates.extend(estimate_event(&current));
+
+    let mut seen = HashSet::new();
+    let mut new_events: Vec<LimitEvent> = candidates
+        .into_iter()
+        .filter(|e| !known.contains(&e.key()) && seen.insert(e.key()))
+        .collect();
+    if new_events.is_empty() {
+        return new_events;
+    }
+
+    for event in &mut new_events {
+        let at = DateTime::parse_from_rfc3339(&event.timestamp)
+            .map(|ts| ts.with_timezone(&Utc))
+            .ok();
+        // Past events are reconstructed; the state of the running block is already known
+        let in_current_block = event.source == "estimate"
+            || at.is_some_and(|at| {
+                current
+                    .window_started_at
+                    .as_deref()
+                    .and_then(|start| DateTime::parse_from_rfc3339(start).ok())
+                    .is_some_and(|start| at >= start)
+            });
+        let quota = match at {
+            Some(at) if !in_current_block => quota_info_at(at),
+            _ => current.clone(),
+        };
+        event.window_usage_percent = quota.usage_percent;
+        event.week_usage_percent = quota.week_usage_percent;
+        event.messages_in_window = quota.messages_in_window;
+    }
+    new_events.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+    if let Err(e) = append_events(&new_events) {
+        log_line!("{e}");
+    }
+    new_events
+}
@@ -0,0 +1,87 @@
+//! Desktop notifications sent by background jobs (scheduler, watchers), honoring the
+//! user's mute, quiet hours and per-alert sounds (`AppConfig::notifications`).
+
+use chrono::Local;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::config::{self, NotificationPrefs};
+use crate::crash::log_line;
+
+pub const DAILY_DIGEST: &str = "daily_digest";
+pub const OFF_HOURS: &str = "off_hours";
+pub const SESSION_COST: &str = "session_cost";
+/// User-defined alert rules, see `alerts`
+pub const ALERT: &str = "alert";
+
+/// Alert kinds, used as keys of `NotificationPrefs::sounds`
+pub const KINDS: [&str; 4] = [DAILY_DIGEST, OFF_HOURS, SESSION_COST, ALERT];
+
+/// Where background jobs deliver notifications: desktop notifications of the app, or the
+/// log in daemon mode, where there is no app to show them
+#[derive(Clone)]
+pub enum Notifier {
+    App(AppHandle),
+    Log,
+}
+
+impl Notifier {
+    /// The app to emit events to, if any
+    pub fn app_handle(&self) -> Option<&AppHandle> {
+        match self {
+            Notifier::App(app_handle) => Some(app_handle),
+            Notifier::Log => None,
+        }
+    }
+
+    /// `send` through the app, or log the notification
+    pub fn send(&self, kind: &str, title: &str, body: &str) {
+        match self {
+            Notifier::App(app_handle) => send(app_handle, kind, title, body),
+            Notifier::Log => log_line!("{title}: {}", body.replace('\n', " / ")),
+        }
+    }
+}
+
+/// Why notifications are currently suppressed, if they are
+pub fn suppressed_reason(prefs: &NotificationPrefs) -> Option<&'static str> {
+    if prefs.muted {
+        return Some("notifications are muted");
+    }
+    if prefs
+        .quiet_hours
+        .as_ref()
+        .is_some_and(|quiet| quiet.contains(Local::now().time()))
+    {
+        return Some("quiet hours are in effect");
+    }
+    None
+}
+
+/// Show a desktop notification of the given kind, logging (not propagating) failures.
+/// Dropped while muted or during quiet hours.
+pub fn send(app_handle: &AppHandle, kind: &str, title: &str, body: &str) {
+    let prefs = config::load_config().notifications;
+    if let Some(reason) = suppressed_reason(&prefs) {
+        log_line!("Notification \"{title}\" not shown: {reason}");
+        return;
+    }
+    show(app_handle, &prefs, kind, title, body);
+}
+
+/// Show a notification regardless of mute and quiet hours
+pub fn show(
+    app_handle: &AppHandle,
+    prefs: &NotificationPrefs,
+    kind: &str,
+    title: &str,
+    body: &str,
+) {
+    let mut builder = app_handle.notification().builder().title(title).body(body);
+    if let Some(sound) = prefs.sounds.get(kind).filter(|sound| !sound.is_empty()) {
+        builder = builder.sound(sound.as_str());
+    }
+    if let Err(e) = builder.show() {
+        log_line!("Failed to show notification: {e}");
+    }
+}
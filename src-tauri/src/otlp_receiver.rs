@@ -0,0 +1,265 @@
+//! Optional OTLP/HTTP receiver for Claude Code's OpenTelemetry export.
+//!
+//! With `usage_source` set to "otlp", Claude Code exports its log events here
+//! (`POST /v1/logs`, JSON encoding) and usage is counted from them instead of from the
+//! journals. Metrics sent to `/v1/metrics` are accepted and dropped so exporters don't
+//! retry them. Protobuf bodies are rejected: set `OTEL_EXPORTER_OTLP_PROTOCOL=http/json`.
+//!
+//! Ingested events are appended to `otlp-events.jsonl` in the config dir and restored at
+//! startup. The file is pruned to `OTLP_RETENTION_DAYS` at startup and then once a day.
+
+use chrono::{DateTime, Utc};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::{self, OtlpReceiverConfig};
+use crate::crash::log_line;
+use crate::http_server::ConnectionSlot;
+use crate::usage::{ingest_otlp_logs, restore_otlp_entries, ParsedEntry, OTLP_RETENTION_DAYS};
+
+/// Called with the entries of each non-empty batch of ingested events, to refresh the widget
+pub type IngestHook = Arc<dyn Fn(&[ParsedEntry]) + Send + Sync>;
+
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_HEADER_LINES: usize = 64;
+/// Request line and headers are cut off after this many bytes
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+/// Larger export batches are rejected
+const MAX_BODY_BYTES: usize = 8 * 1024 * 1024;
+/// Connections handled at once; more are answered with 503 until one finishes
+const MAX_CONNECTIONS: usize = 16;
+/// How often the events file is rewritten without the expired events
+const PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Serializes writes to the events file across connections; holds when it was last pruned
+static EVENTS_FILE_LOCK: Mutex<Option<Instant>> = Mutex::new(None);
+
+static OPEN_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether a protobuf export was rejected, so the hint is logged once
+static PROTOBUF_REJECTED: AtomicBool = AtomicBool::new(false);
+
+struct Request {
+    method: String,
+    path: String,
+    content_type: String,
+    body: Vec<u8>,
+}
+
+fn events_path() -> PathBuf {
+    config::config_dir().join("otlp-events.jsonl")
+}
+
+/// Load the saved events and rewrite the file without the expired ones
+pub fn restore_saved_events() {
+    let contents = match fs::read_to_string(events_path()) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+    let saved: Vec<ParsedEntry> = contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    restore_otlp_entries(saved);
+
+    if let Ok(mut last_pruned) = EVENTS_FILE_LOCK.lock() {
+        prune_events_file(&mut last_pruned);
+    }
+}
+
+/// Rewrite the events file without the events older than `OTLP_RETENTION_DAYS`. The file
+/// is filtered rather than rewritten from the ingested entries, which may hold a batch
+/// another connection is about to append.
+fn prune_events_file(last_pruned: &mut Option<Instant>) {
+    *last_pruned = Some(Instant::now());
+    let path = events_path();
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return;
+    };
+    let cutoff = Utc::now() - chrono::Duration::days(OTLP_RETENTION_DAYS);
+    let kept: String = contents
+        .lines()
+        .filter(|line| {
+            serde_json::from_str::<ParsedEntry>(line)
+                .ok()
+                .and_then(|entry| DateTime::parse_from_rfc3339(&entry.timestamp).ok())
+                .is_some_and(|ts| ts >= cutoff)
+        })
+        .map(|line| format!("{line}\n"))
+        .collect();
+    if let Err(e) = fs::write(&path, kept) {
+        log_line!("Failed to prune OTLP events: {e}");
+    }
+}
+
+fn save_events(entries: &[ParsedEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+    let Ok(mut last_pruned) = EVENTS_FILE_LOCK.lock() else {
+        return;
+    };
+    if last_pruned.is_none_or(|at| at.elapsed() >= PRUNE_INTERVAL) {
+        prune_events_file(&mut last_pruned);
+    }
+    let path = events_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| {
+            for entry in entries {
+                if let Ok(line) = serde_json::to_string(entry) {
+                    writeln!(file, "{line}")?;
+                }
+            }
+            Ok(())
+        });
+    if let Err(e) = result {
+        log_line!("Failed to save OTLP events to {}: {e}", path.display());
+    }
+}
+
+pub fn spawn_otlp_receiver(settings: OtlpReceiverConfig, on_ingest: IngestHook) {
+    thread::spawn(move || {
+        let addr = format!("{}:{}", settings.bind_address, settings.port);
+        let listener = match TcpListener::bind(&addr) {
+            Ok(l) => l,
+            Err(e) => {
+                log_line!("Failed to start OTLP receiver on {addr}: {e}");
+                return;
+            }
+        };
+        log_line!("OTLP receiver listening on http://{addr}/v1/logs");
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+            let Some(slot) = ConnectionSlot::acquire(&OPEN_CONNECTIONS, MAX_CONNECTIONS) else {
+                respond(stream, "503 Service Unavailable", "Too many connections");
+                continue;
+            };
+            let on_ingest = Arc::clone(&on_ingest);
+            thread::spawn(move || {
+                let _slot = slot;
+                handle_connection(stream, &on_ingest);
+            });
+        }
+    });
+}
+
+fn handle_connection(stream: TcpStream, on_ingest: &IngestHook) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let (status, body) = match read_request(&stream) {
+        Some(request) => route(&request, on_ingest),
+        None => ("400 Bad Request", "Bad request".to_string()),
+    };
+    respond(stream, status, &body);
+}
+
+fn respond(mut stream: TcpStream, status: &str, body: &str) {
+    let content_type = if status.starts_with("200") {
+        "application/json"
+    } else {
+        "text/plain; charset=utf-8"
+    };
+
+    let head = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    let _ = stream.write_all(head.as_bytes());
+    let _ = stream.write_all(body.as_bytes());
+}
+
+fn read_request(stream: &TcpStream) -> Option<Request> {
+    let mut reader = BufReader::new(stream.take((MAX_HEADER_BYTES + MAX_BODY_BYTES) as u64));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.split('?').next()?.to_string();
+
+    let mut content_type = String::new();
+    let mut content_length = 0;
+    for _ in 0..MAX_HEADER_LINES {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-type") {
+                content_type = value.trim().to_ascii_lowercase();
+            } else if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().ok()?;
+            }
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        return None;
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).ok()?;
+    Some(Request {
+        method,
+        path,
+        content_type,
+        body,
+    })
+}
+
+fn route(request: &Request, on_ingest: &IngestHook) -> (&'static str, String) {
+    if request.method != "POST" {
+        return (
+            "405 Method Not Allowed",
+            "Only POST is supported".to_string(),
+        );
+    }
+    if !request.content_type.starts_with("application/json") {
+        if request.content_type.starts_with("application/x-protobuf")
+            && !PROTOBUF_REJECTED.swap(true, Ordering::Relaxed)
+        {
+            log_line!(
+                "OTLP export rejected: protobuf isn't supported, set OTEL_EXPORTER_OTLP_PROTOCOL=http/json"
+            );
+        }
+        return (
+            "415 Unsupported Media Type",
+            "Only OTLP/HTTP with JSON encoding is supported (OTEL_EXPORTER_OTLP_PROTOCOL=http/json)"
+                .to_string(),
+        );
+    }
+
+    match request.path.as_str() {
+        "/v1/logs" => match ingest_otlp_logs(&request.body) {
+            Ok(entries) => {
+                save_events(&entries);
+                if !entries.is_empty() {
+                    on_ingest(&entries);
+                }
+                ("200 OK", "{}".to_string())
+            }
+            Err(e) => ("400 Bad Request", e),
+        },
+        // Usage is taken from the log events; metrics would count it twice
+        "/v1/metrics" | "/v1/traces" => ("200 OK", "{}".to_string()),
+        _ => ("404 Not Found", "Not found".to_string()),
+    }
+}
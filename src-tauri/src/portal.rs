@@ -0,0 +1,128 @@
+//! XDG desktop portal access for sandboxed (Flatpak) installs.
+//!
+//! Inside Flatpak the app can't see `~/.claude` unless the user grants access. The
+//! FileChooser portal lets the user pick the directory; the returned document-portal path
+//! is stored in config and picked up by data dir resolution and the file watcher.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use zbus::zvariant::{OwnedValue, Value};
+use zbus::Connection;
+
+pub fn is_flatpak() -> bool {
+    Path::new("/.flatpak-info").exists() || std::env::var_os("FLATPAK_ID").is_some()
+}
+
+/// "flatpak", "snap" or "none"
+pub fn sandbox_kind() -> &'static str {
+    if is_flatpak() {
+        "flatpak"
+    } else if std::env::var_os("SNAP").is_some() {
+        "snap"
+    } else {
+        "none"
+    }
+}
+
+/// Ask the user to pick their Claude data directory through the FileChooser portal.
+/// Returns the Claude root (the directory containing `projects/`) as seen from inside the sandbox.
+pub async fn pick_claude_dir() -> Result<PathBuf, String> {
+    let conn = Connection::session()
+        .await
+        .map_err(|e| format!("Failed to connect to session D-Bus: {e}"))?;
+
+    // The portal publishes its Response on a request path derived from our unique name and token
+    let token = format!("ccusage{}", std::process::id());
+    let sender = conn
+        .unique_name()
+        .map(|name| name.as_str().trim_start_matches(':').replace('.', "_"))
+        .ok_or_else(|| "D-Bus connection has no unique name".to_string())?;
+    let handle_path = format!("/org/freedesktop/portal/desktop/request/{sender}/{token}");
+
+    // Subscribe before calling OpenFile so the Response signal can't be missed
+    let rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface("org.freedesktop.portal.Request")
+        .map_err(|e| e.to_string())?
+        .member("Response")
+        .map_err(|e| e.to_string())?
+        .path(handle_path.as_str())
+        .map_err(|e| e.to_string())?
+        .build();
+    let mut stream = zbus::MessageStream::for_match_rule(rule, &conn, None)
+        .await
+        .map_err(|e| format!("Failed to subscribe to portal response: {e}"))?;
+
+    let mut options: HashMap<&str, Value> = HashMap::new();
+    options.insert("handle_token", Value::from(token.as_str()));
+    options.insert("directory", Value::from(true));
+    options.insert("modal", Value::from(true));
+
+    conn.call_method(
+        Some("org.freedesktop.portal.Desktop"),
+        "/org/freedesktop/portal/desktop",
+        Some("org.freedesktop.portal.FileChooser"),
+        "OpenFile",
+        &("", "Select your Claude Code data directory (~/.claude)", options),
+    )
+    .await
+    .map_err(|e| format!("FileChooser portal request failed: {e}"))?;
+
+    while let Some(msg) = futures_util::StreamExt::next(&mut stream).await {
+        let msg = match msg {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        let (response, results) = msg
+            .body()
+            .deserialize::<(u32, HashMap<String, OwnedValue>)>()
+            .map_err(|e| format!("Invalid portal response: {e}"))?;
+
+        // 0 = success, 1 = cancelled by user, 2 = other error
+        if response != 0 {
+            return Err("Directory selection was cancelled".to_string());
+        }
+
+        let uris: Vec<String> = results
+            .get("uris")
+            .and_then(|v| v.try_clone().ok())
+            .and_then(|v| Vec::<String>::try_from(v).ok())
+            .unwrap_or_default();
+
+        let dir = uris
+            .first()
+            .and_then(|uri| file_uri_to_path(uri))
+            .ok_or_else(|| "Portal returned no directory".to_string())?;
+
+        // Accept either ~/.claude or ~/.claude/projects
+        if dir.file_name().map(|n| n == "projects").unwrap_or(false) {
+            if let Some(parent) = dir.parent() {
+                return Ok(parent.to_path_buf());
+            }
+        }
+        return Ok(dir);
+    }
+
+    Err("Portal request ended without a response".to_string())
+}
+
+/// Convert a `file://` URI into a path, decoding percent-escapes
+fn file_uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
@@ -0,0 +1,167 @@
+//! Privacy mode: project names, directories and teammates' emails are replaced with stable
+//! pseudonyms in everything the app outputs (commands, HTTP dashboard, summary file,
+//! exports, notifications), so the widget can be screen-shared without leaking client names.
+//!
+//! Pseudonyms are derived from the project's directory name with FNV-1a, so the same
+//! project always gets the same pseudonym across refreshes and restarts.
+
+use crate::cost_guard::SessionCostExceeded;
+use crate::limits::LimitEvent;
+use crate::store::StoreVerification;
+use crate::usage::{
+    ActivityBlock, DailyDelta, DailyDigest, FailedRequestReport, ProjectWeekMatrix, SessionMatch,
+    UsageComparison, UsageStats, WeeklyProjectSeries,
+};
+
+/// Stable pseudonym for a project, given its directory path or name
+pub fn pseudonym(project_or_dir: &str) -> String {
+    let name = project_or_dir
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or(project_or_dir);
+    if name.is_empty() {
+        return String::new();
+    }
+    format!("project-{:06x}", fnv1a(name) & 0xff_ffff)
+}
+
+/// FNV-1a: fixed and stable across Rust versions, unlike `DefaultHasher`
+fn fnv1a(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+pub fn apply_to_stats(stats: &mut UsageStats) {
+    for session in &mut stats.active_sessions {
+        session.project = pseudonym(&session.project);
+        session.directory = session.project.clone();
+        session.user = session.user.as_deref().map(user_pseudonym);
+    }
+    for usage in &mut stats.by_user {
+        usage.user = user_pseudonym(&usage.user);
+    }
+    for entry in stats
+        .productivity
+        .projects
+        .iter_mut()
+        .chain(stats.productivity.sessions.iter_mut())
+    {
+        if entry.key == entry.project {
+            entry.key = pseudonym(&entry.key);
+        }
+        entry.project = pseudonym(&entry.project);
+    }
+    if let Some(ref mut analytics) = stats.org_analytics {
+        for user in &mut analytics.users {
+            user.user = user_pseudonym(&user.user);
+        }
+    }
+    // Journal paths name the project directory
+    for issue in &mut stats.degraded {
+        issue.path = pseudonym(&issue.path);
+    }
+}
+
+/// Stable pseudonym for a teammate's email address, API key name or OS user name
+pub fn user_pseudonym(user: &str) -> String {
+    format!("user-{:06x}", fnv1a(user) & 0xff_ffff)
+}
+
+pub fn apply_to_blocks(blocks: &mut [ActivityBlock]) {
+    for block in blocks {
+        block.project = pseudonym(&block.project);
+    }
+}
+
+pub fn apply_to_digest(digest: &mut DailyDigest) {
+    digest.top_project = digest.top_project.as_deref().map(pseudonym);
+}
+
+pub fn apply_to_daily_delta(delta: &mut DailyDelta) {
+    for day in [&mut delta.today, &mut delta.yesterday] {
+        for project in &mut day.projects {
+            *project = pseudonym(project);
+        }
+    }
+    for project in &mut delta.new_projects {
+        *project = pseudonym(project);
+    }
+}
+
+pub fn apply_to_comparison(comparison: &mut UsageComparison) {
+    for change in &mut comparison.by_project {
+        change.key = pseudonym(&change.key);
+        change.display_name = change.key.clone();
+    }
+}
+
+/// Journal paths name the project directory
+pub fn apply_to_store_verification(report: &mut StoreVerification) {
+    if let Some(ref mut cache) = report.cache {
+        for drift in &mut cache.drifted {
+            drift.source = pseudonym(&drift.source);
+        }
+    }
+}
+
+pub fn apply_to_project_weeks(matrix: &mut ProjectWeekMatrix) {
+    for row in &mut matrix.projects {
+        row.project = pseudonym(&row.project);
+    }
+}
+
+pub fn apply_to_project_series(series: &mut WeeklyProjectSeries) {
+    for project in series.series.iter_mut().filter(|project| !project.other) {
+        project.project = pseudonym(&project.project);
+    }
+}
+
+/// Session titles are summaries of the conversation, so they are dropped
+pub fn apply_to_session_matches(sessions: &mut [SessionMatch]) {
+    for session in sessions {
+        session.project = pseudonym(&session.project);
+        session.directory = session.project.clone();
+        session.title = None;
+        session.user = session.user.as_deref().map(user_pseudonym);
+    }
+}
+
+pub fn apply_to_limit_events(events: &mut [LimitEvent]) {
+    for event in events {
+        event.project = pseudonym(&event.project);
+    }
+}
+
+pub fn apply_to_cost_alerts(alerts: &mut [SessionCostExceeded]) {
+    for alert in alerts {
+        alert.project = pseudonym(&alert.project);
+        alert.directory = alert.project.clone();
+    }
+}
+
+pub fn apply_to_failed_requests(report: &mut FailedRequestReport) {
+    for session in &mut report.by_session {
+        session.project = pseudonym(&session.project);
+        session.directory = session.project.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pseudonyms_are_stable_and_named_after_the_directory() {
+        // Fixed values: pseudonyms must not change between releases
+        assert_eq!(pseudonym("api"), "project-6aae07");
+        assert_eq!(user_pseudonym("alice@example.com"), "user-ff2a46");
+
+        assert_eq!(pseudonym("/home/user/work/api"), pseudonym("api"));
+        assert_eq!(pseudonym("/home/user/work/api/"), pseudonym("api"));
+        assert_ne!(pseudonym("/home/user/work/web"), pseudonym("api"));
+        assert_eq!(pseudonym(""), "");
+        assert_eq!(pseudonym("/"), "");
+    }
+}
@@ -0,0 +1,316 @@
+//! Running Claude Code processes, matched to sessions so "active" can mean an actually
+//! running CLI rather than only recent journal writes, and an opt-in emergency stop for them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, Signal, System, UpdateKind};
+
+use crate::usage::{self, ActiveSession};
+
+/// How long a termination confirmation token stays valid
+const CONFIRMATION_TTL: Duration = Duration::from_secs(60);
+
+/// token -> (session id, pid, issued at)
+static PENDING_TERMINATIONS: Mutex<Option<HashMap<String, (String, u32, Instant)>>> =
+    Mutex::new(None);
+
+/// A running `claude` CLI
+#[derive(Debug, Clone)]
+pub struct ClaudeProcess {
+    pub pid: u32,
+    pub cwd: PathBuf,
+    /// From `--resume <id>` / `--session-id <id>` on its command line
+    pub session_id: Option<String>,
+}
+
+/// Whether a process is the Claude Code CLI: the native `claude` binary, or a JavaScript
+/// runtime running the `claude` script or the `@anthropic-ai/claude-code` package
+fn is_claude(name: &str, cmd: &[String]) -> bool {
+    let stem = |s: &str| {
+        Path::new(s)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_lowercase())
+    };
+    if stem(name).as_deref() == Some("claude") {
+        return true;
+    }
+    let runtime = matches!(stem(name).as_deref(), Some("node" | "bun" | "deno"));
+    runtime
+        && cmd.get(1).is_some_and(|script| {
+            script.contains("claude-code") || stem(script).as_deref() == Some("claude")
+        })
+}
+
+fn session_arg(cmd: &[String]) -> Option<String> {
+    let mut args = cmd.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--resume" | "-r" | "--session-id" => return args.next().cloned(),
+            _ => {
+                for prefix in ["--resume=", "--session-id="] {
+                    if let Some(id) = arg.strip_prefix(prefix) {
+                        return Some(id.to_string());
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+fn refresh(system: &mut System, processes: ProcessesToUpdate) {
+    system.refresh_processes_specifics(
+        processes,
+        true,
+        ProcessRefreshKind::new()
+            .with_cmd(UpdateKind::Always)
+            .with_cwd(UpdateKind::Always),
+    );
+}
+
+fn as_claude_process(pid: Pid, process: &sysinfo::Process) -> Option<ClaudeProcess> {
+    // Linux lists threads too
+    if process.thread_kind().is_some() {
+        return None;
+    }
+    let name = process.name().to_string_lossy().to_string();
+    let cmd: Vec<String> = process
+        .cmd()
+        .iter()
+        .map(|arg| arg.to_string_lossy().to_string())
+        .collect();
+    if !is_claude(&name, &cmd) {
+        return None;
+    }
+    Some(ClaudeProcess {
+        pid: pid.as_u32(),
+        cwd: process.cwd()?.to_path_buf(),
+        session_id: session_arg(&cmd),
+    })
+}
+
+/// Scan the process table for Claude Code CLIs whose working directory can be read
+pub fn running_claude_processes() -> Vec<ClaudeProcess> {
+    let mut system = System::new();
+    refresh(&mut system, ProcessesToUpdate::All);
+    system
+        .processes()
+        .iter()
+        .filter_map(|(pid, process)| as_claude_process(*pid, process))
+        .collect()
+}
+
+/// Mark sessions with a running process: first by the session id on its command line (active
+/// sessions carry a prefix of it), then by working directory, each process going to the most
+/// recently active session in its cwd
+pub fn annotate_sessions(sessions: &mut [ActiveSession], processes: &[ClaudeProcess]) {
+    let mut order: Vec<usize> = (0..sessions.len()).collect();
+    order.sort_by(|&a, &b| sessions[b].last_activity.cmp(&sessions[a].last_activity));
+
+    let mut unmatched: Vec<&ClaudeProcess> = Vec::new();
+    for process in processes {
+        let by_id = process.session_id.as_deref().and_then(|id| {
+            sessions
+                .iter_mut()
+                .find(|s| !s.session_id.is_empty() && id.starts_with(&s.session_id))
+        });
+        match by_id {
+            Some(session) => {
+                session.process_running = true;
+                session.pid = Some(process.pid);
+            }
+            None => unmatched.push(process),
+        }
+    }
+    for process in unmatched {
+        let by_cwd = order.iter().copied().find(|&i| {
+            !sessions[i].process_running && Path::new(&sessions[i].directory) == process.cwd
+        });
+        if let Some(i) = by_cwd {
+            sessions[i].process_running = true;
+            sessions[i].pid = Some(process.pid);
+        }
+    }
+}
+
+/// A pending `terminate_session_process`, to be confirmed with `token`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminationRequest {
+    pub session_id: String,
+    pub pid: u32,
+    pub directory: String,
+    pub token: String,
+    pub expires_in_secs: u64,
+}
+
+/// The process running a session: the one started with its id, else the only one in its
+/// working directory. `session_id` is the full id or the prefix shown for active sessions.
+/// Several processes matching either way are ambiguous and refused.
+fn session_process(session_id: &str) -> Result<ClaudeProcess, String> {
+    let prefix = session_id.trim().to_lowercase();
+    if !usage::valid_session_prefix(&prefix) {
+        return Err(format!("Invalid session id '{session_id}'"));
+    }
+    let processes = running_claude_processes();
+    let mut by_id = processes.iter().filter(|p| {
+        p.session_id
+            .as_deref()
+            .is_some_and(|id| id.to_lowercase().starts_with(&prefix))
+    });
+    match (by_id.next(), by_id.next()) {
+        (Some(process), None) => return Ok(process.clone()),
+        (Some(_), Some(_)) => {
+            return Err(format!(
+                "Several Claude Code processes match session {session_id}; use a longer id"
+            ))
+        }
+        (None, _) => {}
+    }
+    let (_, dir) = usage::session_directory(session_id)?;
+    let mut in_dir = processes.into_iter().filter(|p| p.cwd == dir);
+    match (in_dir.next(), in_dir.next()) {
+        (Some(process), None) => Ok(process),
+        (None, _) => Err(format!(
+            "No running Claude Code process for session {session_id}"
+        )),
+        (Some(_), Some(_)) => Err(format!(
+            "Several Claude Code processes run in {}; can't tell which is session {session_id}",
+            dir.display()
+        )),
+    }
+}
+
+/// First step of the emergency stop: find the session's process and issue a token that
+/// `terminate_session` accepts for `CONFIRMATION_TTL`
+pub fn request_termination(session_id: &str) -> Result<TerminationRequest, String> {
+    let process = session_process(session_id)?;
+    let token = format!(
+        "{:016x}",
+        RandomState::new().hash_one((session_id, process.pid, Instant::now()))
+    );
+    let mut pending = PENDING_TERMINATIONS
+        .lock()
+        .map_err(|_| "Termination state unavailable".to_string())?;
+    let pending = pending.get_or_insert_with(HashMap::new);
+    pending.retain(|_, (_, _, issued)| issued.elapsed() < CONFIRMATION_TTL);
+    pending.insert(
+        token.clone(),
+        (session_id.to_string(), process.pid, Instant::now()),
+    );
+    Ok(TerminationRequest {
+        session_id: session_id.to_string(),
+        pid: process.pid,
+        directory: process.cwd.display().to_string(),
+        token,
+        expires_in_secs: CONFIRMATION_TTL.as_secs(),
+    })
+}
+
+/// Second step: with a token from `request_termination` for the same session, send `signal`
+/// ("term", the default, or "int") to its process. Returns the pid signalled.
+pub fn terminate_session(session_id: &str, token: &str, signal: &str) -> Result<u32, String> {
+    let signal = match signal {
+        "term" => Signal::Term,
+        "int" => Signal::Interrupt,
+        other => {
+            return Err(format!(
+                "Unsupported signal '{other}' (expected term or int)"
+            ))
+        }
+    };
+    let (expected_session, pid) = {
+        let mut pending = PENDING_TERMINATIONS
+            .lock()
+            .map_err(|_| "Termination state unavailable".to_string())?;
+        // Tokens are single use
+        match pending.as_mut().and_then(|p| p.remove(token)) {
+            Some((session, pid, issued)) if issued.elapsed() < CONFIRMATION_TTL => (session, pid),
+            Some(_) => return Err("Confirmation expired; request it again".to_string()),
+            None => return Err("Unknown confirmation token".to_string()),
+        }
+    };
+    if expected_session != session_id {
+        return Err("Confirmation token was issued for another session".to_string());
+    }
+
+    // The pid must still be the same Claude process, not a reused one
+    let mut system = System::new();
+    let sys_pid = Pid::from_u32(pid);
+    refresh(&mut system, ProcessesToUpdate::Some(&[sys_pid]));
+    let process = system
+        .process(sys_pid)
+        .filter(|process| as_claude_process(sys_pid, process).is_some())
+        .ok_or_else(|| format!("Process {pid} is no longer running Claude Code"))?;
+    // Windows has no signals; terminate the process instead
+    let sent = process.kill_with(signal).unwrap_or_else(|| process.kill());
+    if !sent {
+        return Err(format!("Failed to signal process {pid}"));
+    }
+    Ok(pid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(session_id: &str, directory: &str, last_activity: &str) -> ActiveSession {
+        serde_json::from_value(serde_json::json!({
+            "session_id": session_id,
+            "project": "project",
+            "directory": directory,
+            "first_activity": "2025-03-10T09:00:00Z",
+            "last_activity": last_activity,
+            "duration_minutes": 0,
+            "message_count": 1,
+            "total_tokens": 0,
+            "cost_usd": 0.0,
+            "model": "claude-sonnet-4-5-20250929",
+            "model_display_name": "Sonnet 4.5",
+            "context_remaining_percent": 100.0,
+            "todo_count": 0,
+        }))
+        .unwrap()
+    }
+
+    fn process(pid: u32, cwd: &str, session_id: Option<&str>) -> ClaudeProcess {
+        ClaudeProcess {
+            pid,
+            cwd: PathBuf::from(cwd),
+            session_id: session_id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn processes_match_truncated_session_ids() {
+        // Active sessions carry the first 8 characters of the id, processes the full id
+        let mut sessions = [
+            session("3f2a9c1e", "/home/user/api", "2025-03-10T10:00:00Z"),
+            session("7b41d0aa", "/home/user/api", "2025-03-10T11:00:00Z"),
+        ];
+        let processes = [process(
+            42,
+            "/home/user/elsewhere",
+            Some("3f2a9c1e-0000-4000-8000-000000000001"),
+        )];
+        annotate_sessions(&mut sessions, &processes);
+        assert!(sessions[0].process_running);
+        assert_eq!(sessions[0].pid, Some(42));
+        assert!(!sessions[1].process_running);
+    }
+
+    #[test]
+    fn processes_without_an_id_go_to_the_latest_session_in_their_cwd() {
+        let mut sessions = [
+            session("3f2a9c1e", "/home/user/api", "2025-03-10T10:00:00Z"),
+            session("7b41d0aa", "/home/user/api", "2025-03-10T11:00:00Z"),
+        ];
+        annotate_sessions(&mut sessions, &[process(7, "/home/user/api", None)]);
+        assert!(!sessions[0].process_running);
+        assert_eq!(sessions[1].pid, Some(7));
+    }
+}
@@ -0,0 +1,133 @@
+//! WebKitGTK rendering workarounds and GPU/session detection.
+//!
+//! Each workaround maps to an environment variable read by WebKitGTK at startup. A
+//! workaround can be forced on/off in config; left unset ("auto"), it's applied only when
+//! the detected environment is known to need it.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::OnceLock;
+
+#[cfg(target_os = "linux")]
+use crate::config::WebkitWorkarounds;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkaroundStatus {
+    pub name: String,
+    pub env_var: String,
+    pub setting: String, // "auto", "on" or "off"
+    pub applied: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RendererInfo {
+    pub session_type: String,
+    pub nvidia_detected: bool,
+    pub wayland: bool,
+    pub workarounds: Vec<WorkaroundStatus>,
+}
+
+/// Workarounds decided at startup (env vars can't change once WebKit has initialized)
+static APPLIED: OnceLock<Vec<WorkaroundStatus>> = OnceLock::new();
+
+fn session_type() -> String {
+    std::env::var("XDG_SESSION_TYPE").unwrap_or_else(|_| "unknown".to_string())
+}
+
+fn is_wayland() -> bool {
+    session_type() == "wayland" || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// NVIDIA proprietary driver loaded, or an NVIDIA (PCI vendor 0x10de) DRM device present
+fn is_nvidia() -> bool {
+    if Path::new("/proc/driver/nvidia/version").exists() {
+        return true;
+    }
+    glob::glob("/sys/class/drm/card*/device/vendor")
+        .map(|paths| {
+            paths.flatten().any(|path| {
+                std::fs::read_to_string(path)
+                    .map(|vendor| vendor.trim() == "0x10de")
+                    .unwrap_or(false)
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Decide and set the WebKit environment variables. Must run before the webview is created.
+#[cfg(target_os = "linux")]
+pub fn apply_webkit_workarounds(settings: &WebkitWorkarounds) {
+    let nvidia = is_nvidia();
+    let wayland = is_wayland();
+
+    // (name, env var, configured value, auto default)
+    let table = [
+        // Transparent window rendering bug: https://github.com/tauri-apps/tauri/issues/10626
+        (
+            "disable_compositing",
+            "WEBKIT_DISABLE_COMPOSITING_MODE",
+            settings.disable_compositing,
+            nvidia || wayland,
+        ),
+        (
+            "disable_dmabuf",
+            "WEBKIT_DISABLE_DMABUF_RENDERER",
+            settings.disable_dmabuf,
+            nvidia || wayland,
+        ),
+        // WebKit's multi-process IPC can break after suspend/resume ("Could not connect to
+        // localhost"); most reports come from NVIDIA systems
+        (
+            "disable_sandbox",
+            "WEBKIT_DISABLE_SANDBOX_THIS_IS_DANGEROUS",
+            settings.disable_sandbox,
+            nvidia,
+        ),
+        (
+            "single_web_process",
+            "WEBKIT_USE_SINGLE_WEB_PROCESS",
+            settings.single_web_process,
+            nvidia,
+        ),
+        (
+            "disable_gpu",
+            "WEBKIT_DISABLE_GPU",
+            settings.disable_gpu,
+            nvidia,
+        ),
+    ];
+
+    let statuses = table
+        .iter()
+        .map(|&(name, env_var, configured, auto_default)| {
+            // A value already present in the environment (set by the user) always wins
+            let preset = std::env::var_os(env_var).is_some();
+            let apply = configured.unwrap_or(auto_default);
+            if apply && !preset {
+                std::env::set_var(env_var, "1");
+            }
+            WorkaroundStatus {
+                name: name.to_string(),
+                env_var: env_var.to_string(),
+                setting: match configured {
+                    None => "auto",
+                    Some(true) => "on",
+                    Some(false) => "off",
+                }
+                .to_string(),
+                applied: apply || preset,
+            }
+        })
+        .collect();
+
+    let _ = APPLIED.set(statuses);
+}
+
+pub fn get_renderer_info() -> RendererInfo {
+    RendererInfo {
+        session_type: session_type(),
+        nvidia_detected: is_nvidia(),
+        wayland: is_wayland(),
+        workarounds: APPLIED.get().cloned().unwrap_or_default(),
+    }
+}
@@ -0,0 +1,219 @@
+//! Background scheduler for periodic jobs such as the daily digest notification, the
+//! off-hours usage alert, user-defined alert rules and the weekly email report.
+//!
+//! Runs on a dedicated thread and wakes once a minute. Job bookkeeping (when each job
+//! last ran) is persisted next to the config so restarts don't repeat a job.
+
+use chrono::{DateTime, Datelike, Local, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use crate::alerts;
+use crate::config::{self, AppConfig, FormatPrefs};
+use crate::crash::log_line;
+use crate::email_report;
+use crate::format::{format_cost, format_tokens};
+use crate::notifications::{self, Notifier};
+use crate::privacy;
+use crate::usage::{
+    collect_jsonl_files, compute_daily_digest, get_claude_data_dirs, parse_usage_from_file,
+    project_name, working_hours, DailyDigest,
+};
+
+const TICK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SchedulerState {
+    /// Local date (YYYY-MM-DD) the daily digest was last sent
+    #[serde(default)]
+    last_daily_digest: Option<String>,
+    /// When the off-hours alert fired (RFC 3339); cleared once working hours resume
+    #[serde(default)]
+    off_hours_alert_sent: Option<String>,
+    /// Local date (YYYY-MM-DD) the weekly email report was last sent
+    #[serde(default)]
+    last_weekly_report: Option<String>,
+    /// Names of alert rules above their threshold, which don't fire again until they drop
+    #[serde(default)]
+    alerts_firing: Vec<String>,
+}
+
+/// Names of alert rules currently above their threshold
+pub fn firing_alerts() -> Vec<String> {
+    load_state().alerts_firing
+}
+
+fn state_path() -> PathBuf {
+    config::config_dir().join("scheduler_state.json")
+}
+
+fn load_state() -> SchedulerState {
+    fs::read_to_string(state_path())
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &SchedulerState) {
+    let path = state_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    match serde_json::to_string_pretty(state) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&path, json) {
+                log_line!("Failed to write scheduler state: {e}");
+            }
+        }
+        Err(e) => log_line!("Failed to serialize scheduler state: {e}"),
+    }
+}
+
+pub fn spawn_scheduler(notifier: Notifier) {
+    thread::spawn(move || {
+        let mut state = load_state();
+        loop {
+            run_due_jobs(&notifier, &mut state);
+            thread::sleep(TICK_INTERVAL);
+        }
+    });
+}
+
+fn run_due_jobs(notifier: &Notifier, state: &mut SchedulerState) {
+    let cfg = config::load_config();
+    let now = Local::now();
+    let today = now.date_naive().format("%Y-%m-%d").to_string();
+
+    if cfg.daily_digest.enabled && state.last_daily_digest.as_deref() != Some(today.as_str()) {
+        let send_at = NaiveTime::parse_from_str(&cfg.daily_digest.time, "%H:%M")
+            .unwrap_or_else(|_| NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+        if now.time() >= send_at {
+            let yesterday = now.date_naive() - chrono::Duration::days(1);
+            let mut digest = compute_daily_digest(yesterday);
+            if cfg.privacy_mode {
+                privacy::apply_to_digest(&mut digest);
+            }
+            let body = format_digest(&digest, &cfg.format);
+            notifier.send(
+                notifications::DAILY_DIGEST,
+                "Claude Code daily digest",
+                &body,
+            );
+            state.last_daily_digest = Some(today);
+            save_state(state);
+        }
+    }
+
+    if cfg.off_hours_alert.enabled {
+        check_off_hours_usage(notifier, &cfg, state);
+    }
+
+    if alerts::evaluate(notifier, &cfg, &mut state.alerts_firing) {
+        save_state(state);
+    }
+
+    let report = &cfg.weekly_report;
+    if report.enabled
+        && now.weekday().number_from_monday() == report.weekday
+        && state.last_weekly_report.as_deref() != Some(today.as_str())
+    {
+        let send_at = NaiveTime::parse_from_str(&report.time, "%H:%M")
+            .unwrap_or_else(|_| NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+        if now.time() >= send_at {
+            // Not retried until next week on failure, to avoid mailing every minute
+            match email_report::send_weekly_report(report) {
+                Ok(()) => log_line!("Weekly report sent to {}", report.to.join(", ")),
+                Err(e) => log_line!("{e}"),
+            }
+            state.last_weekly_report = Some(today);
+            save_state(state);
+        }
+    }
+}
+
+/// Alert once per off-hours stretch when recent token usage crosses the threshold
+fn check_off_hours_usage(notifier: &Notifier, cfg: &AppConfig, state: &mut SchedulerState) {
+    let now = Utc::now();
+    if working_hours().contains(&now) {
+        if state.off_hours_alert_sent.take().is_some() {
+            save_state(state);
+        }
+        return;
+    }
+    if state.off_hours_alert_sent.is_some() {
+        return;
+    }
+
+    let alert = &cfg.off_hours_alert;
+    let since = now - chrono::Duration::minutes(alert.window_minutes as i64);
+    let lookback_hours = alert.window_minutes as i64 / 60 + 1;
+    // project -> tokens within the window
+    let mut by_project: HashMap<String, u64> = HashMap::new();
+    for file in collect_jsonl_files(&get_claude_data_dirs(), Some(lookback_hours)) {
+        for entry in parse_usage_from_file(&file).unwrap_or_default() {
+            let in_window =
+                DateTime::parse_from_rfc3339(&entry.timestamp).is_ok_and(|ts| ts >= since);
+            if !in_window {
+                continue;
+            }
+            let project = project_name(&entry.cwd);
+            let tokens = by_project.entry(project).or_default();
+            *tokens = tokens.saturating_add(entry.tokens.total());
+        }
+    }
+
+    let total: u64 = by_project.values().sum();
+    if total < alert.tokens_threshold {
+        return;
+    }
+    let top_project = by_project
+        .into_iter()
+        .max_by_key(|(_, tokens)| *tokens)
+        .map(|(project, _)| project)
+        .unwrap_or_default();
+    let top_project = if cfg.privacy_mode {
+        privacy::pseudonym(&top_project)
+    } else {
+        top_project
+    };
+    let body = format!(
+        "{} tokens in the last {} min, mostly in {}. Forgotten agent?",
+        format_tokens(total, &cfg.format),
+        alert.window_minutes,
+        top_project
+    );
+    notifier.send(
+        notifications::OFF_HOURS,
+        "Claude Code usage outside working hours",
+        &body,
+    );
+    state.off_hours_alert_sent = Some(now.to_rfc3339());
+    save_state(state);
+}
+
+pub(crate) fn format_digest(digest: &DailyDigest, prefs: &FormatPrefs) -> String {
+    if digest.total_tokens == 0 && digest.prompt_count == 0 {
+        return format!("No Claude Code usage on {}.", digest.date);
+    }
+
+    let mut lines = vec![format!(
+        "{}: {} tokens · {} · {} prompts",
+        digest.date,
+        format_tokens(digest.total_tokens, prefs),
+        format_cost(digest.cost_usd, prefs),
+        digest.prompt_count
+    )];
+    if let Some(ref project) = digest.top_project {
+        lines.push(format!(
+            "Top project: {} ({})",
+            project,
+            format_cost(digest.top_project_cost_usd, prefs)
+        ));
+    }
+    lines.push(format!("Peak 5hr quota: {:.0}%", digest.peak_quota_percent));
+    lines.join("\n")
+}
@@ -0,0 +1,337 @@
+//! Simulation mode for UI development (feature `simulate`): `get_usage` and everything else
+//! built on `fetch_usage` serve canned or generated stats instead of reading Claude's data,
+//! so frontend work and screenshots don't need a real history.
+
+use chrono::{Datelike, Duration, Local, Utc};
+use std::path::Path;
+use std::sync::RwLock;
+
+use crate::crash::log_line;
+use crate::usage::{
+    self, ActiveSession, ContextBreakdown, DailyActivity, ModelUsage, ProductivityStats,
+    QuotaBlock, TokenUsage, UsageStats,
+};
+
+/// Generated fixtures accepted by `load_fixture`; any other name is read as a JSON file of
+/// `UsageStats`, e.g. a saved `get_usage` response
+pub const FIXTURES: &[&str] = &["typical", "empty", "over_quota", "many_sessions"];
+
+/// Started with this variable set to a fixture name, the widget runs in simulation mode
+const FIXTURE_ENV: &str = "CC_USAGE_FIXTURE";
+
+static ACTIVE: RwLock<Option<Fixture>> = RwLock::new(None);
+
+#[derive(Clone)]
+enum Fixture {
+    Generated(Scenario),
+    Canned(Box<UsageStats>),
+}
+
+#[derive(Clone, Copy)]
+struct Scenario {
+    seed: u64,
+    sessions: usize,
+    /// Prompts per day on an average day
+    daily_prompts: f64,
+    /// Weighted prompts in the current 5hr block and the last 7 days, in percent of the
+    /// estimated limits; over 100 is capped like real usage
+    window_percent: f64,
+    week_percent: f64,
+}
+
+fn scenario(name: &str) -> Option<Scenario> {
+    let (sessions, daily_prompts, window_percent, week_percent) = match name {
+        "typical" => (4, 60.0, 35.0, 42.0),
+        "empty" => (0, 0.0, 0.0, 0.0),
+        "over_quota" => (6, 180.0, 120.0, 110.0),
+        "many_sessions" => (50, 120.0, 60.0, 55.0),
+        _ => return None,
+    };
+    Some(Scenario {
+        seed: name.bytes().fold(0x9e3779b97f4a7c15, |seed, byte| {
+            (seed ^ byte as u64).wrapping_mul(0x100000001b3)
+        }),
+        sessions,
+        daily_prompts,
+        window_percent,
+        week_percent,
+    })
+}
+
+/// Switch simulation mode to `name` (see `FIXTURES`), or back to real data with `None`
+pub fn load_fixture(name: Option<&str>) -> Result<(), String> {
+    let fixture = match name {
+        None | Some("") => None,
+        Some(name) => Some(match scenario(name) {
+            Some(scenario) => Fixture::Generated(scenario),
+            None => Fixture::Canned(Box::new(read_fixture_file(Path::new(name))?)),
+        }),
+    };
+    let mut active = ACTIVE
+        .write()
+        .map_err(|_| "Simulation state unavailable".to_string())?;
+    *active = fixture;
+    Ok(())
+}
+
+fn read_fixture_file(path: &Path) -> Result<UsageStats, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        format!(
+            "Unknown fixture '{}' (expected one of {} or a JSON file): {e}",
+            path.display(),
+            FIXTURES.join(", ")
+        )
+    })?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid fixture {}: {e}", path.display()))
+}
+
+/// Enter simulation mode at startup when `CC_USAGE_FIXTURE` is set
+pub fn load_fixture_from_env() {
+    if let Ok(name) = std::env::var(FIXTURE_ENV) {
+        if let Err(e) = load_fixture(Some(&name)) {
+            log_line!("Ignoring {FIXTURE_ENV}: {e}");
+        }
+    }
+}
+
+/// Stats to serve for `periods` in simulation mode; `None` when it's off
+pub fn simulated_usage(periods: &[String]) -> Option<Vec<(String, UsageStats)>> {
+    let fixture = ACTIVE.read().ok()?.clone()?;
+    Some(
+        periods
+            .iter()
+            .map(|period| {
+                let stats = match &fixture {
+                    Fixture::Generated(scenario) => generate(scenario, period),
+                    Fixture::Canned(stats) => (**stats).clone(),
+                };
+                (period.clone(), stats)
+            })
+            .collect(),
+    )
+}
+
+/// xorshift64*, seeded per fixture so every refresh and screenshot shows the same data
+struct Rng(u64);
+
+impl Rng {
+    /// Uniform in [0, 1)
+    fn unit(&mut self) -> f64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545f4914f6cdd1d) >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in [low, high)
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + self.unit() * (high - low)
+    }
+}
+
+const MODELS: &[(&str, f64)] = &[
+    ("claude-opus-4-5", 0.55),
+    ("claude-sonnet-4-5", 0.4),
+    ("claude-haiku-4-5", 0.05),
+];
+
+const PROJECTS: &[&str] = &[
+    "cc-usage-widget",
+    "api-gateway",
+    "billing-service",
+    "mobile-app",
+    "docs-site",
+    "infra",
+    "data-pipeline",
+];
+
+fn tokens_for(prompts: f64) -> TokenUsage {
+    TokenUsage {
+        input_tokens: (prompts * 40.0) as u64,
+        output_tokens: (prompts * 2_500.0) as u64,
+        cache_creation_input_tokens: (prompts * 12_000.0) as u64,
+        cache_read_input_tokens: (prompts * 180_000.0) as u64,
+        thinking_tokens: (prompts * 800.0) as u64,
+        ..Default::default()
+    }
+}
+
+fn generate(scenario: &Scenario, period: &str) -> UsageStats {
+    let mut rng = Rng(scenario.seed);
+    let now = Utc::now();
+    let today = Local::now().date_naive();
+    let period_days = match period {
+        "current-window" | "today" => 1,
+        "week" => 7,
+        "month" => 30,
+        _ => 84,
+    };
+
+    // Twelve weeks of prompts for the heatmap, quieter on weekends
+    let mut daily_activity = Vec::new();
+    for days_ago in (0..84).rev() {
+        let date = today - Duration::days(days_ago);
+        let scale = if date.weekday().number_from_monday() > 5 {
+            0.3
+        } else {
+            1.0
+        };
+        let prompts = (scenario.daily_prompts * scale * rng.range(0.2, 1.8)).round() as u32;
+        if prompts > 0 {
+            daily_activity.push(DailyActivity {
+                date: date.format("%Y-%m-%d").to_string(),
+                prompt_count: prompts,
+                data_complete: true,
+            });
+        }
+    }
+    let period_start = (today - Duration::days(period_days - 1))
+        .format("%Y-%m-%d")
+        .to_string();
+    let period_prompts: f64 = daily_activity
+        .iter()
+        .filter(|day| day.date >= period_start)
+        .map(|day| day.prompt_count as f64)
+        .sum();
+
+    let mut total_tokens = TokenUsage::default();
+    let mut by_model = Vec::new();
+    for (model, share) in MODELS {
+        let tokens = tokens_for(period_prompts * share);
+        if tokens.output_tokens == 0 {
+            continue;
+        }
+        total_tokens.add(&tokens);
+        by_model.push(ModelUsage {
+            model: model.to_string(),
+            display_name: usage::get_model_display_name(model),
+            cost_usd: usage::calculate_cost(model, &tokens),
+            thinking_cost_usd: usage::thinking_cost_for(model, None, &tokens),
+            tokens,
+            provider: None,
+            avg_output_tps: Some(rng.range(40.0, 90.0)),
+            display_tokens: String::new(),
+            display_cost: String::new(),
+        });
+    }
+    let total_cost_usd = by_model.iter().map(|m| m.cost_usd).sum();
+
+    let block = (scenario.window_percent > 0.0).then(|| {
+        let start = now - Duration::minutes(rng.range(10.0, 280.0) as i64);
+        let weighted = usage::ESTIMATED_WINDOW_LIMIT as f64 * scenario.window_percent / 100.0;
+        QuotaBlock {
+            start,
+            end: start + Duration::hours(usage::QUOTA_WINDOW_HOURS),
+            prompts: (weighted * 0.8) as u32,
+            weighted,
+        }
+    });
+    let week_weighted = usage::ESTIMATED_WEEKLY_LIMIT as f64 * scenario.week_percent / 100.0;
+    let quota = usage::build_quota_info(block.as_ref(), week_weighted, week_weighted, Vec::new());
+
+    let active_sessions = (0..scenario.sessions)
+        .map(|i| generate_session(&mut rng, i))
+        .collect();
+
+    UsageStats {
+        total_tokens,
+        total_cost_usd,
+        by_model,
+        session_count: period_prompts as u32,
+        last_updated: now.to_rfc3339(),
+        quota,
+        active_sessions,
+        weekly_usage: usage::compute_weekly_usage(&daily_activity),
+        daily_activity,
+        peer_comparison: None,
+        productivity: ProductivityStats::default(),
+        off_hours_percent: if period_prompts > 0.0 {
+            rng.range(0.0, 25.0)
+        } else {
+            0.0
+        },
+        configured_model: Some("claude-opus-4-5".to_string()),
+        plan_hint: Some("max".to_string()),
+        goals: Vec::new(),
+        billing_cycle: None,
+        org_analytics: None,
+        org_utilization: None,
+        org_summary: None,
+        truncated: false,
+        degraded: Vec::new(),
+        by_user: Vec::new(),
+        display_last_updated: String::new(),
+        display_total_tokens: String::new(),
+        display_total_cost: String::new(),
+    }
+}
+
+fn generate_session(rng: &mut Rng, index: usize) -> ActiveSession {
+    let now = Utc::now();
+    let (model, _) = MODELS[index % 2];
+    let project = PROJECTS[index % PROJECTS.len()];
+    let project = if index < PROJECTS.len() {
+        project.to_string()
+    } else {
+        format!("{project}-{}", index / PROJECTS.len() + 1)
+    };
+    let duration_minutes = rng.range(5.0, 600.0) as u32;
+    let idle_minutes = (index as f64 * 20.0 + rng.range(0.0, 15.0)) as i64;
+    let last = now - Duration::minutes(idle_minutes);
+    let first = last - Duration::minutes(duration_minutes as i64);
+    let message_count = rng.range(5.0, 300.0) as u32;
+    let tokens = tokens_for(message_count as f64);
+    let context_limit = usage::get_model_context_limit(model);
+    let context_used = rng.range(0.1, 0.97) * context_limit as f64;
+    let system_tokens = rng.range(15_000.0, 45_000.0).min(context_used) as u64;
+    let fresh_input_tokens = rng.range(200.0, 8_000.0) as u64;
+    let history_tokens = (context_used as u64).saturating_sub(system_tokens + fresh_input_tokens);
+    let percent = |part: u64| part as f64 / context_limit as f64 * 100.0;
+    // Recently idle sessions have activity in the last two hours
+    let buckets = (usage::SPARKLINE_WINDOW_MINUTES / usage::SPARKLINE_BUCKET_MINUTES) as usize;
+    let recent_activity = (0..buckets)
+        .map(|_| {
+            if idle_minutes < usage::SPARKLINE_WINDOW_MINUTES && rng.unit() > 0.4 {
+                rng.range(0.0, 400_000.0) as u32
+            } else {
+                0
+            }
+        })
+        .collect();
+
+    ActiveSession {
+        session_id: format!("{:08x}", (rng.unit() * u32::MAX as f64) as u32),
+        directory: format!("/home/dev/projects/{project}"),
+        project,
+        first_activity: first.to_rfc3339(),
+        last_activity: last.to_rfc3339(),
+        duration_minutes,
+        message_count,
+        total_tokens: tokens.total(),
+        cost_usd: usage::calculate_cost(model, &tokens),
+        model: model.to_string(),
+        model_display_name: usage::get_model_display_name(model),
+        context_remaining_percent: 100.0 - context_used / context_limit as f64 * 100.0,
+        todo_count: rng.range(0.0, 6.0) as u32,
+        recent_activity,
+        avg_output_tps: Some(rng.range(40.0, 90.0)),
+        branch_count: (rng.unit() * 1.5) as u32,
+        abandoned_branch_tokens: 0,
+        context_breakdown: Some(ContextBreakdown {
+            system_tokens,
+            history_tokens,
+            fresh_input_tokens,
+            context_limit,
+            system_percent: percent(system_tokens),
+            history_percent: percent(history_tokens),
+            fresh_input_percent: percent(fresh_input_tokens),
+        }),
+        commands_executed: rng.range(0.0, 4.0) as u32,
+        user: None,
+        process_running: false,
+        pid: None,
+        display_last_activity: String::new(),
+        display_tokens: String::new(),
+        display_cost: String::new(),
+    }
+}
@@ -0,0 +1,304 @@
+//! Central state shared by the commands, the journal watcher, the D-Bus/IPC services, the
+//! HTTP dashboard, the scheduler and daemon mode: the config and the most recently computed
+//! local stats.
+//!
+//! The config is read from `config.json` once, and again only when the file is changed
+//! outside the app (checked at most every `CONFIG_CHECK_INTERVAL`). Writes go through
+//! `save_config`/`update_config`, which serialize writers so concurrent commands can't drop
+//! each other's changes. Every config read or written is also applied to the core
+//! (`usage::apply_settings`), so a hand-edited `config.json` changes the stats as well.
+//!
+//! Local stats are cached per period with the generation they were computed at. The watcher
+//! (and everything else that emits `usage-updated`) bumps the generation. Callers arriving
+//! while stats are being computed wait for that computation instead of parsing the journals
+//! again. Data derived from the journals outside the period stats (quota for the tray and IPC
+//! services, goals, failed requests, the search index, recent entries for alerts) is cached
+//! the same way through `cached`.
+//!
+//! In the app the state is managed by Tauri (`tauri::State<Arc<AppState>>`); daemon mode and
+//! threads without an app handle reach the same instance through `get()`.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::Utc;
+
+use crate::config::{self, AppConfig};
+use crate::usage::{self, ParsedEntry, QuotaInfo, UsageStats};
+
+/// Cached stats are recomputed after this long even if no journal changed, since quota
+/// windows and "today" move with the clock
+const STATS_MAX_AGE: Duration = Duration::from_secs(15);
+
+/// How often `config.json` is checked for edits made outside the app
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+struct CachedConfig {
+    config: AppConfig,
+    /// Modification time of `config.json` when it was read or written
+    modified: Option<SystemTime>,
+    checked_at: Instant,
+}
+
+struct CachedStats {
+    generation: u64,
+    computed_at: Instant,
+    stats: UsageStats,
+}
+
+struct CachedValue {
+    generation: u64,
+    computed_at: Instant,
+    value: Arc<dyn Any + Send + Sync>,
+}
+
+pub struct AppState {
+    config: RwLock<CachedConfig>,
+    /// Held across read-modify-write config updates
+    config_writer: Mutex<()>,
+    /// Bumped whenever the journals or settings affecting the stats change
+    generation: AtomicU64,
+    stats: Mutex<HashMap<String, CachedStats>>,
+    /// Held while local stats are computed
+    computing: Mutex<()>,
+    /// Other derived data by key (see `cached`)
+    derived: Mutex<HashMap<String, CachedValue>>,
+}
+
+static STATE: OnceLock<Arc<AppState>> = OnceLock::new();
+
+/// The shared state, created on first use
+pub fn get() -> &'static Arc<AppState> {
+    STATE.get_or_init(|| Arc::new(AppState::new()))
+}
+
+fn config_modified() -> Option<SystemTime> {
+    std::fs::metadata(config::config_path())
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+impl AppState {
+    fn new() -> Self {
+        let modified = config_modified();
+        let config = config::read_config_file();
+        usage::apply_settings(&config);
+        Self {
+            config: RwLock::new(CachedConfig {
+                config,
+                modified,
+                checked_at: Instant::now(),
+            }),
+            config_writer: Mutex::new(()),
+            generation: AtomicU64::new(0),
+            stats: Mutex::new(HashMap::new()),
+            computing: Mutex::new(()),
+            derived: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The current config; re-read when `config.json` was edited outside the app
+    pub fn config(&self) -> AppConfig {
+        if let Ok(cached) = self.config.read() {
+            if cached.checked_at.elapsed() < CONFIG_CHECK_INTERVAL {
+                return cached.config.clone();
+            }
+        }
+        let modified = config_modified();
+        if let Ok(mut cached) = self.config.write() {
+            cached.checked_at = Instant::now();
+            if cached.modified == modified {
+                return cached.config.clone();
+            }
+        }
+        let config = config::read_config_file();
+        if let Ok(mut cached) = self.config.write() {
+            *cached = CachedConfig {
+                config: config.clone(),
+                modified,
+                checked_at: Instant::now(),
+            };
+        }
+        usage::apply_settings(&config);
+        self.invalidate();
+        config
+    }
+
+    /// Write `cfg` to `config.json` and make it the current config
+    pub fn save_config(&self, cfg: &AppConfig) -> Result<(), String> {
+        let _writer = self
+            .config_writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.write_config(cfg)
+    }
+
+    /// Apply `change` to the current config and save it, holding off other writers meanwhile
+    pub fn update_config<T>(&self, change: impl FnOnce(&mut AppConfig) -> T) -> Result<T, String> {
+        let _writer = self
+            .config_writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let mut cfg = self.config();
+        let result = change(&mut cfg);
+        self.write_config(&cfg)?;
+        Ok(result)
+    }
+
+    fn write_config(&self, cfg: &AppConfig) -> Result<(), String> {
+        config::write_config_file(cfg)?;
+        if let Ok(mut cached) = self.config.write() {
+            *cached = CachedConfig {
+                config: cfg.clone(),
+                modified: config_modified(),
+                checked_at: Instant::now(),
+            };
+        }
+        // Settings such as excluded sessions or the billing anchor day change the stats
+        usage::apply_settings(cfg);
+        self.invalidate();
+        Ok(())
+    }
+
+    /// Mark the cached stats stale: journals changed, or a setting that affects them did
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Local stats for `periods` (see `usage::get_usage_multi`), computed at most once per
+    /// journal change however many subsystems ask; only the periods not cached are computed
+    pub fn local_usage(&self, periods: &[String]) -> Result<HashMap<String, UsageStats>, String> {
+        let (result, missing) = self.cached_stats(periods);
+        if missing.is_empty() {
+            return Ok(result);
+        }
+        let _computing = self
+            .computing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        // Computed by another caller while this one waited
+        let (mut result, missing) = self.cached_stats(periods);
+        if missing.is_empty() {
+            return Ok(result);
+        }
+
+        // Read first, so journals changing during the computation leave the result stale
+        let generation = self.generation.load(Ordering::SeqCst);
+        let computed = usage::get_usage_multi(&missing)?;
+        if let Ok(mut stats) = self.stats.lock() {
+            // Partial (timed out or cancelled) results are tried again next time
+            for (period, period_stats) in computed.iter().filter(|(_, s)| !s.truncated) {
+                stats.insert(
+                    period.clone(),
+                    CachedStats {
+                        generation,
+                        computed_at: Instant::now(),
+                        stats: period_stats.clone(),
+                    },
+                );
+            }
+        }
+        result.extend(computed);
+        Ok(result)
+    }
+
+    /// Current cached stats among `periods`, and the periods still to compute
+    fn cached_stats(&self, periods: &[String]) -> (HashMap<String, UsageStats>, Vec<String>) {
+        let generation = self.generation.load(Ordering::SeqCst);
+        let mut found = HashMap::new();
+        let mut missing = Vec::new();
+        let stats = match self.stats.lock() {
+            Ok(stats) => stats,
+            Err(_) => return (found, periods.to_vec()),
+        };
+        for period in periods {
+            let cached = stats.get(period).filter(|cached| {
+                cached.generation == generation && cached.computed_at.elapsed() < STATS_MAX_AGE
+            });
+            match cached {
+                Some(cached) => {
+                    found.insert(period.clone(), cached.stats.clone());
+                }
+                None => missing.push(period.clone()),
+            }
+        }
+        (found, missing)
+    }
+
+    /// The result of `compute` stored under `key`, computed again only once the journals or
+    /// settings changed (or after `STATS_MAX_AGE`). Keys include whatever parameters the
+    /// value depends on, e.g. "failed_requests:1".
+    pub fn cached<T: Clone + Send + Sync + 'static>(
+        &self,
+        key: &str,
+        compute: impl FnOnce() -> T,
+    ) -> T {
+        let generation = self.generation.load(Ordering::SeqCst);
+        if let Ok(derived) = self.derived.lock() {
+            let current = derived.get(key).filter(|cached| {
+                cached.generation == generation && cached.computed_at.elapsed() < STATS_MAX_AGE
+            });
+            if let Some(value) = current.and_then(|cached| cached.value.downcast_ref::<T>()) {
+                return value.clone();
+            }
+        }
+        // Computed without holding the lock, so slow values don't hold up the others
+        let value = compute();
+        if let Ok(mut derived) = self.derived.lock() {
+            derived.insert(
+                key.to_string(),
+                CachedValue {
+                    generation,
+                    computed_at: Instant::now(),
+                    value: Arc::new(value.clone()),
+                },
+            );
+        }
+        value
+    }
+
+    /// The current local quota: from stats cached for any period (waiting for a computation
+    /// in progress), otherwise computed on its own, which reads only the last 8 days
+    pub fn quota(&self) -> QuotaInfo {
+        if let Some(quota) = self.cached_quota() {
+            return quota;
+        }
+        drop(
+            self.computing
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        if let Some(quota) = self.cached_quota() {
+            return quota;
+        }
+        self.cached("quota", || usage::quota_info_at(Utc::now()))
+    }
+
+    fn cached_quota(&self) -> Option<QuotaInfo> {
+        let generation = self.generation.load(Ordering::SeqCst);
+        let stats = self.stats.lock().ok()?;
+        stats
+            .values()
+            .find(|cached| {
+                cached.generation == generation && cached.computed_at.elapsed() < STATS_MAX_AGE
+            })
+            .map(|cached| cached.stats.quota.clone())
+    }
+
+    /// Usage entries of the journals written in the last `hours` hours, unfiltered by time;
+    /// shared by callers looking at recent usage (alert windows, goals)
+    pub fn recent_entries(&self, hours: i64) -> Arc<Vec<ParsedEntry>> {
+        self.cached(&format!("entries:{hours}"), || {
+            let files = usage::collect_jsonl_files(&usage::get_claude_data_dirs(), Some(hours));
+            Arc::new(
+                files
+                    .iter()
+                    .flat_map(|file| usage::parse_usage_from_file(file).unwrap_or_default())
+                    .collect(),
+            )
+        })
+    }
+}
@@ -0,0 +1,470 @@
+//! Persistent store of usage history imported from other tools (`history.json` under the
+//! config dir), so users migrating to the widget keep totals from before their local
+//! journals were cleaned up.
+//!
+//! Records are one day of one model's usage, tagged with the tool they came from. The
+//! store holds at most one record per (date, model): a later import of the same day
+//! replaces the earlier one instead of double counting. Days that local journals still
+//! cover are skipped on import, since those are counted from the journals themselves.
+//!
+//! Supported formats:
+//! - "ccusage": `ccusage daily --json` output
+//! - "claude_csv": usage CSV exported from the Claude Console
+//!
+//! The store also keeps the outcome of each completed usage goal period (`goals.json`),
+//! for the same reason: streaks must not reset when old journals are deleted.
+//!
+//! `verify_store` checks the stored data against the journals: the parsed journal cache
+//! against a re-parse of sampled journals, and imported days against days the journals
+//! now cover (counted twice otherwise).
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::usage::{
+    calculate_cost, collect_jsonl_files, ensure_outside_data_dirs, get_claude_data_dirs,
+    get_model_display_name, month_period_start, parse_usage_from_file, thinking_cost_for,
+    verify_cache, CacheVerification, ModelUsage, TokenUsage, UsageStats,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    pub date: String, // YYYY-MM-DD (local)
+    pub model: String,
+    pub tokens: TokenUsage,
+    pub cost_usd: f64,
+    pub source: String, // Import format, e.g. "ccusage"
+    pub imported_at: String,
+}
+
+/// Outcome of one `import_history` call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub source: String,
+    pub records_read: usize,
+    pub added: usize,
+    pub replaced: usize,
+    /// Days already covered by local journals
+    pub skipped_local: usize,
+}
+
+fn store_path() -> PathBuf {
+    config::config_dir().join("history.json")
+}
+
+/// All imported records, oldest first
+pub fn load_history() -> Vec<HistoryRecord> {
+    fs::read_to_string(store_path())
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_history(records: &[HistoryRecord]) -> Result<(), String> {
+    write_json(&store_path(), records)
+}
+
+fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    ensure_outside_data_dirs(path)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    // Write then rename so a crash never leaves a truncated store
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| format!("Failed to write {}: {e}", tmp.display()))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {}: {e}", path.display()))
+}
+
+/// Outcome of one completed goal period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalRecord {
+    pub goal_id: String,
+    pub period_start: String, // YYYY-MM-DD (local): the day, or the week's first day
+    /// Target the period was judged against; records for an old target are re-evaluated
+    pub target: f64,
+    pub value: f64,
+    pub met: bool,
+    /// Nothing was used in the period; it neither extends nor breaks a streak
+    #[serde(default)]
+    pub idle: bool,
+}
+
+fn goals_path() -> PathBuf {
+    config::config_dir().join("goals.json")
+}
+
+pub fn load_goal_records() -> Vec<GoalRecord> {
+    fs::read_to_string(goals_path())
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_goal_records(records: &[GoalRecord]) -> Result<(), String> {
+    write_json(&goals_path(), records)
+}
+
+/// Parse an export and merge it into the store
+pub fn import_history(path: &Path, format: &str) -> Result<ImportSummary, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let parsed = match format {
+        "ccusage" => parse_ccusage(&contents)?,
+        "claude_csv" => parse_claude_csv(&contents)?,
+        _ => {
+            return Err(format!(
+                "Unknown import format '{format}', expected ccusage or claude_csv"
+            ))
+        }
+    };
+
+    let local_dates = local_journal_dates();
+    let imported_at = Local::now().to_rfc3339();
+    let mut records = load_history();
+    let mut summary = ImportSummary {
+        source: format.to_string(),
+        records_read: parsed.len(),
+        added: 0,
+        replaced: 0,
+        skipped_local: 0,
+    };
+
+    for ((date, model), (tokens, cost)) in parsed {
+        if local_dates.contains(&date) {
+            summary.skipped_local += 1;
+            continue;
+        }
+        let record = HistoryRecord {
+            // Exports without a cost get the widget's own API price estimate
+            cost_usd: cost.unwrap_or_else(|| calculate_cost(&model, &tokens)),
+            date,
+            model,
+            tokens,
+            source: format.to_string(),
+            imported_at: imported_at.clone(),
+        };
+        match records
+            .iter_mut()
+            .find(|r| r.date == record.date && r.model == record.model)
+        {
+            Some(existing) => {
+                *existing = record;
+                summary.replaced += 1;
+            }
+            None => {
+                records.push(record);
+                summary.added += 1;
+            }
+        }
+    }
+
+    records.sort_by(|a, b| (&a.date, &a.model).cmp(&(&b.date, &b.model)));
+    save_history(&records)?;
+    Ok(summary)
+}
+
+/// Outcome of `verify_store`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreVerification {
+    /// None when the entry cache is disabled
+    pub cache: Option<CacheVerification>,
+    pub history_records: usize,
+    /// Imported (date, model) records for days local journals now cover, as "date model"
+    pub history_overlaps: Vec<String>,
+    /// Overlapping records dropped, with `repair`
+    pub history_removed: usize,
+}
+
+/// Cross-check the persistent stores against the journals (see the module docs); with
+/// `repair`, drifted cache files are rebuilt and overlapping imported records dropped
+pub fn verify_store(sample: usize, repair: bool) -> Result<StoreVerification, String> {
+    let cache = verify_cache(sample, repair).ok();
+    let mut records = load_history();
+    let history_records = records.len();
+    let local_dates = if records.is_empty() {
+        HashSet::new()
+    } else {
+        local_journal_dates()
+    };
+    let history_overlaps: Vec<String> = records
+        .iter()
+        .filter(|r| local_dates.contains(&r.date))
+        .map(|r| format!("{} {}", r.date, r.model))
+        .collect();
+    let mut history_removed = 0;
+    if repair && !history_overlaps.is_empty() {
+        records.retain(|r| !local_dates.contains(&r.date));
+        save_history(&records)?;
+        history_removed = history_overlaps.len();
+    }
+    Ok(StoreVerification {
+        cache,
+        history_records,
+        history_overlaps,
+        history_removed,
+    })
+}
+
+/// Add imported days within the period (up to yesterday) to the stats' totals
+pub fn add_imported_usage(stats: &mut UsageStats, period: &str) {
+    let today = Local::now().date_naive();
+    let since = match period {
+        "current-window" | "today" => return,
+        "week" => Some(today - chrono::Duration::days(7)),
+        "month" => Some(month_period_start(today)),
+        _ => None, // "all"
+    };
+    for record in load_history() {
+        let date = match NaiveDate::parse_from_str(&record.date, "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => continue,
+        };
+        if date >= today || since.is_some_and(|since_date| date < since_date) {
+            continue;
+        }
+
+        stats.total_tokens.add(&record.tokens);
+        stats.total_cost_usd += record.cost_usd;
+        match stats.by_model.iter_mut().find(|m| m.model == record.model) {
+            Some(model_usage) => {
+                model_usage.tokens.add(&record.tokens);
+                model_usage.cost_usd += record.cost_usd;
+                model_usage.thinking_cost_usd +=
+                    thinking_cost_for(&record.model, None, &record.tokens);
+            }
+            None => stats.by_model.push(ModelUsage {
+                display_name: get_model_display_name(&record.model),
+                thinking_cost_usd: thinking_cost_for(&record.model, None, &record.tokens),
+                model: record.model,
+                tokens: record.tokens,
+                cost_usd: record.cost_usd,
+                provider: None,
+                avg_output_tps: None,
+                display_tokens: String::new(),
+                display_cost: String::new(),
+            }),
+        }
+    }
+}
+
+/// Local dates with usage in the journals still on disk
+fn local_journal_dates() -> HashSet<String> {
+    let mut dates = HashSet::new();
+    for file in collect_jsonl_files(&get_claude_data_dirs(), None) {
+        for entry in parse_usage_from_file(&file).unwrap_or_default() {
+            if let Ok(ts) = DateTime::parse_from_rfc3339(&entry.timestamp) {
+                dates.insert(ts.with_timezone(&Local).format("%Y-%m-%d").to_string());
+            }
+        }
+    }
+    dates
+}
+
+/// (date, model) -> (tokens, cost if the export has one)
+type ParsedHistory = BTreeMap<(String, String), (TokenUsage, Option<f64>)>;
+
+fn merge_parsed(
+    parsed: &mut ParsedHistory,
+    date: String,
+    model: String,
+    tokens: TokenUsage,
+    cost: Option<f64>,
+) {
+    let entry = parsed
+        .entry((date, model))
+        .or_insert_with(|| (TokenUsage::default(), None));
+    entry.0.add(&tokens);
+    if let Some(cost) = cost {
+        entry.1 = Some(entry.1.unwrap_or(0.0) + cost);
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CcusageDaily {
+    daily: Vec<CcusageDay>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CcusageDay {
+    date: String,
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_tokens: u64,
+    #[serde(default)]
+    cache_read_tokens: u64,
+    #[serde(default)]
+    total_cost: Option<f64>,
+    #[serde(default)]
+    model_breakdowns: Vec<CcusageModel>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CcusageModel {
+    model_name: String,
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_tokens: u64,
+    #[serde(default)]
+    cache_read_tokens: u64,
+    #[serde(default)]
+    cost: Option<f64>,
+}
+
+fn parse_ccusage(contents: &str) -> Result<ParsedHistory, String> {
+    let export: CcusageDaily = serde_json::from_str(contents)
+        .map_err(|e| format!("Not a `ccusage daily --json` export: {e}"))?;
+    let mut parsed = ParsedHistory::new();
+
+    for day in export.daily {
+        if NaiveDate::parse_from_str(&day.date, "%Y-%m-%d").is_err() {
+            continue;
+        }
+        if day.model_breakdowns.is_empty() {
+            let tokens = TokenUsage {
+                input_tokens: day.input_tokens,
+                output_tokens: day.output_tokens,
+                cache_creation_input_tokens: day.cache_creation_tokens,
+                cache_read_input_tokens: day.cache_read_tokens,
+                ..Default::default()
+            };
+            merge_parsed(
+                &mut parsed,
+                day.date,
+                "unknown".to_string(),
+                tokens,
+                day.total_cost,
+            );
+            continue;
+        }
+        for model in day.model_breakdowns {
+            let tokens = TokenUsage {
+                input_tokens: model.input_tokens,
+                output_tokens: model.output_tokens,
+                cache_creation_input_tokens: model.cache_creation_tokens,
+                cache_read_input_tokens: model.cache_read_tokens,
+                ..Default::default()
+            };
+            merge_parsed(
+                &mut parsed,
+                day.date.clone(),
+                model.model_name,
+                tokens,
+                model.cost,
+            );
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Split a CSV line, honoring double-quoted fields
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Claude Console usage CSV. Columns are matched by header name; token columns that share
+/// a field (e.g. 5m and 1h cache writes) are summed. Rows for other API keys or workspaces
+/// on the same day and model are merged.
+fn parse_claude_csv(contents: &str) -> Result<ParsedHistory, String> {
+    let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<String> = lines
+        .next()
+        .map(split_csv_line)
+        .ok_or("CSV export is empty")?
+        .into_iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+
+    let column = |names: &[&str]| -> Option<usize> {
+        header.iter().position(|h| names.contains(&h.as_str()))
+    };
+    let date_col = column(&["usage_date_utc", "date"]).ok_or("CSV has no date column")?;
+    let model_col = column(&["model_version", "model"]).ok_or("CSV has no model column")?;
+    let cost_col = column(&["cost_usd", "cost"]);
+    // field -> columns summed into it
+    let mut token_cols: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, name) in header.iter().enumerate() {
+        let field = if name.contains("cache_read") {
+            "cache_read"
+        } else if name.contains("cache_write") || name.contains("cache_creation") {
+            "cache_creation"
+        } else if name.contains("output_tokens") {
+            "output"
+        } else if name.contains("input_tokens") {
+            "input"
+        } else {
+            continue;
+        };
+        token_cols.entry(field).or_default().push(i);
+    }
+    if token_cols.is_empty() {
+        return Err("CSV has no token columns".to_string());
+    }
+
+    let mut parsed = ParsedHistory::new();
+    for line in lines {
+        let fields = split_csv_line(line);
+        let get = |i: usize| fields.get(i).map(|f| f.trim()).unwrap_or("");
+        let sum = |field: &str| -> u64 {
+            token_cols
+                .get(field)
+                .map(|cols| {
+                    cols.iter()
+                        .filter_map(|&i| get(i).parse::<u64>().ok())
+                        .sum()
+                })
+                .unwrap_or(0)
+        };
+
+        // Dates may carry a time part ("2025-06-01 00:00:00")
+        let date = get(date_col).chars().take(10).collect::<String>();
+        if NaiveDate::parse_from_str(&date, "%Y-%m-%d").is_err() {
+            continue;
+        }
+        let tokens = TokenUsage {
+            input_tokens: sum("input"),
+            output_tokens: sum("output"),
+            cache_creation_input_tokens: sum("cache_creation"),
+            cache_read_input_tokens: sum("cache_read"),
+            ..Default::default()
+        };
+        let cost = cost_col.and_then(|i| get(i).trim_start_matches('$').parse::<f64>().ok());
+        let model = match get(model_col) {
+            "" => "unknown".to_string(),
+            model => model.to_string(),
+        };
+        merge_parsed(&mut parsed, date, model, tokens, cost);
+    }
+
+    Ok(parsed)
+}
@@ -0,0 +1,91 @@
+//! `summary.json`: a compact snapshot of the widget's state rewritten on every refresh, so
+//! shell prompts, conky and scripts can read a file instead of talking D-Bus or IPC.
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::{self, SummaryFileConfig};
+use crate::usage::{self, UsageStats};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageSummary {
+    pub updated_at: String,
+    pub window_percent: f64,
+    pub window_ends_at: Option<String>,
+    pub week_percent: f64,
+    /// Local date `cost_today_usd` refers to
+    pub today: String,
+    pub cost_today_usd: Option<f64>,
+    pub active_sessions: Vec<SessionSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub project: String,
+    pub model: String,
+    pub context_remaining_percent: f64,
+    pub cost_usd: f64,
+    pub last_activity: String,
+}
+
+pub fn summary_path(settings: &SummaryFileConfig) -> PathBuf {
+    settings
+        .path
+        .as_ref()
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config::config_dir().join("summary.json"))
+}
+
+/// Rewrite the summary from freshly loaded stats. Only "today" stats carry today's cost;
+/// other periods keep the cost from the previous summary if it is from the same day.
+pub fn write_summary(
+    settings: &SummaryFileConfig,
+    stats: &UsageStats,
+    period: &str,
+) -> Result<(), String> {
+    let path = summary_path(settings);
+    let today = Local::now().format("%Y-%m-%d").to_string();
+
+    let cost_today_usd = if period == "today" {
+        Some(stats.total_cost_usd)
+    } else {
+        fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<UsageSummary>(&contents).ok())
+            .filter(|previous| previous.today == today)
+            .and_then(|previous| previous.cost_today_usd)
+    };
+
+    let summary = UsageSummary {
+        updated_at: Local::now().to_rfc3339(),
+        window_percent: stats.quota.usage_percent,
+        window_ends_at: stats.quota.window_ends_at.clone(),
+        week_percent: stats.quota.week_usage_percent,
+        today,
+        cost_today_usd,
+        active_sessions: stats
+            .active_sessions
+            .iter()
+            .map(|s| SessionSummary {
+                project: s.project.clone(),
+                model: s.model_display_name.clone(),
+                context_remaining_percent: s.context_remaining_percent,
+                cost_usd: s.cost_usd,
+                last_activity: s.last_activity.clone(),
+            })
+            .collect(),
+    };
+
+    usage::ensure_outside_data_dirs(&path)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())?;
+    // Write then rename so readers never see a half-written file
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| format!("Failed to write {}: {e}", tmp.display()))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("Failed to replace {}: {e}", path.display()))
+}
@@ -0,0 +1,112 @@
+//! Quick resume: open a terminal in a session's working directory, optionally running
+//! `claude --resume <id>` there, so the session list doubles as a launcher.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::config::TerminalConfig;
+
+/// Outcome of `open_session_in_terminal`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalLaunch {
+    pub directory: String,
+    /// `claude --resume <id>`, to run in the new terminal unless `resumed`
+    pub resume_command: String,
+    /// The terminal was started with the resume command (`resume_args` configured)
+    pub resumed: bool,
+}
+
+/// Terminals tried in order when none is configured
+fn default_commands() -> Vec<Vec<String>> {
+    let commands: &[&[&str]] = if cfg!(target_os = "macos") {
+        &[&["open", "-a", "Terminal", "{dir}"]]
+    } else if cfg!(windows) {
+        &[&["wt", "-d", "{dir}"], &["cmd", "/c", "start", "cmd"]]
+    } else {
+        &[
+            &["x-terminal-emulator"],
+            &["gnome-terminal"],
+            &["konsole"],
+            &["xfce4-terminal"],
+            &["kitty"],
+            &["alacritty"],
+            &["xterm"],
+        ]
+    };
+    let mut commands: Vec<Vec<String>> = commands
+        .iter()
+        .map(|args| args.iter().map(|arg| arg.to_string()).collect())
+        .collect();
+    // The user's preferred terminal, by the common convention
+    if let Ok(terminal) = std::env::var("TERMINAL") {
+        if !terminal.is_empty() && cfg!(all(unix, not(target_os = "macos"))) {
+            commands.insert(0, vec![terminal]);
+        }
+    }
+    commands
+}
+
+fn substitute(args: &[String], dir: &str, session_id: &str) -> Vec<String> {
+    args.iter()
+        .map(|arg| {
+            arg.replace("{dir}", dir)
+                .replace("{session_id}", session_id)
+        })
+        .collect()
+}
+
+/// Start a terminal in `dir`. With `resume`, the configured `resume_args` are appended so
+/// the terminal runs `claude --resume`; without them the command is only suggested.
+pub fn open_in_terminal(
+    settings: &TerminalConfig,
+    dir: &Path,
+    session_id: &str,
+    resume: bool,
+) -> Result<TerminalLaunch, String> {
+    if !dir.is_dir() {
+        return Err(format!(
+            "Session directory {} no longer exists",
+            dir.display()
+        ));
+    }
+    let dir_str = dir.display().to_string();
+    let candidates = if settings.command.is_empty() {
+        default_commands()
+    } else {
+        vec![settings.command.clone()]
+    };
+    let resumed = resume && !settings.resume_args.is_empty();
+
+    let mut last_error = "No terminal command".to_string();
+    for candidate in candidates {
+        let mut args = substitute(&candidate, &dir_str, session_id);
+        if resumed {
+            args.extend(substitute(&settings.resume_args, &dir_str, session_id));
+        }
+        let (program, rest) = match args.split_first() {
+            Some(split) => split,
+            None => continue,
+        };
+        let spawned = Command::new(program)
+            .args(rest)
+            .current_dir(dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match spawned {
+            Ok(mut child) => {
+                // Reap the launcher (e.g. `open`, `wt`) when it exits
+                std::thread::spawn(move || child.wait());
+                return Ok(TerminalLaunch {
+                    directory: dir_str,
+                    resume_command: format!("claude --resume {session_id}"),
+                    resumed,
+                });
+            }
+            Err(e) => last_error = format!("Failed to start {program}: {e}"),
+        }
+    }
+    Err(last_error)
+}
@@ -0,0 +1,235 @@
+//! Tray icon with a context menu of quick actions (show the widget, refresh, pause
+//! notifications, switch the displayed period, quit). Each action is also a command, so the
+//! widget and the menu stay in sync. The icon itself shows the current quota usage, redrawn
+//! on every refresh (see `icon`), on a thread of its own so a slow desktop theme lookup
+//! never holds up the journal watcher.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::config::{self, AppConfig};
+use crate::crash::log_line;
+use crate::events;
+use crate::icon;
+
+/// Set while a background icon update runs
+static ICON_UPDATE_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Set by each request for a background icon update, cleared by the update that serves it
+static ICON_UPDATE_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Periods the widget can display, with their menu labels
+pub const PERIODS: [(&str, &str); 5] = [
+    ("current-window", "This 5hr window"),
+    ("today", "Today"),
+    ("week", "Last 7 days"),
+    ("month", "Last 30 days"),
+    ("all", "All time"),
+];
+
+/// Menu label of a period; with a billing anchor day the "month" period is the billing cycle
+fn period_label(period: &str, cfg: &AppConfig) -> &'static str {
+    if period == "month" && cfg.billing_cycle_anchor_day.is_some() {
+        return "This billing cycle";
+    }
+    PERIODS
+        .iter()
+        .find(|(p, _)| *p == period)
+        .map_or("", |(_, label)| *label)
+}
+
+/// Menu items whose check state mirrors the config
+struct TrayItems {
+    pause: CheckMenuItem<Wry>,
+    periods: Vec<(&'static str, CheckMenuItem<Wry>)>,
+}
+
+/// The period shown by the widget ("today" unless switched)
+pub fn display_period() -> String {
+    config::load_config()
+        .display_period
+        .unwrap_or_else(|| "today".to_string())
+}
+
+/// Create the tray icon and its menu
+pub fn setup_tray(app: &tauri::App) -> tauri::Result<()> {
+    let handle = app.handle();
+    let cfg = config::load_config();
+    let current_period = display_period();
+
+    let show = MenuItem::with_id(handle, "show", "Show widget", true, None::<&str>)?;
+    let refresh = MenuItem::with_id(handle, "refresh", "Refresh now", true, None::<&str>)?;
+    let pause = CheckMenuItem::with_id(
+        handle,
+        "pause_notifications",
+        "Pause notifications",
+        true,
+        cfg.notifications.muted,
+        None::<&str>,
+    )?;
+    let mut periods = Vec::new();
+    for (period, _) in PERIODS {
+        let item = CheckMenuItem::with_id(
+            handle,
+            format!("period:{period}"),
+            period_label(period, &cfg),
+            true,
+            period == current_period,
+            None::<&str>,
+        )?;
+        periods.push((period, item));
+    }
+    let period_items: Vec<&dyn IsMenuItem<Wry>> = periods
+        .iter()
+        .map(|(_, item)| item as &dyn IsMenuItem<Wry>)
+        .collect();
+    let period_menu = Submenu::with_items(handle, "Period", true, &period_items)?;
+    let separator = PredefinedMenuItem::separator(handle)?;
+    let quit = MenuItem::with_id(handle, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        handle,
+        &[&show, &refresh, &pause, &period_menu, &separator, &quit],
+    )?;
+
+    TrayIconBuilder::with_id("main")
+        .icon(crate::load_icon())
+        .tooltip("Claude Code Usage")
+        .menu(&menu)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()))
+        .build(app)?;
+
+    app.manage(TrayItems { pause, periods });
+
+    spawn_icon_update(handle);
+    Ok(())
+}
+
+/// `update_icon` on a background thread. Requests while an update runs are merged into one
+/// more redraw after it.
+pub fn spawn_icon_update(app: &AppHandle) {
+    ICON_UPDATE_REQUESTED.store(true, Ordering::SeqCst);
+    if ICON_UPDATE_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let handle = app.clone();
+    std::thread::spawn(move || loop {
+        while ICON_UPDATE_REQUESTED.swap(false, Ordering::SeqCst) {
+            update_icon(&handle);
+        }
+        ICON_UPDATE_RUNNING.store(false, Ordering::SeqCst);
+        // A request made just before the flag was cleared saw it set and left the redraw here
+        if !ICON_UPDATE_REQUESTED.load(Ordering::SeqCst)
+            || ICON_UPDATE_RUNNING.swap(true, Ordering::SeqCst)
+        {
+            break;
+        }
+    });
+}
+
+/// Redraw the tray icon from the current quota usage, or restore the app icon when
+/// `tray_icon.show_usage` is off
+pub fn update_icon(app: &AppHandle) {
+    let tray = match app.tray_by_id("main") {
+        Some(tray) => tray,
+        None => return,
+    };
+    let settings = config::load_config().tray_icon;
+    let image = if settings.show_usage {
+        let quota = crate::state::get().quota();
+        let rgba = icon::render_usage_icon(
+            quota.usage_percent,
+            quota.week_usage_percent,
+            icon::dark_panel(settings.theme),
+        );
+        Image::new_owned(rgba.into_raw(), icon::ICON_SIZE, icon::ICON_SIZE)
+    } else {
+        crate::load_icon()
+    };
+    if let Err(e) = tray.set_icon(Some(image)) {
+        log_line!("Failed to update tray icon: {e}");
+    }
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    let result = match id {
+        "show" => show_widget(app),
+        "refresh" => {
+            refresh_now(app);
+            Ok(())
+        }
+        "pause_notifications" => {
+            let paused = !config::load_config().notifications.muted;
+            set_notifications_paused(app, paused)
+        }
+        "quit" => {
+            app.exit(0);
+            Ok(())
+        }
+        _ => match id.strip_prefix("period:") {
+            Some(period) => set_display_period(app, period),
+            None => Ok(()),
+        },
+    };
+    if let Err(e) = result {
+        log_line!("Tray action {id} failed: {e}");
+    }
+}
+
+/// Show, unminimize and focus the widget window
+pub fn show_widget(app: &AppHandle) -> Result<(), String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or_else(|| "Widget window not found".to_string())?;
+    window
+        .show()
+        .and_then(|_| window.unminimize())
+        .and_then(|_| window.set_focus())
+        .map_err(|e| format!("Failed to show window: {e}"))
+}
+
+/// Make the widget reload its stats and redraw the tray icon
+pub fn refresh_now(app: &AppHandle) {
+    events::emit(app, "usage-updated", ());
+    spawn_icon_update(app);
+}
+
+/// Mute or unmute all notifications (`NotificationPrefs::muted`)
+pub fn set_notifications_paused(app: &AppHandle, paused: bool) -> Result<(), String> {
+    config::update_config(|cfg| {
+        cfg.notifications.muted = paused;
+    })?;
+    sync_menu(app);
+    Ok(())
+}
+
+/// Switch the period shown by the widget; emits `period-changed` with the new period
+pub fn set_display_period(app: &AppHandle, period: &str) -> Result<(), String> {
+    if !PERIODS.iter().any(|(p, _)| *p == period) {
+        return Err(format!("Unknown period: {period}"));
+    }
+    config::update_config(|cfg| {
+        cfg.display_period = Some(period.to_string());
+    })?;
+    sync_menu(app);
+    events::emit(app, "period-changed", period);
+    Ok(())
+}
+
+/// Update the menu's check marks and period labels from the config, after changes made from
+/// either side
+pub fn sync_menu(app: &AppHandle) {
+    let items = match app.try_state::<TrayItems>() {
+        Some(items) => items,
+        None => return,
+    };
+    let cfg = config::load_config();
+    let _ = items.pause.set_checked(cfg.notifications.muted);
+    let current_period = cfg.display_period.as_deref().unwrap_or("today");
+    for (period, item) in &items.periods {
+        let _ = item.set_checked(*period == current_period);
+        let _ = item.set_text(period_label(period, &cfg));
+    }
+}
@@ -0,0 +1,86 @@
+//! Update check against the project's GitHub releases. Only reports the newest release;
+//! nothing is downloaded or installed.
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const LATEST_RELEASE_URL: &str =
+    "https://api.github.com/repos/monkut/cc-usage-widget/releases/latest";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Release notes longer than this (chars) are cut, the full notes are at `release_url`
+const MAX_NOTES_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub current_version: String,
+    pub latest_version: String,
+    /// The latest release is newer than the running version
+    pub update_available: bool,
+    pub release_url: String,
+    pub release_notes: Option<String>,
+    pub published_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    html_url: String,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    published_at: Option<String>,
+}
+
+/// "v1.2.3" or "1.2.3" as a version
+fn parse_version(tag: &str) -> Result<Version, String> {
+    let trimmed = tag.trim();
+    Version::parse(trimmed.strip_prefix('v').unwrap_or(trimmed))
+        .map_err(|e| format!("Release tag '{tag}' is not a version: {e}"))
+}
+
+/// Fetch the latest published release (drafts and pre-releases are skipped by GitHub) and
+/// compare it with the running version
+pub async fn check_for_updates() -> Result<UpdateInfo, String> {
+    let current_version = env!("CARGO_PKG_VERSION");
+    let release: Release = reqwest::Client::new()
+        .get(LATEST_RELEASE_URL)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        // GitHub rejects API requests without a user agent
+        .header(
+            reqwest::header::USER_AGENT,
+            concat!("cc-usage-widget/", env!("CARGO_PKG_VERSION")),
+        )
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to check for updates: {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to read the latest release: {e}"))?;
+
+    let latest = parse_version(&release.tag_name)?;
+    let current = parse_version(current_version)?;
+    let release_notes = release
+        .body
+        .map(|notes| {
+            notes
+                .trim()
+                .chars()
+                .take(MAX_NOTES_CHARS)
+                .collect::<String>()
+        })
+        .filter(|notes| !notes.is_empty());
+
+    Ok(UpdateInfo {
+        current_version: current.to_string(),
+        latest_version: latest.to_string(),
+        update_available: latest > current,
+        release_url: release.html_url,
+        release_notes,
+        published_at: release.published_at,
+    })
+}
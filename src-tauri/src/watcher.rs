@@ -0,0 +1,258 @@
+//! Journal watcher setup. By default the platform's native event backend is used (inotify on
+//! Linux, FSEvents on macOS, ReadDirectoryChangesW on Windows); the `watcher` config can
+//! switch to polling, tune the refresh latency and limit how deep the data dirs are watched,
+//! for all platforms and per platform. Content-hash change detection always polls, comparing
+//! contents. If the native backend can't be created (e.g. inotify instance limits), the
+//! watcher falls back to polling and `get_watcher_status` reports why.
+
+use notify::{Config, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::{AppConfig, DebounceConfig, WatcherBackend, WatcherSettings};
+use crate::crash::log_line;
+use crate::debounce::{self, WatcherStats};
+use crate::events;
+use crate::usage::{self, get_claude_data_dirs};
+
+/// How often the watcher looks for data dirs that appeared after startup (and, with a depth
+/// limit, for new directories inside them)
+pub const DATA_DIR_RESCAN_INTERVAL: Duration = Duration::from_secs(30);
+
+const DEFAULT_POLL_INTERVAL_MS: u64 = 2000;
+
+/// Shortest poll interval allowed; below this the poller would rescan the data dirs nonstop
+const MIN_POLL_INTERVAL_MS: u64 = 100;
+
+/// Refresh latency of daemon mode when `latency_ms` is unset
+const DAEMON_LATENCY_MS: u64 = 500;
+
+/// What the journal watcher runs with, from `get_watcher_status`
+#[derive(Debug, Clone, Serialize)]
+pub struct WatcherStatus {
+    /// "inotify", "fsevents", "kqueue", "read_directory_changes" or "poll"
+    pub backend: String,
+    /// Set for the poll backend
+    pub poll_interval_ms: Option<u64>,
+    /// The poller compares contents (content-hash change detection)
+    pub compare_contents: bool,
+    /// Configured refresh latency, if it overrides the debounce
+    pub latency_ms: Option<u64>,
+    pub max_depth: Option<u32>,
+    /// Directories watched, data dirs first
+    pub watched_dirs: Vec<String>,
+    /// Why the configured backend isn't the one running
+    pub error: Option<String>,
+    pub stats: WatcherStats,
+}
+
+struct ActiveWatcher {
+    backend: &'static str,
+    poll_interval_ms: Option<u64>,
+    compare_contents: bool,
+    settings: WatcherSettings,
+    watched_dirs: Vec<PathBuf>,
+    error: Option<String>,
+}
+
+static ACTIVE: Mutex<Option<ActiveWatcher>> = Mutex::new(None);
+
+/// Name of the backend behind `RecommendedWatcher` on this platform
+fn native_backend() -> &'static str {
+    if cfg!(target_os = "linux") {
+        "inotify"
+    } else if cfg!(target_os = "macos") {
+        "fsevents"
+    } else if cfg!(windows) {
+        "read_directory_changes"
+    } else {
+        "kqueue"
+    }
+}
+
+/// The `watcher` config for this platform: its override's fields where set, the shared ones
+/// otherwise
+pub fn effective_settings(cfg: &AppConfig) -> WatcherSettings {
+    let watcher = &cfg.watcher;
+    let platform = if cfg!(target_os = "linux") {
+        watcher.linux.as_ref()
+    } else if cfg!(target_os = "macos") {
+        watcher.macos.as_ref()
+    } else if cfg!(windows) {
+        watcher.windows.as_ref()
+    } else {
+        None
+    };
+    let all = &watcher.all;
+    match platform {
+        Some(platform) => WatcherSettings {
+            backend: platform.backend.or(all.backend),
+            poll_interval_ms: platform.poll_interval_ms.or(all.poll_interval_ms),
+            latency_ms: platform.latency_ms.or(all.latency_ms),
+            max_depth: platform.max_depth.or(all.max_depth),
+        },
+        None => all.clone(),
+    }
+}
+
+/// Debounce settings with the configured latency as the quiet time after a lone write
+pub fn debounce_config(cfg: &AppConfig) -> DebounceConfig {
+    let mut debounce = cfg.watcher_debounce.clone();
+    if let Some(latency_ms) = effective_settings(cfg).latency_ms {
+        debounce.min_ms = latency_ms;
+    }
+    debounce
+}
+
+/// Quiet time daemon mode waits for before refreshing; at least 10ms, so the loop never spins
+pub fn daemon_latency(cfg: &AppConfig) -> Duration {
+    let latency_ms = effective_settings(cfg)
+        .latency_ms
+        .unwrap_or(DAEMON_LATENCY_MS);
+    Duration::from_millis(latency_ms.max(10))
+}
+
+/// Create the journal watcher configured for this platform
+pub fn create_watcher(
+    tx: Sender<notify::Result<notify::Event>>,
+) -> notify::Result<Box<dyn Watcher + Send>> {
+    let cfg = crate::config::load_config();
+    let settings = effective_settings(&cfg);
+    let compare_contents = cfg.change_detection == usage::ChangeDetection::ContentHash;
+    let poll_interval = settings
+        .poll_interval_ms
+        .unwrap_or(DEFAULT_POLL_INTERVAL_MS)
+        .max(MIN_POLL_INTERVAL_MS);
+
+    let mut error = None;
+    let wants_native = settings.backend != Some(WatcherBackend::Poll) && !compare_contents;
+    if wants_native {
+        match RecommendedWatcher::new(tx.clone(), Config::default()) {
+            Ok(watcher) => {
+                set_active(native_backend(), None, false, settings, None);
+                return Ok(Box::new(watcher));
+            }
+            Err(e) => {
+                log_line!("Native watcher unavailable, polling instead: {:?}", e);
+                error = Some(format!("{} unavailable: {e}", native_backend()));
+            }
+        }
+    }
+
+    let poll_config = Config::default()
+        .with_poll_interval(Duration::from_millis(poll_interval))
+        .with_compare_contents(compare_contents);
+    let watcher = PollWatcher::new(tx, poll_config)?;
+    set_active(
+        "poll",
+        Some(poll_interval),
+        compare_contents,
+        settings,
+        error,
+    );
+    Ok(Box::new(watcher))
+}
+
+fn set_active(
+    backend: &'static str,
+    poll_interval_ms: Option<u64>,
+    compare_contents: bool,
+    settings: WatcherSettings,
+    error: Option<String>,
+) {
+    if let Ok(mut active) = ACTIVE.lock() {
+        *active = Some(ActiveWatcher {
+            backend,
+            poll_interval_ms,
+            compare_contents,
+            settings,
+            watched_dirs: Vec::new(),
+            error,
+        });
+    }
+}
+
+/// `dir` and its subdirectories down to `depth` levels below it
+fn dirs_to_depth(dir: &Path, depth: u32, dirs: &mut Vec<PathBuf>) {
+    dirs.push(dir.to_path_buf());
+    if depth == 0 {
+        return;
+    }
+    let Ok(children) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for child in children.flatten() {
+        if child.file_type().is_ok_and(|kind| kind.is_dir()) {
+            dirs_to_depth(&child.path(), depth - 1, dirs);
+        }
+    }
+}
+
+/// Watch any data dirs not yet watched (e.g. created later or granted via the portal), and
+/// the config roots holding Claude Code's `settings.json` so model changes refresh the widget.
+/// With `max_depth`, data dirs are watched level by level, and new directories within the
+/// depth are picked up on the next call.
+pub fn watch_new_data_dirs(watcher: &mut (dyn Watcher + Send), watched: &mut Vec<PathBuf>) {
+    let max_depth = effective_settings(&crate::config::load_config()).max_depth;
+    // Roots are watched rather than settings.json itself, since editors replace the file
+    let settings_roots = usage::claude_settings_files()
+        .into_iter()
+        .filter_map(|settings| settings.parent().map(PathBuf::from))
+        .map(|root| (root, RecursiveMode::NonRecursive));
+    let data_dirs: Vec<(PathBuf, RecursiveMode)> = match max_depth {
+        Some(depth) => {
+            let mut dirs = Vec::new();
+            for dir in get_claude_data_dirs() {
+                dirs_to_depth(&dir, depth, &mut dirs);
+            }
+            dirs.into_iter()
+                .map(|dir| (dir, RecursiveMode::NonRecursive))
+                .collect()
+        }
+        None => get_claude_data_dirs()
+            .into_iter()
+            .map(|dir| (dir, RecursiveMode::Recursive))
+            .collect(),
+    };
+
+    for (dir, mode) in data_dirs.into_iter().chain(settings_roots) {
+        if watched.contains(&dir) {
+            continue;
+        }
+        if let Err(e) = watcher.watch(&dir, mode) {
+            log_line!("Failed to watch {:?}: {:?}", dir, e);
+            events::warning(&format!("Failed to watch {}: {e}", dir.display()));
+            continue;
+        }
+        watched.push(dir);
+    }
+    if let Ok(mut active) = ACTIVE.lock() {
+        if let Some(active) = active.as_mut() {
+            active.watched_dirs = watched.clone();
+        }
+    }
+}
+
+/// The running watcher's backend and settings, or None while no journals are watched (OTLP
+/// source, or the watcher failed to start)
+pub fn watcher_status() -> Option<WatcherStatus> {
+    let active = ACTIVE.lock().ok()?;
+    let active = active.as_ref()?;
+    Some(WatcherStatus {
+        backend: active.backend.to_string(),
+        poll_interval_ms: active.poll_interval_ms,
+        compare_contents: active.compare_contents,
+        latency_ms: active.settings.latency_ms,
+        max_depth: active.settings.max_depth,
+        watched_dirs: active
+            .watched_dirs
+            .iter()
+            .map(|dir| dir.display().to_string())
+            .collect(),
+        error: active.error.clone(),
+        stats: debounce::watcher_stats(),
+    })
+}
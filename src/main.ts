@@ -1,12 +1,16 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { getCurrentWindow } from "@tauri-apps/api/window";
+import { openUrl } from "@tauri-apps/plugin-opener";
 
 interface TokenUsage {
   input_tokens: number;
   output_tokens: number;
   cache_creation_input_tokens: number;
   cache_read_input_tokens: number;
+  cache_creation_5m_input_tokens: number;
+  cache_creation_1h_input_tokens: number;
+  thinking_tokens: number;
 }
 
 interface ModelUsage {
@@ -14,6 +18,11 @@ interface ModelUsage {
   display_name: string;
   tokens: TokenUsage;
   cost_usd: number;
+  provider: string | null;
+  avg_output_tps: number | null;
+  thinking_cost_usd: number;
+  display_tokens: string;
+  display_cost: string;
 }
 
 interface QuotaInfo {
@@ -24,6 +33,143 @@ interface QuotaInfo {
   plan: string;
   week_usage_percent: number;
   week_limit_hours: number;
+  window_started_at: string | null;
+  window_ends_at: string | null;
+  week_to_date_percent: number;
+  pace_status: "Ahead" | "OnTrack" | "Behind";
+  pace_delta_percent: number;
+  safe_pace_percent_per_hour: number;
+  expected_week_percent: number;
+  projected_week_usage_percent: number;
+  windows: QuotaWindow[];
+}
+
+interface QuotaWindow {
+  name: string;
+  metric: "prompts" | "tokens" | "weighted";
+  window_minutes: number;
+  limit: number;
+  used: number;
+  usage_percent: number;
+  builtin: boolean;
+}
+
+interface GoalStatus {
+  id: string;
+  kind: string;
+  label: string;
+  target: number;
+  current: number;
+  met: boolean;
+  streak: number;
+  best_streak: number;
+}
+
+interface UserCodeMetrics {
+  user: string;
+  sessions: number;
+  lines_added: number;
+  lines_removed: number;
+  commits: number;
+  pull_requests: number;
+  edits_accepted: number;
+  edits_rejected: number;
+  cost_usd: number;
+}
+
+interface OrgUtilization {
+  seat_count: number;
+  active_seats: number | null;
+  week_tokens: number;
+  week_cost_usd: number;
+  capacity_tokens: number | null;
+  capacity_cost_usd: number | null;
+  utilization_percent: number;
+  cost_per_seat_usd: number;
+}
+
+interface BillingCycle {
+  start: string;
+  renews_on: string;
+  elapsed_percent: number;
+  days_left: number;
+  cost_usd: number;
+  projected_cost_usd: number;
+  projected_tokens: number;
+}
+
+interface OrgSummary {
+  name: string;
+  member_count: number;
+  pending_invites: number;
+  workspace_count: number;
+  today_cost_usd: number;
+  fetched_at: string;
+}
+
+interface SourceIssue {
+  kind: string;
+  path: string;
+  detail: string;
+}
+
+interface DailyDelta {
+  tokens_delta: number;
+  cost_delta_usd: number;
+  prompts_delta: number;
+  sessions_started_delta: number;
+  new_projects: string[];
+  summary: string;
+}
+
+interface FailedRequests {
+  errors: number;
+  aborted: number;
+  wasted_tokens: TokenUsage;
+  wasted_cost_usd: number;
+}
+
+interface FailedRequestReport {
+  total: FailedRequests;
+}
+
+interface OrgAnalytics {
+  date: string;
+  users: UserCodeMetrics[];
+  sessions: number;
+  lines_added: number;
+  lines_removed: number;
+  commits: number;
+  pull_requests: number;
+  cost_usd: number;
+}
+
+interface ContextBreakdown {
+  system_tokens: number;
+  history_tokens: number;
+  fresh_input_tokens: number;
+  context_limit: number;
+  system_percent: number;
+  history_percent: number;
+  fresh_input_percent: number;
+}
+
+interface ContextPoint {
+  timestamp: string;
+  context_tokens: number;
+  used_percent: number;
+  model: string;
+  after_compaction: boolean;
+}
+
+interface SessionContextHistory {
+  session_id: string;
+  context_limit: number;
+  points: ContextPoint[];
+  compactions: number;
+  tokens_per_message: number | null;
+  messages_until_compaction: number | null;
+  compaction_expected_at: string | null;
 }
 
 interface ActiveSession {
@@ -40,11 +186,47 @@ interface ActiveSession {
   model_display_name: string;
   context_remaining_percent: number;
   todo_count: number;
+  recent_activity: number[];
+  avg_output_tps: number | null;
+  branch_count: number;
+  abandoned_branch_tokens: number;
+  context_breakdown: ContextBreakdown | null;
+  commands_executed: number;
+  user: string | null;
+  display_last_activity: string;
+  display_tokens: string;
+  display_cost: string;
+  process_running: boolean;
+  pid: number | null;
+}
+
+interface AppInfo {
+  version: string;
+  git_hash: string;
+  build_date: string | null;
+  debug_build: boolean;
+  tauri_version: string;
+  features: string[];
+  services: string[];
+  os: string;
+  os_version: string | null;
+  arch: string;
+  active_workarounds: string[];
+}
+
+interface UpdateInfo {
+  current_version: string;
+  latest_version: string;
+  update_available: boolean;
+  release_url: string;
+  release_notes: string | null;
+  published_at: string | null;
 }
 
 interface DailyActivity {
   date: string;
   prompt_count: number;
+  data_complete: boolean;
 }
 
 interface WeekDay {
@@ -71,9 +253,39 @@ interface UsageStats {
   active_sessions: ActiveSession[];
   daily_activity: DailyActivity[];
   weekly_usage: WeeklyUsage;
+  off_hours_percent: number;
+  configured_model: string | null;
+  plan_hint: string | null;
+  goals: GoalStatus[];
+  billing_cycle: BillingCycle | null;
+  org_analytics: OrgAnalytics | null;
+  org_utilization: OrgUtilization | null;
+  org_summary: OrgSummary | null;
+  truncated: boolean;
+  degraded: SourceIssue[];
+  display_last_updated: string;
+  display_total_tokens: string;
+  display_total_cost: string;
 }
 
 let transparency = 85;
+// Period shown, switched from the tray menu ("current-window", "today", "week", "month" or "all")
+let displayPeriod = "today";
+// Low-memory mode: no prefetching and no activity heatmap
+let lowMemory = false;
+const PERIOD_LABELS: Record<string, string> = {
+  "current-window": "This 5hr window",
+  week: "Last 7 days",
+  month: "Last 30 days",
+  all: "All time",
+};
+// Periods fetched together so switching between them needs no re-parse
+const PREFETCH_PERIODS = ["today", "week", "month"];
+let usageByPeriod: Record<string, UsageStats> = {};
+let dailyDelta: DailyDelta | null = null;
+let failedToday: FailedRequests | null = null;
+// Session awaiting a second click to stop its process (see setupSessionLauncher)
+let pendingTermination: TerminationRequest | null = null;
 let settingsOpen = false;
 let retryCount = 0;
 let retryTimeoutId: ReturnType<typeof setTimeout> | null = null;
@@ -96,6 +308,44 @@ function saveSettings(): void {
   localStorage.setItem("cc-widget-settings", JSON.stringify({ transparency }));
 }
 
+// Version line at the bottom of the settings panel; the tooltip has the details for bug reports
+async function showAppInfo(): Promise<void> {
+  const el = document.getElementById("app-info");
+  if (!el) return;
+  try {
+    const info = await invoke<AppInfo>("get_app_info");
+    el.textContent = `v${info.version} (${info.git_hash})${info.debug_build ? " debug" : ""}`;
+    el.title = [
+      `Built: ${info.build_date ?? "unknown"}`,
+      `Tauri: ${info.tauri_version}`,
+      `Platform: ${info.os_version ?? info.os} ${info.arch}`,
+      `Features: ${info.features.join(", ") || "none"}`,
+      `Services: ${info.services.join(", ") || "none"}`,
+      `Workarounds: ${info.active_workarounds.join(", ") || "none"}`,
+    ].join("\n");
+  } catch (e) {
+    console.error("Failed to get app info:", e);
+  }
+}
+
+// Small title bar hint when a newer release exists; failures (offline, rate limits) stay silent
+async function checkForUpdates(): Promise<void> {
+  const hint = document.getElementById("update-hint");
+  if (!hint) return;
+  try {
+    if (!(await invoke<boolean>("get_update_check"))) return;
+    const info = await invoke<UpdateInfo>("check_for_updates");
+    if (!info.update_available) return;
+    hint.textContent = `v${info.latest_version}`;
+    hint.title = `Update available: ${info.current_version} → ${info.latest_version}` +
+      (info.release_notes ? `\n\n${info.release_notes}` : "");
+    hint.onclick = () => openUrl(info.release_url);
+    hint.style.display = "inline-block";
+  } catch (e) {
+    console.debug("Update check failed:", e);
+  }
+}
+
 function applyTransparency(): void {
   const container = document.querySelector(".container") as HTMLElement;
   if (container) {
@@ -103,6 +353,35 @@ function applyTransparency(): void {
   }
 }
 
+// Tooltip for the context column: what fills the context window
+function contextBreakdownTitle(breakdown: ContextBreakdown | null): string {
+  if (!breakdown) return "";
+  const part = (label: string, tokens: number, percent: number) =>
+    `${label}: ${formatNumber(tokens)} (${percent.toFixed(0)}%)`;
+  return [
+    part("System, tools & memory", breakdown.system_tokens, breakdown.system_percent),
+    part("Conversation history", breakdown.history_tokens, breakdown.history_percent),
+    part("Fresh input", breakdown.fresh_input_tokens, breakdown.fresh_input_percent),
+  ].join("\n");
+}
+
+// Context growth since the session started and when auto-compaction is expected
+function contextHistoryTitle(history: SessionContextHistory): string {
+  const growth = history.points
+    .slice(-12)
+    .map((p) => `${p.after_compaction ? "↓" : ""}${p.used_percent.toFixed(0)}%`)
+    .join(" → ");
+  const lines = [`Context used (last ${Math.min(history.points.length, 12)} messages): ${growth}`];
+  if (history.compactions > 0) lines.push(`${history.compactions} compaction${history.compactions === 1 ? "" : "s"} so far`);
+  if (history.messages_until_compaction != null) {
+    const at = history.compaction_expected_at ? ` (~${new Date(history.compaction_expected_at).toLocaleTimeString()})` : "";
+    lines.push(`Auto-compact in ~${history.messages_until_compaction} message${history.messages_until_compaction === 1 ? "" : "s"}${at}`);
+  } else if (history.tokens_per_message != null) {
+    lines.push("Context isn't growing");
+  }
+  return lines.join("\n");
+}
+
 function formatNumber(num: number): string {
   if (num >= 1_000_000) {
     return (num / 1_000_000).toFixed(1) + "M";
@@ -112,6 +391,11 @@ function formatNumber(num: number): string {
   return num.toLocaleString();
 }
 
+const PROVIDER_LABELS: Record<string, string> = {
+  bedrock: "Bedrock",
+  vertex: "Vertex AI",
+};
+
 function getModelClass(model: string): string {
   if (model.includes("opus")) return "model-opus";
   if (model.includes("sonnet")) return "model-sonnet";
@@ -134,43 +418,68 @@ function formatDuration(minutes: number): string {
   return `${hours}h ${mins}m`;
 }
 
+function renderSparkline(values: number[]): string {
+  if (!values || values.length === 0) return '<span class="session-sparkline"></span>';
+  const width = 40;
+  const height = 12;
+  const max = Math.max(...values, 1);
+  const step = width / Math.max(values.length - 1, 1);
+  const points = values
+    .map((v, i) => `${(i * step).toFixed(1)},${(height - (v / max) * height).toFixed(1)}`)
+    .join(" ");
+  return `<svg class="session-sparkline" width="${width}" height="${height}" viewBox="0 0 ${width} ${height}"><polyline points="${points}" /></svg>`;
+}
+
 function formatDirectory(path: string, maxLength: number = 30): string {
   if (path.length <= maxLength) return path;
   // Truncate from the beginning, keep the end
   return "..." + path.slice(-(maxLength - 3));
 }
 
-function getNextWeeklyReset(): string {
+// Day the week starts on (0 = Sunday), from the backend's current week (week_starts_on)
+function getWeekStartDow(weeklyUsage: WeeklyUsage): number {
+  const [year, month, day] = weeklyUsage.week_start.split("-").map(Number);
+  return new Date(year, month - 1, day).getDay();
+}
+
+function getNextWeeklyReset(weekStartDow: number): string {
   const now = new Date();
   const dayOfWeek = now.getDay(); // 0 = Sunday
-  // Reset is on Sunday (day 0)
-  const daysUntilReset = dayOfWeek === 0 ? 7 : 7 - dayOfWeek;
+  // Reset is at the start of the next week; 7 days away on its first day
+  const daysUntilReset = (weekStartDow - dayOfWeek + 7) % 7 || 7;
   const resetDate = new Date(now);
   resetDate.setDate(now.getDate() + daysUntilReset);
   return `${resetDate.getMonth() + 1}/${resetDate.getDate()}`;
 }
 
-function getWeeklyExpectedPercent(): number {
-  const dayOfWeek = new Date().getDay(); // 0 = Sunday
-  // Week starts on Sunday (day 1), ends Saturday (day 7)
-  const currentDay = dayOfWeek + 1;
-  return (currentDay / 7) * 100;
+function formatPace(quota: QuotaInfo): string {
+  const delta = `${quota.pace_delta_percent >= 0 ? "+" : ""}${quota.pace_delta_percent.toFixed(0)}%`;
+  switch (quota.pace_status) {
+    case "Ahead":
+      return `Ahead of pace ${delta}`;
+    case "Behind":
+      return `Room to spare ${delta}`;
+    default:
+      return "On pace";
+  }
 }
 
-function getRollingExpectedPercent(windowHours: number): number {
-  const now = new Date();
-  const minutesSinceMidnight = now.getHours() * 60 + now.getMinutes();
-  const windowMinutes = windowHours * 60;
-  const minutesIntoWindow = minutesSinceMidnight % windowMinutes;
-  return ((minutesIntoWindow + 1) / windowMinutes) * 100;
+// Minutes elapsed in the active quota block (0 when no block is running)
+function getMinutesIntoWindow(quota: QuotaInfo): number {
+  if (!quota.window_started_at) return 0;
+  const elapsedMs = Date.now() - new Date(quota.window_started_at).getTime();
+  return Math.min(Math.max(Math.floor(elapsedMs / 60000), 0), quota.window_hours * 60);
 }
 
-function getRollingRemainingTime(windowHours: number): string {
-  const now = new Date();
-  const minutesSinceMidnight = now.getHours() * 60 + now.getMinutes();
-  const windowMinutes = windowHours * 60;
-  const minutesIntoWindow = minutesSinceMidnight % windowMinutes;
-  const minutesRemaining = windowMinutes - minutesIntoWindow;
+function getRollingExpectedPercent(quota: QuotaInfo): number {
+  if (!quota.window_started_at) return 0;
+  const windowMinutes = quota.window_hours * 60;
+  return ((getMinutesIntoWindow(quota) + 1) / windowMinutes) * 100;
+}
+
+function getRollingRemainingTime(quota: QuotaInfo): string {
+  const windowMinutes = quota.window_hours * 60;
+  const minutesRemaining = windowMinutes - getMinutesIntoWindow(quota);
   const hours = Math.floor(minutesRemaining / 60);
   const minutes = minutesRemaining % 60;
   return `${hours.toString().padStart(2, "0")}:${minutes.toString().padStart(2, "0")}`;
@@ -182,25 +491,28 @@ function formatUsageDiff(actual: number, expected: number): string {
   return `(${sign}${diff.toFixed(0)}%)`;
 }
 
-function renderActivityHeatmap(dailyActivity: DailyActivity[]): string {
+function renderActivityHeatmap(dailyActivity: DailyActivity[], weekStartDow: number): string {
   // Build a map of date -> prompt_count
   const activityMap = new Map<string, number>();
+  // Days in a gap that looks like missing logs rather than a break
+  const missingDays = new Set<string>();
   let maxCount = 0;
   for (const day of dailyActivity) {
     activityMap.set(day.date, day.prompt_count);
+    if (day.data_complete === false) missingDays.add(day.date);
     if (day.prompt_count > maxCount) maxCount = day.prompt_count;
   }
 
-  // Generate exactly 12 weeks of dates, ending on Saturday of current week
+  // Generate exactly 12 weeks of dates, ending on the last day of the current week
   // This ensures the grid is always full with the latest week on the far-right
   const today = new Date();
-  const todayDow = today.getDay(); // 0=Sun, 6=Sat
+  const todayIndex = (today.getDay() - weekStartDow + 7) % 7; // Position in the week, 0-6
 
-  // Find the Saturday of the current week (end of the rightmost column)
+  // Find the last day of the current week (end of the rightmost column)
   const endDate = new Date(today);
-  endDate.setDate(today.getDate() + (6 - todayDow));
+  endDate.setDate(today.getDate() + (6 - todayIndex));
 
-  // Go back 12 weeks (84 days) from that Saturday to get the starting Sunday
+  // Go back 12 weeks (84 days) from that day to get the first day of the first week
   const startDate = new Date(endDate);
   startDate.setDate(endDate.getDate() - 83);
 
@@ -245,13 +557,13 @@ function renderActivityHeatmap(dailyActivity: DailyActivity[]): string {
   // Build grid HTML
   let html = '<div class="heatmap-grid">';
 
-  // For each day of week (row)
+  // For each day of week (row), starting with the first day of the week
   for (let dow = 0; dow < 7; dow++) {
     html += '<div class="heatmap-row">';
     // Day label
     const dayLabels = ["S", "M", "T", "W", "T", "F", "S"];
     if (dow === 1 || dow === 3 || dow === 5) {
-      html += `<span class="heatmap-label">${dayLabels[dow]}</span>`;
+      html += `<span class="heatmap-label">${dayLabels[(weekStartDow + dow) % 7]}</span>`;
     } else {
       html += '<span class="heatmap-label"></span>';
     }
@@ -261,6 +573,8 @@ function renderActivityHeatmap(dailyActivity: DailyActivity[]): string {
       const dayData = week[dow]; // Direct index since each week has all 7 days in order
       if (dayData.isFuture) {
         html += '<div class="heatmap-cell future"></div>';
+      } else if (missingDays.has(dayData.date)) {
+        html += `<div class="heatmap-cell missing" data-tooltip="${dayData.date}: logs missing?"></div>`;
       } else {
         const level = getLevel(dayData.count);
         const tooltip = `${dayData.date}: ${dayData.count}`;
@@ -274,6 +588,148 @@ function renderActivityHeatmap(dailyActivity: DailyActivity[]): string {
   return html;
 }
 
+function renderGoals(goals: GoalStatus[]): string {
+  if (!goals || goals.length === 0) return "";
+  const periodUnit = (kind: string) => (kind === "max_daily_cost" ? "day" : kind === "max_monthly_cost" ? "month" : "week");
+  return `
+      <div class="goals-section">
+        <h3>Goals</h3>
+        ${goals
+          .map((g) => {
+            const current = g.kind === "min_cache_hit_rate" ? `${g.current.toFixed(0)}%` : `$${g.current.toFixed(2)}`;
+            const unit = periodUnit(g.kind);
+            return `
+          <div class="goal-row ${g.met ? "goal-met" : "goal-missed"}">
+            <span class="goal-label">${g.label}</span>
+            <span class="goal-current">${current}</span>
+            <span class="goal-streak" title="Best: ${g.best_streak} ${unit}${g.best_streak === 1 ? "" : "s"}">${g.streak > 0 ? `🔥${g.streak} ${unit}${g.streak === 1 ? "" : "s"}` : "-"}</span>
+          </div>`;
+          })
+          .join("")}
+      </div>`;
+}
+
+function renderOrgAnalytics(analytics: OrgAnalytics | null): string {
+  if (!analytics) return "";
+  const escape = (text: string) => text.replace(/[&<>"']/g, (c) => `&#${c.charCodeAt(0)};`);
+  return `
+      <div class="org-section">
+        <h3>Team Claude Code (${analytics.date})</h3>
+        <div class="org-totals">${analytics.sessions} sessions · +${formatNumber(analytics.lines_added)}/-${formatNumber(analytics.lines_removed)} lines · ${analytics.commits} commits · ${analytics.pull_requests} PRs · $${analytics.cost_usd.toFixed(2)}</div>
+        ${analytics.users
+          .slice(0, 5)
+          .map((u) => {
+            const edits = u.edits_accepted + u.edits_rejected;
+            const acceptance = edits > 0 ? `${((u.edits_accepted / edits) * 100).toFixed(0)}% accepted` : "";
+            return `
+          <div class="org-user-row">
+            <span class="org-user" title="${escape(u.user)}">${escape(u.user)}</span>
+            <span class="org-user-meta" title="${acceptance}">${u.sessions} sess · +${formatNumber(u.lines_added)}</span>
+            <span class="org-user-cost">$${u.cost_usd.toFixed(2)}</span>
+          </div>`;
+          })
+          .join("")}
+      </div>`;
+}
+
+function renderOrgUtilization(org: OrgUtilization | null): string {
+  if (!org) return "";
+  const seats = org.active_seats != null ? `${org.active_seats}/${org.seat_count} seats active today` : `${org.seat_count} seats`;
+  const capacity = [
+    org.capacity_tokens != null ? `${formatNumber(org.week_tokens)}/${formatNumber(org.capacity_tokens)} tokens` : "",
+    org.capacity_cost_usd != null ? `$${org.week_cost_usd.toFixed(2)}/$${org.capacity_cost_usd.toFixed(2)}` : "",
+  ].filter(Boolean).join(" · ");
+  return `
+      <div class="org-section">
+        <h3>Team Seats (7 days)</h3>
+        <div class="quota-bar-container">
+          <div class="quota-bar" style="width: ${Math.min(org.utilization_percent, 100)}%; background: ${getWeekDiffColor(org.utilization_percent - 100)};"></div>
+        </div>
+        <div class="org-totals">${org.utilization_percent.toFixed(1)}% of seat allowance · ${seats} · $${org.cost_per_seat_usd.toFixed(2)}/seat${capacity ? ` · ${capacity}` : ""}</div>
+      </div>`;
+}
+
+// "month" with a billing anchor day: the cycle so far and where its cost is heading
+function renderBillingCycle(cycle: BillingCycle): string {
+  const renews = new Date(`${cycle.renews_on}T00:00:00`).toLocaleDateString(undefined, { month: "short", day: "numeric" });
+  return ` · <span title="${cycle.elapsed_percent.toFixed(0)}% of the billing cycle elapsed, ${cycle.days_left} day${cycle.days_left === 1 ? "" : "s"} left">Since ${cycle.start} · ~$${cycle.projected_cost_usd.toFixed(2)} by ${renews}</span>`;
+}
+
+// Title bar: "Acme Org · 14 members · $231 today" in API mode, the app name otherwise
+function renderOrgHeader(org: OrgSummary | null): void {
+  const title = document.querySelector(".title-text") as HTMLElement | null;
+  if (!title) return;
+  if (!org) {
+    title.textContent = "Claude Code Usage";
+    return;
+  }
+  const invited = org.pending_invites > 0 ? ` (+${org.pending_invites} invited)` : "";
+  title.textContent = `${org.name} · ${org.member_count} member${org.member_count === 1 ? "" : "s"}${invited} · $${org.today_cost_usd.toFixed(0)} today`;
+}
+
+function formatWindowLength(minutes: number): string {
+  if (minutes % (24 * 60) === 0) return `${minutes / (24 * 60)}d`;
+  if (minutes % 60 === 0) return `${minutes / 60}h`;
+  return `${minutes}m`;
+}
+
+// Custom quota windows; the built-in 5hr block and week have their own rows above
+function renderQuotaWindows(windows: QuotaWindow[]): string {
+  const custom = windows.filter((w) => !w.builtin);
+  if (!custom.length) return "";
+  const escape = (text: string) => text.replace(/[&<>"']/g, (c) => `&#${c.charCodeAt(0)};`);
+  return custom
+    .map((w) => {
+      // Rolling windows have no expected pace: warn from 80% of the limit
+      const color = getWeekDiffColor(w.usage_percent - 80);
+      const amount = w.metric === "tokens" ? `${formatNumber(w.used)}/${formatNumber(w.limit)}` : `${w.used.toFixed(w.metric === "weighted" ? 1 : 0)}/${w.limit}`;
+      return `
+        <div class="quota-item quota-window">
+          <div class="quota-header">
+            <span class="quota-title">${escape(w.name)} (${formatWindowLength(w.window_minutes)} ${w.metric})</span>
+          </div>
+          <div class="quota-bar-container">
+            <div class="quota-bar" style="width: ${w.usage_percent}%; background: ${color};"></div>
+          </div>
+          <div class="quota-details">
+            <span class="quota-percent" style="color: ${color};">${w.usage_percent.toFixed(1)}%</span>
+            <span class="quota-count">${amount}</span>
+          </div>
+        </div>`;
+    })
+    .join("");
+}
+
+// Warning badge for data sources left out of the totals
+function renderDegraded(issues: SourceIssue[]): string {
+  if (!issues.length) return "";
+  const escape = (text: string) => text.replace(/[&<>"']/g, (c) => `&#${c.charCodeAt(0)};`);
+  const title = issues.map((issue) => `${issue.path}: ${issue.detail}`).join("\n");
+  const noun = issues.length === 1 ? "source" : "sources";
+  return `<span class="degraded-badge" title="${escape(title)}">⚠ ${issues.length} ${noun} unreadable</span>`;
+}
+
+function renderDailyDelta(delta: DailyDelta | null): string {
+  if (!delta) return "";
+  const escape = (text: string) => text.replace(/[&<>"']/g, (c) => `&#${c.charCodeAt(0)};`);
+  const sign = (n: number) => (n < 0 ? "-" : "+");
+  const title = [
+    `${sign(delta.tokens_delta)}${formatNumber(Math.abs(delta.tokens_delta))} tokens`,
+    `${sign(delta.sessions_started_delta)}${Math.abs(delta.sessions_started_delta)} sessions started`,
+    ...(delta.new_projects.length ? [`New: ${delta.new_projects.join(", ")}`] : []),
+  ].join(" · ");
+  return `<div class="daily-delta" title="${escape(title)}">vs yesterday: ${escape(delta.summary)}</div>`;
+}
+
+// Today's API errors and interrupted requests, hidden while there are none
+function renderFailedRequests(failed: FailedRequests | null): string {
+  if (!failed || failed.errors + failed.aborted === 0) return "";
+  const tokens = failed.wasted_tokens;
+  const wasted = tokens.input_tokens + tokens.output_tokens + tokens.cache_creation_input_tokens + tokens.cache_read_input_tokens;
+  const title = `${failed.errors} API errors · ${failed.aborted} interrupted · ${formatNumber(wasted)} tokens wasted`;
+  return `<div class="failed-requests" title="${title}">Lost to errors today: ${failed.errors + failed.aborted} requests, $${failed.wasted_cost_usd.toFixed(2)}</div>`;
+}
+
 function renderWeeklyUsageChart(weeklyUsage: WeeklyUsage): string {
   const { days, estimated_weekly_limit } = weeklyUsage;
 
@@ -362,7 +818,22 @@ function scheduleRetry(): void {
   }, delay);
 }
 
-async function fetchUsage(): Promise<void> {
+// Stats for the displayed period and the prefetched ones, in one backend call
+async function fetchAllPeriods(): Promise<UsageStats> {
+  const periods = lowMemory
+    ? [displayPeriod]
+    : [displayPeriod, ...PREFETCH_PERIODS.filter((p) => p !== displayPeriod)];
+  let failed: FailedRequestReport | null;
+  [usageByPeriod, dailyDelta, failed] = await Promise.all([
+    invoke<Record<string, UsageStats>>("get_usage_multi", { periods }),
+    invoke<DailyDelta>("get_daily_delta").catch(() => null),
+    invoke<FailedRequestReport>("get_failed_requests", { days: 1 }).catch(() => null),
+  ]);
+  failedToday = failed?.total ?? null;
+  return usageByPeriod[displayPeriod];
+}
+
+async function fetchUsage(useCached = false): Promise<void> {
   const statsEl = document.getElementById("stats");
   const errorEl = document.getElementById("error");
   const loadingEl = document.getElementById("loading");
@@ -370,7 +841,7 @@ async function fetchUsage(): Promise<void> {
   if (!statsEl || !errorEl || !loadingEl) return;
 
   try {
-    const stats: UsageStats = await invoke("get_usage", { period: "today" });
+    const stats: UsageStats = (useCached && usageByPeriod[displayPeriod]) || (await fetchAllPeriods());
 
     // Success - reset retry state and clear reload flag
     retryCount = 0;
@@ -384,19 +855,25 @@ async function fetchUsage(): Promise<void> {
     requestAnimationFrame(() => {
       loadingEl.style.display = "none";
       errorEl.style.display = "none";
+      renderOrgHeader(stats.org_summary);
 
-      const expectedRollingPercent = getRollingExpectedPercent(stats.quota.window_hours);
+      const expectedRollingPercent = getRollingExpectedPercent(stats.quota);
       const rollingDiff = stats.quota.usage_percent - expectedRollingPercent;
       const quotaColor = getWeekDiffColor(rollingDiff);
       const rollingDiffText = formatUsageDiff(stats.quota.usage_percent, expectedRollingPercent);
-      const rollingRemainingTime = getRollingRemainingTime(stats.quota.window_hours);
+      const rollingRemainingTime = getRollingRemainingTime(stats.quota);
 
-      const expectedWeekPercent = getWeeklyExpectedPercent();
-      const weekDiff = stats.quota.week_usage_percent - expectedWeekPercent;
+      const weekStartDow = getWeekStartDow(stats.weekly_usage);
+      // Share of the week elapsed, not counting configured days off
+      const expectedWeekPercent = stats.quota.expected_week_percent;
+      // Judged on usage since the week started; the bar shows the rolling 7 days
+      const weekDiff = stats.quota.week_to_date_percent - expectedWeekPercent;
       const weekColor = getWeekDiffColor(weekDiff);
-      const weekDiffText = formatUsageDiff(stats.quota.week_usage_percent, expectedWeekPercent);
+      const weekDiffText = formatUsageDiff(stats.quota.week_to_date_percent, expectedWeekPercent);
 
       statsEl.innerHTML = `
+      ${renderDailyDelta(dailyDelta)}
+      ${renderFailedRequests(failedToday)}
       <div class="quota-section">
         <div class="quota-row">
           <div class="quota-item">
@@ -414,7 +891,8 @@ async function fetchUsage(): Promise<void> {
           <div class="quota-item">
             <div class="quota-header">
               <span class="quota-title">Weekly Limit</span>
-              <span class="quota-reset">Reset ${getNextWeeklyReset()}</span>
+              <span class="pace-status pace-${stats.quota.pace_status.toLowerCase()}" title="Safe pace: ${(stats.quota.safe_pace_percent_per_hour * 24).toFixed(1)}%/day until reset, projected ${stats.quota.projected_week_usage_percent.toFixed(0)}% at reset">${formatPace(stats.quota)}</span>
+              <span class="quota-reset">Reset ${getNextWeeklyReset(weekStartDow)}</span>
             </div>
             <div class="quota-bar-container">
               <div class="quota-bar" style="width: ${stats.quota.week_usage_percent}%; background: ${weekColor};"></div>
@@ -425,6 +903,7 @@ async function fetchUsage(): Promise<void> {
             </div>
           </div>
         </div>
+        ${renderQuotaWindows(stats.quota.windows)}
       </div>
 
       <div class="weekly-section">
@@ -432,13 +911,15 @@ async function fetchUsage(): Promise<void> {
         ${renderWeeklyUsageChart(stats.weekly_usage)}
       </div>
 
-      <div class="activity-section">
+      ${renderGoals(stats.goals)}
+
+      ${lowMemory ? "" : `<div class="activity-section">
         <h3>Activity (12 weeks)</h3>
-        ${renderActivityHeatmap(stats.daily_activity)}
-      </div>
+        ${renderActivityHeatmap(stats.daily_activity, weekStartDow)}
+      </div>`}
 
       <div class="model-breakdown">
-        <h3>Models</h3>
+        <h3>Models${stats.configured_model ? ` <span class="configured-model" title="Default model from Claude Code settings">default: ${stats.configured_model}</span>` : ""}${stats.plan_hint ? ` <span class="plan-hint">${stats.plan_hint}</span>` : ""}</h3>
         ${stats.by_model.length > 0 ? stats.by_model
           .map(
             (m) => {
@@ -448,8 +929,11 @@ async function fetchUsage(): Promise<void> {
           <div class="model-row ${getModelClass(m.model)}">
             <div class="model-info">
               <span class="model-name">${m.display_name}</span>
+              ${m.provider ? `<span class="model-provider">${PROVIDER_LABELS[m.provider] ?? m.provider}</span>` : ""}
+              ${m.avg_output_tps != null ? `<span class="model-speed">${m.avg_output_tps.toFixed(0)} tok/s</span>` : ""}
+              ${m.tokens.thinking_tokens > 0 ? `<span class="model-thinking" title="Extended thinking (estimated): $${m.thinking_cost_usd.toFixed(2)}">${formatNumber(m.tokens.thinking_tokens)} thinking</span>` : ""}
             </div>
-            <span class="model-tokens">${formatNumber(totalTokens)} tokens</span>
+            <span class="model-tokens">${m.display_tokens || formatNumber(totalTokens)} tokens</span>
           </div>
         `;
             }
@@ -464,11 +948,15 @@ async function fetchUsage(): Promise<void> {
             .map(
               (s) => `
             <div class="session-row">
-              <span class="session-directory" title="${s.directory}">${formatDirectory(s.directory)}</span>
-              <span class="session-model ${getModelClass(s.model)}">${s.model_display_name}</span>
-              <span class="session-context">${s.context_remaining_percent.toFixed(0)}%</span>
-              <span class="session-todos">${s.todo_count > 0 ? s.todo_count : "-"}</span>
-              <span class="session-duration">${formatDuration(s.duration_minutes)}</span>
+              ${pendingTermination?.session_id === s.session_id
+                ? `<button class="session-stop" data-session-id="${s.session_id}" title="Send SIGTERM to pid ${pendingTermination.pid}">Stop?</button>`
+                : `<span class="session-process${s.process_running ? " running" : ""}" data-session-id="${s.session_id}" title="${s.process_running ? `Claude Code running (pid ${s.pid}); click to stop it` : "No running Claude Code process"}">●</span>`}
+              <span class="session-directory" data-session-id="${s.session_id}" title="${s.user ? `${s.user}: ` : ""}${s.directory}\nClick: open a terminal here · Shift+click: resume the session">${formatDirectory(s.directory)}</span>
+              ${renderSparkline(s.recent_activity)}
+              <span class="session-model ${getModelClass(s.model)}" title="${s.avg_output_tps != null ? `${s.avg_output_tps.toFixed(0)} output tok/s` : ""}">${s.model_display_name}</span>
+              <span class="session-context" data-session-id="${s.session_id}" title="${contextBreakdownTitle(s.context_breakdown)}${s.context_breakdown ? "\n" : ""}Click: context growth and compaction forecast">${s.context_remaining_percent.toFixed(0)}%</span>
+              <span class="session-todos" title="${s.commands_executed > 0 ? `${s.commands_executed} command${s.commands_executed === 1 ? "" : "s"} run` : ""}">${s.todo_count > 0 ? s.todo_count : "-"}</span>
+              <span class="session-duration" title="${s.branch_count > 0 ? `${s.branch_count} abandoned branch${s.branch_count === 1 ? "" : "es"} (${formatNumber(s.abandoned_branch_tokens)} tokens)` : ""}">${formatDuration(s.duration_minutes)}${s.branch_count > 0 ? ` <span class="session-branches">⑂${s.branch_count}</span>` : ""}</span>
             </div>
           `
             )
@@ -476,8 +964,15 @@ async function fetchUsage(): Promise<void> {
         </div>
       </div>
 
+      ${renderOrgUtilization(stats.org_utilization)}
+      ${renderOrgAnalytics(stats.org_analytics)}
+
       <div class="last-updated">
-        ${stats.last_updated ? new Date(stats.last_updated).toLocaleTimeString() : "—"}
+        ${stats.display_last_updated || (stats.last_updated ? new Date(stats.last_updated).toLocaleTimeString() : "—")}
+        ${stats.billing_cycle ? renderBillingCycle(stats.billing_cycle) : PERIOD_LABELS[displayPeriod] ? ` · ${PERIOD_LABELS[displayPeriod]}` : ""}
+        ${stats.off_hours_percent >= 1 ? ` · ${stats.off_hours_percent.toFixed(0)}% off-hours` : ""}
+        ${stats.truncated ? " · partial (timed out)" : ""}
+        ${renderDegraded(stats.degraded)}
       </div>
     `;
 
@@ -513,9 +1008,84 @@ async function fetchUsage(): Promise<void> {
   }
 }
 
+// Backend events arrive as {seq, payload}; see resync in events.rs
+interface EventEnvelope<T> {
+  seq: number;
+  payload: T;
+}
+
+interface Resync {
+  seq: number;
+  events: { seq: number; event: string; payload: unknown }[];
+  complete: boolean;
+  display_period: string;
+}
+
+// Sequence number of the last backend event handled, kept across reloads
+const EVENT_SEQ_KEY = "lastEventSeq";
+let lastEventSeq: number | null = null;
+let resyncing = false;
+const eventHandlers: Record<string, (payload: unknown) => void> = {};
+
+function markEventSeq(seq: number): void {
+  lastEventSeq = seq;
+  sessionStorage.setItem(EVENT_SEQ_KEY, String(seq));
+}
+
+// Listen for a backend event, catching up through resync when earlier ones went missing
+async function listenEvent<T>(name: string, handler: (payload: T) => void): Promise<void> {
+  eventHandlers[name] = handler as (payload: unknown) => void;
+  await listen<EventEnvelope<T>>(name, (event) => {
+    const { seq, payload } = event.payload;
+    if (lastEventSeq !== null && seq <= lastEventSeq) return;
+    if (lastEventSeq !== null && seq > lastEventSeq + 1) {
+      // Replays this event too, after the missed ones
+      resyncEvents();
+      return;
+    }
+    markEventSeq(seq);
+    handler(payload);
+  });
+}
+
+// Replay the events emitted since the last one handled; reload everything if they are gone
+async function resyncEvents(): Promise<void> {
+  if (resyncing) return;
+  resyncing = true;
+  try {
+    const result = await invoke<Resync>("resync", { sinceSeq: lastEventSeq });
+    if (lastEventSeq !== null && !result.complete) {
+      displayPeriod = result.display_period;
+      markEventSeq(result.seq);
+      fetchUsage();
+      return;
+    }
+    for (const e of result.events) {
+      if (lastEventSeq !== null && e.seq <= lastEventSeq) continue;
+      markEventSeq(e.seq);
+      eventHandlers[e.event]?.(e.payload);
+    }
+    if (lastEventSeq === null || result.seq > lastEventSeq) markEventSeq(result.seq);
+  } catch (e) {
+    console.error("Failed to resync events:", e);
+  } finally {
+    resyncing = false;
+  }
+}
+
+async function setupEventResync(): Promise<void> {
+  const stored = sessionStorage.getItem(EVENT_SEQ_KEY);
+  if (lastEventSeq === null && stored !== null) lastEventSeq = Number(stored);
+  await resyncEvents();
+  // Events sent while the webview was hidden or suspended may not have arrived
+  document.addEventListener("visibilitychange", () => {
+    if (document.visibilityState === "visible") resyncEvents();
+  });
+}
+
 async function setupFileWatcher(): Promise<void> {
   try {
-    await listen("usage-updated", () => {
+    await listenEvent("usage-updated", () => {
       // Debounce file watcher updates to prevent rapid fetches
       if (fetchDebounceId) clearTimeout(fetchDebounceId);
       fetchDebounceId = setTimeout(() => {
@@ -528,9 +1098,201 @@ async function setupFileWatcher(): Promise<void> {
   }
 }
 
+interface SessionCostExceeded {
+  session_id: string;
+  project: string;
+  directory: string;
+  cost_usd: number;
+  ceiling_usd: number;
+}
+
+async function setupCostAlertHandler(): Promise<void> {
+  const alertEl = document.getElementById("cost-alert");
+  if (!alertEl) return;
+  alertEl.addEventListener("click", () => {
+    alertEl.style.display = "none";
+  });
+  try {
+    await listenEvent<SessionCostExceeded[]>("session-cost-exceeded", (alerts) => {
+      alertEl.textContent = alerts
+        .map((a) => `Session ${a.session_id} (${a.project}) cost $${a.cost_usd.toFixed(2)}, over the $${a.ceiling_usd.toFixed(2)} ceiling`)
+        .join(" · ");
+      alertEl.style.display = "block";
+    });
+  } catch (e) {
+    console.error("Failed to set up cost alert handler:", e);
+  }
+}
+
+interface SessionMatch {
+  session_id: string;
+  title: string | null;
+  project: string;
+  directory: string;
+  first_activity: string;
+  last_activity: string;
+  models: string[];
+  message_count: number;
+  total_tokens: number;
+  cost_usd: number;
+  active: boolean;
+  excluded: boolean;
+  score: number;
+}
+
+function setupSessionSearch(): void {
+  const inputEl = document.getElementById("session-search-input") as HTMLInputElement | null;
+  const resultsEl = document.getElementById("session-search-results");
+  if (!inputEl || !resultsEl) return;
+
+  let debounceId: ReturnType<typeof setTimeout> | null = null;
+  inputEl.addEventListener("input", () => {
+    if (debounceId) clearTimeout(debounceId);
+    debounceId = setTimeout(async () => {
+      const query = inputEl.value.trim();
+      if (!query) {
+        resultsEl.style.display = "none";
+        return;
+      }
+      try {
+        const sessions: SessionMatch[] = await invoke("search_sessions", { query, filters: { limit: 10 } });
+        // Built with textContent: titles come from conversation summaries
+        resultsEl.replaceChildren(
+          ...(sessions.length > 0 ? sessions.map((s) => {
+            const row = document.createElement("div");
+            row.className = "session-search-row" + (s.active ? " active" : "") + (s.excluded ? " excluded" : "");
+            row.title = s.directory;
+            const name = document.createElement("span");
+            name.className = "session-search-name";
+            name.textContent = s.title ? `${s.project}: ${s.title}` : s.project;
+            const meta = document.createElement("span");
+            meta.className = "session-search-meta";
+            meta.textContent = `${new Date(s.last_activity).toLocaleDateString()} · $${s.cost_usd.toFixed(2)}`;
+            // Leave a demo or test session out of the totals, or count it again
+            const toggle = document.createElement("button");
+            toggle.className = "session-exclude-btn";
+            const setToggle = () => {
+              toggle.textContent = s.excluded ? "↺" : "⊘";
+              toggle.title = s.excluded ? "Count this session again" : "Exclude from totals";
+              row.classList.toggle("excluded", s.excluded);
+            };
+            setToggle();
+            toggle.addEventListener("click", async (event) => {
+              event.stopPropagation();
+              try {
+                await invoke(s.excluded ? "include_session" : "exclude_session", { sessionId: s.session_id });
+                s.excluded = !s.excluded;
+                setToggle();
+                fetchUsage();
+              } catch (e) {
+                console.error("Failed to update excluded sessions:", e);
+              }
+            });
+            row.append(name, meta, toggle);
+            return row;
+          }) : [Object.assign(document.createElement("div"), { className: "muted", textContent: "No matching sessions" })])
+        );
+        resultsEl.style.display = "block";
+      } catch (e) {
+        console.error("Session search failed:", e);
+      }
+    }, 300);
+  });
+}
+
+interface TerminationRequest {
+  session_id: string;
+  pid: number;
+  directory: string;
+  token: string;
+  expires_in_secs: number;
+}
+
+interface TerminalLaunch {
+  directory: string;
+  resume_command: string;
+  resumed: boolean;
+}
+
+// Session directories open a terminal there; Shift+click also resumes the session when
+// the terminal's resume_args are configured, otherwise the resume command is copied.
+// Running sessions' dots stop the process after a second, confirming click.
+function setupSessionLauncher(): void {
+  document.getElementById("stats")?.addEventListener("click", async (event) => {
+    const el = event.target as HTMLElement;
+    // Emergency stop: the first click on a running session's dot asks for confirmation
+    const dot = el.closest<HTMLElement>(".session-process.running");
+    if (dot) {
+      try {
+        const request: TerminationRequest = await invoke("request_session_termination", { sessionId: dot.dataset.sessionId });
+        pendingTermination = request;
+        setTimeout(() => {
+          if (pendingTermination?.token === request.token) {
+            pendingTermination = null;
+            fetchUsage(true);
+          }
+        }, request.expires_in_secs * 1000);
+        fetchUsage(true);
+      } catch (e) {
+        dot.title = `${e}`;
+      }
+      return;
+    }
+    const stop = el.closest<HTMLElement>(".session-stop");
+    if (stop && pendingTermination) {
+      const { session_id, token } = pendingTermination;
+      pendingTermination = null;
+      try {
+        await invoke("terminate_session_process", { sessionId: session_id, confirmationToken: token });
+      } catch (e) {
+        console.error("Failed to stop session:", e);
+      }
+      fetchUsage();
+      return;
+    }
+
+    const context = el.closest<HTMLElement>(".session-context[data-session-id]");
+    if (context) {
+      try {
+        const history: SessionContextHistory = await invoke("get_session_context_history", { sessionId: context.dataset.sessionId });
+        context.title = contextHistoryTitle(history);
+      } catch (e) {
+        context.title = `${e}`;
+      }
+      return;
+    }
+
+    const target = el.closest<HTMLElement>(".session-directory[data-session-id]");
+    if (!target) return;
+    try {
+      const launch: TerminalLaunch = await invoke("open_session_in_terminal", {
+        sessionId: target.dataset.sessionId,
+        resume: event.shiftKey,
+      });
+      if (!launch.resumed) {
+        await navigator.clipboard.writeText(launch.resume_command).catch(() => undefined);
+        target.title = `${launch.directory}\nCopied: ${launch.resume_command}`;
+      }
+    } catch (e) {
+      target.title = `Failed to open a terminal: ${e}`;
+    }
+  });
+}
+
+async function setupPeriodHandler(): Promise<void> {
+  try {
+    await listenEvent<string>("period-changed", (period) => {
+      displayPeriod = period;
+      fetchUsage(true);
+    });
+  } catch (e) {
+    console.error("Failed to set up period handler:", e);
+  }
+}
+
 async function setupSuspendHandler(): Promise<void> {
   try {
-    await listen("system-resumed", () => {
+    await listenEvent("system-resumed", () => {
       // System just resumed from suspend - WebKit processes may be stale.
       // Reset retry state and attempt to recover by reloading the page.
       console.log("System resumed from suspend, reloading to recover WebKit state");
@@ -728,15 +1490,25 @@ window.addEventListener("DOMContentLoaded", () => {
   setupTitleBar();
   setupSettings();
   setupApiKeySettings();
+  setupSessionSearch();
+  setupSessionLauncher();
 
   document.getElementById("refresh-btn")?.addEventListener("click", fetchUsage);
 
   // Delay before first invoke to ensure WebKit IPC is fully initialized
   setTimeout(async () => {
+    try {
+      displayPeriod = await invoke("get_display_period");
+    } catch (e) {
+      console.error("Failed to get display period:", e);
+    }
+    lowMemory = await invoke<boolean>("get_low_memory").catch(() => false);
     await fetchUsage();
     showApiKeyModal();
-    setupFileWatcher();
-    setupSuspendHandler();
+    await Promise.all([setupFileWatcher(), setupPeriodHandler(), setupSuspendHandler(), setupCostAlertHandler()]);
+    setupEventResync();
+    checkForUpdates();
+    showAppInfo();
     // Refresh data every 30 seconds (reduced from 10s to minimize IPC load)
     setInterval(fetchUsage, 30000);
   }, 500);